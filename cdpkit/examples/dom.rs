@@ -1,5 +1,5 @@
 // DOM manipulation example: Query and interact with DOM
-use cdpkit::{dom, page, target, CDP};
+use cdpkit::{dom, page, target, Page, CDP};
 use futures::StreamExt;
 
 #[tokio::main]
@@ -31,24 +31,30 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     events.next().await;
     println!("Page loaded");
 
-    // Get document
-    let doc = dom::methods::GetDocument::new().send(&session).await?;
-    println!("Document node ID: {}", doc.root.node_id);
-
-    // Query selector for h1
-    let result = dom::methods::QuerySelector::new(doc.root.node_id, "h1")
-        .send(&session)
-        .await?;
-
-    if result.node_id > 0 {
-        println!("Found h1 element with node ID: {}", result.node_id);
+    // Query elements through handles that survive document updates
+    let page = Page::new(&session);
+    if let Some(heading) = page.query_selector("h1").await? {
+        println!("Found h1: {:?}", heading.text().await?);
+        if let Some(bbox) = heading.bounding_box().await? {
+            println!(
+                "h1 box: {}x{} at ({}, {})",
+                bbox.width, bbox.height, bbox.x, bbox.y
+            );
+        }
     }
 
     // Query all paragraphs
-    let result = dom::methods::QuerySelectorAll::new(doc.root.node_id, "p")
+    let paragraphs = page.query_selector_all("p").await?;
+    println!("Found {} paragraph elements", paragraphs.len());
+
+    // Raw NodeIds are still available, but are invalidated by DOM.documentUpdated
+    let doc = dom::methods::GetDocument::new().send(&session).await?;
+    let result = dom::methods::QuerySelector::new(doc.root.node_id, "a")
         .send(&session)
         .await?;
-    println!("Found {} paragraph elements", result.node_ids.len());
+    if result.node_id > 0 {
+        println!("Found link with node ID: {}", result.node_id);
+    }
 
     Ok(())
 }
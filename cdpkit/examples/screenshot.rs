@@ -1,5 +1,5 @@
-// Screenshot example: Capture page screenshot
-use cdpkit::{page, target, CDP};
+// Screenshot example: Capture viewport, full-page and PDF output
+use cdpkit::{page, target, Page, PdfOptions, ScreenshotOptions, CDP};
 use futures::StreamExt;
 
 #[tokio::main]
@@ -30,24 +30,35 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     events.next().await;
     println!("Page loaded");
 
-    // Wait a bit for rendering
-    tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+    let page = Page::new(&session);
 
-    // Capture screenshot
-    println!("Capturing screenshot...");
-    let result = page::methods::CaptureScreenshot::new()
-        .with_format("png")
-        .send(&session)
-        .await?;
+    // Capture the viewport
+    let image = page.screenshot(ScreenshotOptions::default()).await?;
+    std::fs::write("screenshot.png", &image)?;
+    println!("Screenshot saved to screenshot.png ({} bytes)", image.len());
 
-    // Decode and save
-    use base64::Engine;
-    let image_data = base64::engine::general_purpose::STANDARD.decode(&result.data)?;
-    std::fs::write("screenshot.png", &image_data)?;
-    println!(
-        "Screenshot saved to screenshot.png ({} bytes)",
-        image_data.len()
-    );
+    // Capture the whole scrollable page
+    page.save_screenshot(
+        "full-page.png",
+        ScreenshotOptions {
+            full_page: true,
+            ..Default::default()
+        },
+    )
+    .await?;
+    println!("Full-page screenshot saved to full-page.png");
+
+    // Print to PDF, streamed to disk in chunks
+    let written = page
+        .save_pdf(
+            "page.pdf",
+            PdfOptions {
+                print_background: Some(true),
+                ..Default::default()
+            },
+        )
+        .await?;
+    println!("PDF saved to page.pdf ({written} bytes)");
 
     Ok(())
 }
@@ -0,0 +1,676 @@
+use crate::accessibility::types::{AXNode, AXNodeId, AXPropertyName, AXValue};
+use crate::dom::types::BackendNodeId;
+use crate::page::types::FrameId;
+use crate::{accessibility, CdpError, Sender};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{self, Write as _};
+
+/// Roles that only group other nodes; the snapshot shows their children in their place.
+const STRUCTURAL_ROLES: [&str; 4] = ["none", "generic", "InlineTextBox", "LineBreak"];
+
+/// State of a tristate property such as `checked` or `pressed`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AxTristate {
+    False,
+    True,
+    Mixed,
+}
+
+impl AxTristate {
+    fn parse(value: &Value) -> Option<Self> {
+        match value {
+            Value::Bool(false) => Some(Self::False),
+            Value::Bool(true) => Some(Self::True),
+            Value::String(s) if s == "false" => Some(Self::False),
+            Value::String(s) if s == "true" => Some(Self::True),
+            Value::String(s) if s == "mixed" => Some(Self::Mixed),
+            _ => None,
+        }
+    }
+}
+
+/// Typed widget states of an [`AxNode`]. Properties Chrome did not report are `false` or
+/// `None`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AxStates {
+    pub disabled: bool,
+    pub focusable: bool,
+    pub focused: bool,
+    pub editable: bool,
+    pub readonly: bool,
+    pub required: bool,
+    pub invalid: bool,
+    pub hidden: bool,
+    pub busy: bool,
+    pub modal: bool,
+    pub multiline: bool,
+    pub multiselectable: bool,
+    /// `None` for nodes that cannot be checked.
+    pub checked: Option<AxTristate>,
+    /// `None` for nodes that are not toggle buttons.
+    pub pressed: Option<AxTristate>,
+    pub expanded: Option<bool>,
+    pub selected: Option<bool>,
+    /// Heading, tree item or list nesting level.
+    pub level: Option<u32>,
+}
+
+/// One node of an [`AxTree`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct AxNode {
+    /// Position in the tree, used by [`AxTree::node`] and friends.
+    pub index: usize,
+    pub id: AXNodeId,
+    /// ARIA role such as `button` or `heading`, or a Chrome role such as `StaticText`.
+    pub role: String,
+    /// Accessible name; empty when the node has none.
+    pub name: String,
+    pub description: Option<String>,
+    /// Current value of form controls, sliders and the like.
+    pub value: Option<String>,
+    /// Not exposed to assistive technology; skipped by queries and snapshots.
+    pub ignored: bool,
+    pub states: AxStates,
+    /// The DOM node this was computed from, usable with
+    /// [`ElementHandle::from_backend_node_id`](crate::ElementHandle::from_backend_node_id).
+    pub backend_node_id: Option<BackendNodeId>,
+    pub frame_id: Option<FrameId>,
+    pub parent: Option<usize>,
+    pub children: Vec<usize>,
+    /// Every reported property by its CDP name, including the ones in `states`.
+    pub properties: BTreeMap<String, Value>,
+}
+
+impl AxNode {
+    fn new(index: usize, node: AXNode) -> Self {
+        let mut properties = BTreeMap::new();
+        let mut states = AxStates::default();
+        for property in node.properties.unwrap_or_default() {
+            let value = property.value.value.unwrap_or(Value::Null);
+            let flag = value.as_bool().unwrap_or(false) || value.as_str() == Some("true");
+            match property.name {
+                AXPropertyName::Disabled => states.disabled = flag,
+                AXPropertyName::Focusable => states.focusable = flag,
+                AXPropertyName::Focused => states.focused = flag,
+                // A token: "plaintext" or "richtext".
+                AXPropertyName::Editable => states.editable = value.is_string(),
+                AXPropertyName::Readonly => states.readonly = flag,
+                AXPropertyName::Required => states.required = flag,
+                // A token: "false", "true", "grammar" or "spelling".
+                AXPropertyName::Invalid => {
+                    states.invalid = value.as_str().is_some_and(|token| token != "false")
+                }
+                AXPropertyName::Hidden => states.hidden = flag,
+                AXPropertyName::Busy => states.busy = flag,
+                AXPropertyName::Modal => states.modal = flag,
+                AXPropertyName::Multiline => states.multiline = flag,
+                AXPropertyName::Multiselectable => states.multiselectable = flag,
+                AXPropertyName::Checked => states.checked = AxTristate::parse(&value),
+                AXPropertyName::Pressed => states.pressed = AxTristate::parse(&value),
+                AXPropertyName::Expanded => states.expanded = value.as_bool(),
+                AXPropertyName::Selected => states.selected = value.as_bool(),
+                AXPropertyName::Level => {
+                    states.level = value.as_u64().and_then(|level| u32::try_from(level).ok())
+                }
+                _ => {}
+            }
+            if let Ok(Value::String(name)) = serde_json::to_value(&property.name) {
+                properties.insert(name, value);
+            }
+        }
+        Self {
+            index,
+            id: node.node_id,
+            role: node.role.as_ref().and_then(text).unwrap_or_default(),
+            name: node.name.as_ref().and_then(text).unwrap_or_default(),
+            description: node.description.as_ref().and_then(text),
+            value: node.value.as_ref().and_then(text),
+            ignored: node.ignored,
+            states,
+            backend_node_id: node.backend_dom_node_id,
+            frame_id: node.frame_id,
+            parent: None,
+            children: Vec::new(),
+            properties,
+        }
+    }
+
+    /// States shown in snapshots, in the order they are written.
+    fn snapshot_states(&self) -> Vec<String> {
+        let states = &self.states;
+        let tristate = |name: &str, state: Option<AxTristate>| match state {
+            Some(AxTristate::True) => Some(name.to_string()),
+            Some(AxTristate::Mixed) => Some(format!("{name}=mixed")),
+            Some(AxTristate::False) | None => None,
+        };
+        let flags = [
+            (states.disabled, "disabled"),
+            (states.expanded == Some(true), "expanded"),
+            (states.focused, "focused"),
+            (states.invalid, "invalid"),
+            (states.readonly, "readonly"),
+            (states.required, "required"),
+            (states.selected == Some(true), "selected"),
+        ];
+        tristate("checked", states.checked)
+            .into_iter()
+            .chain(
+                flags
+                    .iter()
+                    .filter(|(set, _)| *set)
+                    .map(|(_, name)| name.to_string()),
+            )
+            .chain(states.level.map(|level| format!("level={level}")))
+            .chain(tristate("pressed", states.pressed))
+            .collect()
+    }
+}
+
+/// `button "Save" [disabled]`, the key of the node's line in [`AxTree::to_yaml`] before
+/// YAML escaping.
+impl fmt::Display for AxNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.role)?;
+        if !self.name.is_empty() {
+            write!(f, " {}", quote(&self.name))?;
+        }
+        for state in self.snapshot_states() {
+            write!(f, " [{state}]")?;
+        }
+        Ok(())
+    }
+}
+
+fn text(value: &AXValue) -> Option<String> {
+    match value.value.as_ref()? {
+        Value::String(s) => Some(s.clone()),
+        Value::Null => None,
+        other => Some(other.to_string()),
+    }
+}
+
+/// A double-quoted string that is valid YAML.
+fn quote(s: &str) -> String {
+    Value::String(s.to_string()).to_string()
+}
+
+/// `key` as a YAML mapping key or sequence item: single-quoted as a whole when a plain
+/// scalar would be read as syntax, like Playwright's `yamlEscapeKeyIfNeeded`.
+fn yaml_key(key: &str) -> String {
+    const INDICATORS: [char; 19] = [
+        '-', '?', ':', ',', '[', ']', '{', '}', '#', '&', '*', '!', '|', '>', '\'', '"', '%', '@',
+        '`',
+    ];
+    let needs_quotes = key.is_empty()
+        || key.trim() != key
+        || key.starts_with(INDICATORS)
+        || key.contains(": ")
+        || key.contains(" #")
+        || key.ends_with(':');
+    if needs_quotes {
+        format!("'{}'", key.replace('\'', "''"))
+    } else {
+        key.to_string()
+    }
+}
+
+/// Selects [`AxTree`] nodes by role and accessible name.
+///
+/// ```
+/// # use cdpkit::AxQuery;
+/// let save = AxQuery::new().with_role("button").with_name("Save");
+/// let any_heading = AxQuery::new().with_role("heading");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct AxQuery {
+    role: Option<String>,
+    name: Option<String>,
+    name_containing: Option<String>,
+    include_ignored: bool,
+}
+
+impl AxQuery {
+    /// A query matching every node that is not ignored.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_role(mut self, role: impl Into<String>) -> Self {
+        self.role = Some(role.into());
+        self
+    }
+
+    /// Match the accessible name exactly.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Match nodes whose accessible name contains `text`.
+    pub fn with_name_containing(mut self, text: impl Into<String>) -> Self {
+        self.name_containing = Some(text.into());
+        self
+    }
+
+    /// Also match nodes that are hidden from assistive technology.
+    pub fn with_ignored(mut self, include: bool) -> Self {
+        self.include_ignored = include;
+        self
+    }
+
+    pub fn matches(&self, node: &AxNode) -> bool {
+        (self.include_ignored || !node.ignored)
+            && self.role.as_ref().map_or(true, |role| node.role == *role)
+            && self.name.as_ref().map_or(true, |name| node.name == *name)
+            && self
+                .name_containing
+                .as_ref()
+                .map_or(true, |text| node.name.contains(text.as_str()))
+    }
+}
+
+/// The accessibility tree of a page with parents and children linked.
+///
+/// `Accessibility.getFullAXTree` reports a flat list of nodes whose roles, names and
+/// states are buried in property arrays. `AxTree` decodes them into [`AxNode`]s, answers
+/// role+name queries and renders a compact YAML snapshot for golden-file tests.
+///
+/// # Example
+/// ```no_run
+/// # use cdpkit::{AxQuery, AxTree, ElementHandle, OwnedSession};
+/// # async fn example(session: OwnedSession) -> Result<(), cdpkit::CdpError> {
+/// let tree = AxTree::capture(&session).await?;
+/// let submit = tree
+///     .find(&AxQuery::new().with_role("button").with_name("Submit"))
+///     .expect("submit button");
+/// assert!(!submit.states.disabled);
+///
+/// if let Some(id) = submit.backend_node_id {
+///     ElementHandle::from_backend_node_id(&session, id).await?.click().await?;
+/// }
+/// println!("{}", tree.to_yaml());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct AxTree {
+    nodes: Vec<AxNode>,
+    roots: Vec<usize>,
+    by_id: HashMap<AXNodeId, usize>,
+    by_backend_node_id: HashMap<BackendNodeId, usize>,
+}
+
+impl AxTree {
+    /// The accessibility tree of the target's main frame.
+    pub async fn capture(target: &(impl Sender + Sync)) -> Result<Self, CdpError> {
+        let response = accessibility::methods::GetFullAxTree::new()
+            .send(target)
+            .await?;
+        Ok(Self::from_nodes(response.nodes))
+    }
+
+    /// The accessibility tree of one frame, e.g. an out-of-process iframe's document.
+    pub async fn capture_frame(
+        target: &(impl Sender + Sync),
+        frame_id: FrameId,
+    ) -> Result<Self, CdpError> {
+        let response = accessibility::methods::GetFullAxTree::new()
+            .with_frame_id(frame_id)
+            .send(target)
+            .await?;
+        Ok(Self::from_nodes(response.nodes))
+    }
+
+    /// Link nodes as returned by any of the `Accessibility.get*` methods. Children that
+    /// were not reported are dropped; nodes without a reported parent become roots.
+    pub fn from_nodes(nodes: Vec<AXNode>) -> Self {
+        let mut child_ids = Vec::with_capacity(nodes.len());
+        let mut parent_ids = Vec::with_capacity(nodes.len());
+        let mut tree = Self::default();
+        for (index, mut node) in nodes.into_iter().enumerate() {
+            child_ids.push(node.child_ids.take().unwrap_or_default());
+            parent_ids.push(node.parent_id.take());
+            let node = AxNode::new(index, node);
+            tree.by_id.insert(node.id.clone(), index);
+            if let Some(backend_node_id) = node.backend_node_id {
+                tree.by_backend_node_id
+                    .entry(backend_node_id)
+                    .or_insert(index);
+            }
+            tree.nodes.push(node);
+        }
+        for (index, ids) in child_ids.iter().enumerate() {
+            for id in ids {
+                if let Some(&child) = tree.by_id.get(id) {
+                    if tree.nodes[child].parent.is_none() && child != index {
+                        tree.nodes[child].parent = Some(index);
+                        tree.nodes[index].children.push(child);
+                    }
+                }
+            }
+        }
+        // Partial trees may report a parent without listing the child under it.
+        for (index, parent_id) in parent_ids.into_iter().enumerate() {
+            let parent = parent_id.and_then(|id| tree.by_id.get(&id).copied());
+            if let (None, Some(parent)) = (tree.nodes[index].parent, parent) {
+                if parent != index {
+                    tree.nodes[index].parent = Some(parent);
+                    tree.nodes[parent].children.push(index);
+                }
+            }
+        }
+        tree.roots = (0..tree.nodes.len())
+            .filter(|&index| tree.nodes[index].parent.is_none())
+            .collect();
+        tree
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// The document node, for a full tree.
+    pub fn root(&self) -> Option<&AxNode> {
+        self.roots.first().map(|&index| &self.nodes[index])
+    }
+
+    pub fn node(&self, index: usize) -> Option<&AxNode> {
+        self.nodes.get(index)
+    }
+
+    /// All nodes in the order Chrome reported them, ignored ones included.
+    pub fn nodes(&self) -> impl Iterator<Item = &AxNode> + '_ {
+        self.nodes.iter()
+    }
+
+    pub fn node_by_id(&self, id: &str) -> Option<&AxNode> {
+        self.by_id.get(id).map(|&index| &self.nodes[index])
+    }
+
+    /// The node computed from a DOM node, e.g. one from
+    /// [`ElementHandle::backend_node_id`](crate::ElementHandle::backend_node_id).
+    pub fn node_by_backend_node_id(&self, id: BackendNodeId) -> Option<&AxNode> {
+        self.by_backend_node_id
+            .get(&id)
+            .map(|&index| &self.nodes[index])
+    }
+
+    pub fn parent(&self, index: usize) -> Option<&AxNode> {
+        self.nodes
+            .get(index)?
+            .parent
+            .map(|parent| &self.nodes[parent])
+    }
+
+    pub fn children(&self, index: usize) -> impl Iterator<Item = &AxNode> + '_ {
+        let children = self.nodes.get(index).map_or(&[][..], |node| &node.children);
+        children.iter().map(|&child| &self.nodes[child])
+    }
+
+    /// Parent, grandparent, ... of node `index`, up to its root.
+    pub fn ancestors(&self, index: usize) -> impl Iterator<Item = &AxNode> + '_ {
+        std::iter::successors(self.parent(index), |node| self.parent(node.index))
+    }
+
+    /// Nodes matching `query`, in document order.
+    pub fn query(&self, query: &AxQuery) -> Vec<&AxNode> {
+        let mut matches = Vec::new();
+        for &root in &self.roots {
+            self.walk(root, &mut |node| {
+                if query.matches(node) {
+                    matches.push(node);
+                }
+            });
+        }
+        matches
+    }
+
+    /// The first node matching `query` in document order.
+    pub fn find(&self, query: &AxQuery) -> Option<&AxNode> {
+        self.query(query).into_iter().next()
+    }
+
+    /// Nodes below `index` that match `query`, in document order.
+    pub fn query_within(&self, index: usize, query: &AxQuery) -> Vec<&AxNode> {
+        let mut matches = Vec::new();
+        for child in self.children(index) {
+            self.walk(child.index, &mut |node| {
+                if query.matches(node) {
+                    matches.push(node);
+                }
+            });
+        }
+        matches
+    }
+
+    fn walk<'a>(&'a self, index: usize, visit: &mut impl FnMut(&'a AxNode)) {
+        let mut stack = vec![index];
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            visit(node);
+            stack.extend(node.children.iter().rev());
+        }
+    }
+
+    /// A YAML snapshot of the whole tree, one node per line:
+    ///
+    /// ```yaml
+    /// - RootWebArea "Sign in":
+    ///   - heading "Welcome" [level=1]
+    ///   - textbox "Email" [focused]: "ada@example.com"
+    ///   - button "Submit" [disabled]
+    /// ```
+    ///
+    /// Ignored nodes and unnamed `generic`/`none` wrappers are replaced by their
+    /// children, and text that repeats its parent's name is left out, so the snapshot
+    /// only changes when something a screen reader announces changes.
+    pub fn to_yaml(&self) -> String {
+        let mut out = String::new();
+        for &root in &self.roots {
+            self.write_yaml(&mut out, root, 0, "");
+        }
+        out
+    }
+
+    /// Like [`to_yaml`](Self::to_yaml) for the subtree rooted at node `index`.
+    pub fn subtree_yaml(&self, index: usize) -> String {
+        let mut out = String::new();
+        if index < self.nodes.len() {
+            self.write_yaml(&mut out, index, 0, "");
+        }
+        out
+    }
+
+    fn write_yaml(&self, out: &mut String, index: usize, depth: usize, parent_name: &str) {
+        let node = &self.nodes[index];
+        let structural = node.ignored
+            || (STRUCTURAL_ROLES.contains(&node.role.as_str()) && node.name.is_empty());
+        if structural {
+            for &child in &node.children {
+                self.write_yaml(out, child, depth, parent_name);
+            }
+            return;
+        }
+        let indent = "  ".repeat(depth);
+        if node.role == "StaticText" {
+            if node.name != parent_name && !node.name.trim().is_empty() {
+                let _ = writeln!(out, "{indent}- text: {}", quote(&node.name));
+            }
+            return;
+        }
+
+        let mut children = String::new();
+        for &child in &node.children {
+            self.write_yaml(&mut children, child, depth + 1, &node.name);
+        }
+        let value = node.value.as_deref().filter(|value| !value.is_empty());
+        let _ = match (value, children.is_empty()) {
+            (Some(value), true) => {
+                writeln!(
+                    out,
+                    "{indent}- {}: {}",
+                    yaml_key(&node.to_string()),
+                    quote(value)
+                )
+            }
+            (None, true) => writeln!(out, "{indent}- {}", yaml_key(&node.to_string())),
+            (Some(value), false) => {
+                let key = format!("{node} [value={}]", quote(value));
+                writeln!(out, "{indent}- {}:", yaml_key(&key))
+            }
+            (None, false) => writeln!(out, "{indent}- {}:", yaml_key(&node.to_string())),
+        };
+        out.push_str(&children);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sign_in_form() -> AxTree {
+        let nodes: Vec<AXNode> = serde_json::from_value(json!([
+            {"nodeId": "1", "ignored": false, "role": {"type": "internalRole", "value": "RootWebArea"},
+             "name": {"type": "computedString", "value": "Sign in"}, "childIds": ["2"],
+             "backendDOMNodeId": 1, "frameId": "main"},
+            {"nodeId": "2", "ignored": true, "role": {"type": "role", "value": "none"},
+             "parentId": "1", "childIds": ["3", "5", "6", "7", "8"], "backendDOMNodeId": 2},
+            {"nodeId": "3", "ignored": false, "role": {"type": "role", "value": "heading"},
+             "name": {"type": "computedString", "value": "Welcome"}, "parentId": "2",
+             "childIds": ["4"], "backendDOMNodeId": 10,
+             "properties": [{"name": "level", "value": {"type": "integer", "value": 1}}]},
+            {"nodeId": "4", "ignored": false, "role": {"type": "internalRole", "value": "StaticText"},
+             "name": {"type": "computedString", "value": "Welcome"}, "parentId": "3",
+             "backendDOMNodeId": 11},
+            {"nodeId": "5", "ignored": false, "role": {"type": "role", "value": "textbox"},
+             "name": {"type": "computedString", "value": "Email"},
+             "value": {"type": "string", "value": "ada@example.com"}, "parentId": "2",
+             "backendDOMNodeId": 12, "properties": [
+                {"name": "focusable", "value": {"type": "booleanOrUndefined", "value": true}},
+                {"name": "focused", "value": {"type": "booleanOrUndefined", "value": true}},
+                {"name": "editable", "value": {"type": "token", "value": "plaintext"}},
+                {"name": "invalid", "value": {"type": "token", "value": "false"}}]},
+            {"nodeId": "6", "ignored": false, "role": {"type": "role", "value": "checkbox"},
+             "name": {"type": "computedString", "value": "Remember me"}, "parentId": "2",
+             "backendDOMNodeId": 13, "properties": [
+                {"name": "checked", "value": {"type": "tristate", "value": "mixed"}}]},
+            {"nodeId": "7", "ignored": false, "role": {"type": "role", "value": "button"},
+             "name": {"type": "computedString", "value": "Submit"}, "parentId": "2",
+             "backendDOMNodeId": 14, "properties": [
+                {"name": "disabled", "value": {"type": "boolean", "value": true}}]},
+            {"nodeId": "8", "ignored": false, "role": {"type": "role", "value": "button"},
+             "name": {"type": "computedString", "value": "Say \"hi\""}, "parentId": "2",
+             "backendDOMNodeId": 15}
+        ]))
+        .unwrap();
+        AxTree::from_nodes(nodes)
+    }
+
+    #[test]
+    fn links_nodes_and_decodes_states() {
+        let tree = sign_in_form();
+        assert_eq!(tree.root().unwrap().name, "Sign in");
+        assert_eq!(tree.children(1).count(), 5);
+        let textbox = tree.node_by_backend_node_id(12).unwrap();
+        assert_eq!(textbox.value.as_deref(), Some("ada@example.com"));
+        assert!(textbox.states.focused && textbox.states.editable && !textbox.states.invalid);
+        let ancestors: Vec<&str> = tree
+            .ancestors(textbox.index)
+            .map(|n| n.id.as_str())
+            .collect();
+        assert_eq!(ancestors, ["2", "1"]);
+        assert_eq!(
+            tree.node_by_id("6").unwrap().states.checked,
+            Some(AxTristate::Mixed)
+        );
+        assert_eq!(tree.node_by_id("3").unwrap().states.level, Some(1));
+        assert_eq!(
+            tree.node_by_id("7").unwrap().properties["disabled"],
+            json!(true)
+        );
+    }
+
+    #[test]
+    fn queries_match_role_and_name() {
+        let tree = sign_in_form();
+        let buttons = tree.query(&AxQuery::new().with_role("button"));
+        assert_eq!(buttons.len(), 2);
+        let submit = tree
+            .find(&AxQuery::new().with_role("button").with_name("Submit"))
+            .unwrap();
+        assert_eq!(submit.backend_node_id, Some(14));
+        assert_eq!(submit.to_string(), "button \"Submit\" [disabled]");
+        assert!(tree
+            .find(&AxQuery::new().with_name_containing("Remember"))
+            .is_some_and(|node| node.role == "checkbox"));
+        assert!(tree.query(&AxQuery::new().with_role("none")).is_empty());
+        assert_eq!(
+            tree.query(&AxQuery::new().with_role("none").with_ignored(true))
+                .len(),
+            1
+        );
+        assert_eq!(tree.query_within(2, &AxQuery::new()).len(), 1);
+    }
+
+    #[test]
+    fn yaml_snapshot_skips_structure_and_redundant_text() {
+        assert_eq!(
+            sign_in_form().to_yaml(),
+            "- RootWebArea \"Sign in\":\n\
+             \x20 - heading \"Welcome\" [level=1]\n\
+             \x20 - textbox \"Email\" [focused]: \"ada@example.com\"\n\
+             \x20 - checkbox \"Remember me\" [checked=mixed]\n\
+             \x20 - button \"Submit\" [disabled]\n\
+             \x20 - button \"Say \\\"hi\\\"\"\n"
+        );
+    }
+
+    fn buttons(names: &[&str]) -> AxTree {
+        let mut nodes = vec![json!({
+            "nodeId": "1", "ignored": false,
+            "role": {"type": "internalRole", "value": "RootWebArea"},
+            "childIds": (2..names.len() + 2).map(|id| id.to_string()).collect::<Vec<_>>()
+        })];
+        for (i, name) in names.iter().enumerate() {
+            nodes.push(json!({
+                "nodeId": (i + 2).to_string(), "ignored": false, "parentId": "1",
+                "role": {"type": "role", "value": "button"},
+                "name": {"type": "computedString", "value": name}
+            }));
+        }
+        AxTree::from_nodes(serde_json::from_value(Value::Array(nodes)).unwrap())
+    }
+
+    #[test]
+    fn yaml_keys_with_indicators_are_quoted() {
+        assert_eq!(
+            buttons(&["Price: $5", "Item #3", "Ends with:", "It's: done"]).to_yaml(),
+            "- RootWebArea:\n\
+             \x20 - 'button \"Price: $5\"'\n\
+             \x20 - 'button \"Item #3\"'\n\
+             \x20 - button \"Ends with:\"\n\
+             \x20 - 'button \"It''s: done\"'\n"
+        );
+    }
+
+    #[test]
+    fn yaml_keys_with_leading_indicators_in_the_name_stay_plain() {
+        assert_eq!(
+            buttons(&["- 5 items", "[Beta] Search", "#hashtag", "a:b"]).to_yaml(),
+            "- RootWebArea:\n\
+             \x20 - button \"- 5 items\"\n\
+             \x20 - button \"[Beta] Search\"\n\
+             \x20 - button \"#hashtag\"\n\
+             \x20 - button \"a:b\"\n"
+        );
+        assert_eq!(yaml_key("- item"), "'- item'");
+        assert_eq!(yaml_key("[x]"), "'[x]'");
+        assert_eq!(yaml_key("#x"), "'#x'");
+    }
+}
@@ -0,0 +1,232 @@
+use crate::browser::types::PermissionType;
+use crate::{
+    spawn_cleanup, target, CdpError, DownloadOptions, Downloads, OwnedSession, Permissions, CDP,
+};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tracing::warn;
+
+/// Settings for [`BrowserContext::create`].
+#[derive(Debug, Clone, Default)]
+pub struct BrowserContextOptions {
+    proxy_server: Option<String>,
+    proxy_bypass_list: Option<String>,
+    download_path: Option<PathBuf>,
+    permissions: Vec<(Option<String>, Vec<PermissionType>)>,
+}
+
+impl BrowserContextOptions {
+    /// A context with the browser's network settings and downloads denied.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Route the context's traffic through a proxy, e.g. `socks5://127.0.0.1:1080`.
+    pub fn with_proxy_server(mut self, proxy_server: impl Into<String>) -> Self {
+        self.proxy_server = Some(proxy_server.into());
+        self
+    }
+
+    /// Hosts that bypass the proxy, comma separated, e.g. `localhost,*.internal`.
+    pub fn with_proxy_bypass_list(mut self, bypass_list: impl Into<String>) -> Self {
+        self.proxy_bypass_list = Some(bypass_list.into());
+        self
+    }
+
+    /// Accept downloads into this directory; see [`BrowserContext::downloads`].
+    pub fn with_download_path(mut self, download_path: impl Into<PathBuf>) -> Self {
+        self.download_path = Some(download_path.into());
+        self
+    }
+
+    /// Grant permissions to one origin, or to all origins with `None`.
+    pub fn with_permissions(
+        mut self,
+        origin: Option<&str>,
+        permissions: impl IntoIterator<Item = PermissionType>,
+    ) -> Self {
+        self.permissions
+            .push((origin.map(str::to_owned), permissions.into_iter().collect()));
+        self
+    }
+}
+
+/// A page created in a [`BrowserContext`].
+#[derive(Clone)]
+pub struct ContextPage {
+    pub target_id: String,
+    pub session: OwnedSession,
+}
+
+/// An isolated, incognito-like browser context created with `Target.createBrowserContext`.
+///
+/// Cookies, storage and cache are not shared with other contexts. Pages created with
+/// [`new_page`](Self::new_page) belong to the context and are closed with it. The context
+/// is disposed by [`close`](Self::close), or on drop, where `Target.disposeBrowserContext`
+/// is sent from a spawned task without waiting for it.
+///
+/// # Example
+/// ```no_run
+/// # use cdpkit::{BrowserContext, BrowserContextOptions, CDP};
+/// # use cdpkit::browser::types::PermissionType;
+/// # async fn example(cdp: CDP) -> Result<(), cdpkit::CdpError> {
+/// let options = BrowserContextOptions::new()
+///     .with_proxy_server("http://127.0.0.1:8080")
+///     .with_permissions(Some("https://maps.test"), [PermissionType::Geolocation]);
+/// let context = BrowserContext::create(&cdp, options).await?;
+/// let page = context.new_page("https://maps.test").await?;
+/// // ... drive `page` ...
+/// context.close().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct BrowserContext {
+    cdp: CDP,
+    id: String,
+    pages: Mutex<Vec<ContextPage>>,
+    downloads: Option<Downloads>,
+    permissions: Permissions,
+    disposed: bool,
+}
+
+impl BrowserContext {
+    /// Create the context and apply its download and permission settings.
+    pub async fn create(cdp: &CDP, options: BrowserContextOptions) -> Result<Self, CdpError> {
+        let mut cmd = target::methods::CreateBrowserContext::new();
+        if let Some(proxy_server) = options.proxy_server {
+            cmd = cmd.with_proxy_server(proxy_server);
+        }
+        if let Some(bypass_list) = options.proxy_bypass_list {
+            cmd = cmd.with_proxy_bypass_list(bypass_list);
+        }
+        let id = cmd.send(cdp).await?.browser_context_id;
+
+        // From here on, a failure drops `context`, which disposes it.
+        let mut context = Self {
+            cdp: cdp.clone(),
+            permissions: Permissions::new(cdp).with_browser_context_id(&id),
+            id,
+            pages: Mutex::new(Vec::new()),
+            downloads: None,
+            disposed: false,
+        };
+        for (origin, permissions) in options.permissions {
+            context
+                .grant_permissions(origin.as_deref(), permissions)
+                .await?;
+        }
+        if let Some(download_path) = options.download_path {
+            let options = DownloadOptions::new(download_path).with_browser_context_id(&context.id);
+            context.downloads = Some(Downloads::start(cdp, options).await?);
+        }
+        Ok(context)
+    }
+
+    /// The `Browser.BrowserContextID`.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Open a page in this context and attach to it.
+    pub async fn new_page(&self, url: &str) -> Result<OwnedSession, CdpError> {
+        let target_id = target::methods::CreateTarget::new(url)
+            .with_browser_context_id(self.id.clone())
+            .send(&self.cdp)
+            .await?
+            .target_id;
+        let attach = target::methods::AttachToTarget::new(target_id.clone())
+            .with_flatten(true)
+            .send(&self.cdp)
+            .await?;
+        let session = self.cdp.owned_session(attach.session_id);
+        self.pages
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(ContextPage {
+                target_id,
+                session: session.clone(),
+            });
+        Ok(session)
+    }
+
+    /// Pages opened with [`new_page`](Self::new_page), oldest first.
+    pub fn pages(&self) -> Vec<ContextPage> {
+        self.pages.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Close one page of this context with `Target.closeTarget`.
+    pub async fn close_page(&self, target_id: &str) -> Result<(), CdpError> {
+        target::methods::CloseTarget::new(target_id.to_string())
+            .send(&self.cdp)
+            .await?;
+        self.pages
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|page| page.target_id != target_id);
+        Ok(())
+    }
+
+    /// Downloads of this context, if it was created with a download path.
+    pub fn downloads(&mut self) -> Option<&mut Downloads> {
+        self.downloads.as_mut()
+    }
+
+    /// Grant permissions to one origin, or to all origins with `None`, for the lifetime of
+    /// the context. Use [`permissions`](Self::permissions) for grants that are revoked again.
+    pub async fn grant_permissions(
+        &self,
+        origin: Option<&str>,
+        permissions: impl IntoIterator<Item = PermissionType>,
+    ) -> Result<(), CdpError> {
+        self.permissions
+            .grant_persistent(origin, permissions.into_iter().collect())
+            .await
+    }
+
+    /// Revoke all permissions granted in this context, including guarded ones.
+    pub async fn reset_permissions(&self) -> Result<(), CdpError> {
+        self.permissions.reset().await
+    }
+
+    /// Guarded permission grants scoped to this context. Every call returns the same
+    /// shared registry, so releasing a guard keeps the context-level grants and the
+    /// grants of all other guards.
+    pub fn permissions(&self) -> Permissions {
+        self.permissions.clone()
+    }
+
+    /// Dispose the context, closing all of its pages, and wait for Chrome to confirm.
+    pub async fn close(mut self) -> Result<(), CdpError> {
+        self.disposed = true;
+        self.release_downloads();
+        target::methods::DisposeBrowserContext::new(self.id.clone())
+            .send(&self.cdp)
+            .await
+    }
+
+    /// Disposing the context discards its download behavior; skip restoring it.
+    fn release_downloads(&mut self) {
+        if let Some(mut downloads) = self.downloads.take() {
+            downloads.skip_restore();
+        }
+    }
+}
+
+impl Drop for BrowserContext {
+    fn drop(&mut self) {
+        if self.disposed {
+            return;
+        }
+        self.release_downloads();
+        let cdp = self.cdp.clone();
+        let id = self.id.clone();
+        spawn_cleanup(async move {
+            if let Err(e) = target::methods::DisposeBrowserContext::new(id.clone())
+                .send(&cdp)
+                .await
+            {
+                warn!(context = %id, error = %e, "Failed to dispose browser context");
+            }
+        });
+    }
+}
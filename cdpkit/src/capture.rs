@@ -0,0 +1,328 @@
+use crate::dom::types::{BackendNodeId, RGBA};
+use crate::encoding::decode_base64;
+use crate::io_stream::read_chunk;
+use crate::page::types::Viewport;
+use crate::{
+    dom, element, emulation, io, page, BoundingBox, CdpError, DeviceMetrics, EmulationOverrides,
+    OverrideGuard, Page, Sender,
+};
+use std::path::Path;
+use tokio::io::AsyncWriteExt;
+use tracing::warn;
+
+/// Image encoding for [`Page::screenshot`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ScreenshotFormat {
+    #[default]
+    Png,
+    Jpeg,
+    Webp,
+}
+
+impl ScreenshotFormat {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Jpeg => "jpeg",
+            Self::Webp => "webp",
+        }
+    }
+}
+
+/// Options for [`Page::screenshot`].
+///
+/// `element`, `clip` and `full_page` are mutually exclusive, in that order of precedence;
+/// with none of them the current viewport is captured.
+#[derive(Debug, Clone, Default)]
+pub struct ScreenshotOptions {
+    pub format: ScreenshotFormat,
+    /// Compression quality 0–100; ignored for PNG.
+    pub quality: Option<i64>,
+    /// Capture the whole scrollable page instead of the viewport.
+    pub full_page: bool,
+    /// Region to capture, in CSS pixels relative to the document.
+    pub clip: Option<BoundingBox>,
+    /// Render with a transparent default background (PNG and WebP).
+    pub omit_background: bool,
+    /// Capture only this element, e.g. [`ElementHandle::backend_node_id`](crate::ElementHandle::backend_node_id).
+    pub element: Option<BackendNodeId>,
+    /// Overrides of the captured page's session. A full-page capture pushes its device
+    /// metrics override onto them, keeping the pixel ratio and mobile flag in effect,
+    /// and restores the previous override afterwards instead of clearing it.
+    pub emulation: Option<EmulationOverrides>,
+}
+
+/// Options for [`Page::pdf`]. Unset fields use Chrome's defaults (US Letter, 0.4in margins).
+///
+/// Paper sizes and margins are in inches.
+#[derive(Debug, Clone, Default)]
+pub struct PdfOptions {
+    pub landscape: Option<bool>,
+    pub display_header_footer: Option<bool>,
+    pub print_background: Option<bool>,
+    pub scale: Option<f64>,
+    pub paper_width: Option<f64>,
+    pub paper_height: Option<f64>,
+    pub margin_top: Option<f64>,
+    pub margin_bottom: Option<f64>,
+    pub margin_left: Option<f64>,
+    pub margin_right: Option<f64>,
+    /// Pages to print, e.g. `"1-5, 8, 11-13"`.
+    pub page_ranges: Option<String>,
+    pub header_template: Option<String>,
+    pub footer_template: Option<String>,
+    pub prefer_css_page_size: Option<bool>,
+    pub generate_tagged_pdf: Option<bool>,
+    pub generate_document_outline: Option<bool>,
+}
+
+impl PdfOptions {
+    fn command(self) -> page::methods::PrintToPdf {
+        let mut cmd = page::methods::PrintToPdf::new().with_transfer_mode("ReturnAsStream");
+        cmd.landscape = self.landscape;
+        cmd.display_header_footer = self.display_header_footer;
+        cmd.print_background = self.print_background;
+        cmd.scale = self.scale;
+        cmd.paper_width = self.paper_width;
+        cmd.paper_height = self.paper_height;
+        cmd.margin_top = self.margin_top;
+        cmd.margin_bottom = self.margin_bottom;
+        cmd.margin_left = self.margin_left;
+        cmd.margin_right = self.margin_right;
+        cmd.page_ranges = self.page_ranges;
+        cmd.header_template = self.header_template;
+        cmd.footer_template = self.footer_template;
+        cmd.prefer_css_page_size = self.prefer_css_page_size;
+        cmd.generate_tagged_pdf = self.generate_tagged_pdf;
+        cmd.generate_document_outline = self.generate_document_outline;
+        cmd
+    }
+}
+
+/// The device metrics override a full-page capture sets, undone once it is taken.
+enum ScopedMetrics {
+    /// Set directly on the target; cleared afterwards.
+    Clear,
+    /// Pushed onto [`ScreenshotOptions::emulation`]; restores the previous override.
+    Guard(OverrideGuard),
+}
+
+fn viewport(clip: BoundingBox) -> Viewport {
+    Viewport {
+        x: clip.x,
+        y: clip.y,
+        width: clip.width,
+        height: clip.height,
+        scale: 1.0,
+    }
+}
+
+impl<S: Sender + Sync> Page<'_, S> {
+    /// Capture a screenshot and return the encoded image.
+    ///
+    /// A full-page capture temporarily overrides the device metrics with the content size
+    /// from `Page.getLayoutMetrics`. With [`ScreenshotOptions::emulation`] the override is
+    /// scoped through that stack and the previous metrics are re-applied afterwards;
+    /// without it the override is cleared, which also clears device emulation set before.
+    pub async fn screenshot(&self, options: ScreenshotOptions) -> Result<Vec<u8>, CdpError> {
+        let target = self.target();
+        if options.omit_background {
+            emulation::methods::SetDefaultBackgroundColorOverride::new()
+                .with_color(RGBA {
+                    r: 0,
+                    g: 0,
+                    b: 0,
+                    a: Some(0.0),
+                })
+                .send(target)
+                .await?;
+        }
+
+        let mut metrics = None;
+        let result = self.capture(&options, &mut metrics).await;
+
+        match metrics {
+            Some(ScopedMetrics::Guard(guard)) => {
+                if let Err(e) = guard.restore().await {
+                    warn!(error = %e, "Failed to restore device metrics after screenshot");
+                }
+            }
+            Some(ScopedMetrics::Clear) => {
+                if let Err(e) = emulation::methods::ClearDeviceMetricsOverride::new()
+                    .send(target)
+                    .await
+                {
+                    warn!(error = %e, "Failed to clear device metrics override after screenshot");
+                }
+            }
+            None => {}
+        }
+        if options.omit_background {
+            if let Err(e) = emulation::methods::SetDefaultBackgroundColorOverride::new()
+                .send(target)
+                .await
+            {
+                warn!(error = %e, "Failed to restore default background after screenshot");
+            }
+        }
+        result
+    }
+
+    /// Capture a screenshot and write it to `path`.
+    pub async fn save_screenshot(
+        &self,
+        path: impl AsRef<Path>,
+        options: ScreenshotOptions,
+    ) -> Result<(), CdpError> {
+        let image = self.screenshot(options).await?;
+        tokio::fs::write(path, image)
+            .await
+            .map_err(|e| CdpError::Io(e.to_string()))
+    }
+
+    async fn capture(
+        &self,
+        options: &ScreenshotOptions,
+        scoped_metrics: &mut Option<ScopedMetrics>,
+    ) -> Result<Vec<u8>, CdpError> {
+        let target = self.target();
+        let clip = if let Some(backend_node_id) = options.element {
+            dom::methods::ScrollIntoViewIfNeeded::new()
+                .with_backend_node_id(backend_node_id)
+                .send(target)
+                .await?;
+            let bounds = element::border_box(target, backend_node_id)
+                .await?
+                .ok_or(CdpError::ElementNotVisible)?;
+            // Box model quads are viewport-relative; clips are document-relative.
+            let metrics = page::methods::GetLayoutMetrics::new().send(target).await?;
+            Some(BoundingBox {
+                x: bounds.x + metrics.css_visual_viewport.page_x,
+                y: bounds.y + metrics.css_visual_viewport.page_y,
+                ..bounds
+            })
+        } else if let Some(clip) = options.clip {
+            Some(clip)
+        } else if options.full_page {
+            let metrics = page::methods::GetLayoutMetrics::new().send(target).await?;
+            let width = metrics.css_content_size.width.ceil();
+            let height = metrics.css_content_size.height.ceil();
+            *scoped_metrics = Some(match &options.emulation {
+                Some(overrides) => ScopedMetrics::Guard(
+                    overrides
+                        .push_device_metrics(|current| DeviceMetrics {
+                            width: width as i64,
+                            height: height as i64,
+                            ..current.unwrap_or(DeviceMetrics::new(0, 0))
+                        })
+                        .await?,
+                ),
+                None => {
+                    emulation::methods::SetDeviceMetricsOverride::new(
+                        width as i64,
+                        height as i64,
+                        0.0,
+                        false,
+                    )
+                    .send(target)
+                    .await?;
+                    ScopedMetrics::Clear
+                }
+            });
+            Some(BoundingBox {
+                x: 0.0,
+                y: 0.0,
+                width,
+                height,
+            })
+        } else {
+            None
+        };
+
+        let mut cmd = page::methods::CaptureScreenshot::new().with_format(options.format.as_str());
+        if let (Some(quality), false) = (options.quality, options.format == ScreenshotFormat::Png) {
+            cmd = cmd.with_quality(quality);
+        }
+        if let Some(clip) = clip {
+            cmd = cmd
+                .with_clip(viewport(clip))
+                .with_capture_beyond_viewport(true);
+        }
+        decode_base64(&cmd.send(target).await?.data)
+    }
+
+    /// Print the page to PDF and return the document bytes.
+    ///
+    /// The PDF is transferred with `ReturnAsStream` and read in chunks with `IO.read`, so
+    /// large documents are not sent as one JSON message.
+    pub async fn pdf(&self, options: PdfOptions) -> Result<Vec<u8>, CdpError> {
+        let mut pdf = Vec::new();
+        self.print(options, |chunk| {
+            pdf.extend_from_slice(&chunk);
+            async { Ok(()) }
+        })
+        .await?;
+        Ok(pdf)
+    }
+
+    /// Print the page to PDF, streaming it into a file. Returns the number of bytes written.
+    pub async fn save_pdf(
+        &self,
+        path: impl AsRef<Path>,
+        options: PdfOptions,
+    ) -> Result<u64, CdpError> {
+        let file = tokio::fs::File::create(path)
+            .await
+            .map_err(|e| CdpError::Io(e.to_string()))?;
+        let file = std::sync::Arc::new(tokio::sync::Mutex::new(file));
+        let mut written = 0u64;
+        self.print(options, |chunk| {
+            written += chunk.len() as u64;
+            let file = std::sync::Arc::clone(&file);
+            async move {
+                file.lock()
+                    .await
+                    .write_all(&chunk)
+                    .await
+                    .map_err(|e| CdpError::Io(e.to_string()))
+            }
+        })
+        .await?;
+        file.lock()
+            .await
+            .flush()
+            .await
+            .map_err(|e| CdpError::Io(e.to_string()))?;
+        Ok(written)
+    }
+
+    async fn print<F, Fut>(&self, options: PdfOptions, mut on_chunk: F) -> Result<(), CdpError>
+    where
+        F: FnMut(Vec<u8>) -> Fut,
+        Fut: std::future::Future<Output = Result<(), CdpError>>,
+    {
+        let target = self.target();
+        let response = options.command().send(target).await?;
+        let Some(handle) = response.stream else {
+            // Chrome ignored `transferMode`; the whole PDF came back inline.
+            return on_chunk(decode_base64(&response.data)?).await;
+        };
+
+        let result = async {
+            loop {
+                let (chunk, eof) = read_chunk(target, &handle, None).await?;
+                if !chunk.is_empty() {
+                    on_chunk(chunk).await?;
+                }
+                if eof {
+                    return Ok(());
+                }
+            }
+        }
+        .await;
+        if let Err(e) = io::methods::Close::new(handle).send(target).await {
+            warn!(error = %e, "Failed to close PDF stream");
+        }
+        result
+    }
+}
@@ -0,0 +1,658 @@
+use crate::log::events::EntryAdded;
+use crate::log::types::LogEntry;
+use crate::runtime::events::{ConsoleApiCalled, ExceptionThrown};
+use crate::runtime::types::{ExceptionDetails, ObjectPreview, RemoteObject, StackTrace};
+use crate::{decode_event, log, merged_event_stream, runtime, CdpError, OwnedSession};
+use futures::StreamExt;
+use std::fmt;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+
+const CONSOLE_EVENTS: &[&str] = &[
+    "Runtime.consoleAPICalled",
+    "Runtime.exceptionThrown",
+    "Log.entryAdded",
+];
+
+/// Severity of a console message, ordered from least to most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ConsoleLevel {
+    Verbose,
+    Info,
+    Warning,
+    Error,
+}
+
+impl ConsoleLevel {
+    fn from_console_type(type_: &str) -> Self {
+        match type_ {
+            "error" | "assert" => Self::Error,
+            "warning" => Self::Warning,
+            "debug" => Self::Verbose,
+            _ => Self::Info,
+        }
+    }
+
+    fn from_log_level(level: &str) -> Self {
+        match level {
+            "error" => Self::Error,
+            "warning" => Self::Warning,
+            "verbose" => Self::Verbose,
+            _ => Self::Info,
+        }
+    }
+}
+
+impl fmt::Display for ConsoleLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Verbose => "verbose",
+            Self::Info => "info",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        })
+    }
+}
+
+/// Which event produced a [`ConsoleMessage`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConsoleSource {
+    /// `console.*` call (`Runtime.consoleAPICalled`).
+    ConsoleApi,
+    /// Uncaught exception or unhandled rejection (`Runtime.exceptionThrown`).
+    Exception,
+    /// Browser-generated entry such as a network or intervention message (`Log.entryAdded`).
+    Log,
+}
+
+/// Script position of a console message. Line and column are zero-based, as in CDP.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub url: String,
+    pub line_number: i64,
+    pub column_number: Option<i64>,
+}
+
+/// One console API call, uncaught exception or log entry.
+#[derive(Debug, Clone)]
+pub struct ConsoleMessage {
+    pub source: ConsoleSource,
+    /// Console call type (`log`, `table`, ...), log entry source (`network`, ...), or
+    /// `exception`.
+    pub kind: String,
+    pub level: ConsoleLevel,
+    /// Arguments rendered the way the DevTools console shows them.
+    pub text: String,
+    /// Raw console call arguments, for inspecting objects by `object_id`.
+    pub args: Vec<RemoteObject>,
+    pub location: Option<SourceLocation>,
+    pub stack_trace: Option<StackTrace>,
+    /// Milliseconds since the Unix epoch.
+    pub timestamp: f64,
+}
+
+impl ConsoleMessage {
+    fn from_console_api(event: ConsoleApiCalled) -> Self {
+        Self {
+            source: ConsoleSource::ConsoleApi,
+            level: ConsoleLevel::from_console_type(&event.type_),
+            text: format_console_args(&event.args),
+            location: event.stack_trace.as_ref().and_then(top_frame_location),
+            kind: event.type_,
+            args: event.args,
+            stack_trace: event.stack_trace,
+            timestamp: event.timestamp,
+        }
+    }
+
+    fn from_exception(event: ExceptionThrown) -> Self {
+        let details = event.exception_details;
+        let location = match &details.url {
+            Some(url) => Some(SourceLocation {
+                url: url.clone(),
+                line_number: details.line_number,
+                column_number: Some(details.column_number),
+            }),
+            None => details.stack_trace.as_ref().and_then(top_frame_location),
+        };
+        Self {
+            source: ConsoleSource::Exception,
+            kind: "exception".to_string(),
+            level: ConsoleLevel::Error,
+            text: exception_text(&details),
+            args: details.exception.clone().into_iter().collect(),
+            location,
+            stack_trace: details.stack_trace,
+            timestamp: event.timestamp,
+        }
+    }
+
+    fn from_log_entry(entry: LogEntry) -> Self {
+        let location = entry.url.clone().map(|url| SourceLocation {
+            url,
+            line_number: entry.line_number.unwrap_or_default(),
+            column_number: None,
+        });
+        Self {
+            source: ConsoleSource::Log,
+            level: ConsoleLevel::from_log_level(&entry.level),
+            kind: entry.source,
+            text: entry.text,
+            args: entry.args.unwrap_or_default(),
+            location: location.or_else(|| entry.stack_trace.as_ref().and_then(top_frame_location)),
+            stack_trace: entry.stack_trace,
+            timestamp: entry.timestamp,
+        }
+    }
+}
+
+impl fmt::Display for ConsoleMessage {
+    /// `[level] text (url:line:column)`, with one-based line and column.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}", self.level, self.text)?;
+        if let Some(location) = &self.location {
+            write!(f, " ({}:{}", location.url, location.line_number + 1)?;
+            if let Some(column) = location.column_number {
+                write!(f, ":{}", column + 1)?;
+            }
+            f.write_str(")")?;
+        }
+        Ok(())
+    }
+}
+
+fn top_frame_location(stack: &StackTrace) -> Option<SourceLocation> {
+    let frame = stack.call_frames.first()?;
+    Some(SourceLocation {
+        url: frame.url.clone(),
+        line_number: frame.line_number,
+        column_number: Some(frame.column_number),
+    })
+}
+
+/// `Uncaught Error: boom` — the details text followed by the first line of the exception.
+fn exception_text(details: &ExceptionDetails) -> String {
+    let Some(exception) = &details.exception else {
+        return details.text.clone();
+    };
+    let rendered = render_top_level(exception);
+    let rendered = rendered.lines().next().unwrap_or_default();
+    if details.text.is_empty() || details.text == rendered {
+        rendered.to_string()
+    } else {
+        format!("{} {}", details.text, rendered)
+    }
+}
+
+/// Render console arguments like DevTools: printf-style substitution from a leading
+/// format string, then every remaining argument separated by spaces.
+pub(crate) fn format_console_args(args: &[RemoteObject]) -> String {
+    let mut parts = Vec::with_capacity(args.len());
+    let mut rest = args;
+    if let Some((first, tail)) = args.split_first() {
+        if let (Some("string"), Some(format)) = (
+            Some(first.type_.as_str()),
+            first.value.as_ref().and_then(|v| v.as_str()),
+        ) {
+            let (text, consumed) = apply_format(format, tail);
+            parts.push(text);
+            rest = &tail[consumed..];
+        }
+    }
+    parts.extend(rest.iter().map(render_top_level));
+    parts.join(" ")
+}
+
+fn apply_format(format: &str, args: &[RemoteObject]) -> (String, usize) {
+    let mut out = String::with_capacity(format.len());
+    let mut consumed = 0;
+    let mut chars = format.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        let Some(&spec) = chars.peek() else {
+            out.push('%');
+            break;
+        };
+        if spec == '%' {
+            chars.next();
+            out.push('%');
+            continue;
+        }
+        if !matches!(spec, 's' | 'd' | 'i' | 'f' | 'o' | 'O' | 'c') {
+            out.push('%');
+            continue;
+        }
+        let Some(arg) = args.get(consumed) else {
+            out.push('%');
+            continue;
+        };
+        chars.next();
+        consumed += 1;
+        match spec {
+            'd' | 'i' => out.push_str(&number_arg(arg, true)),
+            'f' => out.push_str(&number_arg(arg, false)),
+            // CSS styling has no textual representation.
+            'c' => {}
+            _ => out.push_str(&render_top_level(arg)),
+        }
+    }
+    (out, consumed)
+}
+
+fn number_arg(arg: &RemoteObject, integer: bool) -> String {
+    match arg.value.as_ref().and_then(|v| v.as_f64()) {
+        Some(n) if integer => format!("{}", n.trunc()),
+        Some(n) => format!("{n}"),
+        None => "NaN".to_string(),
+    }
+}
+
+/// Render a remote object as a top-level console argument: strings unquoted.
+pub(crate) fn render_top_level(object: &RemoteObject) -> String {
+    match (object.type_.as_str(), &object.value) {
+        ("string", Some(serde_json::Value::String(s))) => s.clone(),
+        _ => render_remote_object(object),
+    }
+}
+
+/// Render a remote object as DevTools shows it inline, e.g. `(2) [1, 'a']` or `{x: 1}`.
+pub(crate) fn render_remote_object(object: &RemoteObject) -> String {
+    match object.type_.as_str() {
+        "undefined" => "undefined".to_string(),
+        "string" => match &object.value {
+            Some(serde_json::Value::String(s)) => format!("'{s}'"),
+            _ => object.description.clone().unwrap_or_default(),
+        },
+        "number" | "boolean" | "bigint" | "symbol" => object
+            .unserializable_value
+            .clone()
+            .or_else(|| object.description.clone())
+            .or_else(|| object.value.as_ref().map(|v| v.to_string()))
+            .unwrap_or_default(),
+        "function" => function_signature(object.description.as_deref().unwrap_or_default()),
+        _ if object.subtype.as_deref() == Some("null") => "null".to_string(),
+        _ => match (&object.preview, object.subtype.as_deref()) {
+            (_, Some("error")) => object.description.clone().unwrap_or_default(),
+            (Some(preview), _) => render_preview(preview),
+            _ => object
+                .description
+                .clone()
+                .or_else(|| object.class_name.clone())
+                .unwrap_or_else(|| "Object".to_string()),
+        },
+    }
+}
+
+/// `ƒ name()` for a function's source text.
+fn function_signature(source: &str) -> String {
+    let head = source.lines().next().unwrap_or_default();
+    let head = head
+        .strip_prefix("async ")
+        .unwrap_or(head)
+        .strip_prefix("function")
+        .map(str::trim_start)
+        .unwrap_or(head);
+    match head.split_once('(') {
+        Some((name, _)) => format!("ƒ {}()", name.trim()),
+        None => format!("ƒ {head}"),
+    }
+}
+
+fn render_preview(preview: &ObjectPreview) -> String {
+    let description = preview.description.as_deref().unwrap_or_default();
+    let overflow = |items: &mut Vec<String>| {
+        if preview.overflow {
+            items.push("…".to_string());
+        }
+    };
+    match preview.subtype.as_deref() {
+        Some("array") | Some("typedarray") => {
+            let mut items: Vec<String> = preview
+                .properties
+                .iter()
+                .map(|p| {
+                    let value = render_property(&p.type_, p.subtype.as_deref(), p.value.as_deref());
+                    if p.name.parse::<usize>().is_ok() {
+                        value
+                    } else {
+                        format!("{}: {value}", p.name)
+                    }
+                })
+                .collect();
+            overflow(&mut items);
+            let length = description
+                .split_once('(')
+                .and_then(|(_, rest)| rest.strip_suffix(')'))
+                .map(|len| format!("({len}) "))
+                .unwrap_or_default();
+            let prefix = if description.starts_with("Array(") {
+                String::new()
+            } else {
+                description
+                    .split('(')
+                    .next()
+                    .unwrap_or_default()
+                    .to_string()
+            };
+            format!("{prefix}{length}[{}]", items.join(", "))
+        }
+        Some("map") | Some("set") | Some("weakmap") | Some("weakset") => {
+            let mut items: Vec<String> = preview
+                .entries
+                .iter()
+                .flatten()
+                .map(|entry| match &entry.key {
+                    Some(key) => format!(
+                        "{} => {}",
+                        render_entry_preview(key),
+                        render_entry_preview(&entry.value)
+                    ),
+                    None => render_entry_preview(&entry.value),
+                })
+                .collect();
+            overflow(&mut items);
+            format!("{description} {{{}}}", items.join(", "))
+        }
+        Some("error") | Some("node") | Some("regexp") | Some("date") => description.to_string(),
+        _ if preview.type_ != "object" => render_property(
+            &preview.type_,
+            preview.subtype.as_deref(),
+            Some(description),
+        ),
+        _ => {
+            let mut items: Vec<String> = preview
+                .properties
+                .iter()
+                .map(|p| {
+                    format!(
+                        "{}: {}",
+                        p.name,
+                        render_property(&p.type_, p.subtype.as_deref(), p.value.as_deref())
+                    )
+                })
+                .collect();
+            overflow(&mut items);
+            let body = format!("{{{}}}", items.join(", "));
+            if description.is_empty() || description == "Object" {
+                body
+            } else {
+                format!("{description} {body}")
+            }
+        }
+    }
+}
+
+/// Nested values inside a preview are abbreviated: `{…}` for objects, `ƒ` for functions.
+fn render_property(type_: &str, subtype: Option<&str>, value: Option<&str>) -> String {
+    let value = value.unwrap_or_default();
+    match (type_, subtype) {
+        ("string", _) => format!("'{value}'"),
+        ("function", _) => "ƒ".to_string(),
+        ("object", Some("null")) => "null".to_string(),
+        ("object", None) if value == "Object" || value.is_empty() => "{…}".to_string(),
+        ("undefined", _) => "undefined".to_string(),
+        _ => value.to_string(),
+    }
+}
+
+fn render_entry_preview(preview: &ObjectPreview) -> String {
+    if preview.type_ == "object" && preview.subtype.as_deref() != Some("null") {
+        return render_preview(preview);
+    }
+    render_property(
+        &preview.type_,
+        preview.subtype.as_deref(),
+        preview.description.as_deref(),
+    )
+}
+
+/// Configuration for [`ConsoleCollector::start`].
+#[derive(Debug, Clone, Default)]
+pub struct ConsoleOptions {
+    fail_on_uncaught_exception: bool,
+}
+
+impl ConsoleOptions {
+    /// Collect everything and never fail.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Yield `Err(CdpError::JavaScriptException)` from the stream for uncaught exceptions.
+    pub fn with_fail_on_uncaught_exception(mut self, fail: bool) -> Self {
+        self.fail_on_uncaught_exception = fail;
+        self
+    }
+}
+
+/// Collects console output, uncaught exceptions and browser log entries from a session.
+///
+/// Merges `Runtime.consoleAPICalled`, `Runtime.exceptionThrown` and `Log.entryAdded` into
+/// one ordered stream of [`ConsoleMessage`]s, and keeps every message for later
+/// inspection with [`messages`](Self::messages). `Runtime.enable` and `Log.enable` are
+/// sent on start; Chrome then replays messages logged before that.
+///
+/// # Example
+/// ```no_run
+/// # use cdpkit::{ConsoleCollector, ConsoleOptions, OwnedSession};
+/// # use futures::StreamExt;
+/// # async fn example(session: OwnedSession) -> Result<(), cdpkit::CdpError> {
+/// let options = ConsoleOptions::new().with_fail_on_uncaught_exception(true);
+/// let mut console = ConsoleCollector::start(&session, options).await?;
+/// while let Some(message) = console.next().await {
+///     println!("{}", message?);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct ConsoleCollector {
+    messages: Arc<Mutex<Vec<ConsoleMessage>>>,
+    receiver: mpsc::UnboundedReceiver<ConsoleMessage>,
+    fail_on_uncaught_exception: bool,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl ConsoleCollector {
+    /// Subscribe to console events and enable the `Runtime` and `Log` domains.
+    pub async fn start(session: &OwnedSession, options: ConsoleOptions) -> Result<Self, CdpError> {
+        let mut events = merged_event_stream(session, CONSOLE_EVENTS);
+        runtime::methods::Enable::new().send(session).await?;
+        log::methods::Enable::new().send(session).await?;
+
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        let (tx, receiver) = mpsc::unbounded_channel();
+        let history = Arc::clone(&messages);
+        let task = tokio::spawn(async move {
+            while let Some((name, params)) = events.next().await {
+                let message = match &*name {
+                    "Runtime.consoleAPICalled" => {
+                        decode_event(&name, &params).map(ConsoleMessage::from_console_api)
+                    }
+                    "Runtime.exceptionThrown" => {
+                        decode_event(&name, &params).map(ConsoleMessage::from_exception)
+                    }
+                    "Log.entryAdded" => decode_event::<EntryAdded>(&name, &params)
+                        .map(|event| ConsoleMessage::from_log_entry(event.entry)),
+                    _ => None,
+                };
+                if let Some(message) = message {
+                    history
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .push(message.clone());
+                    let _ = tx.send(message);
+                }
+            }
+        });
+
+        Ok(Self {
+            messages,
+            receiver,
+            fail_on_uncaught_exception: options.fail_on_uncaught_exception,
+            task,
+        })
+    }
+
+    /// Every message collected so far, in arrival order.
+    pub fn messages(&self) -> Vec<ConsoleMessage> {
+        self.messages
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Collected messages at `ConsoleLevel::Error`, including uncaught exceptions.
+    pub fn errors(&self) -> Vec<ConsoleMessage> {
+        self.filtered(|m| m.level == ConsoleLevel::Error)
+    }
+
+    /// Collected uncaught exceptions.
+    pub fn uncaught_exceptions(&self) -> Vec<ConsoleMessage> {
+        self.filtered(|m| m.source == ConsoleSource::Exception)
+    }
+
+    /// Forget collected messages. Messages not yet read from the stream are kept there.
+    pub fn clear(&self) {
+        self.messages
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+
+    /// Fail with the first uncaught exception collected so far, if any.
+    pub fn assert_no_uncaught_exceptions(&self) -> Result<(), CdpError> {
+        match self.uncaught_exceptions().into_iter().next() {
+            Some(exception) => Err(CdpError::JavaScriptException(exception.text)),
+            None => Ok(()),
+        }
+    }
+
+    fn filtered(&self, keep: impl Fn(&ConsoleMessage) -> bool) -> Vec<ConsoleMessage> {
+        self.messages
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .filter(|m| keep(m))
+            .cloned()
+            .collect()
+    }
+}
+
+impl futures::Stream for ConsoleCollector {
+    type Item = Result<ConsoleMessage, CdpError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let fail = self.fail_on_uncaught_exception;
+        self.receiver.poll_recv(cx).map(|message| {
+            message.map(|message| {
+                if fail && message.source == ConsoleSource::Exception {
+                    Err(CdpError::JavaScriptException(message.text))
+                } else {
+                    Ok(message)
+                }
+            })
+        })
+    }
+}
+
+impl Drop for ConsoleCollector {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn remote(value: serde_json::Value) -> RemoteObject {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn substitutes_format_specifiers() {
+        let args = vec![
+            remote(json!({"type": "string", "value": "%s has %d items%c (%o) 100%%"})),
+            remote(json!({"type": "string", "value": "cart"})),
+            remote(json!({"type": "number", "value": 3.7, "description": "3.7"})),
+            remote(json!({"type": "string", "value": "color: red"})),
+            remote(json!({"type": "boolean", "value": true, "description": "true"})),
+            remote(json!({"type": "string", "value": "extra"})),
+        ];
+        assert_eq!(
+            format_console_args(&args),
+            "cart has 3 items (true) 100% extra"
+        );
+    }
+
+    #[test]
+    fn renders_array_and_object_previews() {
+        let array = remote(json!({
+            "type": "object", "subtype": "array", "description": "Array(3)",
+            "preview": {
+                "type": "object", "subtype": "array", "description": "Array(3)", "overflow": false,
+                "properties": [
+                    {"name": "0", "type": "number", "value": "1"},
+                    {"name": "1", "type": "string", "value": "a"},
+                    {"name": "2", "type": "object", "value": "Object"}
+                ]
+            }
+        }));
+        assert_eq!(render_remote_object(&array), "(3) [1, 'a', {…}]");
+
+        let object = remote(json!({
+            "type": "object", "className": "Point", "description": "Point",
+            "preview": {
+                "type": "object", "description": "Point", "overflow": true,
+                "properties": [
+                    {"name": "x", "type": "number", "value": "1"},
+                    {"name": "tags", "type": "object", "subtype": "array", "value": "Array(2)"},
+                    {"name": "f", "type": "function", "value": ""}
+                ]
+            }
+        }));
+        assert_eq!(
+            render_remote_object(&object),
+            "Point {x: 1, tags: Array(2), f: ƒ, …}"
+        );
+    }
+
+    #[test]
+    fn renders_maps_primitives_and_functions() {
+        let map = remote(json!({
+            "type": "object", "subtype": "map", "description": "Map(1)",
+            "preview": {
+                "type": "object", "subtype": "map", "description": "Map(1)", "overflow": false,
+                "properties": [],
+                "entries": [{
+                    "key": {"type": "string", "description": "k", "overflow": false, "properties": []},
+                    "value": {"type": "number", "description": "2", "overflow": false, "properties": []}
+                }]
+            }
+        }));
+        assert_eq!(render_remote_object(&map), "Map(1) {'k' => 2}");
+
+        let null = remote(json!({"type": "object", "subtype": "null", "value": null}));
+        let nan =
+            remote(json!({"type": "number", "unserializableValue": "NaN", "description": "NaN"}));
+        let func =
+            remote(json!({"type": "function", "description": "async function load(url) {\n}"}));
+        assert_eq!(format_console_args(&[null, nan, func]), "null NaN ƒ load()");
+    }
+
+    #[test]
+    fn exception_text_uses_first_line_of_description() {
+        let details: ExceptionDetails = serde_json::from_value(json!({
+            "exceptionId": 1, "text": "Uncaught", "lineNumber": 0, "columnNumber": 0,
+            "exception": {"type": "object", "subtype": "error", "description": "Error: boom\n    at x.js:1:1"}
+        }))
+        .unwrap();
+        assert_eq!(exception_text(&details), "Uncaught Error: boom");
+    }
+}
@@ -0,0 +1,770 @@
+use crate::css::events::StyleSheetAdded;
+use crate::css::types::RuleUsage;
+use crate::debugger::events::ScriptParsed;
+use crate::profiler::types::{FunctionCoverage, ScriptCoverage};
+use crate::{
+    css, debugger, decode_event, dom, merged_event_stream, profiler, CdpError, OwnedSession,
+};
+use futures::StreamExt;
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tracing::warn;
+
+/// Configuration for [`Coverage::start`].
+#[derive(Debug, Clone)]
+pub struct CoverageOptions {
+    js: bool,
+    css: bool,
+    detailed: bool,
+    anonymous_scripts: bool,
+}
+
+impl Default for CoverageOptions {
+    fn default() -> Self {
+        Self {
+            js: true,
+            css: true,
+            detailed: true,
+            anonymous_scripts: false,
+        }
+    }
+}
+
+impl CoverageOptions {
+    /// JS block coverage with call counts, plus CSS rule usage.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Collect JavaScript coverage with `Profiler.startPreciseCoverage`.
+    pub fn with_js(mut self, js: bool) -> Self {
+        self.js = js;
+        self
+    }
+
+    /// Collect CSS rule usage with `CSS.startRuleUsageTracking`.
+    pub fn with_css(mut self, css: bool) -> Self {
+        self.css = css;
+        self
+    }
+
+    /// Block-level counts (`detailed: true`); with `false`, only whole functions are
+    /// counted, which is cheaper but marks every line of a called function as covered.
+    pub fn with_detailed(mut self, detailed: bool) -> Self {
+        self.detailed = detailed;
+        self
+    }
+
+    /// Also report scripts without a URL, e.g. from `eval` or `new Function`.
+    pub fn with_anonymous_scripts(mut self, anonymous_scripts: bool) -> Self {
+        self.anonymous_scripts = anonymous_scripts;
+        self
+    }
+}
+
+/// Whether a [`FileCoverage`] is a script or a stylesheet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoverageKind {
+    JavaScript,
+    Css,
+}
+
+/// Hit count of one line (1-based) of the resource at [`FileCoverage::url`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineHits {
+    pub line: u32,
+    pub count: u64,
+}
+
+/// Call count of one function. Lines are 1-based, columns 0-based UTF-16 offsets.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionHits {
+    /// Empty for anonymous functions.
+    pub name: String,
+    pub line: u32,
+    pub column: u32,
+    pub end_line: u32,
+    pub end_column: u32,
+    pub count: u64,
+}
+
+/// Coverage of one script or stylesheet.
+///
+/// Positions are in the resource at `url`, so for inline scripts and styles they count
+/// from the start of the document rather than of `source`.
+#[derive(Debug, Clone)]
+pub struct FileCoverage {
+    pub kind: CoverageKind,
+    /// Script URL or stylesheet `sourceURL`; reports use it as the file path.
+    pub url: String,
+    pub source: String,
+    /// 0-based line and column where `source` starts in the resource at `url`;
+    /// non-zero for inline scripts and styles.
+    pub start_line: u32,
+    pub start_column: u32,
+    /// Lines with code, in order. A line's count is the lowest count of its
+    /// non-whitespace characters, so a partly executed line counts as not covered.
+    pub lines: Vec<LineHits>,
+    /// Functions, excluding the script's top-level code. Empty for stylesheets.
+    pub functions: Vec<FunctionHits>,
+    /// Length of the instrumented code in UTF-16 units (CSS: rule text only).
+    pub total_length: usize,
+    /// Part of `total_length` that ran at least once (CSS: rules that matched).
+    pub used_length: usize,
+}
+
+impl FileCoverage {
+    /// Fraction of lines with a non-zero count, or `None` without instrumented lines.
+    pub fn line_rate(&self) -> Option<f64> {
+        if self.lines.is_empty() {
+            return None;
+        }
+        let hit = self.lines.iter().filter(|l| l.count > 0).count();
+        Some(hit as f64 / self.lines.len() as f64)
+    }
+
+    fn from_counts(
+        kind: CoverageKind,
+        url: String,
+        source: String,
+        counts: &[Option<u64>],
+        functions: Vec<FunctionHits>,
+    ) -> Self {
+        let index = SourceIndex::new(&source);
+        let mut lines = Vec::new();
+        for (i, range) in index.line_ranges().enumerate() {
+            let count = range
+                .filter(|&unit| !index.whitespace[unit])
+                .filter_map(|unit| counts.get(unit).copied().flatten())
+                .min();
+            if let Some(count) = count {
+                lines.push(LineHits {
+                    line: i as u32 + 1,
+                    count,
+                });
+            }
+        }
+        Self {
+            kind,
+            url,
+            start_line: 0,
+            start_column: 0,
+            total_length: counts.iter().filter(|c| c.is_some()).count(),
+            used_length: counts
+                .iter()
+                .filter(|c| matches!(c, Some(n) if *n > 0))
+                .count(),
+            source,
+            lines,
+            functions,
+        }
+    }
+
+    fn from_script(script: &ScriptCoverage, source: String) -> Self {
+        let index = SourceIndex::new(&source);
+        let len = index.whitespace.len();
+        let mut counts = vec![None; len];
+
+        // V8 ranges nest: each function's first range spans the function and later ranges
+        // mark blocks inside it. Painting outer ranges first lets inner ones win.
+        let mut ranges: Vec<_> = script.functions.iter().flat_map(|f| &f.ranges).collect();
+        ranges.sort_by_key(|r| (r.start_offset, std::cmp::Reverse(r.end_offset)));
+        for range in ranges {
+            let start = (range.start_offset.max(0) as usize).min(len);
+            let end = (range.end_offset.max(0) as usize).clamp(start, len);
+            counts[start..end].fill(Some(range.count.max(0) as u64));
+        }
+
+        let functions = script
+            .functions
+            .iter()
+            .filter(|f| !is_top_level(f, len))
+            .filter_map(|f| {
+                let range = f.ranges.first()?;
+                let (line, column) = index.position(range.start_offset.max(0) as usize);
+                let (end_line, end_column) = index.position(range.end_offset.max(0) as usize);
+                Some(FunctionHits {
+                    name: f.function_name.clone(),
+                    line,
+                    column,
+                    end_line,
+                    end_column,
+                    count: range.count.max(0) as u64,
+                })
+            })
+            .collect();
+        Self::from_counts(
+            CoverageKind::JavaScript,
+            script.url.clone(),
+            source,
+            &counts,
+            functions,
+        )
+    }
+
+    /// Move positions from the start of `source` to where it starts in its resource.
+    fn starting_at(mut self, line: u32, column: u32) -> Self {
+        for hits in &mut self.lines {
+            hits.line += line;
+        }
+        for function in &mut self.functions {
+            if function.line == 1 {
+                function.column += column;
+            }
+            if function.end_line == 1 {
+                function.end_column += column;
+            }
+            function.line += line;
+            function.end_line += line;
+        }
+        self.start_line = line;
+        self.start_column = column;
+        self
+    }
+
+    fn from_rules(url: String, source: String, rules: &[&RuleUsage]) -> Self {
+        let len = SourceIndex::new(&source).whitespace.len();
+        let mut counts = vec![None; len];
+        for rule in rules {
+            let start = (rule.start_offset.max(0.0) as usize).min(len);
+            let end = (rule.end_offset.max(0.0) as usize).clamp(start, len);
+            counts[start..end].fill(Some(u64::from(rule.used)));
+        }
+        Self::from_counts(CoverageKind::Css, url, source, &counts, Vec::new())
+    }
+}
+
+/// The anonymous function V8 reports for a script's top-level code.
+fn is_top_level(function: &FunctionCoverage, len: usize) -> bool {
+    function.function_name.is_empty()
+        && function
+            .ranges
+            .first()
+            .is_some_and(|r| r.start_offset == 0 && r.end_offset as usize >= len)
+}
+
+/// Maps UTF-16 offsets, which is what V8 and Blink report, to lines and columns.
+struct SourceIndex {
+    /// Offset of the first unit of each line.
+    line_starts: Vec<usize>,
+    /// Per UTF-16 unit: whether it belongs to a whitespace character.
+    whitespace: Vec<bool>,
+}
+
+impl SourceIndex {
+    fn new(source: &str) -> Self {
+        let mut line_starts = vec![0];
+        let mut whitespace = Vec::with_capacity(source.len());
+        for c in source.chars() {
+            for _ in 0..c.len_utf16() {
+                whitespace.push(c.is_whitespace());
+            }
+            if c == '\n' {
+                line_starts.push(whitespace.len());
+            }
+        }
+        Self {
+            line_starts,
+            whitespace,
+        }
+    }
+
+    fn line_ranges(&self) -> impl Iterator<Item = std::ops::Range<usize>> + '_ {
+        let ends = self.line_starts[1..]
+            .iter()
+            .copied()
+            .chain(std::iter::once(self.whitespace.len()));
+        self.line_starts
+            .iter()
+            .copied()
+            .zip(ends)
+            .map(|(s, e)| s..e)
+    }
+
+    /// 1-based line and 0-based column of `offset`.
+    fn position(&self, offset: usize) -> (u32, u32) {
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        (line as u32 + 1, (offset - self.line_starts[line]) as u32)
+    }
+}
+
+/// Coverage of all scripts and stylesheets seen during a [`Coverage`] run.
+#[derive(Debug, Clone, Default)]
+pub struct CoverageReport {
+    pub files: Vec<FileCoverage>,
+}
+
+impl CoverageReport {
+    /// Files grouped by report path: everything that shares a URL, such as the inline
+    /// scripts and styles of one document, is one entry. Files without a URL each get
+    /// their own `anonymous-<n>` entry.
+    fn entries(&self) -> Vec<(String, Vec<&FileCoverage>)> {
+        let mut entries: Vec<(String, Vec<&FileCoverage>)> = Vec::new();
+        let mut anonymous = 0;
+        for file in &self.files {
+            if file.url.is_empty() {
+                anonymous += 1;
+                entries.push((format!("anonymous-{anonymous}"), vec![file]));
+                continue;
+            }
+            match entries.iter_mut().find(|(path, _)| *path == file.url) {
+                Some((_, files)) => files.push(file),
+                None => entries.push((file.url.clone(), vec![file])),
+            }
+        }
+        entries
+    }
+
+    /// LCOV tracefile (`SF`/`FN`/`FNDA`/`DA` records), as read by `genhtml` and most
+    /// coverage services. Files sharing a URL are merged into one record.
+    pub fn to_lcov(&self) -> String {
+        let mut out = String::new();
+        for (path, files) in self.entries() {
+            let _ = writeln!(out, "TN:\nSF:{path}");
+            let functions: Vec<_> = files.iter().flat_map(|f| &f.functions).collect();
+            for (i, function) in functions.iter().enumerate() {
+                let _ = writeln!(out, "FN:{},{}", function.line, function_name(function, i));
+            }
+            for (i, function) in functions.iter().enumerate() {
+                let _ = writeln!(
+                    out,
+                    "FNDA:{},{}",
+                    function.count,
+                    function_name(function, i)
+                );
+            }
+            let functions_hit = functions.iter().filter(|f| f.count > 0).count();
+            let _ = writeln!(out, "FNF:{}\nFNH:{}", functions.len(), functions_hit);
+            // A line shared by two files counts as covered only if both ran it.
+            let mut lines = BTreeMap::new();
+            for hits in files.iter().flat_map(|f| &f.lines) {
+                lines
+                    .entry(hits.line)
+                    .and_modify(|count: &mut u64| *count = (*count).min(hits.count))
+                    .or_insert(hits.count);
+            }
+            for (line, count) in &lines {
+                let _ = writeln!(out, "DA:{line},{count}");
+            }
+            let lines_hit = lines.values().filter(|&&count| count > 0).count();
+            let _ = writeln!(out, "LF:{}\nLH:{}\nend_of_record", lines.len(), lines_hit);
+        }
+        out
+    }
+
+    /// Istanbul `coverage-final.json` object keyed by URL, with one statement per line
+    /// of each file. Files sharing a URL are merged into one entry.
+    pub fn to_istanbul(&self) -> Value {
+        let mut entries = Map::new();
+        for (path, files) in self.entries() {
+            let mut statement_map = Map::new();
+            let mut statements = Map::new();
+            for file in &files {
+                let index = SourceIndex::new(&file.source);
+                let line_ranges: Vec<_> = index.line_ranges().collect();
+                for line in &file.lines {
+                    let source_line = line.line.saturating_sub(file.start_line) as usize;
+                    let length = source_line
+                        .checked_sub(1)
+                        .and_then(|i| line_ranges.get(i))
+                        .map_or(0, |r| line_length(&index, r.clone()));
+                    let column = if source_line == 1 {
+                        file.start_column
+                    } else {
+                        0
+                    };
+                    let id = statements.len().to_string();
+                    statement_map.insert(
+                        id.clone(),
+                        location(line.line, column, line.line, column + length as u32),
+                    );
+                    statements.insert(id, json!(line.count));
+                }
+            }
+            let mut fn_map = Map::new();
+            let mut functions = Map::new();
+            let all_functions = files.iter().flat_map(|f| &f.functions);
+            for (i, function) in all_functions.enumerate() {
+                let loc = location(
+                    function.line,
+                    function.column,
+                    function.end_line,
+                    function.end_column,
+                );
+                fn_map.insert(
+                    i.to_string(),
+                    json!({
+                        "name": function_name(function, i),
+                        "decl": loc,
+                        "loc": loc,
+                        "line": function.line,
+                    }),
+                );
+                functions.insert(i.to_string(), json!(function.count));
+            }
+            entries.insert(
+                path.clone(),
+                json!({
+                    "path": path,
+                    "statementMap": statement_map,
+                    "fnMap": fn_map,
+                    "branchMap": {},
+                    "s": statements,
+                    "f": functions,
+                    "b": {},
+                }),
+            );
+        }
+        Value::Object(entries)
+    }
+
+    /// Write [`to_lcov`](Self::to_lcov) to a file, conventionally `lcov.info`.
+    pub async fn save_lcov(&self, path: impl AsRef<Path>) -> Result<(), CdpError> {
+        tokio::fs::write(path, self.to_lcov())
+            .await
+            .map_err(|e| CdpError::Io(e.to_string()))
+    }
+
+    /// Write [`to_istanbul`](Self::to_istanbul) to a file, conventionally
+    /// `coverage-final.json`.
+    pub async fn save_istanbul(&self, path: impl AsRef<Path>) -> Result<(), CdpError> {
+        let json = serde_json::to_string_pretty(&self.to_istanbul())?;
+        tokio::fs::write(path, json)
+            .await
+            .map_err(|e| CdpError::Io(e.to_string()))
+    }
+}
+
+/// Istanbul's naming for anonymous functions.
+fn function_name(function: &FunctionHits, index: usize) -> String {
+    if function.name.is_empty() {
+        format!("(anonymous_{index})")
+    } else {
+        function.name.clone()
+    }
+}
+
+/// Length of a line without its line terminator.
+fn line_length(index: &SourceIndex, range: std::ops::Range<usize>) -> usize {
+    let mut end = range.end;
+    while end > range.start && index.whitespace[end - 1] {
+        end -= 1;
+    }
+    end - range.start
+}
+
+fn location(line: u32, column: u32, end_line: u32, end_column: u32) -> Value {
+    json!({
+        "start": {"line": line, "column": column},
+        "end": {"line": end_line, "column": end_column},
+    })
+}
+
+/// Collects JavaScript and CSS coverage on a page session.
+///
+/// JavaScript coverage comes from `Profiler.startPreciseCoverage` (block coverage with
+/// call counts), CSS coverage from `CSS.startRuleUsageTracking`. [`stop`](Self::stop)
+/// fetches the sources with `Debugger.getScriptSource` and `CSS.getStyleSheetText` and
+/// turns the byte ranges into line and function counts.
+///
+/// # Example
+/// ```no_run
+/// # use cdpkit::{Coverage, CoverageOptions, OwnedSession};
+/// # async fn example(session: OwnedSession) -> Result<(), cdpkit::CdpError> {
+/// let coverage = Coverage::start(&session, CoverageOptions::new()).await?;
+/// // ... exercise the page ...
+/// let report = coverage.stop().await?;
+/// report.save_lcov("coverage/lcov.info").await?;
+/// report.save_istanbul("coverage/coverage-final.json").await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Coverage {
+    session: OwnedSession,
+    options: CoverageOptions,
+    origins: Arc<Mutex<Origins>>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+/// Where scripts and stylesheets start in their resource, from `Debugger.scriptParsed`
+/// and `CSS.styleSheetAdded`.
+#[derive(Default)]
+struct Origins {
+    /// `ScriptId` to start line and column.
+    scripts: HashMap<String, (u32, u32)>,
+    /// `StyleSheetId` to `sourceURL`, start line and column.
+    style_sheets: HashMap<String, (String, u32, u32)>,
+}
+
+impl Coverage {
+    /// Enable the domains involved and start collecting.
+    pub async fn start(session: &OwnedSession, options: CoverageOptions) -> Result<Self, CdpError> {
+        let origins = Arc::new(Mutex::new(Origins::default()));
+        let mut events =
+            merged_event_stream(session, &["Debugger.scriptParsed", "CSS.styleSheetAdded"]);
+        let task = {
+            let origins = origins.clone();
+            tokio::spawn(async move {
+                while let Some((name, params)) = events.next().await {
+                    let mut origins = origins.lock().unwrap_or_else(|e| e.into_inner());
+                    if let Some(event) = decode_event::<ScriptParsed>(&name, &params) {
+                        origins.scripts.insert(
+                            event.script_id,
+                            (event.start_line as u32, event.start_column as u32),
+                        );
+                    } else if let Some(event) = decode_event::<StyleSheetAdded>(&name, &params) {
+                        let header = event.header;
+                        origins.style_sheets.insert(
+                            header.style_sheet_id,
+                            (
+                                header.source_url,
+                                header.start_line as u32,
+                                header.start_column as u32,
+                            ),
+                        );
+                    }
+                }
+            })
+        };
+        let coverage = Self {
+            session: session.clone(),
+            options,
+            origins,
+            task,
+        };
+
+        if coverage.options.js {
+            // Debugger must be enabled for `getScriptSource`.
+            debugger::methods::Enable::new().send(session).await?;
+            profiler::methods::Enable::new().send(session).await?;
+            profiler::methods::StartPreciseCoverage::new()
+                .with_call_count(true)
+                .with_detailed(coverage.options.detailed)
+                .send(session)
+                .await?;
+        }
+        if coverage.options.css {
+            dom::methods::Enable::new().send(session).await?;
+            css::methods::Enable::new().send(session).await?;
+            css::methods::StartRuleUsageTracking::new()
+                .send(session)
+                .await?;
+        }
+        Ok(coverage)
+    }
+
+    /// Stop collecting and build the report. Files whose source can no longer be fetched,
+    /// e.g. after navigating away, are skipped with a warning.
+    pub async fn stop(self) -> Result<CoverageReport, CdpError> {
+        let mut report = CoverageReport::default();
+        if self.options.js {
+            let scripts = profiler::methods::TakePreciseCoverage::new()
+                .send(&self.session)
+                .await?
+                .result;
+            profiler::methods::StopPreciseCoverage::new()
+                .send(&self.session)
+                .await?;
+            for script in scripts {
+                if script.url.is_empty() && !self.options.anonymous_scripts {
+                    continue;
+                }
+                match debugger::methods::GetScriptSource::new(script.script_id.clone())
+                    .send(&self.session)
+                    .await
+                {
+                    Ok(source) => {
+                        let (line, column) = self
+                            .origins
+                            .lock()
+                            .unwrap_or_else(|e| e.into_inner())
+                            .scripts
+                            .get(&script.script_id)
+                            .copied()
+                            .unwrap_or_default();
+                        report.files.push(
+                            FileCoverage::from_script(&script, source.script_source)
+                                .starting_at(line, column),
+                        );
+                    }
+                    Err(e) => warn!(url = %script.url, error = %e, "Failed to fetch script source"),
+                }
+            }
+        }
+        if self.options.css {
+            let usage = css::methods::StopRuleUsageTracking::new()
+                .send(&self.session)
+                .await?
+                .rule_usage;
+            let mut by_sheet: Vec<(String, Vec<&RuleUsage>)> = Vec::new();
+            for rule in &usage {
+                match by_sheet
+                    .iter_mut()
+                    .find(|(id, _)| *id == rule.style_sheet_id)
+                {
+                    Some((_, rules)) => rules.push(rule),
+                    None => by_sheet.push((rule.style_sheet_id.clone(), vec![rule])),
+                }
+            }
+            for (id, rules) in by_sheet {
+                let (url, line, column) = self
+                    .origins
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .style_sheets
+                    .get(&id)
+                    .cloned()
+                    .unwrap_or_default();
+                match css::methods::GetStyleSheetText::new(id)
+                    .send(&self.session)
+                    .await
+                {
+                    Ok(text) => report.files.push(
+                        FileCoverage::from_rules(url, text.text, &rules).starting_at(line, column),
+                    ),
+                    Err(e) => warn!(url = %url, error = %e, "Failed to fetch stylesheet text"),
+                }
+            }
+        }
+        Ok(report)
+    }
+}
+
+impl Drop for Coverage {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profiler::types::CoverageRange;
+
+    fn range(start_offset: i64, end_offset: i64, count: i64) -> CoverageRange {
+        CoverageRange {
+            start_offset,
+            end_offset,
+            count,
+        }
+    }
+
+    fn function(name: &str, ranges: Vec<CoverageRange>) -> FunctionCoverage {
+        FunctionCoverage {
+            function_name: name.to_string(),
+            ranges,
+            is_block_coverage: true,
+        }
+    }
+
+    const SOURCE: &str = "function f(x) {\n  if (x) {\n    return 1;\n  }\n  return 2;\n}\nf(1);\n";
+
+    fn script() -> FileCoverage {
+        let len = SOURCE.len() as i64;
+        let script = ScriptCoverage {
+            script_id: "1".to_string(),
+            url: "https://app.test/main.js".to_string(),
+            functions: vec![
+                function("", vec![range(0, len, 1)]),
+                function("f", vec![range(0, 58, 1), range(45, 57, 0)]),
+            ],
+        };
+        FileCoverage::from_script(&script, SOURCE.to_string())
+    }
+
+    #[test]
+    fn block_ranges_become_line_and_function_counts() {
+        let file = script();
+        let counts: Vec<(u32, u64)> = file.lines.iter().map(|l| (l.line, l.count)).collect();
+        assert_eq!(
+            counts,
+            [(1, 1), (2, 1), (3, 1), (4, 1), (5, 0), (6, 1), (7, 1)]
+        );
+        assert_eq!(
+            file.functions,
+            [FunctionHits {
+                name: "f".to_string(),
+                line: 1,
+                column: 0,
+                end_line: 6,
+                end_column: 1,
+                count: 1,
+            }]
+        );
+        assert!(file.used_length < file.total_length);
+    }
+
+    #[test]
+    fn reports_are_lcov_and_istanbul() {
+        let css = "a { color: red }\n\nb { color: blue }\n";
+        let rules = [
+            RuleUsage {
+                style_sheet_id: "s".to_string(),
+                start_offset: 0.0,
+                end_offset: 16.0,
+                used: true,
+            },
+            RuleUsage {
+                style_sheet_id: "s".to_string(),
+                start_offset: 18.0,
+                end_offset: 35.0,
+                used: false,
+            },
+        ];
+        let report = CoverageReport {
+            files: vec![
+                script(),
+                FileCoverage::from_rules(
+                    "https://app.test/site.css".to_string(),
+                    css.to_string(),
+                    &rules.iter().collect::<Vec<_>>(),
+                ),
+            ],
+        };
+
+        let lcov = report.to_lcov();
+        assert!(
+            lcov.starts_with("TN:\nSF:https://app.test/main.js\nFN:1,f\nFNDA:1,f\nFNF:1\nFNH:1\n")
+        );
+        assert!(lcov.contains("DA:5,0\n"));
+        assert!(lcov.contains("LF:7\nLH:6\nend_of_record\n"));
+        assert!(lcov
+            .contains("SF:https://app.test/site.css\nFNF:0\nFNH:0\nDA:1,1\nDA:3,0\nLF:2\nLH:1\n"));
+
+        let istanbul = report.to_istanbul();
+        let main = &istanbul["https://app.test/main.js"];
+        assert_eq!(main["s"]["4"], 0);
+        assert_eq!(
+            main["statementMap"]["4"],
+            json!({"start": {"line": 5, "column": 0}, "end": {"line": 5, "column": 11}})
+        );
+        assert_eq!(main["fnMap"]["0"]["name"], "f");
+        assert_eq!(main["f"]["0"], 1);
+        assert_eq!(
+            istanbul["https://app.test/site.css"]["s"],
+            json!({"0": 1, "1": 0})
+        );
+    }
+
+    #[test]
+    fn files_without_a_url_are_reported_separately() {
+        let anonymous = || {
+            let script = ScriptCoverage {
+                script_id: "2".to_string(),
+                url: String::new(),
+                functions: vec![function("", vec![range(0, 4, 1)])],
+            };
+            FileCoverage::from_script(&script, "f();".to_string())
+        };
+        let report = CoverageReport {
+            files: vec![anonymous(), anonymous()],
+        };
+        assert_eq!(report.to_lcov().matches("SF:anonymous-").count(), 2);
+        let istanbul = report.to_istanbul();
+        assert_eq!(istanbul["anonymous-1"]["s"], json!({"0": 1}));
+        assert_eq!(istanbul["anonymous-2"]["s"], json!({"0": 1}));
+    }
+}
@@ -0,0 +1,392 @@
+use crate::profiler::types::{Profile, ProfileNode};
+use crate::{profiler, spawn_cleanup, CdpError, Sender};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::Path;
+use std::time::Duration;
+use tracing::warn;
+
+/// Configuration for [`CpuProfiler::start`].
+#[derive(Debug, Clone, Default)]
+pub struct CpuProfilerOptions {
+    sampling_interval: Option<Duration>,
+}
+
+impl CpuProfilerOptions {
+    /// V8's default sampling interval of about 1 ms.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Time between samples; smaller intervals give finer profiles at a higher overhead.
+    pub fn with_sampling_interval(mut self, interval: Duration) -> Self {
+        self.sampling_interval = Some(interval);
+        self
+    }
+}
+
+/// Records a V8 CPU profile with `Profiler.start` / `Profiler.stop`.
+///
+/// Works on any target that speaks the `Profiler` domain: a page session, a worker, or a
+/// Node.js inspector connected with [`CDP::connect_ws`](crate::CDP::connect_ws).
+/// Dropping the profiler without calling [`stop`](Self::stop) stops profiling and
+/// discards the profile.
+///
+/// # Example
+/// ```no_run
+/// # use cdpkit::{CpuProfiler, CpuProfilerOptions, OwnedSession};
+/// # async fn example(session: OwnedSession) -> Result<(), cdpkit::CdpError> {
+/// let profiler = CpuProfiler::start(&session, CpuProfilerOptions::new()).await?;
+/// // ... run the workload ...
+/// let profile = profiler.stop().await?;
+/// profile.save("page.cpuprofile").await?;
+/// for function in profile.top(10) {
+///     println!("{:>8.1?} {}", function.self_time, function.function_name);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct CpuProfiler<S: Sender + Clone + Send + Sync + 'static> {
+    target: S,
+    stopped: bool,
+}
+
+impl<S: Sender + Clone + Send + Sync + 'static> CpuProfiler<S> {
+    /// Enable the `Profiler` domain, apply the sampling interval and start profiling.
+    pub async fn start(target: &S, options: CpuProfilerOptions) -> Result<Self, CdpError> {
+        profiler::methods::Enable::new().send(target).await?;
+        if let Some(interval) = options.sampling_interval {
+            // The interval must be set before `Profiler.start`.
+            profiler::methods::SetSamplingInterval::new(interval.as_micros().max(1) as i64)
+                .send(target)
+                .await?;
+        }
+        profiler::methods::Start::new().send(target).await?;
+        Ok(Self {
+            target: target.clone(),
+            stopped: false,
+        })
+    }
+
+    /// Stop profiling and return the recorded profile.
+    pub async fn stop(mut self) -> Result<CpuProfile, CdpError> {
+        self.stopped = true;
+        let response = profiler::methods::Stop::new().send(&self.target).await?;
+        Ok(CpuProfile::from(response.profile))
+    }
+}
+
+impl<S: Sender + Clone + Send + Sync + 'static> Drop for CpuProfiler<S> {
+    fn drop(&mut self) {
+        if self.stopped {
+            return;
+        }
+        let target = self.target.clone();
+        spawn_cleanup(async move {
+            if let Err(e) = profiler::methods::Stop::new().send(&target).await {
+                warn!(error = %e, "Failed to stop CPU profiler");
+            }
+        });
+    }
+}
+
+/// Time spent in one function, aggregated over all of its call sites.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionTime {
+    /// Empty for anonymous functions; V8 also reports `(program)`, `(idle)` and
+    /// `(garbage collector)` pseudo-functions.
+    pub function_name: String,
+    pub url: String,
+    /// 0-based, as in `Runtime.CallFrame`.
+    pub line_number: i64,
+    pub column_number: i64,
+    /// Time sampled in the function itself.
+    pub self_time: Duration,
+    /// Time sampled in the function or anything it called; recursion is counted once.
+    pub total_time: Duration,
+}
+
+/// A V8 CPU profile with analysis helpers.
+///
+/// Saved as a `.cpuprofile` file, it opens in the DevTools Performance panel,
+/// speedscope and VS Code.
+#[derive(Debug, Clone)]
+pub struct CpuProfile {
+    pub profile: Profile,
+}
+
+impl From<Profile> for CpuProfile {
+    fn from(profile: Profile) -> Self {
+        Self { profile }
+    }
+}
+
+/// `(function name, url, line, column)`: functions are merged across call sites by these.
+type FunctionKey<'a> = (&'a str, &'a str, i64, i64);
+
+fn function_key(node: &ProfileNode) -> FunctionKey<'_> {
+    let frame = &node.call_frame;
+    (
+        &frame.function_name,
+        &frame.url,
+        frame.line_number,
+        frame.column_number,
+    )
+}
+
+impl CpuProfile {
+    pub fn from_json(json: &str) -> Result<Self, CdpError> {
+        Ok(Self::from(serde_json::from_str::<Profile>(json)?))
+    }
+
+    pub fn to_json(&self) -> Result<String, CdpError> {
+        Ok(serde_json::to_string(&self.profile)?)
+    }
+
+    /// Read a `.cpuprofile` file.
+    pub async fn load(path: impl AsRef<Path>) -> Result<Self, CdpError> {
+        let json = tokio::fs::read_to_string(path)
+            .await
+            .map_err(|e| CdpError::Io(e.to_string()))?;
+        Self::from_json(&json)
+    }
+
+    /// Write a `.cpuprofile` file.
+    pub async fn save(&self, path: impl AsRef<Path>) -> Result<(), CdpError> {
+        tokio::fs::write(path, self.to_json()?)
+            .await
+            .map_err(|e| CdpError::Io(e.to_string()))
+    }
+
+    /// Wall time covered by the profile.
+    pub fn duration(&self) -> Duration {
+        micros(self.profile.end_time - self.profile.start_time)
+    }
+
+    /// Self and total time per function, sorted by self time, highest first.
+    pub fn functions(&self) -> Vec<FunctionTime> {
+        let nodes = self.nodes();
+        let parents = self.parents();
+        let mut times: HashMap<FunctionKey<'_>, (f64, f64)> = HashMap::new();
+        let mut order: Vec<FunctionKey<'_>> = Vec::new();
+        for (leaf, duration) in self.sample_durations() {
+            let Some(node) = nodes.get(&leaf) else {
+                continue;
+            };
+            let key = function_key(node);
+            if !times.contains_key(&key) {
+                order.push(key);
+            }
+            times.entry(key).or_default().0 += duration;
+
+            let mut seen: Vec<FunctionKey<'_>> = Vec::new();
+            let mut current = Some(leaf);
+            while let Some(id) = current {
+                let Some(node) = nodes.get(&id) else { break };
+                let key = function_key(node);
+                if !seen.contains(&key) {
+                    seen.push(key);
+                    if !times.contains_key(&key) {
+                        order.push(key);
+                    }
+                    times.entry(key).or_default().1 += duration;
+                }
+                current = parents.get(&id).copied();
+            }
+        }
+
+        let mut functions: Vec<FunctionTime> = order
+            .into_iter()
+            .filter(|key| key.0 != "(root)")
+            .map(|key| {
+                let (self_time, total_time) = times[&key];
+                FunctionTime {
+                    function_name: key.0.to_string(),
+                    url: key.1.to_string(),
+                    line_number: key.2,
+                    column_number: key.3,
+                    self_time: micros(self_time),
+                    total_time: micros(total_time),
+                }
+            })
+            .collect();
+        // Stable sort keeps first-sampled order among equal self times.
+        functions.sort_by_key(|f| std::cmp::Reverse(f.self_time));
+        functions
+    }
+
+    /// The `n` functions with the most self time.
+    pub fn top(&self, n: usize) -> Vec<FunctionTime> {
+        let mut functions = self.functions();
+        functions.truncate(n);
+        functions
+    }
+
+    /// Folded stacks (`outer;inner count` per line, counts are samples), the input format
+    /// of `flamegraph.pl`, inferno and speedscope.
+    pub fn folded_stacks(&self) -> String {
+        let nodes = self.nodes();
+        let parents = self.parents();
+        let mut counts: HashMap<i64, u64> = HashMap::new();
+        for (leaf, _) in self.sample_durations() {
+            *counts.entry(leaf).or_default() += 1;
+        }
+        let mut lines: Vec<String> = counts
+            .into_iter()
+            .filter_map(|(leaf, count)| {
+                let mut frames = Vec::new();
+                let mut current = Some(leaf);
+                while let Some(id) = current {
+                    let node = nodes.get(&id)?;
+                    if node.call_frame.function_name != "(root)" {
+                        frames.push(frame_label(node));
+                    }
+                    current = parents.get(&id).copied();
+                }
+                if frames.is_empty() {
+                    return None;
+                }
+                frames.reverse();
+                Some(format!("{} {count}", frames.join(";")))
+            })
+            .collect();
+        lines.sort();
+        let mut out = String::new();
+        for line in lines {
+            let _ = writeln!(out, "{line}");
+        }
+        out
+    }
+
+    fn nodes(&self) -> HashMap<i64, &ProfileNode> {
+        self.profile.nodes.iter().map(|n| (n.id, n)).collect()
+    }
+
+    fn parents(&self) -> HashMap<i64, i64> {
+        let mut parents = HashMap::new();
+        for node in &self.profile.nodes {
+            for child in node.children.iter().flatten() {
+                parents.insert(*child, node.id);
+            }
+        }
+        parents
+    }
+
+    /// `(leaf node id, microseconds)` per sample. A sample lasts until the next one; the
+    /// last lasts until the profile's end time. Profiles without samples fall back to
+    /// `hitCount`, spreading the duration evenly over all hits.
+    fn sample_durations(&self) -> Vec<(i64, f64)> {
+        let profile = &self.profile;
+        let (Some(samples), Some(deltas)) = (&profile.samples, &profile.time_deltas) else {
+            let hits: i64 = profile.nodes.iter().filter_map(|n| n.hit_count).sum();
+            if hits <= 0 {
+                return Vec::new();
+            }
+            let interval = (profile.end_time - profile.start_time) / hits as f64;
+            return profile
+                .nodes
+                .iter()
+                .flat_map(|n| {
+                    std::iter::repeat((n.id, interval)).take(n.hit_count.unwrap_or(0) as usize)
+                })
+                .collect();
+        };
+        let mut timestamps = Vec::with_capacity(samples.len());
+        let mut time = profile.start_time;
+        for delta in deltas.iter().take(samples.len()) {
+            time += *delta as f64;
+            timestamps.push(time);
+        }
+        samples
+            .iter()
+            .zip(&timestamps)
+            .enumerate()
+            .map(|(i, (&id, &start))| {
+                let end = timestamps.get(i + 1).copied().unwrap_or(profile.end_time);
+                (id, (end - start).max(0.0))
+            })
+            .collect()
+    }
+}
+
+fn micros(us: f64) -> Duration {
+    Duration::from_secs_f64(us.max(0.0) / 1_000_000.0)
+}
+
+/// `name (url:line)`, with `;` removed since it separates frames.
+fn frame_label(node: &ProfileNode) -> String {
+    let frame = &node.call_frame;
+    let name = if frame.function_name.is_empty() {
+        "(anonymous)"
+    } else {
+        &frame.function_name
+    };
+    let label = if frame.url.is_empty() {
+        name.to_string()
+    } else {
+        format!("{name} ({}:{})", frame.url, frame.line_number + 1)
+    };
+    label.replace(';', ":")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `(root) -> main -> [work -> work (recursive), (program)]`, sampled every 100 µs.
+    fn profile() -> CpuProfile {
+        CpuProfile::from_json(
+            r#"{
+                "nodes": [
+                    {"id": 1, "callFrame": {"functionName": "(root)", "scriptId": "0", "url": "", "lineNumber": -1, "columnNumber": -1}, "children": [2, 5]},
+                    {"id": 2, "callFrame": {"functionName": "main", "scriptId": "1", "url": "app.js", "lineNumber": 0, "columnNumber": 0}, "children": [3]},
+                    {"id": 3, "callFrame": {"functionName": "work", "scriptId": "1", "url": "app.js", "lineNumber": 4, "columnNumber": 2}, "children": [4]},
+                    {"id": 4, "callFrame": {"functionName": "work", "scriptId": "1", "url": "app.js", "lineNumber": 4, "columnNumber": 2}},
+                    {"id": 5, "callFrame": {"functionName": "(program)", "scriptId": "0", "url": "", "lineNumber": -1, "columnNumber": -1}}
+                ],
+                "startTime": 1000,
+                "endTime": 1500,
+                "samples": [2, 3, 4, 4, 5],
+                "timeDeltas": [0, 100, 100, 100, 100]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn self_and_total_time_per_function() {
+        let profile = profile();
+        assert_eq!(profile.duration(), Duration::from_micros(500));
+        let functions = profile.functions();
+        let summary: Vec<(&str, u128, u128)> = functions
+            .iter()
+            .map(|f| {
+                (
+                    f.function_name.as_str(),
+                    f.self_time.as_micros(),
+                    f.total_time.as_micros(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("work", 300, 300),
+                ("main", 100, 400),
+                ("(program)", 100, 100)
+            ]
+        );
+        assert_eq!(profile.top(1)[0].line_number, 4);
+    }
+
+    #[test]
+    fn folded_stacks_count_samples() {
+        assert_eq!(
+            profile().folded_stacks(),
+            "(program) 1\n\
+             main (app.js:1) 1\n\
+             main (app.js:1);work (app.js:5) 1\n\
+             main (app.js:1);work (app.js:5);work (app.js:5) 2\n"
+        );
+    }
+}
@@ -0,0 +1,691 @@
+use crate::console_collector::render_remote_object;
+use crate::debugger::events::{Paused, ScriptParsed};
+use crate::debugger::types::{BreakpointId, CallFrame, CallFrameId, Location, Scope};
+use crate::element::exception_error;
+use crate::runtime::types::RemoteObject;
+use crate::{
+    debugger, decode_event, merged_event_stream, runtime, spawn_cleanup, CdpError, Sender,
+};
+use futures::StreamExt;
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::watch;
+use tracing::warn;
+
+const DEBUGGER_EVENTS: &[&str] = &[
+    "Debugger.scriptParsed",
+    "Debugger.paused",
+    "Debugger.resumed",
+];
+
+/// A script reported by `Debugger.scriptParsed`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptInfo {
+    pub script_id: String,
+    /// Empty for inline `eval` code without a `//# sourceURL`.
+    pub url: String,
+    /// `//# sourceMappingURL`, relative to `url` unless it is a `data:` URL.
+    pub source_map_url: Option<String>,
+    pub hash: String,
+    pub execution_context_id: i64,
+    /// 0-based position of the script in its resource, e.g. for inline `<script>` tags.
+    pub start_line: i64,
+    pub start_column: i64,
+    pub end_line: i64,
+    pub end_column: i64,
+    pub is_module: bool,
+}
+
+impl From<ScriptParsed> for ScriptInfo {
+    fn from(event: ScriptParsed) -> Self {
+        Self {
+            script_id: event.script_id,
+            url: event.url,
+            source_map_url: event.source_map_url.filter(|url| !url.is_empty()),
+            hash: event.hash,
+            execution_context_id: event.execution_context_id,
+            start_line: event.start_line,
+            start_column: event.start_column,
+            end_line: event.end_line,
+            end_column: event.end_column,
+            is_module: event.is_module.unwrap_or(false),
+        }
+    }
+}
+
+/// Why execution paused, from `Debugger.paused`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PauseReason {
+    /// One or more breakpoints were hit; see [`PauseState::hit_breakpoints`].
+    Breakpoint,
+    Exception,
+    PromiseRejection,
+    Assert,
+    Step,
+    /// Any other reason as reported by V8. `debugger;` statements, `Debugger.pause` and
+    /// most steps are reported as `other`.
+    Other(String),
+}
+
+impl PauseReason {
+    fn new(reason: &str, hit_breakpoints: &[String]) -> Self {
+        if !hit_breakpoints.is_empty() {
+            return Self::Breakpoint;
+        }
+        match reason {
+            "exception" => Self::Exception,
+            "promiseRejection" => Self::PromiseRejection,
+            "assert" => Self::Assert,
+            "step" => Self::Step,
+            other => Self::Other(other.to_string()),
+        }
+    }
+}
+
+/// Kind of a [`FrameScope`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScopeKind {
+    Local,
+    Closure,
+    Block,
+    Catch,
+    With,
+    Script,
+    Module,
+    Eval,
+    Global,
+    Other(String),
+}
+
+impl ScopeKind {
+    fn new(kind: &str) -> Self {
+        match kind {
+            "local" => Self::Local,
+            "closure" => Self::Closure,
+            "block" => Self::Block,
+            "catch" => Self::Catch,
+            "with" => Self::With,
+            "script" => Self::Script,
+            "module" => Self::Module,
+            "eval" => Self::Eval,
+            "global" => Self::Global,
+            other => Self::Other(other.to_string()),
+        }
+    }
+}
+
+/// One scope in a paused frame's scope chain, innermost first.
+#[derive(Debug, Clone)]
+pub struct FrameScope {
+    pub kind: ScopeKind,
+    /// Function name for closures and locals, if V8 knows it.
+    pub name: Option<String>,
+    /// The scope object; list its variables with [`DebuggerClient::scope_variables`].
+    pub object: RemoteObject,
+}
+
+impl From<Scope> for FrameScope {
+    fn from(scope: Scope) -> Self {
+        Self {
+            kind: ScopeKind::new(&scope.type_),
+            name: scope.name,
+            object: scope.object,
+        }
+    }
+}
+
+/// A call frame of the paused stack, top frame first.
+#[derive(Debug, Clone)]
+pub struct PausedFrame {
+    /// Valid only until execution resumes.
+    pub call_frame_id: CallFrameId,
+    pub function_name: String,
+    pub script_id: String,
+    /// URL of the script, resolved from `Debugger.scriptParsed`.
+    pub url: String,
+    /// 0-based position of the paused statement.
+    pub line_number: i64,
+    pub column_number: i64,
+    pub scopes: Vec<FrameScope>,
+    pub this: RemoteObject,
+    /// Value about to be returned when paused at the end of a function.
+    pub return_value: Option<RemoteObject>,
+}
+
+impl PausedFrame {
+    fn new(frame: CallFrame, scripts: &[ScriptInfo]) -> Self {
+        #[allow(deprecated)]
+        let fallback_url = frame.url;
+        let url = scripts
+            .iter()
+            .find(|script| script.script_id == frame.location.script_id)
+            .map(|script| script.url.clone())
+            .unwrap_or(fallback_url);
+        Self {
+            call_frame_id: frame.call_frame_id,
+            function_name: frame.function_name,
+            script_id: frame.location.script_id,
+            url,
+            line_number: frame.location.line_number,
+            column_number: frame.location.column_number.unwrap_or(0),
+            scopes: frame
+                .scope_chain
+                .into_iter()
+                .map(FrameScope::from)
+                .collect(),
+            this: frame.this,
+            return_value: frame.return_value,
+        }
+    }
+}
+
+/// Where and why execution is paused.
+#[derive(Debug, Clone)]
+pub struct PauseState {
+    pub reason: PauseReason,
+    pub hit_breakpoints: Vec<BreakpointId>,
+    pub frames: Vec<PausedFrame>,
+    /// Extra data for the reason, e.g. the thrown value for exceptions.
+    pub data: Option<Value>,
+}
+
+impl PauseState {
+    fn new(event: Paused, scripts: &[ScriptInfo]) -> Self {
+        let hit_breakpoints = event.hit_breakpoints.unwrap_or_default();
+        Self {
+            reason: PauseReason::new(&event.reason, &hit_breakpoints),
+            hit_breakpoints,
+            frames: event
+                .call_frames
+                .into_iter()
+                .map(|frame| PausedFrame::new(frame, scripts))
+                .collect(),
+            data: event.data,
+        }
+    }
+
+    /// The innermost frame, where execution stopped.
+    pub fn top_frame(&self) -> Option<&PausedFrame> {
+        self.frames.first()
+    }
+}
+
+/// A variable in a [`FrameScope`], from `Runtime.getProperties`.
+#[derive(Debug, Clone)]
+pub struct ScopeVariable {
+    pub name: String,
+    /// `None` for accessors and variables in the temporal dead zone.
+    pub value: Option<RemoteObject>,
+}
+
+impl ScopeVariable {
+    /// The value as DevTools shows it inline, e.g. `'text'`, `42` or `{x: 1}`.
+    pub fn display(&self) -> String {
+        self.value
+            .as_ref()
+            .map(render_remote_object)
+            .unwrap_or_else(|| "<unavailable>".to_string())
+    }
+}
+
+/// Which scripts a [`BreakpointSpec`] applies to.
+#[derive(Debug, Clone)]
+enum ScriptMatch {
+    Url(String),
+    UrlRegex(String),
+}
+
+/// A breakpoint for [`DebuggerClient::set_breakpoint`], by script URL and 0-based line.
+///
+/// URL breakpoints also apply to matching scripts parsed later, e.g. after a reload.
+#[derive(Debug, Clone)]
+pub struct BreakpointSpec {
+    scripts: ScriptMatch,
+    line_number: i64,
+    column_number: Option<i64>,
+    condition: Option<String>,
+}
+
+impl BreakpointSpec {
+    /// Break at `line_number` (0-based) of scripts loaded from exactly `url`.
+    pub fn url(url: impl Into<String>, line_number: i64) -> Self {
+        Self::new(ScriptMatch::Url(url.into()), line_number)
+    }
+
+    /// Break at `line_number` (0-based) of every script whose URL matches `pattern`, a
+    /// JavaScript regular expression.
+    pub fn url_regex(pattern: impl Into<String>, line_number: i64) -> Self {
+        Self::new(ScriptMatch::UrlRegex(pattern.into()), line_number)
+    }
+
+    fn new(scripts: ScriptMatch, line_number: i64) -> Self {
+        Self {
+            scripts,
+            line_number,
+            column_number: None,
+            condition: None,
+        }
+    }
+
+    /// 0-based column; without it V8 picks the first breakable position on the line.
+    pub fn with_column(mut self, column_number: i64) -> Self {
+        self.column_number = Some(column_number);
+        self
+    }
+
+    /// Only pause when this JavaScript expression is truthy in the paused frame.
+    pub fn with_condition(mut self, condition: impl Into<String>) -> Self {
+        self.condition = Some(condition.into());
+        self
+    }
+}
+
+/// A breakpoint set by [`DebuggerClient::set_breakpoint`].
+#[derive(Debug, Clone)]
+pub struct Breakpoint {
+    pub id: BreakpointId,
+    /// Where the breakpoint resolved in scripts that were already parsed.
+    pub locations: Vec<Location>,
+}
+
+/// Argument for [`DebuggerClient::set_pause_on_exceptions`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PauseOnExceptions {
+    #[default]
+    None,
+    Uncaught,
+    Caught,
+    All,
+}
+
+impl PauseOnExceptions {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Uncaught => "uncaught",
+            Self::Caught => "caught",
+            Self::All => "all",
+        }
+    }
+}
+
+/// Execution state as last reported by the event task.
+#[derive(Debug, Clone)]
+enum Execution {
+    Running,
+    Paused(PauseState),
+    /// The event stream ended.
+    Closed,
+}
+
+/// A stateful client for the `Debugger` domain.
+///
+/// Tracks parsed scripts and the current pause, so breakpoints, stepping and inspection
+/// do not need to juggle `Debugger.paused` events by hand. Works on page sessions,
+/// workers and Node.js inspector connections. Dropping the client disables the debugger,
+/// which also resumes a paused target. `Debugger.disable` is per session, so this also
+/// stops a [`SourceMapResolver`](crate::SourceMapResolver) enabled on the same session
+/// from learning new scripts.
+///
+/// # Example
+/// ```no_run
+/// # use cdpkit::{BreakpointSpec, DebuggerClient, OwnedSession};
+/// # use std::time::Duration;
+/// # async fn example(session: OwnedSession) -> Result<(), cdpkit::CdpError> {
+/// let debugger = DebuggerClient::enable(&session).await?;
+/// debugger
+///     .set_breakpoint(BreakpointSpec::url("https://app.test/app.js", 41))
+///     .await?;
+/// // ... trigger the code path ...
+/// let pause = debugger.wait_for_pause(Duration::from_secs(10)).await?;
+/// let frame = pause.top_frame().unwrap();
+/// for variable in debugger.scope_variables(&frame.scopes[0]).await? {
+///     println!("{} = {}", variable.name, variable.display());
+/// }
+/// let total = debugger.evaluate_on_frame(frame, "items.length").await?;
+/// println!("items.length = {:?}", total.value);
+/// debugger.step_over().await?;
+/// debugger.resume().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct DebuggerClient<S: Sender + Clone + Send + Sync + 'static> {
+    target: S,
+    scripts: Arc<Mutex<Vec<ScriptInfo>>>,
+    pause: Arc<watch::Sender<Execution>>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl<S: Sender + Clone + Send + Sync + 'static> DebuggerClient<S> {
+    /// Subscribe to debugger events and call `Debugger.enable`. Chrome then reports every
+    /// script that is already loaded.
+    pub async fn enable(target: &S) -> Result<Self, CdpError> {
+        let mut events = merged_event_stream(target, DEBUGGER_EVENTS);
+        let scripts = Arc::new(Mutex::new(Vec::new()));
+        let pause = Arc::new(watch::channel(Execution::Running).0);
+
+        let task = tokio::spawn({
+            let scripts = Arc::clone(&scripts);
+            let pause = Arc::clone(&pause);
+            async move {
+                while let Some((name, params)) = events.next().await {
+                    apply_event(&name, &params, &scripts, &pause);
+                }
+                pause.send_replace(Execution::Closed);
+            }
+        });
+        let client = Self {
+            target: target.clone(),
+            scripts,
+            pause,
+            task,
+        };
+        debugger::methods::Enable::new().send(target).await?;
+        Ok(client)
+    }
+
+    /// Scripts parsed so far, in parse order.
+    pub fn scripts(&self) -> Vec<ScriptInfo> {
+        self.scripts
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    pub fn script(&self, script_id: &str) -> Option<ScriptInfo> {
+        self.scripts
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .find(|script| script.script_id == script_id)
+            .cloned()
+    }
+
+    /// Scripts loaded from `url`; a page can load the same URL more than once.
+    pub fn scripts_for_url(&self, url: &str) -> Vec<ScriptInfo> {
+        self.scripts
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .filter(|script| script.url == url)
+            .cloned()
+            .collect()
+    }
+
+    pub async fn script_source(&self, script_id: &str) -> Result<String, CdpError> {
+        Ok(
+            debugger::methods::GetScriptSource::new(script_id.to_string())
+                .send(&self.target)
+                .await?
+                .script_source,
+        )
+    }
+
+    pub async fn set_breakpoint(&self, spec: BreakpointSpec) -> Result<Breakpoint, CdpError> {
+        let mut command = debugger::methods::SetBreakpointByUrl::new(spec.line_number);
+        command = match spec.scripts {
+            ScriptMatch::Url(url) => command.with_url(url),
+            ScriptMatch::UrlRegex(pattern) => command.with_url_regex(pattern),
+        };
+        if let Some(column) = spec.column_number {
+            command = command.with_column_number(column);
+        }
+        if let Some(condition) = spec.condition {
+            command = command.with_condition(condition);
+        }
+        let response = command.send(&self.target).await?;
+        Ok(Breakpoint {
+            id: response.breakpoint_id,
+            locations: response.locations,
+        })
+    }
+
+    pub async fn remove_breakpoint(&self, breakpoint: &Breakpoint) -> Result<(), CdpError> {
+        debugger::methods::RemoveBreakpoint::new(breakpoint.id.clone())
+            .send(&self.target)
+            .await
+    }
+
+    pub async fn set_pause_on_exceptions(&self, mode: PauseOnExceptions) -> Result<(), CdpError> {
+        debugger::methods::SetPauseOnExceptions::new(mode.as_str())
+            .send(&self.target)
+            .await
+    }
+
+    /// The current pause, or `None` while running.
+    pub fn pause_state(&self) -> Option<PauseState> {
+        match &*self.pause.borrow() {
+            Execution::Paused(state) => Some(state.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        matches!(*self.pause.borrow(), Execution::Paused(_))
+    }
+
+    /// Wait until execution is paused, returning immediately if it already is.
+    ///
+    /// Returns [`CdpError::Timeout`] if nothing pauses within `timeout`, and
+    /// [`CdpError::ConnectionClosed`] if the event stream ends.
+    pub async fn wait_for_pause(&self, timeout: Duration) -> Result<PauseState, CdpError> {
+        let mut rx = self.pause.subscribe();
+        let wait = async move {
+            loop {
+                match &*rx.borrow_and_update() {
+                    Execution::Paused(state) => return Ok(state.clone()),
+                    Execution::Closed => return Err(CdpError::ConnectionClosed),
+                    Execution::Running => {}
+                }
+                rx.changed().await.map_err(|_| CdpError::ConnectionClosed)?;
+            }
+        };
+        tokio::time::timeout(timeout, wait)
+            .await
+            .map_err(|_| CdpError::Timeout)?
+    }
+
+    /// Ask the target to pause at the next statement; see [`wait_for_pause`](Self::wait_for_pause).
+    pub async fn pause(&self) -> Result<(), CdpError> {
+        debugger::methods::Pause::new().send(&self.target).await
+    }
+
+    pub async fn resume(&self) -> Result<(), CdpError> {
+        self.mark_running();
+        debugger::methods::Resume::new().send(&self.target).await
+    }
+
+    /// Run to the next statement in the current function. The new pause arrives as an
+    /// event; wait for it with [`wait_for_pause`](Self::wait_for_pause).
+    pub async fn step_over(&self) -> Result<(), CdpError> {
+        self.mark_running();
+        debugger::methods::StepOver::new().send(&self.target).await
+    }
+
+    /// Step into the function called by the current statement.
+    pub async fn step_into(&self) -> Result<(), CdpError> {
+        self.mark_running();
+        debugger::methods::StepInto::new().send(&self.target).await
+    }
+
+    /// Run until the current function returns.
+    pub async fn step_out(&self) -> Result<(), CdpError> {
+        self.mark_running();
+        debugger::methods::StepOut::new().send(&self.target).await
+    }
+
+    /// Forget the current pause ahead of `Debugger.resumed`, unless the stream has ended.
+    fn mark_running(&self) {
+        self.pause.send_if_modified(|execution| match execution {
+            Execution::Paused(_) => {
+                *execution = Execution::Running;
+                true
+            }
+            _ => false,
+        });
+    }
+
+    /// Evaluate `expression` with the frame's scope chain, e.g. to read a local variable.
+    /// A thrown exception becomes [`CdpError::JavaScriptException`].
+    pub async fn evaluate_on_frame(
+        &self,
+        frame: &PausedFrame,
+        expression: &str,
+    ) -> Result<RemoteObject, CdpError> {
+        let response =
+            debugger::methods::EvaluateOnCallFrame::new(frame.call_frame_id.clone(), expression)
+                .with_silent(true)
+                .send(&self.target)
+                .await?;
+        match response.exception_details {
+            Some(details) => Err(exception_error(&details)),
+            None => Ok(response.result),
+        }
+    }
+
+    /// Variables of one scope, through `Runtime.getProperties` on the scope object.
+    pub async fn scope_variables(
+        &self,
+        scope: &FrameScope,
+    ) -> Result<Vec<ScopeVariable>, CdpError> {
+        let Some(object_id) = scope.object.object_id.clone() else {
+            return Ok(Vec::new());
+        };
+        let response = runtime::methods::GetProperties::new(object_id)
+            .with_own_properties(true)
+            .send(&self.target)
+            .await?;
+        if let Some(details) = response.exception_details {
+            return Err(exception_error(&details));
+        }
+        Ok(response
+            .result
+            .into_iter()
+            .map(|property| ScopeVariable {
+                name: property.name,
+                value: property.value,
+            })
+            .collect())
+    }
+}
+
+impl<S: Sender + Clone + Send + Sync + 'static> Drop for DebuggerClient<S> {
+    fn drop(&mut self) {
+        self.task.abort();
+        let target = self.target.clone();
+        spawn_cleanup(async move {
+            if let Err(e) = debugger::methods::Disable::new().send(&target).await {
+                warn!(error = %e, "Failed to disable the debugger");
+            }
+        });
+    }
+}
+
+fn apply_event(
+    name: &str,
+    params: &Value,
+    scripts: &Mutex<Vec<ScriptInfo>>,
+    pause: &watch::Sender<Execution>,
+) {
+    match name {
+        "Debugger.scriptParsed" => {
+            if let Some(event) = decode_event::<ScriptParsed>(name, params) {
+                let script = ScriptInfo::from(event);
+                let mut scripts = scripts.lock().unwrap_or_else(|e| e.into_inner());
+                match scripts.iter_mut().find(|s| s.script_id == script.script_id) {
+                    Some(existing) => *existing = script,
+                    None => scripts.push(script),
+                }
+            }
+        }
+        "Debugger.paused" => {
+            if let Some(event) = decode_event::<Paused>(name, params) {
+                let scripts = scripts.lock().unwrap_or_else(|e| e.into_inner());
+                pause.send_replace(Execution::Paused(PauseState::new(event, &scripts)));
+            }
+        }
+        "Debugger.resumed" => {
+            pause.send_replace(Execution::Running);
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn paused(reason: &str, hit_breakpoints: &[&str]) -> Value {
+        json!({
+            "reason": reason,
+            "hitBreakpoints": hit_breakpoints,
+            "callFrames": [{
+                "callFrameId": "frame-0",
+                "functionName": "total",
+                "location": {"scriptId": "12", "lineNumber": 4, "columnNumber": 2},
+                "url": "",
+                "scopeChain": [
+                    {"type": "local", "name": "total",
+                     "object": {"type": "object", "objectId": "scope-0"}},
+                    {"type": "wasm-expression-stack",
+                     "object": {"type": "object", "objectId": "scope-1"}}
+                ],
+                "this": {"type": "undefined"}
+            }]
+        })
+    }
+
+    #[test]
+    fn pause_resolves_frame_urls_from_parsed_scripts() {
+        let scripts = Mutex::new(Vec::new());
+        let (tx, rx) = watch::channel(Execution::Running);
+        let script = json!({
+            "scriptId": "12", "url": "https://app.test/cart.js", "startLine": 0,
+            "startColumn": 0, "endLine": 20, "endColumn": 0, "executionContextId": 1,
+            "hash": "abc", "buildId": "", "sourceMapURL": "cart.js.map"
+        });
+        apply_event("Debugger.scriptParsed", &script, &scripts, &tx);
+        apply_event(
+            "Debugger.paused",
+            &paused("other", &["bp-1"]),
+            &scripts,
+            &tx,
+        );
+
+        let Execution::Paused(state) = rx.borrow().clone() else {
+            panic!("not paused");
+        };
+        assert_eq!(state.reason, PauseReason::Breakpoint);
+        assert_eq!(state.hit_breakpoints, ["bp-1"]);
+        let frame = state.top_frame().unwrap();
+        assert_eq!(frame.url, "https://app.test/cart.js");
+        assert_eq!((frame.line_number, frame.column_number), (4, 2));
+        assert_eq!(frame.scopes[0].kind, ScopeKind::Local);
+        assert_eq!(
+            frame.scopes[1].kind,
+            ScopeKind::Other("wasm-expression-stack".into())
+        );
+        assert_eq!(
+            scripts.lock().unwrap()[0].source_map_url.as_deref(),
+            Some("cart.js.map")
+        );
+
+        apply_event("Debugger.resumed", &json!({}), &scripts, &tx);
+        assert!(matches!(*rx.borrow(), Execution::Running));
+    }
+
+    #[test]
+    fn pause_reasons() {
+        assert_eq!(PauseReason::new("exception", &[]), PauseReason::Exception);
+        assert_eq!(
+            PauseReason::new("other", &[]),
+            PauseReason::Other("other".into())
+        );
+        assert_eq!(
+            PauseReason::new("other", &["1:4:0:app.js".into()]),
+            PauseReason::Breakpoint
+        );
+    }
+}
@@ -0,0 +1,312 @@
+use crate::emulation::types::{MediaFeature, ScreenOrientation};
+use crate::{emulation, CdpError, Sender};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Width and height in CSS pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceSize {
+    pub width: i64,
+    pub height: i64,
+}
+
+/// An emulated device: viewport, pixel ratio, user agent and input capabilities.
+///
+/// The serialized form matches Playwright's device descriptors, so entries from its
+/// `deviceDescriptorsSource.json` can be loaded with [`Device::load`].
+///
+/// # Example
+/// ```no_run
+/// # use cdpkit::{Device, OwnedSession};
+/// # async fn example(session: OwnedSession) -> Result<(), cdpkit::CdpError> {
+/// let pixel = Device::named("Pixel 7").unwrap();
+/// pixel.landscape().apply(&session).await?;
+/// // ...
+/// Device::reset(&session).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Device {
+    /// Filled from the map key when loading a descriptor map.
+    #[serde(default)]
+    pub name: String,
+    pub user_agent: String,
+    pub viewport: DeviceSize,
+    /// Physical screen size, if larger than the viewport (browser chrome excluded).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub screen: Option<DeviceSize>,
+    pub device_scale_factor: f64,
+    pub is_mobile: bool,
+    pub has_touch: bool,
+    /// `prefers-color-scheme` to emulate: `light` or `dark`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color_scheme: Option<String>,
+}
+
+const IPHONE_UA: &str = "Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.0 Mobile/15E148 Safari/604.1";
+const IPAD_UA: &str = "Mozilla/5.0 (iPad; CPU OS 17_0 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.0 Mobile/15E148 Safari/604.1";
+
+/// `(name, user agent, viewport, screen, device scale factor, mobile/touch)`
+type Descriptor = (
+    &'static str,
+    &'static str,
+    (i64, i64),
+    (i64, i64),
+    f64,
+    bool,
+);
+
+const DESCRIPTORS: &[Descriptor] = &[
+    ("iPhone SE", IPHONE_UA, (375, 667), (375, 667), 2.0, true),
+    ("iPhone 13", IPHONE_UA, (390, 664), (390, 844), 3.0, true),
+    ("iPhone 15 Pro", IPHONE_UA, (393, 659), (393, 852), 3.0, true),
+    ("iPhone 15 Pro Max", IPHONE_UA, (430, 739), (430, 932), 3.0, true),
+    ("iPad (gen 7)", IPAD_UA, (810, 1080), (810, 1080), 2.0, true),
+    ("iPad Pro 11", IPAD_UA, (834, 1194), (834, 1194), 2.0, true),
+    (
+        "Pixel 5",
+        "Mozilla/5.0 (Linux; Android 11; Pixel 5) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/126.0.0.0 Mobile Safari/537.36",
+        (393, 727),
+        (393, 851),
+        2.75,
+        true,
+    ),
+    (
+        "Pixel 7",
+        "Mozilla/5.0 (Linux; Android 14; Pixel 7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/126.0.0.0 Mobile Safari/537.36",
+        (412, 839),
+        (412, 915),
+        2.625,
+        true,
+    ),
+    (
+        "Galaxy S9+",
+        "Mozilla/5.0 (Linux; Android 8.0.0; SM-G965U Build/R16NW) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/126.0.0.0 Mobile Safari/537.36",
+        (320, 658),
+        (320, 658),
+        4.5,
+        true,
+    ),
+    (
+        "Desktop Chrome",
+        "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/126.0.0.0 Safari/537.36",
+        (1280, 720),
+        (1920, 1080),
+        1.0,
+        false,
+    ),
+    (
+        "Desktop Chrome HiDPI",
+        "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/126.0.0.0 Safari/537.36",
+        (1280, 720),
+        (1280, 720),
+        2.0,
+        false,
+    ),
+];
+
+fn size((width, height): (i64, i64)) -> DeviceSize {
+    DeviceSize { width, height }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DeviceFile {
+    List(Vec<Device>),
+    Single(Box<Device>),
+    Map(BTreeMap<String, Device>),
+}
+
+impl Device {
+    /// The built-in devices, portrait first.
+    pub fn all() -> Vec<Device> {
+        DESCRIPTORS
+            .iter()
+            .map(
+                |&(name, user_agent, viewport, screen, scale, mobile)| Device {
+                    name: name.to_string(),
+                    user_agent: user_agent.to_string(),
+                    viewport: size(viewport),
+                    screen: (screen != viewport).then(|| size(screen)),
+                    device_scale_factor: scale,
+                    is_mobile: mobile,
+                    has_touch: mobile,
+                    color_scheme: None,
+                },
+            )
+            .collect()
+    }
+
+    /// Look up a built-in device by name, ignoring ASCII case.
+    pub fn named(name: &str) -> Option<Device> {
+        Self::all()
+            .into_iter()
+            .find(|device| device.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Parse custom devices: a Playwright-style map of name to descriptor, a list of
+    /// descriptors, or a single descriptor.
+    pub fn from_json(json: &str) -> Result<Vec<Device>, CdpError> {
+        Ok(match serde_json::from_str(json)? {
+            DeviceFile::List(devices) => devices,
+            DeviceFile::Single(device) => vec![*device],
+            DeviceFile::Map(map) => map
+                .into_iter()
+                .map(|(name, mut device)| {
+                    if device.name.is_empty() {
+                        device.name = name;
+                    }
+                    device
+                })
+                .collect(),
+        })
+    }
+
+    /// Read custom devices from a JSON file; see [`from_json`](Self::from_json).
+    pub async fn load(path: impl AsRef<Path>) -> Result<Vec<Device>, CdpError> {
+        let json = tokio::fs::read_to_string(path)
+            .await
+            .map_err(|e| CdpError::Io(e.to_string()))?;
+        Self::from_json(&json)
+    }
+
+    /// The same device rotated by 90 degrees.
+    pub fn landscape(&self) -> Device {
+        let rotate = |s: DeviceSize| DeviceSize {
+            width: s.height,
+            height: s.width,
+        };
+        Device {
+            name: format!("{} landscape", self.name),
+            viewport: rotate(self.viewport),
+            screen: self.screen.map(rotate),
+            ..self.clone()
+        }
+    }
+
+    /// Whether the viewport is wider than it is tall.
+    pub fn is_landscape(&self) -> bool {
+        self.viewport.width > self.viewport.height
+    }
+
+    /// Emulate this device: device metrics, touch, user agent, then media features.
+    pub async fn apply<S: Sender + Sync>(&self, target: &S) -> Result<(), CdpError> {
+        let screen = self.screen.unwrap_or(self.viewport);
+        let mut metrics = emulation::methods::SetDeviceMetricsOverride::new(
+            self.viewport.width,
+            self.viewport.height,
+            self.device_scale_factor,
+            self.is_mobile,
+        )
+        .with_screen_width(screen.width)
+        .with_screen_height(screen.height);
+        if self.is_mobile {
+            metrics = metrics.with_screen_orientation(if self.is_landscape() {
+                ScreenOrientation {
+                    type_: "landscapePrimary".to_string(),
+                    angle: 90,
+                }
+            } else {
+                ScreenOrientation {
+                    type_: "portraitPrimary".to_string(),
+                    angle: 0,
+                }
+            });
+        }
+        metrics.send(target).await?;
+
+        let mut touch = emulation::methods::SetTouchEmulationEnabled::new(self.has_touch);
+        if self.has_touch {
+            touch = touch.with_max_touch_points(5);
+        }
+        touch.send(target).await?;
+
+        emulation::methods::SetUserAgentOverride::new(&self.user_agent)
+            .send(target)
+            .await?;
+
+        if let Some(color_scheme) = &self.color_scheme {
+            emulation::methods::SetEmulatedMedia::new()
+                .with_features(vec![MediaFeature {
+                    name: "prefers-color-scheme".to_string(),
+                    value: color_scheme.clone(),
+                }])
+                .send(target)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Undo [`apply`](Self::apply): clear the metrics, touch, user agent and media overrides.
+    pub async fn reset<S: Sender + Sync>(target: &S) -> Result<(), CdpError> {
+        emulation::methods::ClearDeviceMetricsOverride::new()
+            .send(target)
+            .await?;
+        emulation::methods::SetTouchEmulationEnabled::new(false)
+            .send(target)
+            .await?;
+        // An empty user agent removes the override.
+        emulation::methods::SetUserAgentOverride::new("")
+            .send(target)
+            .await?;
+        emulation::methods::SetEmulatedMedia::new()
+            .with_media("")
+            .with_features(Vec::new())
+            .send(target)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn built_in_devices_are_consistent() {
+        let devices = Device::all();
+        for device in &devices {
+            assert_eq!(device.is_mobile, device.has_touch, "{}", device.name);
+            if device.is_mobile {
+                assert!(!device.is_landscape(), "{}", device.name);
+            }
+        }
+        let pixel = Device::named("pixel 7").unwrap();
+        assert_eq!(
+            pixel.viewport,
+            DeviceSize {
+                width: 412,
+                height: 839
+            }
+        );
+        assert_eq!(pixel.screen.unwrap().height, 915);
+
+        let landscape = pixel.landscape();
+        assert!(landscape.is_landscape());
+        assert_eq!(landscape.screen.unwrap().width, 915);
+    }
+
+    #[test]
+    fn loads_playwright_descriptor_maps() {
+        let json = r#"{
+            "Kiosk": {
+                "userAgent": "Kiosk/1.0",
+                "viewport": {"width": 1080, "height": 1920},
+                "deviceScaleFactor": 1,
+                "isMobile": false,
+                "hasTouch": true,
+                "defaultBrowserType": "chromium"
+            }
+        }"#;
+        let devices = Device::from_json(json).unwrap();
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].name, "Kiosk");
+        assert!(devices[0].has_touch);
+        assert_eq!(devices[0].screen, None);
+
+        let single = serde_json::to_string(&devices[0]).unwrap();
+        assert_eq!(Device::from_json(&single).unwrap(), devices);
+    }
+}
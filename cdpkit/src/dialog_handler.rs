@@ -0,0 +1,260 @@
+use crate::page::events::JavascriptDialogOpening;
+use crate::page::types::DialogType;
+use crate::{page, CdpError, OwnedSession};
+use futures::StreamExt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::watch;
+use tracing::warn;
+
+/// How a single JavaScript dialog is answered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DialogAction {
+    /// Press OK. Prompts are accepted with their default text.
+    Accept,
+    /// Press OK, entering this text into a prompt.
+    AcceptWith(String),
+    /// Press Cancel. Dismissing a `beforeunload` dialog cancels the navigation.
+    Dismiss,
+}
+
+type DialogCallback = Arc<dyn Fn(&JavascriptDialogOpening) -> DialogAction + Send + Sync>;
+
+/// Policy applied by a [`DialogHandler`] to every dialog it sees.
+#[derive(Clone)]
+pub enum DialogPolicy {
+    Accept,
+    Dismiss,
+    /// Accept every dialog, entering this text into prompts.
+    PromptText(String),
+    /// Decide per dialog.
+    Callback(DialogCallback),
+}
+
+impl DialogPolicy {
+    /// Decide per dialog with a callback.
+    pub fn callback(
+        callback: impl Fn(&JavascriptDialogOpening) -> DialogAction + Send + Sync + 'static,
+    ) -> Self {
+        Self::Callback(Arc::new(callback))
+    }
+
+    fn action(&self, dialog: &JavascriptDialogOpening) -> DialogAction {
+        match self {
+            Self::Accept => DialogAction::Accept,
+            Self::Dismiss => DialogAction::Dismiss,
+            Self::PromptText(text) if dialog.type_ == DialogType::Prompt => {
+                DialogAction::AcceptWith(text.clone())
+            }
+            Self::PromptText(_) => DialogAction::Accept,
+            Self::Callback(callback) => callback(dialog),
+        }
+    }
+}
+
+impl std::fmt::Debug for DialogPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Accept => f.write_str("Accept"),
+            Self::Dismiss => f.write_str("Dismiss"),
+            Self::PromptText(text) => f.debug_tuple("PromptText").field(text).finish(),
+            Self::Callback(_) => f.write_str("Callback"),
+        }
+    }
+}
+
+/// A dialog the handler has answered.
+#[derive(Debug, Clone)]
+pub struct HandledDialog {
+    pub dialog: JavascriptDialogOpening,
+    pub action: DialogAction,
+    /// Set if `Page.handleJavaScriptDialog` failed, e.g. because the dialog was already
+    /// closed by the page navigating away.
+    pub error: Option<String>,
+}
+
+impl HandledDialog {
+    pub fn dialog_type(&self) -> &DialogType {
+        &self.dialog.type_
+    }
+
+    pub fn message(&self) -> &str {
+        &self.dialog.message
+    }
+}
+
+/// Answers JavaScript dialogs (`alert`, `confirm`, `prompt`, `beforeunload`) on a session.
+///
+/// An unanswered dialog blocks the page's main thread, so every later `Runtime.evaluate`
+/// hangs until it times out. The handler subscribes to `Page.javascriptDialogOpening`,
+/// answers each dialog with `Page.handleJavaScriptDialog` according to its
+/// [`DialogPolicy`], and keeps a record of every dialog it handled.
+///
+/// # Example
+/// ```no_run
+/// # use cdpkit::{DialogAction, DialogHandler, DialogPolicy, OwnedSession};
+/// # async fn example(session: OwnedSession) -> Result<(), cdpkit::CdpError> {
+/// let dialogs = DialogHandler::start(&session, DialogPolicy::callback(|dialog| {
+///     if dialog.message.contains("Leave site?") {
+///         DialogAction::Dismiss
+///     } else {
+///         DialogAction::Accept
+///     }
+/// }))
+/// .await?;
+/// // ... interact with the page ...
+/// for handled in dialogs.handled() {
+///     println!("{:?}: {}", handled.dialog_type(), handled.message());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct DialogHandler {
+    policy: Arc<Mutex<DialogPolicy>>,
+    handled: Arc<Mutex<Vec<HandledDialog>>>,
+    count: watch::Receiver<usize>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl DialogHandler {
+    /// Subscribe to dialogs and call `Page.enable`.
+    pub async fn start(session: &OwnedSession, policy: DialogPolicy) -> Result<Self, CdpError> {
+        let mut dialogs = JavascriptDialogOpening::subscribe(session);
+        page::methods::Enable::new().send(session).await?;
+
+        let policy = Arc::new(Mutex::new(policy));
+        let handled = Arc::new(Mutex::new(Vec::new()));
+        let (count_tx, count) = watch::channel(0);
+        let task = {
+            let session = session.clone();
+            let policy = Arc::clone(&policy);
+            let handled = Arc::clone(&handled);
+            tokio::spawn(async move {
+                while let Some(dialog) = dialogs.next().await {
+                    let action = policy
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .clone()
+                        .action(&dialog);
+                    let cmd = match &action {
+                        DialogAction::Accept => page::methods::HandleJavaScriptDialog::new(true),
+                        DialogAction::AcceptWith(text) => {
+                            page::methods::HandleJavaScriptDialog::new(true).with_prompt_text(text)
+                        }
+                        DialogAction::Dismiss => page::methods::HandleJavaScriptDialog::new(false),
+                    };
+                    let error = match cmd.send(&session).await {
+                        Ok(()) => None,
+                        Err(e) => {
+                            warn!(message = %dialog.message, error = %e, "Failed to handle JavaScript dialog");
+                            Some(e.to_string())
+                        }
+                    };
+                    handled
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .push(HandledDialog {
+                            dialog,
+                            action,
+                            error,
+                        });
+                    count_tx.send_modify(|count| *count += 1);
+                }
+            })
+        };
+
+        Ok(Self {
+            policy,
+            handled,
+            count,
+            task,
+        })
+    }
+
+    /// Replace the policy for dialogs opened from now on.
+    pub fn set_policy(&self, policy: DialogPolicy) {
+        *self.policy.lock().unwrap_or_else(|e| e.into_inner()) = policy;
+    }
+
+    /// Every dialog handled so far, oldest first.
+    pub fn handled(&self) -> Vec<HandledDialog> {
+        self.handled
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// The most recently handled dialog.
+    pub fn last(&self) -> Option<HandledDialog> {
+        self.handled
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .last()
+            .cloned()
+    }
+
+    /// Forget handled dialogs.
+    pub fn clear(&self) {
+        self.handled
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+
+    /// Wait until the next dialog, opened after this call, has been handled.
+    ///
+    /// Returns [`CdpError::Timeout`] if no dialog opens within `timeout`.
+    pub async fn wait_for_dialog(&self, timeout: Duration) -> Result<HandledDialog, CdpError> {
+        let mut count = self.count.clone();
+        count.borrow_and_update();
+        tokio::time::timeout(timeout, count.changed())
+            .await
+            .map_err(|_| CdpError::Timeout)?
+            .map_err(|_| CdpError::ConnectionClosed)?;
+        self.last().ok_or(CdpError::ConnectionClosed)
+    }
+}
+
+impl Drop for DialogHandler {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn dialog(type_: &str) -> JavascriptDialogOpening {
+        serde_json::from_value(json!({
+            "url": "https://a.test/", "frameId": "F", "message": "Name?", "type": type_,
+            "hasBrowserHandler": false, "defaultPrompt": "anon"
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn prompt_text_only_applies_to_prompts() {
+        let policy = DialogPolicy::PromptText("Ada".to_string());
+        assert_eq!(
+            policy.action(&dialog("prompt")),
+            DialogAction::AcceptWith("Ada".to_string())
+        );
+        assert_eq!(policy.action(&dialog("confirm")), DialogAction::Accept);
+        assert_eq!(
+            DialogPolicy::Dismiss.action(&dialog("beforeunload")),
+            DialogAction::Dismiss
+        );
+    }
+
+    #[test]
+    fn callback_decides_per_dialog() {
+        let policy = DialogPolicy::callback(|d| match d.type_ {
+            DialogType::Confirm => DialogAction::Dismiss,
+            _ => DialogAction::Accept,
+        });
+        assert_eq!(policy.action(&dialog("confirm")), DialogAction::Dismiss);
+        assert_eq!(policy.action(&dialog("alert")), DialogAction::Accept);
+    }
+}
@@ -0,0 +1,375 @@
+use crate::browser::events::{DownloadProgress as ProgressEvent, DownloadWillBegin};
+use crate::{
+    browser, decode_event, merged_event_stream, spawn_cleanup, target, CdpError, EventStream, CDP,
+};
+use futures::StreamExt;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::sync::{mpsc, watch};
+use tracing::warn;
+
+const DOWNLOAD_EVENTS: &[&str] = &["Browser.downloadWillBegin", "Browser.downloadProgress"];
+
+/// Lifecycle state of a [`Download`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DownloadState {
+    InProgress,
+    Completed,
+    Canceled,
+}
+
+impl DownloadState {
+    fn parse(state: &str) -> Self {
+        match state {
+            "completed" => Self::Completed,
+            "canceled" => Self::Canceled,
+            _ => Self::InProgress,
+        }
+    }
+
+    pub fn is_finished(self) -> bool {
+        self != Self::InProgress
+    }
+}
+
+/// Latest `Browser.downloadProgress` for a download.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DownloadStatus {
+    pub state: DownloadState,
+    pub received_bytes: f64,
+    /// Zero when the server sent no `Content-Length`.
+    pub total_bytes: f64,
+}
+
+impl DownloadStatus {
+    /// Fraction received, if the total size is known.
+    pub fn fraction(&self) -> Option<f64> {
+        (self.total_bytes > 0.0).then(|| self.received_bytes / self.total_bytes)
+    }
+}
+
+/// Configuration for [`Downloads::start`].
+#[derive(Debug, Clone)]
+pub struct DownloadOptions {
+    download_path: PathBuf,
+    browser_context_id: Option<String>,
+}
+
+impl DownloadOptions {
+    /// Save downloads into `download_path`, created if missing.
+    pub fn new(download_path: impl Into<PathBuf>) -> Self {
+        Self {
+            download_path: download_path.into(),
+            browser_context_id: None,
+        }
+    }
+
+    /// Apply to one browser context instead of the default context.
+    pub fn with_browser_context_id(mut self, browser_context_id: impl Into<String>) -> Self {
+        self.browser_context_id = Some(browser_context_id.into());
+        self
+    }
+}
+
+/// One file download, from `Browser.downloadWillBegin` until it completes or is canceled.
+///
+/// Chrome writes the file as `<download dir>/<guid>`; use [`save_as`](Self::save_as) to
+/// copy it to its final name.
+pub struct Download {
+    cdp: CDP,
+    guid: String,
+    url: String,
+    suggested_filename: String,
+    frame_id: String,
+    path: PathBuf,
+    browser_context_id: Option<String>,
+    status: watch::Receiver<DownloadStatus>,
+}
+
+impl std::fmt::Debug for Download {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Download")
+            .field("guid", &self.guid)
+            .field("url", &self.url)
+            .field("suggested_filename", &self.suggested_filename)
+            .field("status", &*self.status.borrow())
+            .finish()
+    }
+}
+
+impl Download {
+    pub fn guid(&self) -> &str {
+        &self.guid
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// File name from `Content-Disposition` or the URL, as Chrome would save it.
+    pub fn suggested_filename(&self) -> &str {
+        &self.suggested_filename
+    }
+
+    /// Frame that started the download.
+    pub fn frame_id(&self) -> &str {
+        &self.frame_id
+    }
+
+    /// Where Chrome writes the file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Latest progress.
+    pub fn status(&self) -> DownloadStatus {
+        self.status.borrow().clone()
+    }
+
+    /// Stream of progress updates, starting with the current status and ending after the
+    /// download finishes.
+    pub fn progress(&self) -> EventStream<DownloadStatus> {
+        let mut rx = self.status.clone();
+        rx.mark_changed();
+        Box::pin(futures::stream::unfold(
+            (rx, false),
+            |(mut rx, finished)| async move {
+                if finished {
+                    return None;
+                }
+                rx.changed().await.ok()?;
+                let status = rx.borrow_and_update().clone();
+                let finished = status.state.is_finished();
+                Some((status, (rx, finished)))
+            },
+        ))
+    }
+
+    /// Wait until the download finishes and return the path of the saved file.
+    ///
+    /// Returns [`CdpError::DownloadCanceled`] if it was canceled, and
+    /// [`CdpError::ConnectionClosed`] if the browser went away or the [`Downloads`] that
+    /// yielded this download was dropped first.
+    pub async fn wait_for_completion(&self) -> Result<PathBuf, CdpError> {
+        let mut rx = self.status.clone();
+        let status = rx
+            .wait_for(|status| status.state.is_finished())
+            .await
+            .map_err(|_| CdpError::ConnectionClosed)?
+            .clone();
+        match status.state {
+            DownloadState::Completed => Ok(self.path.clone()),
+            _ => Err(CdpError::DownloadCanceled(self.suggested_filename.clone())),
+        }
+    }
+
+    /// Wait for completion, then copy the file to `destination`.
+    pub async fn save_as(&self, destination: impl AsRef<Path>) -> Result<(), CdpError> {
+        let source = self.wait_for_completion().await?;
+        tokio::fs::copy(source, destination)
+            .await
+            .map_err(|e| CdpError::Io(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Cancel with `Browser.cancelDownload`. Has no effect once the download finished.
+    pub async fn cancel(&self) -> Result<(), CdpError> {
+        let mut cmd = browser::methods::CancelDownload::new(&self.guid);
+        if let Some(id) = &self.browser_context_id {
+            cmd = cmd.with_browser_context_id(id.clone());
+        }
+        cmd.send(&self.cdp).await
+    }
+}
+
+/// Accepts file downloads into a directory and yields a [`Download`] per started download.
+///
+/// Calls `Browser.setDownloadBehavior` with `allowAndName` and events enabled, for the
+/// default browser context or the one in [`DownloadOptions`], and only yields downloads
+/// started by pages of that context. A download is attributed through the target of the
+/// frame that started it; downloads whose frame cannot be resolved to a target are yielded
+/// by every `Downloads`.
+///
+/// Dropping it restores the default behavior and stops tracking progress, so keep it alive
+/// until the [`Download`]s it yielded have finished.
+///
+/// # Example
+/// ```no_run
+/// # use cdpkit::{DownloadOptions, Downloads, CDP};
+/// # use futures::StreamExt;
+/// # async fn example(cdp: CDP) -> Result<(), cdpkit::CdpError> {
+/// let mut downloads = Downloads::start(&cdp, DownloadOptions::new("downloads")).await?;
+/// // ... click a download link ...
+/// if let Some(download) = downloads.next().await {
+///     download
+///         .save_as(format!("out/{}", download.suggested_filename()))
+///         .await?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct Downloads {
+    cdp: CDP,
+    browser_context_id: Option<String>,
+    receiver: mpsc::UnboundedReceiver<Download>,
+    task: tokio::task::JoinHandle<()>,
+    restore_on_drop: bool,
+}
+
+impl Downloads {
+    /// Create the download directory, subscribe to download events and allow downloads.
+    pub async fn start(cdp: &CDP, options: DownloadOptions) -> Result<Self, CdpError> {
+        tokio::fs::create_dir_all(&options.download_path)
+            .await
+            .map_err(|e| CdpError::Io(e.to_string()))?;
+        // Chrome resolves relative download paths against its own working directory.
+        let dir = if options.download_path.is_absolute() {
+            options.download_path.clone()
+        } else {
+            std::env::current_dir()
+                .map_err(|e| CdpError::Io(e.to_string()))?
+                .join(&options.download_path)
+        };
+
+        let mut events = merged_event_stream(cdp, DOWNLOAD_EVENTS);
+        let mut cmd = browser::methods::SetDownloadBehavior::new("allowAndName")
+            .with_download_path(dir.to_string_lossy())
+            .with_events_enabled(true);
+        if let Some(id) = &options.browser_context_id {
+            cmd = cmd.with_browser_context_id(id.clone());
+        }
+        cmd.send(cdp).await?;
+
+        let (tx, receiver) = mpsc::unbounded_channel();
+        let task = {
+            let cdp = cdp.clone();
+            let browser_context_id = options.browser_context_id.clone();
+            tokio::spawn(async move {
+                let mut active: HashMap<String, watch::Sender<DownloadStatus>> = HashMap::new();
+                while let Some((name, params)) = events.next().await {
+                    match &*name {
+                        "Browser.downloadWillBegin" => {
+                            let Some(event) = decode_event::<DownloadWillBegin>(&name, &params)
+                            else {
+                                continue;
+                            };
+                            if !started_in_context(
+                                &cdp,
+                                &event.frame_id,
+                                browser_context_id.as_deref(),
+                            )
+                            .await
+                            {
+                                continue;
+                            }
+                            let (status_tx, status) = watch::channel(DownloadStatus {
+                                state: DownloadState::InProgress,
+                                received_bytes: 0.0,
+                                total_bytes: 0.0,
+                            });
+                            active.insert(event.guid.clone(), status_tx);
+                            let _ = tx.send(Download {
+                                cdp: cdp.clone(),
+                                path: dir.join(&event.guid),
+                                guid: event.guid,
+                                url: event.url,
+                                suggested_filename: event.suggested_filename,
+                                frame_id: event.frame_id,
+                                browser_context_id: browser_context_id.clone(),
+                                status,
+                            });
+                        }
+                        "Browser.downloadProgress" => {
+                            let Some(event) = decode_event::<ProgressEvent>(&name, &params) else {
+                                continue;
+                            };
+                            let state = DownloadState::parse(&event.state);
+                            let Some(status_tx) = active.get(&event.guid) else {
+                                continue;
+                            };
+                            status_tx.send_replace(DownloadStatus {
+                                state,
+                                received_bytes: event.received_bytes,
+                                total_bytes: event.total_bytes,
+                            });
+                            if state.is_finished() {
+                                active.remove(&event.guid);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            })
+        };
+
+        Ok(Self {
+            cdp: cdp.clone(),
+            browser_context_id: options.browser_context_id,
+            receiver,
+            task,
+            restore_on_drop: true,
+        })
+    }
+
+    /// Do not restore the download behavior on drop, e.g. because the browser context
+    /// that owns it is being disposed.
+    pub(crate) fn skip_restore(&mut self) {
+        self.restore_on_drop = false;
+    }
+}
+
+/// Whether the frame that started a download belongs to `browser_context_id`, or to the
+/// default context if `None`. Main frames share their id with their page target; a frame
+/// that is not a target, or a failed lookup, counts as belonging.
+async fn started_in_context(cdp: &CDP, frame_id: &str, browser_context_id: Option<&str>) -> bool {
+    let Ok(info) = target::methods::GetTargetInfo::new()
+        .with_target_id(frame_id.to_owned())
+        .send(cdp)
+        .await
+    else {
+        return true;
+    };
+    let Some(context) = info.target_info.browser_context_id else {
+        return true;
+    };
+    match browser_context_id {
+        Some(id) => context == id,
+        // `Target.getBrowserContexts` lists every context except the default one.
+        None => target::methods::GetBrowserContexts::new()
+            .send(cdp)
+            .await
+            .map_or(true, |contexts| {
+                !contexts.browser_context_ids.contains(&context)
+            }),
+    }
+}
+
+impl futures::Stream for Downloads {
+    type Item = Download;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+impl Drop for Downloads {
+    fn drop(&mut self) {
+        self.task.abort();
+        if !self.restore_on_drop {
+            return;
+        }
+        let cdp = self.cdp.clone();
+        let browser_context_id = self.browser_context_id.take();
+        spawn_cleanup(async move {
+            let mut cmd = browser::methods::SetDownloadBehavior::new("default");
+            if let Some(id) = browser_context_id {
+                cmd = cmd.with_browser_context_id(id);
+            }
+            if let Err(e) = cmd.send(&cdp).await {
+                warn!(error = %e, "Failed to restore download behavior");
+            }
+        });
+    }
+}
@@ -0,0 +1,544 @@
+use crate::dom::types::BackendNodeId;
+use crate::runtime::types::{CallArgument, RemoteObject, RemoteObjectId};
+use crate::{
+    dom, runtime, CdpError, CleanupTarget, ClickOptions, Keyboard, Mouse, Page, ScreenshotOptions,
+    Sender,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Axis-aligned bounding box of an element's border box, in CSS pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BoundingBox {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl BoundingBox {
+    /// Build the smallest box containing every point of a CDP quad (`[x1, y1, x2, y2, ...]`).
+    pub(crate) fn from_quad(quad: &[f64]) -> Option<Self> {
+        if quad.len() < 8 || quad.len() % 2 != 0 {
+            return None;
+        }
+        let xs = quad.iter().step_by(2);
+        let ys = quad.iter().skip(1).step_by(2);
+        let min_x = xs.clone().copied().fold(f64::INFINITY, f64::min);
+        let max_x = xs.copied().fold(f64::NEG_INFINITY, f64::max);
+        let min_y = ys.clone().copied().fold(f64::INFINITY, f64::min);
+        let max_y = ys.copied().fold(f64::NEG_INFINITY, f64::max);
+        Some(Self {
+            x: min_x,
+            y: min_y,
+            width: max_x - min_x,
+            height: max_y - min_y,
+        })
+    }
+
+    /// Center point of the box.
+    pub fn center(&self) -> (f64, f64) {
+        (self.x + self.width / 2.0, self.y + self.height / 2.0)
+    }
+}
+
+/// A `Runtime` object group holding the remote objects of the handles created together,
+/// released with `Runtime.releaseObjectGroup` when the last of them is dropped.
+struct ObjectGroup {
+    name: String,
+    cleanup: CleanupTarget,
+    /// Set once a handle holds the group; a group no handle used holds no objects.
+    in_use: AtomicBool,
+}
+
+impl ObjectGroup {
+    fn new<S: Sender + ?Sized>(target: &S) -> Arc<Self> {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+        Arc::new(Self {
+            name: format!("cdpkit-element-{}", NEXT_ID.fetch_add(1, Ordering::Relaxed)),
+            cleanup: CleanupTarget::new(target),
+            in_use: AtomicBool::new(false),
+        })
+    }
+
+    /// The group for a handle holding one of its objects.
+    fn share(self: &Arc<Self>) -> Arc<Self> {
+        self.in_use.store(true, Ordering::Relaxed);
+        Arc::clone(self)
+    }
+}
+
+impl Drop for ObjectGroup {
+    fn drop(&mut self) {
+        if self.in_use.load(Ordering::Relaxed) {
+            self.cleanup
+                .spawn_send(runtime::methods::ReleaseObjectGroup::new(self.name.clone()));
+        }
+    }
+}
+
+/// Handle to a DOM element, backed by a `BackendNodeId` and a runtime remote object.
+///
+/// Unlike the `NodeId`s returned by `DOM.querySelector`, a backend node id stays valid
+/// across `DOM.documentUpdated` and does not require `DOM.getDocument` to be called first.
+/// If the cached remote object is released or its execution context is destroyed, the
+/// handle re-resolves it from the backend node id on the next call. A node that was
+/// removed from the document and re-created by the page is a different node and cannot
+/// be recovered.
+///
+/// Remote objects the handle resolves belong to an object group shared with its clones
+/// and the other handles of the same query; the group is released once all of them are
+/// dropped.
+pub struct ElementHandle<'a, S> {
+    target: &'a S,
+    backend_node_id: BackendNodeId,
+    object_id: Mutex<RemoteObjectId>,
+    object_group: Arc<ObjectGroup>,
+}
+
+impl<S> Clone for ElementHandle<'_, S> {
+    fn clone(&self) -> Self {
+        Self {
+            target: self.target,
+            backend_node_id: self.backend_node_id,
+            object_id: Mutex::new(self.object_id()),
+            object_group: Arc::clone(&self.object_group),
+        }
+    }
+}
+
+impl<S> std::fmt::Debug for ElementHandle<'_, S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ElementHandle")
+            .field("backend_node_id", &self.backend_node_id)
+            .field("object_id", &self.object_id())
+            .finish()
+    }
+}
+
+impl<S> ElementHandle<'_, S> {
+    /// Backend node id of this element; stable for the lifetime of the node.
+    pub fn backend_node_id(&self) -> BackendNodeId {
+        self.backend_node_id
+    }
+
+    /// Remote object id currently used for JavaScript calls on this element.
+    pub fn object_id(&self) -> RemoteObjectId {
+        self.object_id
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+impl<'a, S: Sender + Sync> ElementHandle<'a, S> {
+    /// Create a handle from a backend node id, e.g. one reported by the accessibility tree.
+    pub async fn from_backend_node_id(
+        target: &'a S,
+        backend_node_id: BackendNodeId,
+    ) -> Result<Self, CdpError> {
+        let object_group = ObjectGroup::new(target);
+        let object_id = resolve_backend_node(target, backend_node_id, &object_group.name).await?;
+        Ok(Self {
+            target,
+            backend_node_id,
+            object_id: Mutex::new(object_id),
+            object_group: object_group.share(),
+        })
+    }
+
+    /// Create a handle from a remote object that references a DOM element.
+    ///
+    /// Returns `None` when the object is `null`/`undefined` or not a DOM node, releasing
+    /// objects that are not nodes. A node object stays in its own object group and is not
+    /// released with the handle.
+    pub async fn from_remote_object(
+        target: &'a S,
+        object: RemoteObject,
+    ) -> Result<Option<Self>, CdpError> {
+        Self::from_grouped_object(target, object, &ObjectGroup::new(target)).await
+    }
+
+    /// Like [`from_remote_object`](Self::from_remote_object) for an object of
+    /// `object_group`, which the handle then shares.
+    async fn from_grouped_object(
+        target: &'a S,
+        object: RemoteObject,
+        object_group: &Arc<ObjectGroup>,
+    ) -> Result<Option<Self>, CdpError> {
+        let Some(object_id) = object.object_id else {
+            return Ok(None);
+        };
+        if object.subtype.as_deref() != Some("node") {
+            let _ = runtime::methods::ReleaseObject::new(object_id)
+                .send(target)
+                .await;
+            return Ok(None);
+        }
+        let node = dom::methods::DescribeNode::new()
+            .with_object_id(object_id.clone())
+            .send(target)
+            .await?
+            .node;
+        Ok(Some(Self {
+            target,
+            backend_node_id: node.backend_node_id,
+            object_id: Mutex::new(object_id),
+            object_group: object_group.share(),
+        }))
+    }
+
+    /// Find the first descendant matching a CSS selector.
+    pub async fn query_selector(&self, selector: &str) -> Result<Option<Self>, CdpError> {
+        let object_group = ObjectGroup::new(self.target);
+        let object = self
+            .call_function_in_group(
+                "function(selector) { return this.querySelector(selector); }",
+                vec![string_argument(selector)],
+                false,
+                Some(&object_group.name),
+            )
+            .await?;
+        Self::from_grouped_object(self.target, object, &object_group).await
+    }
+
+    /// Find all descendants matching a CSS selector, in document order.
+    pub async fn query_selector_all(&self, selector: &str) -> Result<Vec<Self>, CdpError> {
+        let object_group = ObjectGroup::new(self.target);
+        let array = self
+            .call_function_in_group(
+                "function(selector) { return Array.from(this.querySelectorAll(selector)); }",
+                vec![string_argument(selector)],
+                false,
+                Some(&object_group.name),
+            )
+            .await?;
+        collect_array_elements(self.target, array, &object_group).await
+    }
+
+    /// The element's `textContent`.
+    pub async fn text(&self) -> Result<String, CdpError> {
+        let value = self
+            .call_function("function() { return this.textContent; }", vec![], true)
+            .await?;
+        Ok(value
+            .value
+            .as_ref()
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string())
+    }
+
+    /// Value of an attribute, or `None` if the element does not have it.
+    pub async fn attribute(&self, name: &str) -> Result<Option<String>, CdpError> {
+        let value = self
+            .call_function(
+                "function(name) { return this.getAttribute(name); }",
+                vec![string_argument(name)],
+                true,
+            )
+            .await?;
+        Ok(value
+            .value
+            .as_ref()
+            .and_then(Value::as_str)
+            .map(str::to_string))
+    }
+
+    /// Border box of the element from `DOM.getBoxModel`, or `None` if it is not rendered.
+    pub async fn bounding_box(&self) -> Result<Option<BoundingBox>, CdpError> {
+        border_box(self.target, self.backend_node_id).await
+    }
+
+    /// Scroll the element into view if it is not already visible.
+    pub async fn scroll_into_view(&self) -> Result<(), CdpError> {
+        dom::methods::ScrollIntoViewIfNeeded::new()
+            .with_backend_node_id(self.backend_node_id)
+            .send(self.target)
+            .await
+    }
+
+    /// Scroll the element into view and click the center of its box with the left button.
+    pub async fn click(&self) -> Result<(), CdpError> {
+        self.scroll_into_view().await?;
+        let (x, y) = self.clickable_point().await?;
+        Mouse::new(self.target)
+            .click(x, y, ClickOptions::default())
+            .await
+    }
+
+    /// Focus the element and type `text` with synthesized key events.
+    pub async fn type_text(&self, text: &str) -> Result<(), CdpError> {
+        self.focus().await?;
+        Keyboard::new(self.target).type_text(text).await
+    }
+
+    /// Capture a screenshot clipped to the element's border box.
+    pub async fn screenshot(&self, options: ScreenshotOptions) -> Result<Vec<u8>, CdpError> {
+        Page::new(self.target)
+            .screenshot(ScreenshotOptions {
+                element: Some(self.backend_node_id),
+                ..options
+            })
+            .await
+    }
+
+    /// Focus the element.
+    pub async fn focus(&self) -> Result<(), CdpError> {
+        dom::methods::Focus::new()
+            .with_backend_node_id(self.backend_node_id)
+            .send(self.target)
+            .await
+    }
+
+    /// Call a JavaScript function with `this` bound to the element.
+    ///
+    /// Exceptions thrown by the function are returned as [`CdpError::JavaScriptException`].
+    pub async fn call_function(
+        &self,
+        function_declaration: &str,
+        arguments: Vec<CallArgument>,
+        return_by_value: bool,
+    ) -> Result<RemoteObject, CdpError> {
+        self.call_function_in_group(function_declaration, arguments, return_by_value, None)
+            .await
+    }
+
+    /// Like [`call_function`](Self::call_function), placing a returned object in
+    /// `object_group`.
+    async fn call_function_in_group(
+        &self,
+        function_declaration: &str,
+        arguments: Vec<CallArgument>,
+        return_by_value: bool,
+        object_group: Option<&str>,
+    ) -> Result<RemoteObject, CdpError> {
+        let object_id = self.object_id();
+        let call = |object_id: RemoteObjectId| {
+            let mut cmd = runtime::methods::CallFunctionOn::new(function_declaration)
+                .with_object_id(object_id)
+                .with_arguments(arguments.clone())
+                .with_return_by_value(return_by_value)
+                .with_await_promise(true);
+            if let Some(object_group) = object_group {
+                cmd = cmd.with_object_group(object_group);
+            }
+            cmd.send(self.target)
+        };
+        let response = match call(object_id).await {
+            Err(e) if is_stale_object_error(&e) => {
+                let object_id = resolve_backend_node(
+                    self.target,
+                    self.backend_node_id,
+                    &self.object_group.name,
+                )
+                .await?;
+                *self.object_id.lock().unwrap_or_else(|e| e.into_inner()) = object_id.clone();
+                call(object_id).await?
+            }
+            other => other?,
+        };
+        match response.exception_details {
+            Some(details) => Err(exception_error(&details)),
+            None => Ok(response.result),
+        }
+    }
+
+    async fn clickable_point(&self) -> Result<(f64, f64), CdpError> {
+        let result = dom::methods::GetBoxModel::new()
+            .with_backend_node_id(self.backend_node_id)
+            .send(self.target)
+            .await;
+        let model = match result {
+            Ok(response) => response.model,
+            Err(CdpError::Protocol { message, .. }) if message.contains("box model") => {
+                return Err(CdpError::ElementNotVisible);
+            }
+            Err(e) => return Err(e),
+        };
+        match BoundingBox::from_quad(&model.content) {
+            Some(bbox) if bbox.width > 0.0 && bbox.height > 0.0 => Ok(bbox.center()),
+            _ => Err(CdpError::ElementNotVisible),
+        }
+    }
+}
+
+/// Border box of a node from `DOM.getBoxModel`, or `None` if it is not rendered.
+pub(crate) async fn border_box<S: Sender + Sync>(
+    target: &S,
+    backend_node_id: BackendNodeId,
+) -> Result<Option<BoundingBox>, CdpError> {
+    let result = dom::methods::GetBoxModel::new()
+        .with_backend_node_id(backend_node_id)
+        .send(target)
+        .await;
+    match result {
+        Ok(response) => Ok(BoundingBox::from_quad(&response.model.border)),
+        Err(CdpError::Protocol { message, .. }) if message.contains("box model") => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Find the first element in the document matching a CSS selector.
+pub(crate) async fn query_document<'a, S: Sender + Sync>(
+    target: &'a S,
+    selector: &str,
+) -> Result<Option<ElementHandle<'a, S>>, CdpError> {
+    let object_group = ObjectGroup::new(target);
+    let object = evaluate(
+        target,
+        format!("document.querySelector({})", js_string(selector)),
+        &object_group.name,
+    )
+    .await?;
+    ElementHandle::from_grouped_object(target, object, &object_group).await
+}
+
+/// Find all elements in the document matching a CSS selector.
+pub(crate) async fn query_document_all<'a, S: Sender + Sync>(
+    target: &'a S,
+    selector: &str,
+) -> Result<Vec<ElementHandle<'a, S>>, CdpError> {
+    let object_group = ObjectGroup::new(target);
+    let array = evaluate(
+        target,
+        format!(
+            "Array.from(document.querySelectorAll({}))",
+            js_string(selector)
+        ),
+        &object_group.name,
+    )
+    .await?;
+    collect_array_elements(target, array, &object_group).await
+}
+
+async fn evaluate<S: Sender + Sync>(
+    target: &S,
+    expression: String,
+    object_group: &str,
+) -> Result<RemoteObject, CdpError> {
+    let response = runtime::methods::Evaluate::new(expression)
+        .with_object_group(object_group)
+        .send(target)
+        .await?;
+    match response.exception_details {
+        Some(details) => Err(exception_error(&details)),
+        None => Ok(response.result),
+    }
+}
+
+/// Handles for the elements of `array`, an object of `object_group`; its elements are
+/// in the same group.
+async fn collect_array_elements<'a, S: Sender + Sync>(
+    target: &'a S,
+    array: RemoteObject,
+    object_group: &Arc<ObjectGroup>,
+) -> Result<Vec<ElementHandle<'a, S>>, CdpError> {
+    let Some(array_id) = array.object_id else {
+        return Ok(Vec::new());
+    };
+    let properties = runtime::methods::GetProperties::new(array_id.clone())
+        .with_own_properties(true)
+        .send(target)
+        .await;
+    let _ = runtime::methods::ReleaseObject::new(array_id)
+        .send(target)
+        .await;
+
+    let mut indexed: Vec<(usize, RemoteObject)> = properties?
+        .result
+        .into_iter()
+        .filter_map(|property| Some((property.name.parse().ok()?, property.value?)))
+        .collect();
+    indexed.sort_by_key(|(index, _)| *index);
+
+    let mut elements = Vec::with_capacity(indexed.len());
+    for (_, object) in indexed {
+        if let Some(element) =
+            ElementHandle::from_grouped_object(target, object, object_group).await?
+        {
+            elements.push(element);
+        }
+    }
+    Ok(elements)
+}
+
+async fn resolve_backend_node<S: Sender + Sync>(
+    target: &S,
+    backend_node_id: BackendNodeId,
+    object_group: &str,
+) -> Result<RemoteObjectId, CdpError> {
+    dom::methods::ResolveNode::new()
+        .with_backend_node_id(backend_node_id)
+        .with_object_group(object_group)
+        .send(target)
+        .await?
+        .object
+        .object_id
+        .ok_or_else(|| CdpError::protocol(-32000, "No node with given id found"))
+}
+
+/// Remote objects die with their execution context or object group; the node itself may live on.
+fn is_stale_object_error(error: &CdpError) -> bool {
+    match error {
+        CdpError::Protocol { message, .. } => {
+            message.contains("Could not find object with given id")
+                || message.contains("Cannot find context with specified id")
+        }
+        _ => false,
+    }
+}
+
+pub(crate) fn exception_error(details: &runtime::types::ExceptionDetails) -> CdpError {
+    let message = details
+        .exception
+        .as_ref()
+        .and_then(|exception| exception.description.clone())
+        .unwrap_or_else(|| details.text.clone());
+    CdpError::JavaScriptException(message)
+}
+
+fn string_argument(value: &str) -> CallArgument {
+    CallArgument {
+        value: Some(Value::String(value.to_string())),
+        unserializable_value: None,
+        object_id: None,
+    }
+}
+
+fn js_string(value: &str) -> String {
+    Value::String(value.to_string()).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounding_box_from_rotated_quad() {
+        let quad = [10.0, 0.0, 20.0, 10.0, 10.0, 20.0, 0.0, 10.0];
+        let bbox = BoundingBox::from_quad(&quad).unwrap();
+        assert_eq!(
+            bbox,
+            BoundingBox {
+                x: 0.0,
+                y: 0.0,
+                width: 20.0,
+                height: 20.0
+            }
+        );
+        assert_eq!(bbox.center(), (10.0, 10.0));
+    }
+
+    #[test]
+    fn bounding_box_rejects_short_quad() {
+        assert!(BoundingBox::from_quad(&[0.0, 0.0, 1.0]).is_none());
+    }
+
+    #[test]
+    fn selectors_are_quoted_for_javascript() {
+        assert_eq!(js_string(r#"a[href="x"]"#), r#""a[href=\"x\"]""#);
+    }
+}
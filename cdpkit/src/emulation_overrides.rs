@@ -0,0 +1,374 @@
+use crate::{emulation, spawn_cleanup, CdpError, OwnedSession};
+use std::sync::Arc;
+use tokio::sync::{Mutex, MutexGuard};
+use tracing::warn;
+
+/// A position for `Emulation.setGeolocationOverride`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Geolocation {
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Accuracy radius in meters.
+    pub accuracy: f64,
+}
+
+impl Geolocation {
+    /// A position with 1 m accuracy.
+    pub fn new(latitude: f64, longitude: f64) -> Self {
+        Self {
+            latitude,
+            longitude,
+            accuracy: 1.0,
+        }
+    }
+}
+
+/// Viewport metrics for `Emulation.setDeviceMetricsOverride`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DeviceMetrics {
+    pub width: i64,
+    pub height: i64,
+    /// Device pixel ratio; 0 keeps the screen's own.
+    pub device_scale_factor: f64,
+    /// Emulate a mobile viewport (meta viewport, overlay scrollbars, text autosizing).
+    pub mobile: bool,
+}
+
+impl DeviceMetrics {
+    /// A desktop viewport of this size at the screen's pixel ratio.
+    pub fn new(width: i64, height: i64) -> Self {
+        Self {
+            width,
+            height,
+            device_scale_factor: 0.0,
+            mobile: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Override {
+    Timezone(String),
+    Locale(String),
+    Geolocation(Geolocation),
+    DeviceMetrics(DeviceMetrics),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OverrideKind {
+    Timezone,
+    Locale,
+    Geolocation,
+    DeviceMetrics,
+}
+
+impl Override {
+    fn kind(&self) -> OverrideKind {
+        match self {
+            Self::Timezone(_) => OverrideKind::Timezone,
+            Self::Locale(_) => OverrideKind::Locale,
+            Self::Geolocation(_) => OverrideKind::Geolocation,
+            Self::DeviceMetrics(_) => OverrideKind::DeviceMetrics,
+        }
+    }
+
+    async fn apply(&self, session: &OwnedSession) -> Result<(), CdpError> {
+        match self {
+            Self::Timezone(timezone_id) => {
+                emulation::methods::SetTimezoneOverride::new(timezone_id)
+                    .send(session)
+                    .await
+            }
+            Self::Locale(locale) => {
+                emulation::methods::SetLocaleOverride::new()
+                    .with_locale(locale)
+                    .send(session)
+                    .await
+            }
+            Self::Geolocation(position) => {
+                emulation::methods::SetGeolocationOverride::new()
+                    .with_latitude(position.latitude)
+                    .with_longitude(position.longitude)
+                    .with_accuracy(position.accuracy)
+                    .send(session)
+                    .await
+            }
+            Self::DeviceMetrics(metrics) => {
+                emulation::methods::SetDeviceMetricsOverride::new(
+                    metrics.width,
+                    metrics.height,
+                    metrics.device_scale_factor,
+                    metrics.mobile,
+                )
+                .send(session)
+                .await
+            }
+        }
+    }
+}
+
+impl OverrideKind {
+    async fn clear(self, session: &OwnedSession) -> Result<(), CdpError> {
+        match self {
+            // An empty timezone id or a missing locale disables the override.
+            Self::Timezone => {
+                emulation::methods::SetTimezoneOverride::new("")
+                    .send(session)
+                    .await
+            }
+            Self::Locale => {
+                emulation::methods::SetLocaleOverride::new()
+                    .send(session)
+                    .await
+            }
+            Self::Geolocation => {
+                emulation::methods::ClearGeolocationOverride::new()
+                    .send(session)
+                    .await
+            }
+            Self::DeviceMetrics => {
+                emulation::methods::ClearDeviceMetricsOverride::new()
+                    .send(session)
+                    .await
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Stack {
+    next_id: u64,
+    /// Active overrides of all kinds, oldest first.
+    active: Vec<(u64, Override)>,
+}
+
+impl Stack {
+    /// Remove override `id`. If it was the effective override of its kind, returns what
+    /// takes effect instead: the previous override of that kind, or `None` to clear.
+    fn remove(&mut self, id: u64) -> Option<(OverrideKind, Option<Override>)> {
+        let index = self.active.iter().position(|(i, _)| *i == id)?;
+        let (_, removed) = self.active.remove(index);
+        let kind = removed.kind();
+        let same_kind = |(_, o): &&(u64, Override)| o.kind() == kind;
+        if self.active[index..].iter().any(|entry| same_kind(&entry)) {
+            // A newer override of the same kind is still in effect.
+            return None;
+        }
+        let previous = self.active[..index]
+            .iter()
+            .rev()
+            .find(|entry| same_kind(entry))
+            .map(|(_, o)| o.clone());
+        Some((kind, previous))
+    }
+
+    /// The device metrics override currently in effect.
+    fn device_metrics(&self) -> Option<DeviceMetrics> {
+        self.active.iter().rev().find_map(|(_, o)| match o {
+            Override::DeviceMetrics(metrics) => Some(*metrics),
+            _ => None,
+        })
+    }
+}
+
+/// Timezone, locale, geolocation and device metrics overrides for a page session, each
+/// held by a guard.
+///
+/// Releasing an [`OverrideGuard`] restores the override that was in effect before it was
+/// set through the same `EmulationOverrides` (or clears the override if there was none),
+/// so nested and out-of-order guards unwind correctly. Each change to the stack and its
+/// CDP command happen as one step under a lock, so guards dropped together reach Chrome
+/// in order.
+///
+/// # Example
+/// ```no_run
+/// # use cdpkit::{EmulationOverrides, Geolocation, OwnedSession};
+/// # async fn example(session: OwnedSession) -> Result<(), cdpkit::CdpError> {
+/// let overrides = EmulationOverrides::new(&session);
+/// let _tz = overrides.timezone("Asia/Tokyo").await?;
+/// let _locale = overrides.locale("ja-JP").await?;
+/// let _position = overrides.geolocation(Geolocation::new(35.68, 139.69)).await?;
+/// // ... all three are restored when the guards go out of scope ...
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct EmulationOverrides {
+    session: OwnedSession,
+    stack: Arc<Mutex<Stack>>,
+}
+
+impl EmulationOverrides {
+    pub fn new(session: &OwnedSession) -> Self {
+        Self {
+            session: session.clone(),
+            stack: Arc::new(Mutex::new(Stack::default())),
+        }
+    }
+
+    /// Override the timezone with an ICU id such as `Europe/Berlin`.
+    pub async fn timezone(
+        &self,
+        timezone_id: impl Into<String>,
+    ) -> Result<OverrideGuard, CdpError> {
+        self.push(Override::Timezone(timezone_id.into())).await
+    }
+
+    /// Override the ICU locale, e.g. `de-DE`, used by `Intl` and date formatting.
+    pub async fn locale(&self, locale: impl Into<String>) -> Result<OverrideGuard, CdpError> {
+        self.push(Override::Locale(locale.into())).await
+    }
+
+    /// Override the position reported by `navigator.geolocation`. The page also needs the
+    /// `geolocation` permission; see [`Permissions`](crate::Permissions).
+    pub async fn geolocation(&self, position: Geolocation) -> Result<OverrideGuard, CdpError> {
+        self.push(Override::Geolocation(position)).await
+    }
+
+    /// Override the viewport size and pixel ratio. Full-page screenshots taken with these
+    /// overrides in [`ScreenshotOptions::emulation`](crate::ScreenshotOptions::emulation)
+    /// stack their own override on top and restore this one afterwards.
+    pub async fn device_metrics(&self, metrics: DeviceMetrics) -> Result<OverrideGuard, CdpError> {
+        self.push(Override::DeviceMetrics(metrics)).await
+    }
+
+    /// Override the device metrics with `metrics` derived from the ones currently in
+    /// effect, checked and pushed under one lock.
+    pub(crate) async fn push_device_metrics(
+        &self,
+        metrics: impl FnOnce(Option<DeviceMetrics>) -> DeviceMetrics,
+    ) -> Result<OverrideGuard, CdpError> {
+        let stack = self.stack.lock().await;
+        let value = Override::DeviceMetrics(metrics(stack.device_metrics()));
+        self.push_locked(stack, value).await
+    }
+
+    async fn push(&self, value: Override) -> Result<OverrideGuard, CdpError> {
+        let stack = self.stack.lock().await;
+        self.push_locked(stack, value).await
+    }
+
+    async fn push_locked(
+        &self,
+        mut stack: MutexGuard<'_, Stack>,
+        value: Override,
+    ) -> Result<OverrideGuard, CdpError> {
+        value.apply(&self.session).await?;
+        let id = stack.next_id;
+        stack.next_id += 1;
+        stack.active.push((id, value));
+        Ok(OverrideGuard {
+            overrides: self.clone(),
+            id,
+            restored: false,
+        })
+    }
+
+    /// Remove override `id` and apply whatever takes its place, holding the lock so the
+    /// command cannot overtake another push or release.
+    async fn release(&self, id: u64) -> Result<(), CdpError> {
+        let mut stack = self.stack.lock().await;
+        match stack.remove(id) {
+            Some((_, Some(previous))) => previous.apply(&self.session).await,
+            Some((kind, None)) => kind.clear(&self.session).await,
+            None => Ok(()),
+        }
+    }
+}
+
+impl std::fmt::Debug for EmulationOverrides {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EmulationOverrides").finish_non_exhaustive()
+    }
+}
+
+/// Holds one emulation override; releasing it restores the previous state.
+#[must_use = "dropping the guard restores the previous state right away"]
+pub struct OverrideGuard {
+    overrides: EmulationOverrides,
+    id: u64,
+    restored: bool,
+}
+
+impl OverrideGuard {
+    /// Restore the previous state now and wait for Chrome to confirm.
+    pub async fn restore(mut self) -> Result<(), CdpError> {
+        self.restored = true;
+        self.overrides.release(self.id).await
+    }
+}
+
+impl Drop for OverrideGuard {
+    fn drop(&mut self) {
+        if self.restored {
+            return;
+        }
+        let overrides = self.overrides.clone();
+        let id = self.id;
+        spawn_cleanup(async move {
+            if let Err(e) = overrides.release(id).await {
+                warn!(error = %e, "Failed to restore emulation override");
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stack(entries: &[Override]) -> Stack {
+        Stack {
+            next_id: entries.len() as u64,
+            active: entries
+                .iter()
+                .cloned()
+                .enumerate()
+                .map(|(i, o)| (i as u64, o))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn releasing_the_newest_override_restores_the_previous_one() {
+        let mut stack = stack(&[
+            Override::Timezone("Europe/Berlin".into()),
+            Override::Locale("de-DE".into()),
+            Override::Timezone("Asia/Tokyo".into()),
+        ]);
+        assert_eq!(
+            stack.remove(2),
+            Some((
+                OverrideKind::Timezone,
+                Some(Override::Timezone("Europe/Berlin".into()))
+            ))
+        );
+        assert_eq!(stack.remove(0), Some((OverrideKind::Timezone, None)));
+        assert_eq!(stack.remove(1), Some((OverrideKind::Locale, None)));
+        assert_eq!(stack.remove(1), None);
+    }
+
+    #[test]
+    fn releasing_a_shadowed_override_changes_nothing() {
+        let mut stack = stack(&[
+            Override::Locale("de-DE".into()),
+            Override::Locale("fr-FR".into()),
+        ]);
+        assert_eq!(stack.remove(0), None);
+        assert_eq!(stack.remove(1), Some((OverrideKind::Locale, None)));
+    }
+
+    #[test]
+    fn the_newest_device_metrics_are_in_effect() {
+        let mut stack = stack(&[
+            Override::DeviceMetrics(DeviceMetrics::new(390, 844)),
+            Override::Timezone("Asia/Tokyo".into()),
+            Override::DeviceMetrics(DeviceMetrics::new(800, 2400)),
+        ]);
+        assert_eq!(stack.device_metrics(), Some(DeviceMetrics::new(800, 2400)));
+        stack.remove(2);
+        assert_eq!(stack.device_metrics(), Some(DeviceMetrics::new(390, 844)));
+        stack.remove(0);
+        assert_eq!(stack.device_metrics(), None);
+    }
+}
@@ -0,0 +1,14 @@
+use crate::CdpError;
+use base64::Engine;
+
+/// Decode a base64 payload from a CDP response (`binary` protocol type).
+pub(crate) fn decode_base64(data: &str) -> Result<Vec<u8>, CdpError> {
+    base64::engine::general_purpose::STANDARD
+        .decode(data)
+        .map_err(|e| CdpError::InvalidBase64(e.to_string()))
+}
+
+/// Encode bytes for a CDP command parameter of the `binary` protocol type.
+pub(crate) fn encode_base64(data: &[u8]) -> String {
+    base64::engine::general_purpose::STANDARD.encode(data)
+}
@@ -54,6 +54,46 @@ pub enum CdpError {
     /// HTTP discovery response format invalid (cannot parse or missing webSocketDebuggerUrl)
     #[error("Invalid discovery response: {0}")]
     InvalidDiscoveryResponse(String),
+
+    /// Page-side JavaScript threw while evaluating a helper function
+    #[error("JavaScript exception: {0}")]
+    JavaScriptException(String),
+
+    /// Element has no layout box, so it cannot be clicked or measured
+    #[error("Element is not visible or has no layout box")]
+    ElementNotVisible,
+
+    /// Key name not found in the keyboard layout
+    #[error("Unknown key: {0}")]
+    UnknownKey(String),
+
+    /// Binary payload in a CDP response was not valid base64
+    #[error("Invalid base64 payload: {0}")]
+    InvalidBase64(String),
+
+    /// Download was canceled before it completed
+    #[error("Download canceled: {0}")]
+    DownloadCanceled(String),
+
+    /// Heap snapshot JSON did not match the layout described by its `meta` section
+    #[error("Invalid heap snapshot: {0}")]
+    InvalidHeapSnapshot(String),
+
+    /// Source map JSON or its VLQ mappings could not be decoded
+    #[error("Invalid source map: {0}")]
+    InvalidSourceMap(String),
+
+    /// `DOMSnapshot.captureSnapshot` response with missing columns or out-of-range indices
+    #[error("Invalid DOM snapshot: {0}")]
+    InvalidDomSnapshot(String),
+
+    /// CSS selector that could not be parsed or uses unsupported syntax
+    #[error("Invalid selector: {0}")]
+    InvalidSelector(String),
+
+    /// `Page.navigate` reported an `errorText`
+    #[error("Navigation to {url} failed: {error}")]
+    NavigationFailed { url: String, error: String },
 }
 
 impl CdpError {
@@ -0,0 +1,965 @@
+use crate::encoding::decode_base64;
+use crate::network::events::{
+    DataReceived, LoadingFailed, LoadingFinished, RequestServedFromCache, RequestWillBeSent,
+    ResponseReceived,
+};
+use crate::network::types::{ResourceTiming, Response};
+use crate::{
+    decode_event, merged_event_stream, network, CdpError, MergedEventStream, OwnedSession,
+};
+use futures::{FutureExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use tokio::sync::oneshot;
+use tracing::warn;
+
+const NETWORK_EVENTS: &[&str] = &[
+    "Network.requestWillBeSent",
+    "Network.requestWillBeSentExtraInfo",
+    "Network.responseReceived",
+    "Network.responseReceivedExtraInfo",
+    "Network.dataReceived",
+    "Network.loadingFinished",
+    "Network.loadingFailed",
+    "Network.requestServedFromCache",
+];
+
+/// An HTTP Archive (HAR 1.2) document.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Har {
+    pub log: HarLog,
+}
+
+impl Har {
+    /// Serialize as pretty-printed HAR JSON.
+    pub fn to_json(&self) -> Result<String, CdpError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Write the archive to a `.har` file.
+    pub async fn save(&self, path: impl AsRef<Path>) -> Result<(), CdpError> {
+        tokio::fs::write(path, self.to_json()?)
+            .await
+            .map_err(|e| CdpError::Io(e.to_string()))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HarLog {
+    pub version: String,
+    pub creator: HarCreator,
+    pub entries: Vec<HarEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HarCreator {
+    pub name: String,
+    pub version: String,
+}
+
+/// One request/response exchange. Each redirect hop is its own entry.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarEntry {
+    /// ISO 8601 time at which the request was issued.
+    pub started_date_time: String,
+    /// Total time in milliseconds: the sum of the non-negative `timings`.
+    pub time: f64,
+    pub request: HarRequest,
+    pub response: HarResponse,
+    pub cache: HarCache,
+    pub timings: HarTimings,
+    #[serde(rename = "serverIPAddress", skip_serializing_if = "Option::is_none")]
+    pub server_ip_address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connection: Option<String>,
+    /// CDP resource type (`Document`, `XHR`, ...).
+    #[serde(rename = "_resourceType", skip_serializing_if = "Option::is_none")]
+    pub resource_type: Option<String>,
+    /// `Network.loadingFailed` error text for failed requests.
+    #[serde(rename = "_error", skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarRequest {
+    pub method: String,
+    pub url: String,
+    pub http_version: String,
+    pub cookies: Vec<HarCookie>,
+    pub headers: Vec<HarNameValue>,
+    pub query_string: Vec<HarNameValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_data: Option<HarPostData>,
+    pub headers_size: i64,
+    pub body_size: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarResponse {
+    pub status: i64,
+    pub status_text: String,
+    pub http_version: String,
+    pub cookies: Vec<HarCookie>,
+    pub headers: Vec<HarNameValue>,
+    pub content: HarContent,
+    #[serde(rename = "redirectURL")]
+    pub redirect_url: String,
+    pub headers_size: i64,
+    pub body_size: i64,
+    /// Bytes received over the network, including headers.
+    #[serde(rename = "_transferSize", skip_serializing_if = "Option::is_none")]
+    pub transfer_size: Option<i64>,
+    /// `"memory"` or `"disk"` when served from the browser cache.
+    #[serde(rename = "_fromCache", skip_serializing_if = "Option::is_none")]
+    pub from_cache: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HarNameValue {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarCookie {
+    pub name: String,
+    pub value: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub domain: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http_only: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secure: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarPostData {
+    pub mime_type: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarContent {
+    /// Decoded body size in bytes.
+    pub size: i64,
+    pub mime_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// `"base64"` when `text` holds base64-encoded binary content.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
+}
+
+/// Cache information; cdpkit does not record cache entries, so this is always empty.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HarCache {}
+
+/// Phase durations in milliseconds; `-1` marks phases that do not apply.
+///
+/// `ssl` is included in `connect`, as HAR 1.2 specifies.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct HarTimings {
+    pub blocked: f64,
+    pub dns: f64,
+    pub connect: f64,
+    pub send: f64,
+    pub wait: f64,
+    pub receive: f64,
+    pub ssl: f64,
+}
+
+impl HarTimings {
+    /// Sum of all phases that apply, i.e. [`HarEntry::time`].
+    pub fn total(&self) -> f64 {
+        [
+            self.blocked,
+            self.dns,
+            self.connect,
+            self.send,
+            self.wait,
+            self.receive,
+        ]
+        .iter()
+        .filter(|phase| **phase > 0.0)
+        .sum()
+    }
+}
+
+/// Configuration for [`HarRecorder::start`].
+#[derive(Debug, Clone, Default)]
+pub struct HarOptions {
+    include_content: bool,
+}
+
+impl HarOptions {
+    /// Record metadata only, without response bodies.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fetch each response body with `Network.getResponseBody` once it finishes loading.
+    pub fn with_content(mut self, include_content: bool) -> Self {
+        self.include_content = include_content;
+        self
+    }
+}
+
+/// Records `Network` events on a session into an HTTP Archive.
+///
+/// Requests are correlated by `RequestId`; every redirect hop becomes its own entry with
+/// `redirectURL` set. Timings are derived from `ResourceTiming` the way DevTools exports
+/// them. `Network.enable` is sent on start and left enabled on stop.
+///
+/// # Example
+/// ```no_run
+/// # use cdpkit::{HarOptions, HarRecorder};
+/// # use cdpkit::OwnedSession;
+/// # async fn example(session: OwnedSession) -> Result<(), cdpkit::CdpError> {
+/// let recorder = HarRecorder::start(&session, HarOptions::new().with_content(true)).await?;
+/// // ... navigate ...
+/// let har = recorder.stop().await?;
+/// har.save("page.har").await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct HarRecorder {
+    stop: Option<oneshot::Sender<()>>,
+    task: Option<tokio::task::JoinHandle<Recording>>,
+}
+
+impl HarRecorder {
+    /// Subscribe to network events and call `Network.enable`.
+    pub async fn start(session: &OwnedSession, options: HarOptions) -> Result<Self, CdpError> {
+        let events = merged_event_stream(session, NETWORK_EVENTS);
+        network::methods::Enable::new().send(session).await?;
+
+        let (stop_tx, stop_rx) = oneshot::channel();
+        let task = tokio::spawn(record(session.clone(), events, options, stop_rx));
+        Ok(Self {
+            stop: Some(stop_tx),
+            task: Some(task),
+        })
+    }
+
+    /// Stop recording and build the archive. Requests still in flight are included with
+    /// the data received so far.
+    pub async fn stop(mut self) -> Result<Har, CdpError> {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
+        let task = self.task.take().ok_or(CdpError::ChannelClosed)?;
+        let recording = task.await.map_err(|_| CdpError::ChannelClosed)?;
+        Ok(recording.into_har())
+    }
+}
+
+impl Drop for HarRecorder {
+    fn drop(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+    }
+}
+
+async fn record(
+    session: OwnedSession,
+    mut events: MergedEventStream,
+    options: HarOptions,
+    mut stop: oneshot::Receiver<()>,
+) -> Recording {
+    let mut recording = Recording::default();
+    loop {
+        tokio::select! {
+            biased;
+            _ = &mut stop => break,
+            event = events.next() => match event {
+                Some((name, params)) => {
+                    recording.handle(&session, &options, &name, &params).await;
+                }
+                None => return recording,
+            },
+        }
+    }
+    // Events already dispatched before `stop()` still belong to the recording.
+    while let Some(Some((name, params))) = events.next().now_or_never() {
+        recording.handle(&session, &options, &name, &params).await;
+    }
+    recording
+}
+
+/// Everything known about one redirect hop of a request.
+#[derive(Debug)]
+struct PendingEntry {
+    request: network::types::Request,
+    resource_type: Option<String>,
+    wall_time: f64,
+    issue_time: f64,
+    request_headers: Option<Value>,
+    response: Option<Response>,
+    response_headers: Option<Value>,
+    response_headers_text: Option<String>,
+    response_time: Option<f64>,
+    from_memory_cache: bool,
+    data_length: i64,
+    encoded_data_length: Option<f64>,
+    end_time: Option<f64>,
+    redirect_url: Option<String>,
+    error: Option<String>,
+    body: Option<(String, bool)>,
+}
+
+#[derive(Debug, Default)]
+struct Recording {
+    entries: Vec<PendingEntry>,
+    active: HashMap<String, usize>,
+    /// `requestWillBeSentExtraInfo` can arrive before `requestWillBeSent`.
+    early_request_headers: HashMap<String, Value>,
+}
+
+impl Recording {
+    fn active_entry(&mut self, request_id: &str) -> Option<&mut PendingEntry> {
+        let index = *self.active.get(request_id)?;
+        self.entries.get_mut(index)
+    }
+
+    async fn handle(
+        &mut self,
+        session: &OwnedSession,
+        options: &HarOptions,
+        name: &str,
+        params: &Value,
+    ) {
+        match name {
+            "Network.requestWillBeSent" => {
+                if let Some(event) = decode_event(name, params) {
+                    self.request_will_be_sent(event);
+                }
+            }
+            "Network.requestWillBeSentExtraInfo" => {
+                let (Some(request_id), Some(headers)) =
+                    (params["requestId"].as_str(), params.get("headers"))
+                else {
+                    return;
+                };
+                let headers = headers.clone();
+                match self.active_entry(request_id) {
+                    Some(entry) if entry.response.is_none() => {
+                        entry.request_headers = Some(headers)
+                    }
+                    _ => {
+                        self.early_request_headers
+                            .insert(request_id.to_string(), headers);
+                    }
+                }
+            }
+            "Network.responseReceived" => {
+                if let Some(event) = decode_event::<ResponseReceived>(name, params) {
+                    if let Some(entry) = self.active_entry(&event.request_id) {
+                        entry.response_time = Some(event.timestamp);
+                        entry.response = Some(event.response);
+                    }
+                }
+            }
+            "Network.responseReceivedExtraInfo" => {
+                let Some(request_id) = params["requestId"].as_str() else {
+                    return;
+                };
+                let headers_text = params["headersText"].as_str().map(str::to_owned);
+                let headers = params.get("headers").cloned();
+                if let Some(entry) = self.active_entry(request_id) {
+                    entry.response_headers = headers;
+                    entry.response_headers_text = headers_text;
+                }
+            }
+            "Network.dataReceived" => {
+                if let Some(event) = decode_event::<DataReceived>(name, params) {
+                    if let Some(entry) = self.active_entry(&event.request_id) {
+                        entry.data_length += event.data_length;
+                    }
+                }
+            }
+            "Network.requestServedFromCache" => {
+                if let Some(event) = decode_event::<RequestServedFromCache>(name, params) {
+                    if let Some(entry) = self.active_entry(&event.request_id) {
+                        entry.from_memory_cache = true;
+                    }
+                }
+            }
+            "Network.loadingFinished" => {
+                let Some(event) = decode_event::<LoadingFinished>(name, params) else {
+                    return;
+                };
+                let Some(index) = self.active.remove(&event.request_id) else {
+                    return;
+                };
+                let entry = &mut self.entries[index];
+                entry.end_time = Some(event.timestamp);
+                entry.encoded_data_length = Some(event.encoded_data_length);
+                if options.include_content {
+                    match network::methods::GetResponseBody::new(event.request_id.clone())
+                        .send(session)
+                        .await
+                    {
+                        Ok(body) => entry.body = Some((body.body, body.base64_encoded)),
+                        Err(e) => {
+                            warn!(request_id = %event.request_id, error = %e, "Failed to fetch response body for HAR")
+                        }
+                    }
+                }
+            }
+            "Network.loadingFailed" => {
+                let Some(event) = decode_event::<LoadingFailed>(name, params) else {
+                    return;
+                };
+                let Some(index) = self.active.remove(&event.request_id) else {
+                    return;
+                };
+                let entry = &mut self.entries[index];
+                entry.end_time = Some(event.timestamp);
+                entry.error = Some(if event.canceled == Some(true) {
+                    format!("{} (canceled)", event.error_text)
+                } else {
+                    event.error_text
+                });
+            }
+            _ => {}
+        }
+    }
+
+    fn request_will_be_sent(&mut self, event: RequestWillBeSent) {
+        if let Some(redirect_response) = event.redirect_response {
+            if let Some(previous) = self.active_entry(&event.request_id) {
+                previous.response_time.get_or_insert(event.timestamp);
+                previous.encoded_data_length = Some(redirect_response.encoded_data_length);
+                previous.response = Some(redirect_response);
+                previous.end_time = Some(event.timestamp);
+                previous.redirect_url = Some(event.request.url.clone());
+            }
+        }
+
+        self.active
+            .insert(event.request_id.clone(), self.entries.len());
+        self.entries.push(PendingEntry {
+            request_headers: self.early_request_headers.remove(&event.request_id),
+            request: event.request,
+            resource_type: event.type_.map(|t| t.as_ref().to_string()),
+            wall_time: event.wall_time,
+            issue_time: event.timestamp,
+            response: None,
+            response_headers: None,
+            response_headers_text: None,
+            response_time: None,
+            from_memory_cache: false,
+            data_length: 0,
+            encoded_data_length: None,
+            end_time: None,
+            redirect_url: None,
+            error: None,
+            body: None,
+        });
+    }
+
+    fn into_har(self) -> Har {
+        let mut entries: Vec<HarEntry> = self.entries.iter().map(har_entry).collect();
+        entries.sort_by(|a, b| a.started_date_time.cmp(&b.started_date_time));
+        Har {
+            log: HarLog {
+                version: "1.2".to_string(),
+                creator: HarCreator {
+                    name: "cdpkit".to_string(),
+                    version: env!("CARGO_PKG_VERSION").to_string(),
+                },
+                entries,
+            },
+        }
+    }
+}
+
+#[allow(deprecated)]
+fn har_entry(entry: &PendingEntry) -> HarEntry {
+    let response = entry.response.as_ref();
+    let http_version = response
+        .and_then(|r| r.protocol.as_deref())
+        .map(http_version)
+        .unwrap_or_default();
+    let end_time = entry
+        .end_time
+        .or(entry.response_time)
+        .unwrap_or(entry.issue_time);
+    let timings = har_timings(
+        response.and_then(|r| r.timing.as_ref()),
+        entry.issue_time,
+        entry.response_time,
+        end_time,
+    );
+
+    let request_headers = name_values(entry.request_headers.as_ref().unwrap_or_else(|| {
+        response
+            .and_then(|r| r.request_headers.as_ref())
+            .unwrap_or(&entry.request.headers)
+    }));
+    let post_data = entry.request.post_data.clone().or_else(|| {
+        let bytes = entry
+            .request
+            .post_data_entries
+            .iter()
+            .flatten()
+            .filter_map(|part| part.bytes.as_deref())
+            .map(decode_base64)
+            .collect::<Result<Vec<_>, _>>()
+            .ok()?
+            .concat();
+        (!bytes.is_empty()).then(|| String::from_utf8_lossy(&bytes).into_owned())
+    });
+    let request = HarRequest {
+        method: entry.request.method.clone(),
+        url: entry.request.url.clone(),
+        http_version: http_version.clone(),
+        cookies: header_values(&request_headers, "cookie")
+            .flat_map(|value| value.split(';'))
+            .filter_map(parse_cookie)
+            .collect(),
+        query_string: query_string(&entry.request.url),
+        body_size: post_data.as_ref().map_or(0, |data| data.len() as i64),
+        post_data: post_data.map(|text| HarPostData {
+            mime_type: header_values(&request_headers, "content-type")
+                .next()
+                .unwrap_or_default()
+                .to_string(),
+            text,
+        }),
+        headers: request_headers,
+        headers_size: -1,
+    };
+
+    let response_headers = entry
+        .response_headers
+        .as_ref()
+        .or(response.map(|r| &r.headers))
+        .map(name_values)
+        .unwrap_or_default();
+    let headers_size = entry
+        .response_headers_text
+        .as_ref()
+        .or(response.and_then(|r| r.headers_text.as_ref()))
+        .map_or(-1, |text| text.len() as i64);
+    let transfer_size = entry.encoded_data_length.map(|len| len as i64);
+    let (text, encoding) = match &entry.body {
+        Some((body, true)) => (Some(body.clone()), Some("base64".to_string())),
+        Some((body, false)) => (Some(body.clone()), None),
+        None => (None, None),
+    };
+    let content_size = match &entry.body {
+        Some((body, false)) if entry.data_length == 0 => body.len() as i64,
+        Some((body, true)) if entry.data_length == 0 => {
+            decode_base64(body).map_or(0, |bytes| bytes.len() as i64)
+        }
+        _ => entry.data_length,
+    };
+    let from_cache = if entry.from_memory_cache {
+        Some("memory".to_string())
+    } else if response.and_then(|r| r.from_disk_cache) == Some(true) {
+        Some("disk".to_string())
+    } else {
+        None
+    };
+    let har_response = HarResponse {
+        status: response.map_or(0, |r| r.status),
+        status_text: response.map(|r| r.status_text.clone()).unwrap_or_default(),
+        http_version,
+        cookies: header_values(&response_headers, "set-cookie")
+            .filter_map(parse_set_cookie)
+            .collect(),
+        content: HarContent {
+            size: content_size,
+            mime_type: response.map(|r| r.mime_type.clone()).unwrap_or_default(),
+            text,
+            encoding,
+        },
+        redirect_url: entry.redirect_url.clone().unwrap_or_default(),
+        headers: response_headers,
+        headers_size,
+        body_size: match transfer_size {
+            Some(transfer) if headers_size >= 0 => (transfer - headers_size).max(0),
+            _ => -1,
+        },
+        transfer_size,
+        from_cache,
+    };
+
+    HarEntry {
+        started_date_time: format_iso8601(entry.wall_time),
+        time: timings.total(),
+        request,
+        response: har_response,
+        cache: HarCache::default(),
+        timings,
+        server_ip_address: response.and_then(|r| r.remote_ip_address.clone()),
+        connection: response
+            .filter(|r| r.connection_id > 0.0)
+            .map(|r| format!("{}", r.connection_id as i64)),
+        resource_type: entry.resource_type.clone(),
+        error: entry.error.clone(),
+    }
+}
+
+/// Convert `ResourceTiming` offsets into HAR phases, following the DevTools HAR exporter.
+///
+/// `issue_time`, `response_time` and `end_time` are `MonotonicTime` seconds from the
+/// `requestWillBeSent`, `responseReceived` and `loadingFinished`/`loadingFailed` events.
+fn har_timings(
+    timing: Option<&ResourceTiming>,
+    issue_time: f64,
+    response_time: Option<f64>,
+    end_time: f64,
+) -> HarTimings {
+    let Some(t) = timing else {
+        // Cached, data: and failed-before-response requests carry no timing breakdown.
+        let response_time = response_time.unwrap_or(end_time);
+        return HarTimings {
+            blocked: -1.0,
+            dns: -1.0,
+            connect: -1.0,
+            send: 0.0,
+            wait: ((response_time - issue_time) * 1000.0).max(0.0),
+            receive: ((end_time - response_time) * 1000.0).max(0.0),
+            ssl: -1.0,
+        };
+    };
+
+    let least_non_negative = |values: &[f64]| {
+        values
+            .iter()
+            .copied()
+            .filter(|v| *v >= 0.0)
+            .fold(f64::INFINITY, f64::min)
+    };
+
+    let mut blocked = if issue_time < t.request_time {
+        (t.request_time - issue_time) * 1000.0
+    } else {
+        -1.0
+    };
+    let blocked_start = least_non_negative(&[t.dns_start, t.connect_start, t.send_start]);
+    if blocked_start.is_finite() {
+        blocked = blocked.max(0.0) + blocked_start;
+    }
+    if t.proxy_end >= 0.0 && t.proxy_end - t.proxy_start > blocked {
+        blocked = t.proxy_end - t.proxy_start;
+    }
+
+    let dns_end = if t.dns_end >= 0.0 { t.dns_end } else { -1.0 };
+    let dns = if t.dns_end >= 0.0 {
+        t.dns_end - blocked_start
+    } else {
+        -1.0
+    };
+    let ssl = if t.ssl_end > 0.0 {
+        t.ssl_end - t.ssl_start
+    } else {
+        -1.0
+    };
+    let connect_end = if t.connect_end >= 0.0 {
+        t.connect_end
+    } else {
+        -1.0
+    };
+    let connect = if t.connect_end >= 0.0 {
+        t.connect_end
+            - if dns_end >= 0.0 {
+                dns_end
+            } else {
+                blocked_start
+            }
+    } else {
+        -1.0
+    };
+    let blocked_start = if blocked_start.is_finite() {
+        blocked_start
+    } else {
+        0.0
+    };
+    let send = if t.send_end >= 0.0 {
+        (t.send_end - connect_end.max(dns_end).max(blocked_start)).max(0.0)
+    } else {
+        0.0
+    };
+    let highest = [t.send_end, connect_end, t.ssl_end, dns_end, blocked_start]
+        .iter()
+        .copied()
+        .fold(0.0, f64::max);
+    let wait = (t.receive_headers_end - highest).max(0.0);
+    let receive = ((end_time - t.request_time) * 1000.0 - t.receive_headers_end).max(0.0);
+
+    HarTimings {
+        blocked,
+        dns,
+        connect,
+        send,
+        wait,
+        receive,
+        ssl,
+    }
+}
+
+fn http_version(protocol: &str) -> String {
+    match protocol {
+        "h2" => "HTTP/2.0".to_string(),
+        p if p.starts_with("h3") || p.contains("quic") => "HTTP/3.0".to_string(),
+        p if p.starts_with("http/") => p.to_ascii_uppercase(),
+        other => other.to_string(),
+    }
+}
+
+/// Flatten a CDP `Headers` object; Chrome joins repeated headers with `\n`.
+fn name_values(headers: &Value) -> Vec<HarNameValue> {
+    let Some(headers) = headers.as_object() else {
+        return Vec::new();
+    };
+    headers
+        .iter()
+        .flat_map(|(name, value)| {
+            let value = match value {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            value
+                .split('\n')
+                .map(|v| HarNameValue {
+                    name: name.clone(),
+                    value: v.to_string(),
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+fn header_values<'h>(
+    headers: &'h [HarNameValue],
+    name: &'h str,
+) -> impl Iterator<Item = &'h str> + 'h {
+    headers
+        .iter()
+        .filter(move |h| h.name.eq_ignore_ascii_case(name))
+        .map(|h| h.value.as_str())
+}
+
+fn parse_cookie(pair: &str) -> Option<HarCookie> {
+    let (name, value) = pair.trim().split_once('=')?;
+    Some(HarCookie {
+        name: name.trim().to_string(),
+        value: value.trim().to_string(),
+        path: None,
+        domain: None,
+        expires: None,
+        http_only: None,
+        secure: None,
+    })
+}
+
+fn parse_set_cookie(line: &str) -> Option<HarCookie> {
+    let mut parts = line.split(';');
+    let mut cookie = parse_cookie(parts.next()?)?;
+    for attribute in parts {
+        let (key, value) = attribute
+            .trim()
+            .split_once('=')
+            .map_or((attribute.trim(), ""), |(k, v)| (k.trim(), v.trim()));
+        match key.to_ascii_lowercase().as_str() {
+            "path" => cookie.path = Some(value.to_string()),
+            "domain" => cookie.domain = Some(value.to_string()),
+            "expires" => cookie.expires = Some(value.to_string()),
+            "httponly" => cookie.http_only = Some(true),
+            "secure" => cookie.secure = Some(true),
+            _ => {}
+        }
+    }
+    Some(cookie)
+}
+
+fn query_string(url: &str) -> Vec<HarNameValue> {
+    let url = url.split('#').next().unwrap_or_default();
+    let Some((_, query)) = url.split_once('?') else {
+        return Vec::new();
+    };
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            HarNameValue {
+                name: percent_decode(name),
+                value: percent_decode(value),
+            }
+        })
+        .collect()
+}
+
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = |b: u8| (b as char).to_digit(16);
+                match (hex(bytes[i + 1]), hex(bytes[i + 2])) {
+                    (Some(high), Some(low)) => {
+                        out.push((high * 16 + low) as u8);
+                        i += 2;
+                    }
+                    _ => out.push(b'%'),
+                }
+            }
+            byte => out.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Format seconds since the Unix epoch as an ISO 8601 UTC timestamp with milliseconds.
+pub(crate) fn format_iso8601(seconds: f64) -> String {
+    let millis = (seconds * 1000.0).round() as i64;
+    let (days, millis_of_day) = (millis.div_euclid(86_400_000), millis.rem_euclid(86_400_000));
+
+    // Civil-from-days conversion (proleptic Gregorian calendar).
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+        millis_of_day / 3_600_000,
+        millis_of_day / 60_000 % 60,
+        millis_of_day / 1000 % 60,
+        millis_of_day % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn timing(overrides: Value) -> ResourceTiming {
+        let mut base = json!({
+            "requestTime": 100.0,
+            "proxyStart": -1.0, "proxyEnd": -1.0,
+            "dnsStart": 2.0, "dnsEnd": 12.0,
+            "connectStart": 12.0, "connectEnd": 40.0,
+            "sslStart": 20.0, "sslEnd": 40.0,
+            "workerStart": -1.0, "workerReady": -1.0,
+            "workerFetchStart": -1.0, "workerRespondWithSettled": -1.0,
+            "sendStart": 41.0, "sendEnd": 42.0,
+            "pushStart": 0.0, "pushEnd": 0.0,
+            "receiveHeadersStart": 90.0, "receiveHeadersEnd": 92.0
+        });
+        for (key, value) in overrides.as_object().unwrap() {
+            base[key] = value.clone();
+        }
+        serde_json::from_value(base).unwrap()
+    }
+
+    #[test]
+    fn timings_follow_devtools_phases() {
+        let timings = har_timings(Some(&timing(json!({}))), 99.999, Some(100.1), 100.2);
+        assert_eq!(timings.dns, 10.0);
+        assert_eq!(timings.connect, 28.0);
+        assert_eq!(timings.ssl, 20.0);
+        assert_eq!(timings.send, 2.0);
+        assert_eq!(timings.wait, 50.0);
+        assert!((timings.blocked - 3.0).abs() < 1e-6);
+        assert!((timings.receive - 108.0).abs() < 1e-6);
+        assert!((timings.total() - 201.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn reused_connection_has_no_dns_or_connect() {
+        let reused = timing(json!({
+            "dnsStart": -1.0, "dnsEnd": -1.0,
+            "connectStart": -1.0, "connectEnd": -1.0,
+            "sslStart": -1.0, "sslEnd": -1.0,
+            "sendStart": 1.0, "sendEnd": 2.0,
+            "receiveHeadersEnd": 10.0
+        }));
+        let timings = har_timings(Some(&reused), 100.0, None, 100.02);
+        assert_eq!(
+            (timings.dns, timings.connect, timings.ssl),
+            (-1.0, -1.0, -1.0)
+        );
+        assert_eq!(
+            (timings.blocked, timings.send, timings.wait),
+            (1.0, 1.0, 8.0)
+        );
+        assert!((timings.receive - 10.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn missing_timing_splits_wait_and_receive() {
+        let timings = har_timings(None, 10.0, Some(10.25), 10.5);
+        assert_eq!((timings.wait, timings.receive), (250.0, 250.0));
+        assert_eq!(timings.total(), 500.0);
+    }
+
+    #[test]
+    fn formats_iso8601_timestamps() {
+        assert_eq!(format_iso8601(0.0), "1970-01-01T00:00:00.000Z");
+        assert_eq!(format_iso8601(951_782_400.5), "2000-02-29T00:00:00.500Z");
+        assert_eq!(
+            format_iso8601(1_792_329_845.123),
+            "2026-10-18T13:24:05.123Z"
+        );
+    }
+
+    #[test]
+    fn parses_query_strings_and_cookies() {
+        assert_eq!(
+            query_string("https://a.test/p?q=a%20b&x=1+2&flag#frag"),
+            vec![
+                HarNameValue {
+                    name: "q".into(),
+                    value: "a b".into()
+                },
+                HarNameValue {
+                    name: "x".into(),
+                    value: "1 2".into()
+                },
+                HarNameValue {
+                    name: "flag".into(),
+                    value: String::new()
+                },
+            ]
+        );
+
+        let cookie = parse_set_cookie("sid=abc; Path=/; HttpOnly; Secure").unwrap();
+        assert_eq!(
+            (cookie.name.as_str(), cookie.value.as_str()),
+            ("sid", "abc")
+        );
+        assert_eq!(cookie.path.as_deref(), Some("/"));
+        assert_eq!((cookie.http_only, cookie.secure), (Some(true), Some(true)));
+
+        let headers = name_values(&json!({"Set-Cookie": "a=1\nb=2", "X-Test": "v"}));
+        assert_eq!(header_values(&headers, "set-cookie").count(), 2);
+    }
+}
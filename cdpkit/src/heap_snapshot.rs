@@ -0,0 +1,948 @@
+use crate::{backpressured_event_stream, heap_profiler, send_with_timeout, CdpError, Sender};
+use serde::Deserialize;
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::Path;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+
+/// Chunks queued before the connection stops reading from Chrome.
+const CHUNK_QUEUE: usize = 16;
+
+/// Marks unreachable nodes in the dominator tree.
+const UNREACHABLE: u32 = u32::MAX;
+
+/// Default for [`HeapSnapshotOptions::with_timeout`].
+const DEFAULT_SNAPSHOT_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Configuration for [`HeapSnapshot::capture`] and [`HeapSnapshot::capture_to_file`].
+#[derive(Debug, Clone)]
+pub struct HeapSnapshotOptions {
+    collect_garbage: bool,
+    expose_internals: bool,
+    capture_numeric_values: bool,
+    timeout: Duration,
+}
+
+impl Default for HeapSnapshotOptions {
+    fn default() -> Self {
+        Self {
+            collect_garbage: false,
+            expose_internals: false,
+            capture_numeric_values: false,
+            timeout: DEFAULT_SNAPSHOT_TIMEOUT,
+        }
+    }
+}
+
+impl HeapSnapshotOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `HeapProfiler.collectGarbage` first so the snapshot holds only live objects.
+    pub fn with_collect_garbage(mut self, enabled: bool) -> Self {
+        self.collect_garbage = enabled;
+        self
+    }
+
+    /// Include V8 internals that DevTools normally hides.
+    pub fn with_expose_internals(mut self, enabled: bool) -> Self {
+        self.expose_internals = enabled;
+        self
+    }
+
+    /// Record the values of heap numbers and smis.
+    pub fn with_numeric_values(mut self, enabled: bool) -> Self {
+        self.capture_numeric_values = enabled;
+        self
+    }
+
+    /// How long `HeapProfiler.takeHeapSnapshot` may take, including the transfer of all
+    /// chunks (default: 10 minutes). The connection's command timeout does not apply,
+    /// because large heaps take longer than any sensible default.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+/// A parsed V8 heap snapshot (`.heapsnapshot`).
+///
+/// `HeapProfiler.takeHeapSnapshot` delivers the snapshot as a flood of
+/// `HeapProfiler.addHeapSnapshotChunk` events. [`capture`](Self::capture) and
+/// [`capture_to_file`](Self::capture_to_file) read them with backpressure: once a few
+/// chunks are queued the connection stops reading from Chrome until they are consumed,
+/// so no chunk is dropped and memory stays bounded by the destination.
+///
+/// Nodes are addressed by their index in the snapshot; index 0 is the synthetic root.
+/// Retained sizes come from the dominator tree over all non-weak edges.
+///
+/// # Example
+/// ```no_run
+/// # use cdpkit::{HeapSnapshot, HeapSnapshotOptions, OwnedSession};
+/// # async fn example(session: OwnedSession) -> Result<(), cdpkit::CdpError> {
+/// let options = HeapSnapshotOptions::new().with_collect_garbage(true);
+/// let before = HeapSnapshot::capture(&session, options.clone()).await?;
+/// // ... exercise the page ...
+/// HeapSnapshot::capture_to_file(&session, "after.heapsnapshot", options).await?;
+/// let after = HeapSnapshot::load("after.heapsnapshot").await?;
+///
+/// for class in after.summary().iter().take(10) {
+///     println!("{:>10} {:>6} {}", class.retained_size, class.count, class.name);
+/// }
+/// for change in before.diff(&after) {
+///     println!("{:+} bytes {}", change.size_delta(), change.name);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct HeapSnapshot {
+    layout: Layout,
+    node_types: Vec<String>,
+    edge_types: Vec<String>,
+    nodes: Vec<u64>,
+    edges: Vec<u64>,
+    strings: Vec<String>,
+    /// Index of each node's first outgoing edge; `node_count + 1` entries.
+    first_edge: Vec<usize>,
+    /// Immediate dominator of each node, [`UNREACHABLE`] if not reachable from the root.
+    dominators: Vec<u32>,
+    retained_sizes: Vec<u64>,
+}
+
+/// One object in a [`HeapSnapshot`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeapNode<'a> {
+    /// Position in the snapshot, used by [`HeapSnapshot::node`] and [`HeapSnapshot::edges`].
+    pub index: usize,
+    /// `object`, `closure`, `string`, `array`, `hidden`, `synthetic`, ...
+    pub node_type: &'a str,
+    /// Constructor name for objects, the contents for strings.
+    pub name: &'a str,
+    /// Heap object id; stable across snapshots of the same target.
+    pub id: u64,
+    pub self_size: u64,
+    /// Size freed if this object were collected; 0 for unreachable objects.
+    pub retained_size: u64,
+}
+
+impl<'a> HeapNode<'a> {
+    /// The class name DevTools groups this object under in its summary view.
+    pub fn class_name(&self) -> Cow<'a, str> {
+        match self.node_type {
+            "object" | "native" => Cow::Borrowed(self.name),
+            "string" | "concatenated string" | "sliced string" => Cow::Borrowed("(string)"),
+            "code" => Cow::Borrowed("(compiled code)"),
+            "hidden" => Cow::Borrowed("(system)"),
+            other => Cow::Owned(format!("({other})")),
+        }
+    }
+}
+
+/// Property name or element index of a [`HeapEdge`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeapEdgeName<'a> {
+    Name(&'a str),
+    Index(u64),
+}
+
+impl fmt::Display for HeapEdgeName<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Name(name) => f.write_str(name),
+            Self::Index(index) => write!(f, "[{index}]"),
+        }
+    }
+}
+
+/// A reference from one [`HeapNode`] to another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeapEdge<'a> {
+    /// `property`, `element`, `context`, `internal`, `hidden`, `shortcut` or `weak`.
+    pub edge_type: &'a str,
+    pub name: HeapEdgeName<'a>,
+    pub from: usize,
+    pub to: usize,
+}
+
+/// Objects of one class in [`HeapSnapshot::summary`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeapClassSummary {
+    pub name: String,
+    pub count: usize,
+    pub self_size: u64,
+    /// Retained size of all instances, counting instances nested in one another once.
+    pub retained_size: u64,
+}
+
+/// Per-class change between two snapshots, see [`HeapSnapshot::diff`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HeapClassDiff {
+    pub name: String,
+    pub added_count: usize,
+    pub removed_count: usize,
+    pub added_size: u64,
+    pub removed_size: u64,
+}
+
+impl HeapClassDiff {
+    pub fn count_delta(&self) -> i64 {
+        self.added_count as i64 - self.removed_count as i64
+    }
+
+    pub fn size_delta(&self) -> i64 {
+        self.added_size as i64 - self.removed_size as i64
+    }
+}
+
+impl HeapSnapshot {
+    /// Take a heap snapshot and parse it in memory.
+    pub async fn capture(
+        target: &(impl Sender + Sync),
+        options: HeapSnapshotOptions,
+    ) -> Result<Self, CdpError> {
+        let mut json = Vec::new();
+        take_snapshot(target, &options, &mut json).await?;
+        Self::from_slice(&json)
+    }
+
+    /// Take a heap snapshot and stream it into a `.heapsnapshot` file without holding it
+    /// in memory. Returns the number of bytes written; the file is removed if the capture
+    /// fails, e.g. on [`CdpError::Timeout`].
+    pub async fn capture_to_file(
+        target: &(impl Sender + Sync),
+        path: impl AsRef<Path>,
+        options: HeapSnapshotOptions,
+    ) -> Result<u64, CdpError> {
+        let path = path.as_ref();
+        let file = tokio::fs::File::create(path)
+            .await
+            .map_err(|e| CdpError::Io(e.to_string()))?;
+        let mut sink = FileSink {
+            file: tokio::io::BufWriter::new(file),
+            written: 0,
+        };
+        let result = match take_snapshot(target, &options, &mut sink).await {
+            Ok(()) => sink
+                .file
+                .flush()
+                .await
+                .map(|()| sink.written)
+                .map_err(|e| CdpError::Io(e.to_string())),
+            Err(e) => Err(e),
+        };
+        if result.is_err() {
+            drop(sink);
+            let _ = tokio::fs::remove_file(path).await;
+        }
+        result
+    }
+
+    /// Read a `.heapsnapshot` file written by cdpkit or Chrome DevTools.
+    pub async fn load(path: impl AsRef<Path>) -> Result<Self, CdpError> {
+        let json = tokio::fs::read(path)
+            .await
+            .map_err(|e| CdpError::Io(e.to_string()))?;
+        Self::from_slice(&json)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, CdpError> {
+        Self::from_slice(json.as_bytes())
+    }
+
+    pub fn from_slice(json: &[u8]) -> Result<Self, CdpError> {
+        let raw: RawSnapshot = serde_json::from_slice(json)?;
+        let meta = raw.snapshot.meta;
+        let layout = Layout::new(&meta.node_fields, &meta.edge_fields)?;
+        let node_types = enum_values(&meta.node_types, layout.node_type, "node")?;
+        let edge_types = enum_values(&meta.edge_types, layout.edge_type, "edge")?;
+
+        let mut snapshot = Self {
+            layout,
+            node_types,
+            edge_types,
+            nodes: raw.nodes,
+            edges: raw.edges,
+            strings: raw.strings,
+            first_edge: Vec::new(),
+            dominators: Vec::new(),
+            retained_sizes: Vec::new(),
+        };
+        snapshot.validate()?;
+        snapshot.compute_retained_sizes();
+        Ok(snapshot)
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.nodes.len() / self.layout.node_stride
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.edges.len() / self.layout.edge_stride
+    }
+
+    /// Total size of the objects reachable from the root.
+    pub fn total_size(&self) -> u64 {
+        self.retained_sizes.first().copied().unwrap_or(0)
+    }
+
+    pub fn node(&self, index: usize) -> Option<HeapNode<'_>> {
+        if index >= self.node_count() {
+            return None;
+        }
+        let base = index * self.layout.node_stride;
+        let field = |offset: usize| self.nodes[base + offset];
+        Some(HeapNode {
+            index,
+            node_type: &self.node_types[field(self.layout.node_type) as usize],
+            name: &self.strings[field(self.layout.node_name) as usize],
+            id: field(self.layout.node_id),
+            self_size: field(self.layout.node_self_size),
+            retained_size: self.retained_sizes[index],
+        })
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = HeapNode<'_>> + '_ {
+        (0..self.node_count()).filter_map(|index| self.node(index))
+    }
+
+    /// Look up an object by its heap object id.
+    pub fn node_by_id(&self, id: u64) -> Option<HeapNode<'_>> {
+        self.nodes().find(|node| node.id == id)
+    }
+
+    /// Outgoing references of node `index`.
+    pub fn edges(&self, index: usize) -> impl Iterator<Item = HeapEdge<'_>> + '_ {
+        let range = match self.first_edge.get(index..index + 2) {
+            Some(bounds) => bounds[0]..bounds[1],
+            None => 0..0,
+        };
+        range.map(move |edge| self.edge(index, edge))
+    }
+
+    /// Immediate dominator of node `index`: the closest object that every path from the
+    /// root passes through. `None` for the root and unreachable objects.
+    pub fn dominator(&self, index: usize) -> Option<usize> {
+        match self.dominators.get(index) {
+            Some(&dominator) if index != 0 && dominator != UNREACHABLE => Some(dominator as usize),
+            _ => None,
+        }
+    }
+
+    /// Reachable objects grouped by class, largest retained size first. Synthetic nodes
+    /// such as the root and `(GC roots)` are left out.
+    pub fn summary(&self) -> Vec<HeapClassSummary> {
+        let mut classes: Vec<HeapClassSummary> = Vec::new();
+        let mut class_ids: HashMap<Cow<'_, str>, usize> = HashMap::new();
+        let mut class_of = vec![usize::MAX; self.node_count()];
+        for node in self.counted_nodes() {
+            let class = node.class_name();
+            let id = *class_ids.entry(class).or_insert_with_key(|name| {
+                classes.push(HeapClassSummary {
+                    name: name.to_string(),
+                    count: 0,
+                    self_size: 0,
+                    retained_size: 0,
+                });
+                classes.len() - 1
+            });
+            class_of[node.index] = id;
+            classes[id].count += 1;
+            classes[id].self_size += node.self_size;
+        }
+
+        // Walk the dominator tree, adding an object's retained size to its class only
+        // when no dominating object of the same class has already counted it.
+        let children = self.dominator_children();
+        let mut active = vec![0usize; classes.len()];
+        let mut stack = vec![(0usize, false)];
+        while let Some((index, exiting)) = stack.pop() {
+            let class = class_of[index];
+            if exiting {
+                active[class] -= 1;
+                continue;
+            }
+            if class != usize::MAX {
+                if active[class] == 0 {
+                    classes[class].retained_size += self.retained_sizes[index];
+                }
+                active[class] += 1;
+                stack.push((index, true));
+            }
+            stack.extend(children[index].iter().map(|&child| (child as usize, false)));
+        }
+
+        classes.sort_by(|a, b| {
+            b.retained_size
+                .cmp(&a.retained_size)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        classes
+    }
+
+    /// Objects allocated and freed between this snapshot and `newer`, per class, matched
+    /// by heap object id. Both snapshots must come from the same target. Classes that grew
+    /// the most come first.
+    pub fn diff(&self, newer: &HeapSnapshot) -> Vec<HeapClassDiff> {
+        let old_ids: HashSet<u64> = self.counted_nodes().map(|node| node.id).collect();
+        let new_ids: HashSet<u64> = newer.counted_nodes().map(|node| node.id).collect();
+
+        fn class<'m>(
+            classes: &'m mut HashMap<String, HeapClassDiff>,
+            node: &HeapNode<'_>,
+        ) -> &'m mut HeapClassDiff {
+            let name = node.class_name();
+            classes
+                .entry(name.to_string())
+                .or_insert_with(|| HeapClassDiff {
+                    name: name.into_owned(),
+                    ..HeapClassDiff::default()
+                })
+        }
+
+        let mut classes = HashMap::new();
+        for node in newer.counted_nodes().filter(|n| !old_ids.contains(&n.id)) {
+            let class = class(&mut classes, &node);
+            class.added_count += 1;
+            class.added_size += node.self_size;
+        }
+        for node in self.counted_nodes().filter(|n| !new_ids.contains(&n.id)) {
+            let class = class(&mut classes, &node);
+            class.removed_count += 1;
+            class.removed_size += node.self_size;
+        }
+
+        let mut diff: Vec<HeapClassDiff> = classes.into_values().collect();
+        diff.sort_by(|a, b| {
+            b.size_delta()
+                .cmp(&a.size_delta())
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        diff
+    }
+
+    fn edge(&self, from: usize, edge: usize) -> HeapEdge<'_> {
+        let base = edge * self.layout.edge_stride;
+        let edge_type = &self.edge_types[self.edges[base + self.layout.edge_type] as usize];
+        let name_or_index = self.edges[base + self.layout.edge_name];
+        let name = match edge_type.as_str() {
+            "element" | "hidden" => HeapEdgeName::Index(name_or_index),
+            _ => HeapEdgeName::Name(&self.strings[name_or_index as usize]),
+        };
+        HeapEdge {
+            edge_type,
+            name,
+            from,
+            to: self.edge_target(edge),
+        }
+    }
+
+    fn edge_target(&self, edge: usize) -> usize {
+        self.edges[edge * self.layout.edge_stride + self.layout.edge_to] as usize
+            / self.layout.node_stride
+    }
+
+    /// Reachable, non-synthetic nodes: what summaries and diffs count.
+    fn counted_nodes(&self) -> impl Iterator<Item = HeapNode<'_>> + '_ {
+        self.nodes().filter(|node| {
+            node.node_type != "synthetic" && self.dominators[node.index] != UNREACHABLE
+        })
+    }
+
+    /// Check every index in the flat arrays once, so accessors can index directly.
+    fn validate(&mut self) -> Result<(), CdpError> {
+        let invalid = |message: String| Err(CdpError::InvalidHeapSnapshot(message));
+        let layout = &self.layout;
+        if self.nodes.len() % layout.node_stride != 0 {
+            return invalid("nodes array is not a multiple of node_fields".into());
+        }
+        if self.edges.len() % layout.edge_stride != 0 {
+            return invalid("edges array is not a multiple of edge_fields".into());
+        }
+
+        let node_count = self.nodes.len() / layout.node_stride;
+        let mut first_edge = Vec::with_capacity(node_count + 1);
+        let mut edge = 0usize;
+        for node in self.nodes.chunks_exact(layout.node_stride) {
+            first_edge.push(edge);
+            if node[layout.node_type] as usize >= self.node_types.len() {
+                return invalid(format!("unknown node type {}", node[layout.node_type]));
+            }
+            if node[layout.node_name] as usize >= self.strings.len() {
+                return invalid(format!("node name {} out of range", node[layout.node_name]));
+            }
+            edge += node[layout.node_edge_count] as usize;
+        }
+        first_edge.push(edge);
+        if edge != self.edges.len() / layout.edge_stride {
+            return invalid(format!(
+                "nodes reference {edge} edges, snapshot has {}",
+                self.edges.len() / layout.edge_stride
+            ));
+        }
+
+        for edge in self.edges.chunks_exact(layout.edge_stride) {
+            let Some(edge_type) = self.edge_types.get(edge[layout.edge_type] as usize) else {
+                return invalid(format!("unknown edge type {}", edge[layout.edge_type]));
+            };
+            let named = !matches!(edge_type.as_str(), "element" | "hidden");
+            if named && edge[layout.edge_name] as usize >= self.strings.len() {
+                return invalid(format!("edge name {} out of range", edge[layout.edge_name]));
+            }
+            let to = edge[layout.edge_to] as usize;
+            if to % layout.node_stride != 0 || to / layout.node_stride >= node_count {
+                return invalid(format!("edge target {to} is not a node"));
+            }
+        }
+        self.first_edge = first_edge;
+        Ok(())
+    }
+
+    /// Dominators with the iterative Cooper–Harvey–Kennedy algorithm, then retained
+    /// sizes by summing each node into its dominator in post-order.
+    fn compute_retained_sizes(&mut self) {
+        let node_count = self.node_count();
+        self.dominators = vec![UNREACHABLE; node_count];
+        self.retained_sizes = vec![0; node_count];
+        if node_count == 0 {
+            return;
+        }
+        let weak = self.edge_types.iter().position(|t| t == "weak");
+        let is_strong = |edge: usize| {
+            Some(self.edges[edge * self.layout.edge_stride + self.layout.edge_type] as usize)
+                != weak
+        };
+
+        // Depth-first post-order from the root over strong edges.
+        let mut post_number = vec![UNREACHABLE; node_count];
+        let mut post_order: Vec<u32> = Vec::with_capacity(node_count);
+        let mut visited = vec![false; node_count];
+        let mut stack = vec![(0usize, self.first_edge[0])];
+        visited[0] = true;
+        while let Some((node, next_edge)) = stack.last_mut() {
+            let node = *node;
+            if *next_edge < self.first_edge[node + 1] {
+                let edge = *next_edge;
+                *next_edge += 1;
+                let to = self.edge_target(edge);
+                if is_strong(edge) && !visited[to] {
+                    visited[to] = true;
+                    stack.push((to, self.first_edge[to]));
+                }
+            } else {
+                post_number[node] = post_order.len() as u32;
+                post_order.push(node as u32);
+                stack.pop();
+            }
+        }
+
+        // Predecessor lists of reachable nodes, in CSR form.
+        let mut pred_start = vec![0usize; node_count + 1];
+        for &node in &post_order {
+            let node = node as usize;
+            for edge in self.first_edge[node]..self.first_edge[node + 1] {
+                if is_strong(edge) {
+                    pred_start[self.edge_target(edge) + 1] += 1;
+                }
+            }
+        }
+        for i in 0..node_count {
+            pred_start[i + 1] += pred_start[i];
+        }
+        let mut fill = pred_start.clone();
+        let mut preds = vec![0u32; pred_start[node_count]];
+        for &node in &post_order {
+            for edge in self.first_edge[node as usize]..self.first_edge[node as usize + 1] {
+                if is_strong(edge) {
+                    let to = self.edge_target(edge);
+                    preds[fill[to]] = node;
+                    fill[to] += 1;
+                }
+            }
+        }
+
+        let dominators = &mut self.dominators;
+        dominators[0] = 0;
+        let intersect = |dominators: &[u32], mut a: u32, mut b: u32| {
+            while a != b {
+                while post_number[a as usize] < post_number[b as usize] {
+                    a = dominators[a as usize];
+                }
+                while post_number[b as usize] < post_number[a as usize] {
+                    b = dominators[b as usize];
+                }
+            }
+            a
+        };
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &node in post_order.iter().rev().skip(1) {
+                let node = node as usize;
+                let mut dominator = UNREACHABLE;
+                for &pred in &preds[pred_start[node]..pred_start[node + 1]] {
+                    if dominators[pred as usize] == UNREACHABLE {
+                        continue;
+                    }
+                    dominator = if dominator == UNREACHABLE {
+                        pred
+                    } else {
+                        intersect(dominators, pred, dominator)
+                    };
+                }
+                if dominators[node] != dominator {
+                    dominators[node] = dominator;
+                    changed = true;
+                }
+            }
+        }
+
+        // A node's dominator is its ancestor in the DFS tree, so post-order visits every
+        // node before its dominator.
+        for &node in &post_order {
+            let node = node as usize;
+            let base = node * self.layout.node_stride;
+            self.retained_sizes[node] += self.nodes[base + self.layout.node_self_size];
+            if node != 0 {
+                let dominator = self.dominators[node] as usize;
+                self.retained_sizes[dominator] += self.retained_sizes[node];
+            }
+        }
+    }
+
+    fn dominator_children(&self) -> Vec<Vec<u32>> {
+        let mut children = vec![Vec::new(); self.node_count()];
+        for (node, &dominator) in self.dominators.iter().enumerate().skip(1) {
+            if dominator != UNREACHABLE {
+                children[dominator as usize].push(node as u32);
+            }
+        }
+        children
+    }
+}
+
+impl fmt::Debug for HeapSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HeapSnapshot")
+            .field("node_count", &self.node_count())
+            .field("edge_count", &self.edge_count())
+            .field("total_size", &self.total_size())
+            .finish()
+    }
+}
+
+/// Destination of the snapshot chunks.
+trait ChunkSink {
+    async fn write(&mut self, chunk: &str) -> Result<(), CdpError>;
+}
+
+impl ChunkSink for Vec<u8> {
+    async fn write(&mut self, chunk: &str) -> Result<(), CdpError> {
+        self.extend_from_slice(chunk.as_bytes());
+        Ok(())
+    }
+}
+
+struct FileSink {
+    file: tokio::io::BufWriter<tokio::fs::File>,
+    written: u64,
+}
+
+impl ChunkSink for FileSink {
+    async fn write(&mut self, chunk: &str) -> Result<(), CdpError> {
+        self.file
+            .write_all(chunk.as_bytes())
+            .await
+            .map_err(|e| CdpError::Io(e.to_string()))?;
+        self.written += chunk.len() as u64;
+        Ok(())
+    }
+}
+
+async fn take_snapshot(
+    target: &(impl Sender + Sync),
+    options: &HeapSnapshotOptions,
+    sink: &mut impl ChunkSink,
+) -> Result<(), CdpError> {
+    heap_profiler::methods::Enable::new().send(target).await?;
+    if options.collect_garbage {
+        heap_profiler::methods::CollectGarbage::new()
+            .send(target)
+            .await?;
+    }
+
+    let mut chunks =
+        backpressured_event_stream(target, "HeapProfiler.addHeapSnapshotChunk", CHUNK_QUEUE);
+    let take = send_with_timeout(
+        target,
+        heap_profiler::methods::TakeHeapSnapshot::new()
+            .with_report_progress(false)
+            .with_expose_internals(options.expose_internals)
+            .with_capture_numeric_value(options.capture_numeric_values),
+        options.timeout,
+    );
+    tokio::pin!(take);
+
+    loop {
+        tokio::select! {
+            biased;
+            Some(params) = chunks.recv() => sink.write(chunk_text(&params)).await?,
+            result = &mut take => {
+                result?;
+                break;
+            }
+        }
+    }
+    // Chrome sends every chunk before the command response, and the connection does not
+    // read the response until all of them are queued here.
+    while let Ok(params) = chunks.try_recv() {
+        sink.write(chunk_text(&params)).await?;
+    }
+    Ok(())
+}
+
+fn chunk_text(params: &Value) -> &str {
+    params
+        .get("chunk")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+}
+
+#[derive(Deserialize)]
+struct RawSnapshot {
+    snapshot: RawHeader,
+    nodes: Vec<u64>,
+    edges: Vec<u64>,
+    strings: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct RawHeader {
+    meta: RawMeta,
+}
+
+#[derive(Deserialize)]
+struct RawMeta {
+    node_fields: Vec<String>,
+    node_types: Vec<Value>,
+    edge_fields: Vec<String>,
+    edge_types: Vec<Value>,
+}
+
+/// Field offsets from the snapshot's `meta` section.
+struct Layout {
+    node_stride: usize,
+    node_type: usize,
+    node_name: usize,
+    node_id: usize,
+    node_self_size: usize,
+    node_edge_count: usize,
+    edge_stride: usize,
+    edge_type: usize,
+    edge_name: usize,
+    edge_to: usize,
+}
+
+impl Layout {
+    fn new(node_fields: &[String], edge_fields: &[String]) -> Result<Self, CdpError> {
+        let offset = |fields: &[String], name: &str| {
+            fields.iter().position(|f| f == name).ok_or_else(|| {
+                CdpError::InvalidHeapSnapshot(format!("meta is missing the `{name}` field"))
+            })
+        };
+        Ok(Self {
+            node_stride: node_fields.len(),
+            node_type: offset(node_fields, "type")?,
+            node_name: offset(node_fields, "name")?,
+            node_id: offset(node_fields, "id")?,
+            node_self_size: offset(node_fields, "self_size")?,
+            node_edge_count: offset(node_fields, "edge_count")?,
+            edge_stride: edge_fields.len(),
+            edge_type: offset(edge_fields, "type")?,
+            edge_name: offset(edge_fields, "name_or_index")?,
+            edge_to: offset(edge_fields, "to_node")?,
+        })
+    }
+}
+
+/// The names of an enum field, e.g. `meta.node_types[type offset]`.
+fn enum_values(types: &[Value], offset: usize, kind: &str) -> Result<Vec<String>, CdpError> {
+    types
+        .get(offset)
+        .and_then(Value::as_array)
+        .map(|names| {
+            names
+                .iter()
+                .map(|name| name.as_str().unwrap_or_default().to_string())
+                .collect()
+        })
+        .ok_or_else(|| CdpError::InvalidHeapSnapshot(format!("meta has no {kind} type names")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    type TestNode<'a> = (&'a str, &'a str, u64, u64, &'a [(&'a str, &'a str, usize)]);
+
+    /// Build a snapshot from `(type, name, id, self_size, [(edge type, name, to)])`.
+    fn snapshot(nodes: &[TestNode<'_>]) -> HeapSnapshot {
+        let node_types = ["hidden", "object", "string", "synthetic"];
+        let edge_types = ["element", "property", "weak"];
+        let mut strings: Vec<String> = Vec::new();
+        let mut intern = |s: &str| match strings.iter().position(|x| x == s) {
+            Some(i) => i,
+            None => {
+                strings.push(s.to_string());
+                strings.len() - 1
+            }
+        };
+        let (mut flat_nodes, mut flat_edges) = (Vec::new(), Vec::new());
+        for &(node_type, name, id, self_size, edges) in nodes {
+            let node_type = node_types.iter().position(|t| *t == node_type).unwrap();
+            flat_nodes.extend([node_type, intern(name), id as usize, self_size as usize]);
+            flat_nodes.push(edges.len());
+            for (i, &(edge_type, name, to)) in edges.iter().enumerate() {
+                let edge_type = edge_types.iter().position(|t| *t == edge_type).unwrap();
+                let name = if edge_type == 0 { i } else { intern(name) };
+                flat_edges.extend([edge_type, name, to * 5]);
+            }
+        }
+        let json = json!({
+            "snapshot": {"meta": {
+                "node_fields": ["type", "name", "id", "self_size", "edge_count"],
+                "node_types": [node_types, "string", "number", "number", "number"],
+                "edge_fields": ["type", "name_or_index", "to_node"],
+                "edge_types": [edge_types, "string_or_number", "node"],
+            }},
+            "nodes": flat_nodes,
+            "edges": flat_edges,
+            "strings": strings,
+        });
+        HeapSnapshot::from_json(&json.to_string()).unwrap()
+    }
+
+    /// root -> A(Foo) -> {D(string), C(Foo), F(Foo)}; root -> B(Bar) -> {C, weak E(Foo)}.
+    fn fixture() -> HeapSnapshot {
+        snapshot(&[
+            (
+                "synthetic",
+                "",
+                1,
+                0,
+                &[("element", "", 1), ("element", "", 2)],
+            ),
+            (
+                "object",
+                "Foo",
+                3,
+                100,
+                &[
+                    ("property", "name", 4),
+                    ("property", "child", 3),
+                    ("property", "own", 6),
+                ],
+            ),
+            (
+                "object",
+                "Bar",
+                5,
+                50,
+                &[("property", "shared", 3), ("weak", "w", 5)],
+            ),
+            ("object", "Foo", 7, 10, &[]),
+            ("string", "hello", 9, 20, &[]),
+            ("object", "Foo", 11, 30, &[]),
+            ("object", "Foo", 13, 5, &[]),
+        ])
+    }
+
+    #[test]
+    fn retained_sizes_follow_the_dominator_tree() {
+        let snapshot = fixture();
+        let retained: Vec<u64> = snapshot.nodes().map(|n| n.retained_size).collect();
+        // C is reachable through A and B, so only the root dominates it; E is only
+        // weakly held and therefore unreachable.
+        assert_eq!(retained, [185, 125, 50, 10, 20, 0, 5]);
+        assert_eq!(snapshot.total_size(), 185);
+        assert_eq!(snapshot.dominator(3), Some(0));
+        assert_eq!(snapshot.dominator(4), Some(1));
+        assert_eq!(snapshot.dominator(5), None);
+
+        let edges: Vec<String> = snapshot.edges(2).map(|e| e.name.to_string()).collect();
+        assert_eq!(edges, ["shared", "w"]);
+        assert_eq!(snapshot.node_by_id(9).unwrap().class_name(), "(string)");
+    }
+
+    #[test]
+    fn summary_counts_nested_instances_of_a_class_once() {
+        let summary = fixture().summary();
+        let row = |name: &str, count, self_size, retained_size| HeapClassSummary {
+            name: name.into(),
+            count,
+            self_size,
+            retained_size,
+        };
+        assert_eq!(
+            summary,
+            [
+                row("Foo", 3, 115, 135),
+                row("Bar", 1, 50, 50),
+                row("(string)", 1, 20, 20),
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_matches_objects_by_id() {
+        let newer = snapshot(&[
+            (
+                "synthetic",
+                "",
+                1,
+                0,
+                &[("element", "", 1), ("element", "", 4)],
+            ),
+            (
+                "object",
+                "Foo",
+                3,
+                100,
+                &[("property", "name", 3), ("property", "child", 2)],
+            ),
+            ("object", "Foo", 7, 10, &[]),
+            ("string", "hello", 9, 20, &[]),
+            ("object", "Bar", 15, 40, &[]),
+        ]);
+        let diff = fixture().diff(&newer);
+        let deltas: Vec<(&str, i64, i64)> = diff
+            .iter()
+            .map(|d| (d.name.as_str(), d.count_delta(), d.size_delta()))
+            .collect();
+        assert_eq!(deltas, [("Foo", -1, -5), ("Bar", 0, -10)]);
+        assert_eq!((diff[1].added_count, diff[1].removed_count), (1, 1));
+    }
+
+    #[test]
+    fn rejects_edges_pointing_outside_the_node_array() {
+        let json = json!({
+            "snapshot": {"meta": {
+                "node_fields": ["type", "name", "id", "self_size", "edge_count"],
+                "node_types": [["synthetic"]],
+                "edge_fields": ["type", "name_or_index", "to_node"],
+                "edge_types": [["element"]],
+            }},
+            "nodes": [0, 0, 1, 0, 1],
+            "edges": [0, 0, 5],
+            "strings": [""],
+        });
+        assert!(matches!(
+            HeapSnapshot::from_json(&json.to_string()),
+            Err(CdpError::InvalidHeapSnapshot(_))
+        ));
+    }
+}
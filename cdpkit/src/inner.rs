@@ -1,5 +1,5 @@
 use crate::error::CdpError;
-use crate::listeners::{EventListeners, EventReceiver};
+use crate::listeners::{EventListeners, EventReceiver, PendingDelivery, TaggedEvent};
 use crate::types::Method;
 use crate::{EventStreamPolicy, EventStreamResult, EventStreamStats};
 use futures::stream::Stream;
@@ -121,6 +121,16 @@ impl CDPInner {
         &self,
         cmd: C,
         session_id: Option<&str>,
+    ) -> Result<C::Response, CdpError> {
+        self.send_command_with_timeout(cmd, session_id, self.command_timeout())
+            .await
+    }
+
+    pub async fn send_command_with_timeout<C: Method>(
+        &self,
+        cmd: C,
+        session_id: Option<&str>,
+        timeout: Duration,
     ) -> Result<C::Response, CdpError> {
         let mut msg = serde_json::json!({
             "method": C::METHOD,
@@ -131,7 +141,7 @@ impl CDPInner {
             msg["sessionId"] = serde_json::json!(sid);
         }
 
-        let response = self.send_message(msg, C::METHOD).await?;
+        let response = self.send_message(msg, C::METHOD, timeout).await?;
 
         let response = if response.is_null() || response.as_object().is_some_and(|o| o.is_empty()) {
             Value::Null
@@ -156,10 +166,15 @@ impl CDPInner {
             msg["sessionId"] = serde_json::json!(sid);
         }
 
-        self.send_message(msg, method).await
+        self.send_message(msg, method, self.command_timeout()).await
     }
 
-    async fn send_message(&self, mut msg: Value, method: &str) -> Result<Value, CdpError> {
+    async fn send_message(
+        &self,
+        mut msg: Value,
+        method: &str,
+        timeout: Duration,
+    ) -> Result<Value, CdpError> {
         let id = self.next_id.fetch_add(1, Ordering::Relaxed);
         msg["id"] = serde_json::json!(id);
 
@@ -186,7 +201,7 @@ impl CDPInner {
             return Err(CdpError::ConnectionClosed);
         }
 
-        let response = match tokio::time::timeout(self.command_timeout(), rx).await {
+        let response = match tokio::time::timeout(timeout, rx).await {
             Ok(Ok(result)) => result?,
             Ok(Err(_)) => return Err(CdpError::ChannelClosed),
             Err(_) => {
@@ -295,6 +310,47 @@ impl CDPInner {
         )
     }
 
+    pub fn merged_event_stream(
+        &self,
+        event_names: &[&str],
+        session_id: Option<String>,
+    ) -> Pin<Box<dyn Stream<Item = TaggedEvent> + Send>> {
+        debug!(events = ?event_names, "Subscribing to merged events");
+
+        let receiver = self
+            .event_listeners
+            .write()
+            .unwrap_or_else(|e| {
+                warn!("EventListeners RwLock was poisoned, recovering");
+                e.into_inner()
+            })
+            .add_merged_listener(event_names, session_id);
+
+        Box::pin(tokio_stream::wrappers::UnboundedReceiverStream::new(
+            receiver,
+        ))
+    }
+
+    pub fn backpressured_event_stream(
+        &self,
+        event_name: &str,
+        session_id: Option<String>,
+        capacity: usize,
+    ) -> mpsc::Receiver<Arc<Value>> {
+        debug!(
+            event = event_name,
+            capacity, "Subscribing with backpressure"
+        );
+
+        self.event_listeners
+            .write()
+            .unwrap_or_else(|e| {
+                warn!("EventListeners RwLock was poisoned, recovering");
+                e.into_inner()
+            })
+            .add_backpressure_listener(event_name, session_id, capacity)
+    }
+
     async fn message_loop(&self, mut ws: WsStream, mut rx: mpsc::Receiver<Message>) {
         debug!("Starting message loop");
         let exit_reason: Option<crate::CloseReason>;
@@ -305,7 +361,15 @@ impl CDPInner {
                         Some(Ok(Message::Text(text))) => {
                             trace!("Received message: {}", text);
                             if let Ok(value) = serde_json::from_str::<Value>(&text) {
-                                self.handle_message(value).await;
+                                let deliveries = self.handle_message(value).await;
+                                if !deliveries.is_empty() {
+                                    if let Some(reason) =
+                                        self.deliver(deliveries, &mut ws, &mut rx).await
+                                    {
+                                        exit_reason = Some(reason);
+                                        break;
+                                    }
+                                }
                             } else {
                                 warn!("Failed to parse message as JSON: {}", text);
                             }
@@ -330,19 +394,9 @@ impl CDPInner {
                     }
                 }
                 msg = rx.recv() => {
-                    match msg {
-                        Some(msg) => {
-                            if let Err(e) = ws.send(msg).await {
-                                error!("Failed to send message: {}", e);
-                                exit_reason = Some(crate::CloseReason::Error(e.to_string()));
-                                break;
-                            }
-                        }
-                        None => {
-                            debug!("All senders dropped, shutting down");
-                            exit_reason = Some(crate::CloseReason::Normal);
-                            break;
-                        }
+                    if let Some(reason) = Self::forward(&mut ws, msg).await {
+                        exit_reason = Some(reason);
+                        break;
                     }
                 }
             }
@@ -382,7 +436,62 @@ impl CDPInner {
         let _ = self.close_complete.send(true);
     }
 
-    async fn handle_message(&self, value: Value) {
+    /// Send one outgoing message. Returns why the loop must exit, if it must.
+    async fn forward(ws: &mut WsStream, msg: Option<Message>) -> Option<crate::CloseReason> {
+        match msg {
+            Some(msg) => {
+                if let Err(e) = ws.send(msg).await {
+                    error!("Failed to send message: {}", e);
+                    return Some(crate::CloseReason::Error(e.to_string()));
+                }
+                None
+            }
+            None => {
+                debug!("All senders dropped, shutting down");
+                Some(crate::CloseReason::Normal)
+            }
+        }
+    }
+
+    /// Wait for backpressured listeners to take `deliveries`. No further messages are
+    /// read from the socket meanwhile, but outgoing commands and close keep being sent,
+    /// so a slow consumer delays responses instead of blocking the connection. Once the
+    /// connection is closing, events still waiting are dropped. Returns why the loop must
+    /// exit, if it must.
+    async fn deliver(
+        &self,
+        deliveries: Vec<PendingDelivery>,
+        ws: &mut WsStream,
+        rx: &mut mpsc::Receiver<Message>,
+    ) -> Option<crate::CloseReason> {
+        let delivered = async {
+            for (sender, event) in deliveries {
+                trace!("Waiting for backpressured listener");
+                // A send error only means the receiver is gone; the listener is
+                // removed on the next dispatch.
+                let _ = sender.send(event).await;
+            }
+        };
+        tokio::pin!(delivered);
+        loop {
+            if self.is_closed() {
+                debug!("Connection closing, dropping events for backpressured listeners");
+                return None;
+            }
+            tokio::select! {
+                () = &mut delivered => return None,
+                msg = rx.recv() => {
+                    if let Some(reason) = Self::forward(ws, msg).await {
+                        return Some(reason);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Resolve a command response or dispatch an event. Returns the events that did not
+    /// fit into a backpressured listener's channel.
+    async fn handle_message(&self, value: Value) -> Vec<PendingDelivery> {
         if let Some(id) = value.get("id").and_then(|v| v.as_u64()) {
             if let Some(tx) = self.pending.lock().await.remove(&id) {
                 if let Some(error) = value.get("error") {
@@ -406,7 +515,8 @@ impl CDPInner {
             let params = value.get("params").cloned().unwrap_or(Value::Null);
             let session_id = value.get("sessionId").and_then(|v| v.as_str());
             trace!(event = method, "Dispatching event");
-            self.event_listeners
+            return self
+                .event_listeners
                 .write()
                 .unwrap_or_else(|e| {
                     warn!("EventListeners RwLock was poisoned, recovering");
@@ -416,6 +526,7 @@ impl CDPInner {
         } else {
             warn!("Received message without id or method: {}", value);
         }
+        Vec::new()
     }
 }
 
@@ -484,4 +595,62 @@ mod tests {
         assert!(inner.pending.lock().await.is_empty());
         assert!(events.next().await.is_none());
     }
+
+    #[tokio::test]
+    async fn stalled_backpressured_listener_does_not_block_commands_or_close() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let (received_tx, mut received) = mpsc::unbounded_channel();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+            for _ in 0..2 {
+                let event = serde_json::json!({"method": "Test.event", "params": {}});
+                ws.send(Message::Text(event.to_string().into()))
+                    .await
+                    .unwrap();
+            }
+            while let Some(Ok(msg)) = ws.next().await {
+                let close = msg.is_close();
+                received_tx.send(msg).unwrap();
+                if close {
+                    break;
+                }
+            }
+        });
+
+        let inner = CDPInner::connect(
+            &format!("ws://127.0.0.1:{}", addr.port()),
+            Duration::from_secs(2),
+        )
+        .await
+        .unwrap();
+        // Never consumed: the second event waits for a free slot.
+        let mut events = inner.backpressured_event_stream("Test.event", None, 1);
+        while events.is_empty() {
+            tokio::task::yield_now().await;
+        }
+
+        let command = {
+            let inner = inner.clone();
+            tokio::spawn(async move { inner.send_raw("Test.command", Value::Null, None).await })
+        };
+        let sent = tokio::time::timeout(Duration::from_secs(2), received.recv())
+            .await
+            .expect("command was blocked by the stalled listener");
+        assert!(matches!(sent, Some(Message::Text(text)) if text.contains("Test.command")));
+
+        inner.close().await;
+        tokio::time::timeout(Duration::from_secs(2), inner.closed())
+            .await
+            .expect("close was blocked by the stalled listener");
+        server.await.unwrap();
+        assert!(received.recv().await.unwrap().is_close());
+        assert!(matches!(
+            command.await.unwrap(),
+            Err(CdpError::ConnectionClosed)
+        ));
+        assert!(events.recv().await.is_some());
+    }
 }
@@ -0,0 +1,375 @@
+use crate::encoding::{decode_base64, encode_base64};
+use crate::fetch::events::{AuthRequired, RequestPaused};
+use crate::fetch::types::{AuthChallengeResponse, HeaderEntry, RequestPattern, RequestStage};
+use crate::network::types::{ErrorReason, ResourceType};
+use crate::{fetch, network, spawn_cleanup, CdpError, EventStream, OwnedSession};
+use futures::StreamExt;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tracing::warn;
+
+/// Username and password answered to an HTTP authentication challenge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+impl Credentials {
+    pub fn new(username: impl Into<String>, password: impl Into<String>) -> Self {
+        Self {
+            username: username.into(),
+            password: password.into(),
+        }
+    }
+}
+
+type CredentialsCallback = Arc<dyn Fn(&AuthRequired) -> Option<Credentials> + Send + Sync>;
+
+/// Configuration for [`RequestInterceptor::enable`].
+#[derive(Clone, Default)]
+pub struct InterceptorOptions {
+    patterns: Vec<RequestPattern>,
+    credentials: Option<CredentialsCallback>,
+}
+
+impl std::fmt::Debug for InterceptorOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InterceptorOptions")
+            .field("patterns", &self.patterns)
+            .field("credentials", &self.credentials.is_some())
+            .finish()
+    }
+}
+
+impl InterceptorOptions {
+    /// Intercept every request at the request stage.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a raw `Fetch.RequestPattern`.
+    pub fn with_pattern(mut self, pattern: RequestPattern) -> Self {
+        self.patterns.push(pattern);
+        self
+    }
+
+    /// Intercept requests whose URL matches a wildcard pattern (`*` and `?`) at `stage`.
+    pub fn with_url_pattern(mut self, url_pattern: impl Into<String>, stage: RequestStage) -> Self {
+        self.patterns.push(RequestPattern {
+            url_pattern: Some(url_pattern.into()),
+            resource_type: None,
+            request_stage: Some(stage),
+        });
+        self
+    }
+
+    /// Intercept all requests of one resource type at `stage`.
+    pub fn with_resource_type(mut self, resource_type: ResourceType, stage: RequestStage) -> Self {
+        self.patterns.push(RequestPattern {
+            url_pattern: None,
+            resource_type: Some(resource_type),
+            request_stage: Some(stage),
+        });
+        self
+    }
+
+    /// Answer `Fetch.authRequired` challenges. Returning `None` cancels the challenge, and
+    /// so does a panic in `callback`, after which later challenges are still answered.
+    pub fn with_credentials(
+        mut self,
+        callback: impl Fn(&AuthRequired) -> Option<Credentials> + Send + Sync + 'static,
+    ) -> Self {
+        self.credentials = Some(Arc::new(callback));
+        self
+    }
+}
+
+/// Request overrides for [`InterceptedRequest::continue_with`].
+#[derive(Debug, Clone, Default)]
+pub struct ContinueOverrides {
+    pub url: Option<String>,
+    pub method: Option<String>,
+    pub post_data: Option<Vec<u8>>,
+    pub headers: Option<Vec<(String, String)>>,
+    /// Pause this request again at the response stage.
+    pub intercept_response: Option<bool>,
+}
+
+/// Intercepts network requests on a session through the `Fetch` domain.
+///
+/// Yields one [`InterceptedRequest`] per `Fetch.requestPaused`. Every paused request is
+/// resolved exactly once: if an `InterceptedRequest` is dropped without being resolved,
+/// including while a handler unwinds from a panic, it is continued unmodified. Dropping
+/// the interceptor disables the `Fetch` domain, which releases any request that was
+/// paused but not yet yielded.
+///
+/// # Example
+/// ```no_run
+/// # use cdpkit::{InterceptorOptions, RequestInterceptor, OwnedSession};
+/// # use cdpkit::fetch::types::RequestStage;
+/// # use futures::StreamExt;
+/// # async fn example(session: OwnedSession) -> Result<(), cdpkit::CdpError> {
+/// let options = InterceptorOptions::new().with_url_pattern("*.png", RequestStage::Request);
+/// let mut interceptor = RequestInterceptor::enable(&session, options).await?;
+/// while let Some(request) = interceptor.next().await {
+///     tokio::spawn(async move {
+///         let _ = request.fulfill(200, &[("Content-Type", "image/png")], b"").await;
+///     });
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct RequestInterceptor {
+    session: OwnedSession,
+    paused: EventStream<RequestPaused>,
+    auth_task: Option<tokio::task::JoinHandle<()>>,
+    disabled: bool,
+}
+
+impl RequestInterceptor {
+    /// Subscribe to paused requests and call `Fetch.enable` with the configured patterns.
+    pub async fn enable(
+        session: &OwnedSession,
+        options: InterceptorOptions,
+    ) -> Result<Self, CdpError> {
+        let paused = RequestPaused::subscribe(session);
+        let auth_task = options.credentials.as_ref().map(|callback| {
+            let events = AuthRequired::subscribe(session);
+            tokio::spawn(answer_auth_challenges(
+                session.clone(),
+                events,
+                Arc::clone(callback),
+            ))
+        });
+
+        let mut enable = fetch::methods::Enable::new();
+        if !options.patterns.is_empty() {
+            enable = enable.with_patterns(options.patterns);
+        }
+        if options.credentials.is_some() {
+            enable = enable.with_handle_auth_requests(true);
+        }
+        if let Err(e) = enable.send(session).await {
+            if let Some(task) = auth_task {
+                task.abort();
+            }
+            return Err(e);
+        }
+
+        Ok(Self {
+            session: session.clone(),
+            paused,
+            auth_task,
+            disabled: false,
+        })
+    }
+
+    /// Stop intercepting. Requests that are still paused are released by Chrome.
+    pub async fn disable(mut self) -> Result<(), CdpError> {
+        self.disabled = true;
+        if let Some(task) = self.auth_task.take() {
+            task.abort();
+        }
+        fetch::methods::Disable::new().send(&self.session).await
+    }
+}
+
+impl futures::Stream for RequestInterceptor {
+    type Item = InterceptedRequest;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        this.paused.as_mut().poll_next(cx).map(|event| {
+            event.map(|event| InterceptedRequest {
+                session: this.session.clone(),
+                event,
+                resolved: false,
+            })
+        })
+    }
+}
+
+impl Drop for RequestInterceptor {
+    fn drop(&mut self) {
+        if let Some(task) = self.auth_task.take() {
+            task.abort();
+        }
+        if self.disabled {
+            return;
+        }
+        let session = self.session.clone();
+        spawn_cleanup(async move {
+            let _ = fetch::methods::Disable::new().send(&session).await;
+        });
+    }
+}
+
+/// A request paused by `Fetch.requestPaused`, owned by the handler that resolves it.
+pub struct InterceptedRequest {
+    session: OwnedSession,
+    event: RequestPaused,
+    resolved: bool,
+}
+
+impl std::fmt::Debug for InterceptedRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InterceptedRequest")
+            .field("request_id", &self.event.request_id)
+            .field("url", &self.event.request.url)
+            .field("resolved", &self.resolved)
+            .finish()
+    }
+}
+
+impl InterceptedRequest {
+    /// Fetch-domain id of the paused request.
+    pub fn request_id(&self) -> &str {
+        &self.event.request_id
+    }
+
+    /// The request as Chrome is about to send it.
+    pub fn request(&self) -> &network::types::Request {
+        &self.event.request
+    }
+
+    /// The raw `Fetch.requestPaused` event.
+    pub fn event(&self) -> &RequestPaused {
+        &self.event
+    }
+
+    /// Whether the request is paused at the response stage (response headers received).
+    pub fn is_response_stage(&self) -> bool {
+        self.event.response_status_code.is_some() || self.event.response_error_reason.is_some()
+    }
+
+    /// Read the response body. Only available at the response stage.
+    pub async fn response_body(&self) -> Result<Vec<u8>, CdpError> {
+        let body = fetch::methods::GetResponseBody::new(self.event.request_id.clone())
+            .send(&self.session)
+            .await?;
+        if body.base64_encoded {
+            decode_base64(&body.body)
+        } else {
+            Ok(body.body.into_bytes())
+        }
+    }
+
+    /// Continue the request unmodified.
+    pub async fn continue_request(self) -> Result<(), CdpError> {
+        self.continue_with(ContinueOverrides::default()).await
+    }
+
+    /// Continue the request with overridden URL, method, body or headers.
+    pub async fn continue_with(mut self, overrides: ContinueOverrides) -> Result<(), CdpError> {
+        let mut cmd = fetch::methods::ContinueRequest::new(self.event.request_id.clone());
+        if let Some(url) = overrides.url {
+            cmd = cmd.with_url(url);
+        }
+        if let Some(method) = overrides.method {
+            cmd = cmd.with_method(method);
+        }
+        if let Some(post_data) = overrides.post_data {
+            cmd = cmd.with_post_data(encode_base64(&post_data));
+        }
+        if let Some(headers) = overrides.headers {
+            cmd = cmd.with_headers(
+                headers
+                    .into_iter()
+                    .map(|(name, value)| HeaderEntry { name, value })
+                    .collect(),
+            );
+        }
+        if let Some(intercept_response) = overrides.intercept_response {
+            cmd = cmd.with_intercept_response(intercept_response);
+        }
+        cmd.send(&self.session).await?;
+        self.resolved = true;
+        Ok(())
+    }
+
+    /// Answer the request with a synthetic response instead of sending it.
+    pub async fn fulfill(
+        mut self,
+        status: i64,
+        headers: &[(&str, &str)],
+        body: impl AsRef<[u8]>,
+    ) -> Result<(), CdpError> {
+        fetch::methods::FulfillRequest::new(self.event.request_id.clone(), status)
+            .with_response_headers(
+                headers
+                    .iter()
+                    .map(|(name, value)| HeaderEntry {
+                        name: name.to_string(),
+                        value: value.to_string(),
+                    })
+                    .collect(),
+            )
+            .with_body(encode_base64(body.as_ref()))
+            .send(&self.session)
+            .await?;
+        self.resolved = true;
+        Ok(())
+    }
+
+    /// Fail the request with a network error.
+    pub async fn fail(mut self, reason: ErrorReason) -> Result<(), CdpError> {
+        fetch::methods::FailRequest::new(self.event.request_id.clone(), reason)
+            .send(&self.session)
+            .await?;
+        self.resolved = true;
+        Ok(())
+    }
+}
+
+impl Drop for InterceptedRequest {
+    fn drop(&mut self) {
+        if self.resolved {
+            return;
+        }
+        let session = self.session.clone();
+        let request_id = self.event.request_id.clone();
+        spawn_cleanup(async move {
+            if let Err(e) = fetch::methods::ContinueRequest::new(request_id.clone())
+                .send(&session)
+                .await
+            {
+                warn!(request_id = %request_id, error = %e, "Failed to continue dropped intercepted request");
+            }
+        });
+    }
+}
+
+async fn answer_auth_challenges(
+    session: OwnedSession,
+    mut events: EventStream<AuthRequired>,
+    callback: CredentialsCallback,
+) {
+    while let Some(event) = events.next().await {
+        let credentials =
+            catch_unwind(AssertUnwindSafe(|| callback(&event))).unwrap_or_else(|_| {
+                warn!(request_id = %event.request_id, "Credentials callback panicked");
+                None
+            });
+        let response = match credentials {
+            Some(credentials) => AuthChallengeResponse {
+                response: "ProvideCredentials".to_string(),
+                username: Some(credentials.username),
+                password: Some(credentials.password),
+            },
+            None => AuthChallengeResponse {
+                response: "CancelAuth".to_string(),
+                username: None,
+                password: None,
+            },
+        };
+        if let Err(e) = fetch::methods::ContinueWithAuth::new(event.request_id.clone(), response)
+            .send(&session)
+            .await
+        {
+            warn!(request_id = %event.request_id, error = %e, "Failed to answer auth challenge");
+        }
+    }
+}
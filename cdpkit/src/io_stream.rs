@@ -0,0 +1,207 @@
+use crate::encoding::decode_base64;
+use crate::io::types::StreamHandle;
+use crate::{io, spawn_cleanup, CdpError, Sender};
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, ReadBuf};
+use tracing::warn;
+
+type ReadFuture = Pin<Box<dyn Future<Output = Result<(Vec<u8>, bool), CdpError>> + Send>>;
+
+/// [`AsyncRead`] over an `IO.StreamHandle`, reading chunks with `IO.read`.
+///
+/// Returned by `Page.printToPDF` and `Tracing.tracingComplete` with
+/// `transferMode: ReturnAsStream`, and by `Network.takeResponseBodyAsStream`.
+/// Base64 chunks are decoded transparently. The handle is closed with `IO.close` when the
+/// reader is dropped, or explicitly with [`close`](Self::close).
+///
+/// The target must be owned (`CDP` or `OwnedSession`), since reads run as `'static`
+/// futures between polls.
+///
+/// # Example
+/// ```no_run
+/// # use cdpkit::{page, CdpReadStream, OwnedSession};
+/// # async fn example(session: OwnedSession) -> Result<(), Box<dyn std::error::Error>> {
+/// let pdf = page::methods::PrintToPdf::new()
+///     .with_transfer_mode("ReturnAsStream")
+///     .send(&session)
+///     .await?;
+/// let mut stream = CdpReadStream::new(session, pdf.stream.unwrap());
+/// let mut file = tokio::fs::File::create("page.pdf").await?;
+/// tokio::io::copy(&mut stream, &mut file).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct CdpReadStream<S: Sender + Clone + Send + Sync + Unpin + 'static> {
+    target: S,
+    handle: StreamHandle,
+    chunk_size: Option<i64>,
+    buffer: Vec<u8>,
+    position: usize,
+    pending: Option<ReadFuture>,
+    eof: bool,
+    closed: bool,
+}
+
+impl<S: Sender + Clone + Send + Sync + Unpin + 'static> CdpReadStream<S> {
+    /// Wrap a stream handle; the reader takes ownership of closing it.
+    pub fn new(target: S, handle: impl Into<StreamHandle>) -> Self {
+        Self {
+            target,
+            handle: handle.into(),
+            chunk_size: None,
+            buffer: Vec::new(),
+            position: 0,
+            pending: None,
+            eof: false,
+            closed: false,
+        }
+    }
+
+    /// Request at most `bytes` per `IO.read` instead of Chrome's default chunk size.
+    pub fn with_chunk_size(mut self, bytes: i64) -> Self {
+        self.chunk_size = Some(bytes);
+        self
+    }
+
+    /// The underlying stream handle.
+    pub fn handle(&self) -> &str {
+        &self.handle
+    }
+
+    /// Whether `IO.read` has reported end of file.
+    pub fn is_eof(&self) -> bool {
+        self.eof
+    }
+
+    /// Read the remaining stream into memory and close it.
+    pub async fn into_bytes(mut self) -> Result<Vec<u8>, CdpError> {
+        let mut bytes = Vec::new();
+        tokio::io::AsyncReadExt::read_to_end(&mut self, &mut bytes)
+            .await
+            .map_err(io_to_cdp_error)?;
+        self.close().await?;
+        Ok(bytes)
+    }
+
+    /// Copy the remaining stream into a file and close it. Returns the number of bytes written.
+    pub async fn save(mut self, path: impl AsRef<Path>) -> Result<u64, CdpError> {
+        let mut file = tokio::fs::File::create(path)
+            .await
+            .map_err(|e| CdpError::Io(e.to_string()))?;
+        let written = tokio::io::copy(&mut self, &mut file)
+            .await
+            .map_err(io_to_cdp_error)?;
+        tokio::io::AsyncWriteExt::flush(&mut file)
+            .await
+            .map_err(|e| CdpError::Io(e.to_string()))?;
+        self.close().await?;
+        Ok(written)
+    }
+
+    /// Close the handle now instead of on drop.
+    pub async fn close(mut self) -> Result<(), CdpError> {
+        self.closed = true;
+        self.pending = None;
+        io::methods::Close::new(self.handle.clone())
+            .send(&self.target)
+            .await
+    }
+
+    fn read_future(&self) -> ReadFuture {
+        let target = self.target.clone();
+        let handle = self.handle.clone();
+        let size = self.chunk_size;
+        Box::pin(async move { read_chunk(&target, &handle, size).await })
+    }
+}
+
+/// Read and decode one `IO.read` chunk; returns the bytes and whether EOF was reached.
+pub(crate) async fn read_chunk<S: Sender + Sync>(
+    target: &S,
+    handle: &str,
+    size: Option<i64>,
+) -> Result<(Vec<u8>, bool), CdpError> {
+    let mut read = io::methods::Read::new(handle.to_string());
+    if let Some(size) = size {
+        read = read.with_size(size);
+    }
+    let response = read.send(target).await?;
+    let chunk = if response.base64_encoded == Some(true) {
+        decode_base64(&response.data)?
+    } else {
+        response.data.into_bytes()
+    };
+    Ok((chunk, response.eof))
+}
+
+/// Surface a `CdpError` carried through `std::io::Error` without wrapping it twice.
+fn io_to_cdp_error(e: std::io::Error) -> CdpError {
+    match e.into_inner() {
+        Some(inner) => match inner.downcast::<CdpError>() {
+            Ok(cdp) => *cdp,
+            Err(other) => CdpError::Io(other.to_string()),
+        },
+        None => CdpError::Io("stream read failed".to_string()),
+    }
+}
+
+impl<S: Sender + Clone + Send + Sync + Unpin + 'static> AsyncRead for CdpReadStream<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let this = &mut *self;
+        loop {
+            if this.position < this.buffer.len() {
+                let available = &this.buffer[this.position..];
+                let n = available.len().min(buf.remaining());
+                buf.put_slice(&available[..n]);
+                this.position += n;
+                return Poll::Ready(Ok(()));
+            }
+            if this.eof || this.closed {
+                return Poll::Ready(Ok(()));
+            }
+
+            if this.pending.is_none() {
+                this.pending = Some(this.read_future());
+            }
+            let Some(pending) = this.pending.as_mut() else {
+                continue;
+            };
+            let response = match pending.as_mut().poll(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(response) => response,
+            };
+            this.pending = None;
+
+            match response {
+                Ok((chunk, eof)) => {
+                    this.buffer = chunk;
+                    this.position = 0;
+                    this.eof = eof;
+                }
+                Err(e) => return Poll::Ready(Err(std::io::Error::other(e))),
+            }
+        }
+    }
+}
+
+impl<S: Sender + Clone + Send + Sync + Unpin + 'static> Drop for CdpReadStream<S> {
+    fn drop(&mut self) {
+        if self.closed {
+            return;
+        }
+        let target = self.target.clone();
+        let handle = self.handle.clone();
+        spawn_cleanup(async move {
+            if let Err(e) = io::methods::Close::new(handle.clone()).send(&target).await {
+                warn!(handle = %handle, error = %e, "Failed to close IO stream");
+            }
+        });
+    }
+}
@@ -0,0 +1,269 @@
+use crate::keyboard_layout::KeyDefinition;
+use crate::mouse::Mouse;
+use crate::touchscreen::Touchscreen;
+use crate::{input, CdpError, Sender};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Modifier bits used by `Input.dispatchKeyEvent`, `dispatchMouseEvent` and `dispatchTouchEvent`.
+pub(crate) const MODIFIER_ALT: i64 = 1;
+pub(crate) const MODIFIER_CONTROL: i64 = 2;
+pub(crate) const MODIFIER_META: i64 = 4;
+pub(crate) const MODIFIER_SHIFT: i64 = 8;
+
+fn modifier_bit(key: &str) -> i64 {
+    match key {
+        "Alt" => MODIFIER_ALT,
+        "Control" => MODIFIER_CONTROL,
+        "Meta" => MODIFIER_META,
+        "Shift" => MODIFIER_SHIFT,
+        _ => 0,
+    }
+}
+
+/// Fully resolved key event fields for one key under the current modifiers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct KeyDescription {
+    key: String,
+    code: &'static str,
+    key_code: i64,
+    text: String,
+    location: i64,
+}
+
+fn describe(name: &str, modifiers: i64) -> Result<KeyDescription, CdpError> {
+    let (def, shifted) =
+        KeyDefinition::lookup(name).ok_or_else(|| CdpError::UnknownKey(name.to_string()))?;
+    let shift = shifted || modifiers & MODIFIER_SHIFT != 0;
+    let key = match def.shift_key {
+        Some(shift_key) if shift => shift_key,
+        _ => def.key,
+    };
+    let mut text = match def.text {
+        Some(text) => text.to_string(),
+        None if key.chars().count() == 1 => key.to_string(),
+        None => String::new(),
+    };
+    // Chrome does not produce text while a non-Shift modifier is held (e.g. Ctrl+A).
+    if modifiers & !MODIFIER_SHIFT != 0 {
+        text.clear();
+    }
+    Ok(KeyDescription {
+        key: key.to_string(),
+        code: def.code,
+        key_code: def.key_code,
+        text,
+        location: def.location,
+    })
+}
+
+/// Keyboard input synthesized from the US key layout.
+///
+/// Tracks pressed keys and held modifiers across calls, so `down("Shift")` followed by
+/// `press("a")` types `A` exactly as a user would. The modifier state is shared with the
+/// [`Mouse`] and [`Touchscreen`] created from this keyboard.
+///
+/// # Example
+/// ```no_run
+/// # use cdpkit::{Keyboard, CDP};
+/// # async fn example(cdp: CDP, session_id: String) -> Result<(), cdpkit::CdpError> {
+/// let session = cdp.session(session_id);
+/// let keyboard = Keyboard::new(&session);
+/// keyboard.type_text("Hello\n").await?;
+/// keyboard.shortcut(&["Control", "a"]).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Keyboard<'a, S> {
+    target: &'a S,
+    pressed: Mutex<HashSet<&'static str>>,
+    modifiers: Arc<AtomicI64>,
+}
+
+impl<'a, S: Sender + Sync> Keyboard<'a, S> {
+    /// Create a keyboard with no keys pressed.
+    pub fn new(target: &'a S) -> Self {
+        Self {
+            target,
+            pressed: Mutex::new(HashSet::new()),
+            modifiers: Arc::new(AtomicI64::new(0)),
+        }
+    }
+
+    /// Currently held modifiers as a CDP bit mask (Alt=1, Control=2, Meta=4, Shift=8).
+    pub fn modifiers(&self) -> i64 {
+        self.modifiers.load(Ordering::Acquire)
+    }
+
+    /// Create a mouse that reports this keyboard's held modifiers.
+    pub fn mouse(&self) -> Mouse<'a, S> {
+        Mouse::with_modifiers(self.target, Arc::clone(&self.modifiers))
+    }
+
+    /// Create a touchscreen that reports this keyboard's held modifiers.
+    pub fn touchscreen(&self) -> Touchscreen<'a, S> {
+        Touchscreen::with_modifiers(self.target, Arc::clone(&self.modifiers))
+    }
+
+    /// Press a key without releasing it. Pressing an already held key sends an auto-repeat.
+    pub async fn down(&self, key: &str) -> Result<(), CdpError> {
+        let description = describe(key, self.modifiers())?;
+        let auto_repeat = !self
+            .pressed
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(description.code);
+        self.modifiers
+            .fetch_or(modifier_bit(&description.key), Ordering::AcqRel);
+
+        let type_ = if description.text.is_empty() {
+            "rawKeyDown"
+        } else {
+            "keyDown"
+        };
+        let mut event = input::methods::DispatchKeyEvent::new(type_)
+            .with_modifiers(self.modifiers())
+            .with_windows_virtual_key_code(description.key_code)
+            .with_code(description.code)
+            .with_key(description.key)
+            .with_auto_repeat(auto_repeat)
+            .with_location(description.location);
+        if description.location == 3 {
+            event = event.with_is_keypad(true);
+        }
+        if !description.text.is_empty() {
+            event = event
+                .with_unmodified_text(description.text.clone())
+                .with_text(description.text);
+        }
+        event.send(self.target).await
+    }
+
+    /// Release a held key.
+    pub async fn up(&self, key: &str) -> Result<(), CdpError> {
+        let description = describe(key, self.modifiers())?;
+        self.modifiers
+            .fetch_and(!modifier_bit(&description.key), Ordering::AcqRel);
+        self.pressed
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(description.code);
+
+        input::methods::DispatchKeyEvent::new("keyUp")
+            .with_modifiers(self.modifiers())
+            .with_windows_virtual_key_code(description.key_code)
+            .with_code(description.code)
+            .with_key(description.key)
+            .with_location(description.location)
+            .send(self.target)
+            .await
+    }
+
+    /// Press and release a key.
+    pub async fn press(&self, key: &str) -> Result<(), CdpError> {
+        self.down(key).await?;
+        self.up(key).await
+    }
+
+    /// Type text one character at a time.
+    ///
+    /// Characters on the US layout are sent as full key presses; anything else
+    /// (e.g. emoji or CJK text) is sent with `Input.insertText`.
+    pub async fn type_text(&self, text: &str) -> Result<(), CdpError> {
+        let mut buf = [0u8; 4];
+        for c in text.chars() {
+            let c = c.encode_utf8(&mut buf);
+            if KeyDefinition::lookup(c).is_some() {
+                self.press(c).await?;
+            } else {
+                self.send_character(c).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Insert text directly, without generating key events.
+    pub async fn send_character(&self, text: &str) -> Result<(), CdpError> {
+        input::methods::InsertText::new(text)
+            .send(self.target)
+            .await
+    }
+
+    /// Press a key combination such as `["Control", "Shift", "KeyT"]`.
+    ///
+    /// Keys are pressed in order and released in reverse order.
+    pub async fn shortcut(&self, keys: &[&str]) -> Result<(), CdpError> {
+        for key in keys {
+            self.down(key).await?;
+        }
+        for key in keys.iter().rev() {
+            self.up(key).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describes_plain_and_shifted_characters() {
+        let a = describe("a", 0).unwrap();
+        assert_eq!(
+            (a.key.as_str(), a.code, a.key_code, a.text.as_str()),
+            ("a", "KeyA", 65, "a")
+        );
+
+        let upper = describe("A", 0).unwrap();
+        assert_eq!(
+            (upper.key.as_str(), upper.code, upper.text.as_str()),
+            ("A", "KeyA", "A")
+        );
+
+        let held_shift = describe("KeyA", MODIFIER_SHIFT).unwrap();
+        assert_eq!(held_shift.text, "A");
+
+        let bang = describe("!", 0).unwrap();
+        assert_eq!((bang.code, bang.key_code), ("Digit1", 49));
+    }
+
+    #[test]
+    fn enter_and_newline_produce_carriage_return() {
+        for name in ["Enter", "\n", "\r"] {
+            let enter = describe(name, 0).unwrap();
+            assert_eq!(
+                (enter.key.as_str(), enter.code, enter.key_code),
+                ("Enter", "Enter", 13)
+            );
+            assert_eq!(enter.text, "\r");
+        }
+    }
+
+    #[test]
+    fn control_suppresses_text() {
+        let a = describe("a", MODIFIER_CONTROL).unwrap();
+        assert_eq!(a.text, "");
+        assert_eq!(a.key, "a");
+    }
+
+    #[test]
+    fn modifiers_and_locations() {
+        let shift = describe("Shift", 0).unwrap();
+        assert_eq!((shift.code, shift.location), ("ShiftLeft", 1));
+        assert_eq!(modifier_bit(&shift.key), MODIFIER_SHIFT);
+
+        let numpad = describe("Numpad5", 0).unwrap();
+        assert_eq!((numpad.key.as_str(), numpad.location), ("5", 3));
+        assert_eq!(describe("5", 0).unwrap().code, "Digit5");
+    }
+
+    #[test]
+    fn unknown_key_is_an_error() {
+        assert!(matches!(
+            describe("NotAKey", 0),
+            Err(CdpError::UnknownKey(name)) if name == "NotAKey"
+        ));
+    }
+}
@@ -0,0 +1,201 @@
+/// One physical key of the US keyboard layout.
+///
+/// Mirrors the `KeyboardEvent` fields Chrome expects in `Input.dispatchKeyEvent`:
+/// `key`/`code`, the legacy `keyCode` (sent as `windowsVirtualKeyCode`), the text the
+/// key produces, and its location (0 standard, 1 left, 2 right, 3 numpad).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyDefinition {
+    pub key: &'static str,
+    pub shift_key: Option<&'static str>,
+    pub code: &'static str,
+    pub key_code: i64,
+    pub text: Option<&'static str>,
+    pub location: i64,
+}
+
+const fn key(
+    code: &'static str,
+    key_code: i64,
+    key: &'static str,
+    shift_key: Option<&'static str>,
+) -> KeyDefinition {
+    KeyDefinition {
+        key,
+        shift_key,
+        code,
+        key_code,
+        text: None,
+        location: 0,
+    }
+}
+
+const fn text_key(
+    code: &'static str,
+    key_code: i64,
+    key: &'static str,
+    text: &'static str,
+) -> KeyDefinition {
+    KeyDefinition {
+        key,
+        shift_key: None,
+        code,
+        key_code,
+        text: Some(text),
+        location: 0,
+    }
+}
+
+const fn located(
+    code: &'static str,
+    key_code: i64,
+    key: &'static str,
+    location: i64,
+) -> KeyDefinition {
+    KeyDefinition {
+        key,
+        shift_key: None,
+        code,
+        key_code,
+        text: None,
+        location,
+    }
+}
+
+const fn numpad(code: &'static str, key_code: i64, key: &'static str) -> KeyDefinition {
+    located(code, key_code, key, 3)
+}
+
+pub(crate) const US_LAYOUT: &[KeyDefinition] = &[
+    key("Digit0", 48, "0", Some(")")),
+    key("Digit1", 49, "1", Some("!")),
+    key("Digit2", 50, "2", Some("@")),
+    key("Digit3", 51, "3", Some("#")),
+    key("Digit4", 52, "4", Some("$")),
+    key("Digit5", 53, "5", Some("%")),
+    key("Digit6", 54, "6", Some("^")),
+    key("Digit7", 55, "7", Some("&")),
+    key("Digit8", 56, "8", Some("*")),
+    key("Digit9", 57, "9", Some("(")),
+    key("KeyA", 65, "a", Some("A")),
+    key("KeyB", 66, "b", Some("B")),
+    key("KeyC", 67, "c", Some("C")),
+    key("KeyD", 68, "d", Some("D")),
+    key("KeyE", 69, "e", Some("E")),
+    key("KeyF", 70, "f", Some("F")),
+    key("KeyG", 71, "g", Some("G")),
+    key("KeyH", 72, "h", Some("H")),
+    key("KeyI", 73, "i", Some("I")),
+    key("KeyJ", 74, "j", Some("J")),
+    key("KeyK", 75, "k", Some("K")),
+    key("KeyL", 76, "l", Some("L")),
+    key("KeyM", 77, "m", Some("M")),
+    key("KeyN", 78, "n", Some("N")),
+    key("KeyO", 79, "o", Some("O")),
+    key("KeyP", 80, "p", Some("P")),
+    key("KeyQ", 81, "q", Some("Q")),
+    key("KeyR", 82, "r", Some("R")),
+    key("KeyS", 83, "s", Some("S")),
+    key("KeyT", 84, "t", Some("T")),
+    key("KeyU", 85, "u", Some("U")),
+    key("KeyV", 86, "v", Some("V")),
+    key("KeyW", 87, "w", Some("W")),
+    key("KeyX", 88, "x", Some("X")),
+    key("KeyY", 89, "y", Some("Y")),
+    key("KeyZ", 90, "z", Some("Z")),
+    key("Semicolon", 186, ";", Some(":")),
+    key("Equal", 187, "=", Some("+")),
+    key("Comma", 188, ",", Some("<")),
+    key("Minus", 189, "-", Some("_")),
+    key("Period", 190, ".", Some(">")),
+    key("Slash", 191, "/", Some("?")),
+    key("Backquote", 192, "`", Some("~")),
+    key("BracketLeft", 219, "[", Some("{")),
+    key("Backslash", 220, "\\", Some("|")),
+    key("BracketRight", 221, "]", Some("}")),
+    key("Quote", 222, "'", Some("\"")),
+    key("Space", 32, " ", None),
+    text_key("Enter", 13, "Enter", "\r"),
+    key("Tab", 9, "Tab", None),
+    key("Backspace", 8, "Backspace", None),
+    key("Escape", 27, "Escape", None),
+    key("Delete", 46, "Delete", None),
+    key("Insert", 45, "Insert", None),
+    key("Home", 36, "Home", None),
+    key("End", 35, "End", None),
+    key("PageUp", 33, "PageUp", None),
+    key("PageDown", 34, "PageDown", None),
+    key("ArrowLeft", 37, "ArrowLeft", None),
+    key("ArrowUp", 38, "ArrowUp", None),
+    key("ArrowRight", 39, "ArrowRight", None),
+    key("ArrowDown", 40, "ArrowDown", None),
+    key("CapsLock", 20, "CapsLock", None),
+    key("Pause", 19, "Pause", None),
+    key("ContextMenu", 93, "ContextMenu", None),
+    located("ShiftLeft", 16, "Shift", 1),
+    located("ShiftRight", 16, "Shift", 2),
+    located("ControlLeft", 17, "Control", 1),
+    located("ControlRight", 17, "Control", 2),
+    located("AltLeft", 18, "Alt", 1),
+    located("AltRight", 18, "Alt", 2),
+    located("MetaLeft", 91, "Meta", 1),
+    located("MetaRight", 92, "Meta", 2),
+    key("F1", 112, "F1", None),
+    key("F2", 113, "F2", None),
+    key("F3", 114, "F3", None),
+    key("F4", 115, "F4", None),
+    key("F5", 116, "F5", None),
+    key("F6", 117, "F6", None),
+    key("F7", 118, "F7", None),
+    key("F8", 119, "F8", None),
+    key("F9", 120, "F9", None),
+    key("F10", 121, "F10", None),
+    key("F11", 122, "F11", None),
+    key("F12", 123, "F12", None),
+    numpad("Numpad0", 96, "0"),
+    numpad("Numpad1", 97, "1"),
+    numpad("Numpad2", 98, "2"),
+    numpad("Numpad3", 99, "3"),
+    numpad("Numpad4", 100, "4"),
+    numpad("Numpad5", 101, "5"),
+    numpad("Numpad6", 102, "6"),
+    numpad("Numpad7", 103, "7"),
+    numpad("Numpad8", 104, "8"),
+    numpad("Numpad9", 105, "9"),
+    numpad("NumpadMultiply", 106, "*"),
+    numpad("NumpadAdd", 107, "+"),
+    numpad("NumpadSubtract", 109, "-"),
+    numpad("NumpadDecimal", 110, "."),
+    numpad("NumpadDivide", 111, "/"),
+    KeyDefinition {
+        key: "Enter",
+        shift_key: None,
+        code: "NumpadEnter",
+        key_code: 13,
+        text: Some("\r"),
+        location: 3,
+    },
+];
+
+impl KeyDefinition {
+    /// Look up a key by `code` (`"KeyA"`), `key` (`"a"`, `"Enter"`) or shifted key (`"A"`).
+    ///
+    /// Returns the definition and whether the name refers to the shifted key. Codes win
+    /// over keys, and main-block keys win over numpad keys with the same `key`.
+    pub fn lookup(name: &str) -> Option<(&'static KeyDefinition, bool)> {
+        let name = match name {
+            "\n" | "\r" => "Enter",
+            "\t" => "Tab",
+            other => other,
+        };
+        if let Some(def) = US_LAYOUT.iter().find(|def| def.code == name) {
+            return Some((def, false));
+        }
+        if let Some(def) = US_LAYOUT.iter().find(|def| def.key == name) {
+            return Some((def, false));
+        }
+        US_LAYOUT
+            .iter()
+            .find(|def| def.shift_key == Some(name))
+            .map(|def| (def, true))
+    }
+}
@@ -1,6 +1,37 @@
+mod ax_tree;
+mod browser_context;
+mod capture;
+mod console_collector;
+mod coverage;
+mod cpu_profiler;
+mod debugger_client;
+mod devices;
+mod dialog_handler;
+mod downloads;
+mod element;
+mod emulation_overrides;
+mod encoding;
 mod error;
+mod har;
+mod heap_snapshot;
 mod inner;
+mod interception;
+mod io_stream;
+mod keyboard;
+mod keyboard_layout;
 mod listeners;
+mod metrics;
+mod mouse;
+mod network_tracker;
+mod page_archive;
+mod page_handle;
+mod page_snapshot;
+mod permissions;
+mod screencast;
+mod source_map;
+mod storage_state;
+mod touchscreen;
+mod trace_recorder;
 mod types;
 
 // Generated CDP protocol definitions
@@ -8,7 +39,58 @@ mod types;
 #[allow(deprecated)]
 pub mod protocol;
 
+pub use ax_tree::{AxNode, AxQuery, AxStates, AxTree, AxTristate};
+pub use browser_context::{BrowserContext, BrowserContextOptions, ContextPage};
+pub use capture::{PdfOptions, ScreenshotFormat, ScreenshotOptions};
+pub use console_collector::{
+    ConsoleCollector, ConsoleLevel, ConsoleMessage, ConsoleOptions, ConsoleSource, SourceLocation,
+};
+pub use coverage::{
+    Coverage, CoverageKind, CoverageOptions, CoverageReport, FileCoverage, FunctionHits, LineHits,
+};
+pub use cpu_profiler::{CpuProfile, CpuProfiler, CpuProfilerOptions, FunctionTime};
+pub use debugger_client::{
+    Breakpoint, BreakpointSpec, DebuggerClient, FrameScope, PauseOnExceptions, PauseReason,
+    PauseState, PausedFrame, ScopeKind, ScopeVariable, ScriptInfo,
+};
+pub use devices::{Device, DeviceSize};
+pub use dialog_handler::{DialogAction, DialogHandler, DialogPolicy, HandledDialog};
+pub use downloads::{Download, DownloadOptions, DownloadState, DownloadStatus, Downloads};
+pub use element::{BoundingBox, ElementHandle};
+pub use emulation_overrides::{DeviceMetrics, EmulationOverrides, Geolocation, OverrideGuard};
 pub use error::CdpError;
+pub use har::{
+    Har, HarCache, HarContent, HarCookie, HarCreator, HarEntry, HarLog, HarNameValue, HarOptions,
+    HarPostData, HarRecorder, HarRequest, HarResponse, HarTimings,
+};
+pub use heap_snapshot::{
+    HeapClassDiff, HeapClassSummary, HeapEdge, HeapEdgeName, HeapNode, HeapSnapshot,
+    HeapSnapshotOptions,
+};
+pub use interception::{
+    ContinueOverrides, Credentials, InterceptedRequest, InterceptorOptions, RequestInterceptor,
+};
+pub use io_stream::CdpReadStream;
+pub use keyboard::Keyboard;
+pub use keyboard_layout::KeyDefinition;
+pub use metrics::{Metrics, MetricsDiff, PageMetrics, WebVitals};
+pub use mouse::{ClickOptions, Mouse};
+pub use network_tracker::{InflightRequest, NetworkSnapshot, NetworkTracker};
+pub use page_archive::{
+    ArchiveEntry, ArchiveEntryKind, ArchiveFailure, ArchiveManifest, PageArchive, PageArchiveMode,
+    PageArchiveOptions,
+};
+pub use page_handle::Page;
+pub use page_snapshot::{
+    LayoutBox, PageSnapshot, PageSnapshotOptions, SnapshotChange, SnapshotChangeKind,
+    SnapshotDocument, SnapshotNode,
+};
+pub use permissions::{PermissionGuard, Permissions};
+pub use screencast::{Screencast, ScreencastFormat, ScreencastImage, ScreencastOptions};
+pub use source_map::{MappedFrame, OriginalPosition, SourceMap, SourceMapResolver};
+pub use storage_state::{OriginStorage, StorageCookie, StorageItem, StorageState};
+pub use touchscreen::Touchscreen;
+pub use trace_recorder::{Trace, TraceOptions, TracePreset, TraceRecorder, TraceTransferMode};
 pub use types::Method;
 
 // Re-export all CDP domains
@@ -237,6 +319,108 @@ impl Sender for CDP {}
 impl Sender for Session<'_> {}
 impl Sender for OwnedSession {}
 
+/// Ordered stream of `(event name, params)` for several events, see [`merged_event_stream`].
+pub(crate) type MergedEventStream = EventStream<listeners::TaggedEvent>;
+
+/// Subscribe to several events through one channel.
+///
+/// Separate `event_stream()` subscriptions each buffer independently, so polling them
+/// together can reorder events of different types. Helpers that correlate events (e.g.
+/// `requestWillBeSent` before `responseReceived`) use this instead.
+pub(crate) fn merged_event_stream<S: Sender + ?Sized>(
+    target: &S,
+    event_names: &[&str],
+) -> MergedEventStream {
+    sealed::Sealed::inner(target).merged_event_stream(
+        event_names,
+        sealed::Sealed::session_id(target).map(str::to_owned),
+    )
+}
+
+/// Subscribe to one event without ever dropping it: when `capacity` events are queued,
+/// the connection stops reading from the socket until the receiver catches up.
+///
+/// This stalls every other event and command response on the connection, so the receiver
+/// must be drained by code that does not wait on CDP itself (e.g. writing to a file).
+pub(crate) fn backpressured_event_stream<S: Sender + ?Sized>(
+    target: &S,
+    event_name: &str,
+    capacity: usize,
+) -> tokio::sync::mpsc::Receiver<Arc<serde_json::Value>> {
+    sealed::Sealed::inner(target).backpressured_event_stream(
+        event_name,
+        sealed::Sealed::session_id(target).map(str::to_owned),
+        capacity,
+    )
+}
+
+/// Send a command with its own timeout instead of the connection's command timeout, for
+/// commands whose duration depends on the workload.
+pub(crate) async fn send_with_timeout<S: Sender + Sync + ?Sized, C: Method>(
+    target: &S,
+    cmd: C,
+    timeout: Duration,
+) -> Result<C::Response, CdpError> {
+    cmd.validate()?;
+    sealed::Sealed::inner(target)
+        .send_command_with_timeout(cmd, sealed::Sealed::session_id(target), timeout)
+        .await
+}
+
+/// Run async cleanup from `Drop`; skipped (with a warning) outside a Tokio runtime.
+pub(crate) fn spawn_cleanup(task: impl std::future::Future<Output = ()> + Send + 'static) {
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) => {
+            handle.spawn(task);
+        }
+        Err(_) => ::tracing::warn!("Dropped outside a Tokio runtime; skipping CDP cleanup"),
+    }
+}
+
+/// Sends cleanup commands from `Drop` for types that only borrow their target and so
+/// cannot move it into [`spawn_cleanup`]. The connection is held weakly; cleanup is
+/// skipped once it is gone.
+pub(crate) struct CleanupTarget {
+    inner: std::sync::Weak<CDPInner>,
+    session_id: Option<String>,
+}
+
+impl CleanupTarget {
+    pub(crate) fn new<S: Sender + ?Sized>(target: &S) -> Self {
+        Self {
+            inner: Arc::downgrade(sealed::Sealed::inner(target)),
+            session_id: sealed::Sealed::session_id(target).map(str::to_owned),
+        }
+    }
+
+    /// Send `cmd` from a spawned task, logging failures.
+    pub(crate) fn spawn_send<C: Method + 'static>(&self, cmd: C) {
+        let Some(inner) = self.inner.upgrade().filter(|inner| !inner.is_closed()) else {
+            return;
+        };
+        let session_id = self.session_id.clone();
+        spawn_cleanup(async move {
+            if let Err(e) = inner.send_command(cmd, session_id.as_deref()).await {
+                ::tracing::warn!(method = C::METHOD, error = %e, "Cleanup command failed");
+            }
+        });
+    }
+}
+
+/// Decode the params of a merged event, logging and skipping malformed payloads.
+pub(crate) fn decode_event<T: serde::de::DeserializeOwned>(
+    event_name: &str,
+    params: &serde_json::Value,
+) -> Option<T> {
+    match serde_json::from_value(params.clone()) {
+        Ok(event) => Some(event),
+        Err(e) => {
+            ::tracing::warn!(event = %event_name, error = %e, "Failed to deserialize event");
+            None
+        }
+    }
+}
+
 struct DiscoveryEndpoint {
     connect_addr: String,
     host_header: String,
@@ -347,6 +531,7 @@ impl Session<'_> {
 ///
 /// Created via [`CDP::owned_session()`]. Holds a clone of the `CDP` handle
 /// (which is cheap — just an Arc increment) so it satisfies `Send + 'static`.
+#[derive(Clone)]
 pub struct OwnedSession {
     cdp: CDP,
     session_id: String,
@@ -36,6 +36,14 @@ impl OverflowState {
     }
 }
 
+/// Event name and params delivered to a merged (multi-event) listener.
+pub(crate) type TaggedEvent = (Arc<str>, Arc<Value>);
+
+/// An event that did not fit into a backpressured listener's channel. The message loop
+/// awaits these after releasing the listener lock, so no further messages are read from
+/// the socket until the consumer catches up; outgoing messages are still sent meanwhile.
+pub(crate) type PendingDelivery = (Sender<Arc<Value>>, Arc<Value>);
+
 enum ListenerSender {
     Unbounded(UnboundedSender<Arc<Value>>),
     Bounded {
@@ -43,6 +51,13 @@ enum ListenerSender {
         overflow: EventOverflowStrategy,
         state: Arc<OverflowState>,
     },
+    /// One channel shared by several event names, so their relative order is preserved.
+    Tagged {
+        event_name: Arc<str>,
+        sender: UnboundedSender<TaggedEvent>,
+    },
+    /// Never drops: a full channel pauses the message loop instead.
+    Backpressure(Sender<Arc<Value>>),
 }
 
 struct Listener {
@@ -98,7 +113,53 @@ impl EventListeners {
         receiver
     }
 
-    pub fn dispatch(&mut self, event_name: &str, session_id: Option<&str>, event: Arc<Value>) {
+    pub fn add_merged_listener(
+        &mut self,
+        event_names: &[&str],
+        session_id: Option<String>,
+    ) -> UnboundedReceiver<TaggedEvent> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        for event_name in event_names {
+            self.listeners
+                .entry(event_name.to_string())
+                .or_default()
+                .push(Listener {
+                    session_id: session_id.clone(),
+                    sender: ListenerSender::Tagged {
+                        event_name: Arc::from(*event_name),
+                        sender: tx.clone(),
+                    },
+                });
+        }
+        rx
+    }
+
+    pub fn add_backpressure_listener(
+        &mut self,
+        event_name: &str,
+        session_id: Option<String>,
+        capacity: usize,
+    ) -> Receiver<Arc<Value>> {
+        let (tx, rx) = mpsc::channel(capacity);
+        self.listeners
+            .entry(event_name.to_string())
+            .or_default()
+            .push(Listener {
+                session_id,
+                sender: ListenerSender::Backpressure(tx),
+            });
+        rx
+    }
+
+    /// Deliver `event` to matching listeners. Returns deliveries for backpressured
+    /// listeners whose channel is full; the caller must await them in order.
+    pub fn dispatch(
+        &mut self,
+        event_name: &str,
+        session_id: Option<&str>,
+        event: Arc<Value>,
+    ) -> Vec<PendingDelivery> {
+        let mut pending = Vec::new();
         if let Some(listeners) = self.listeners.get_mut(event_name) {
             listeners.retain(|listener| {
                 let matches = match (&listener.session_id, session_id) {
@@ -114,6 +175,9 @@ impl EventListeners {
                 // UnboundedSender::send only fails when the receiver is dropped
                 match &listener.sender {
                     ListenerSender::Unbounded(sender) => sender.send(event.clone()).is_ok(),
+                    ListenerSender::Tagged { event_name, sender } => {
+                        sender.send((Arc::clone(event_name), event.clone())).is_ok()
+                    }
                     ListenerSender::Bounded {
                         sender,
                         overflow,
@@ -131,9 +195,18 @@ impl EventListeners {
                         }
                         Err(tokio::sync::mpsc::error::TrySendError::Closed(_)) => false,
                     },
+                    ListenerSender::Backpressure(sender) => match sender.try_send(event.clone()) {
+                        Ok(()) => true,
+                        Err(tokio::sync::mpsc::error::TrySendError::Full(event)) => {
+                            pending.push((sender.clone(), event));
+                            true
+                        }
+                        Err(tokio::sync::mpsc::error::TrySendError::Closed(_)) => false,
+                    },
                 }
             });
         }
+        pending
     }
 
     pub fn clear(&mut self) {
@@ -292,6 +365,34 @@ mod tests {
         assert_eq!(*rx.try_recv().unwrap(), json!(2));
     }
 
+    #[test]
+    fn merged_listener_preserves_order_across_events() {
+        let mut listeners = EventListeners::new();
+        let mut rx =
+            listeners.add_merged_listener(&["Test.first", "Test.second"], Some("s".into()));
+
+        listeners.dispatch("Test.second", Some("s"), Arc::new(json!(1)));
+        listeners.dispatch("Test.first", Some("s"), Arc::new(json!(2)));
+        listeners.dispatch("Test.first", Some("other"), Arc::new(json!(3)));
+        listeners.dispatch("Test.second", Some("s"), Arc::new(json!(4)));
+
+        let received: Vec<(String, Value)> = std::iter::from_fn(|| rx.try_recv().ok())
+            .map(|(name, value)| (name.to_string(), (*value).clone()))
+            .collect();
+        assert_eq!(
+            received,
+            vec![
+                ("Test.second".to_string(), json!(1)),
+                ("Test.first".to_string(), json!(2)),
+                ("Test.second".to_string(), json!(4)),
+            ]
+        );
+
+        drop(rx);
+        listeners.dispatch("Test.first", Some("s"), Arc::new(json!(5)));
+        assert!(listeners.listeners["Test.first"].is_empty());
+    }
+
     #[test]
     fn bounded_drop_newest_keeps_listener_registered() {
         let mut listeners = EventListeners::new();
@@ -327,6 +428,25 @@ mod tests {
         assert_eq!(listeners.listeners["Test.event"].len(), 1);
     }
 
+    #[test]
+    fn backpressure_listener_returns_overflow_as_pending() {
+        let mut listeners = EventListeners::new();
+        let mut rx = listeners.add_backpressure_listener("Test.event", None, 1);
+
+        assert!(listeners
+            .dispatch("Test.event", None, Arc::new(json!(1)))
+            .is_empty());
+        let pending = listeners.dispatch("Test.event", None, Arc::new(json!(2)));
+        assert_eq!(pending.len(), 1);
+        assert_eq!(*pending[0].1, json!(2));
+        assert_eq!(*rx.try_recv().unwrap(), json!(1));
+
+        drop(rx);
+        drop(pending);
+        listeners.dispatch("Test.event", None, Arc::new(json!(3)));
+        assert!(listeners.listeners["Test.event"].is_empty());
+    }
+
     #[test]
     fn bounded_close_stream_removes_listener_on_overflow() {
         let mut listeners = EventListeners::new();
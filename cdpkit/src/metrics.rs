@@ -0,0 +1,467 @@
+use crate::dom::types::BackendNodeId;
+use crate::element::exception_error;
+use crate::performance::types::Metric;
+use crate::performance_timeline::events::TimelineEventAdded;
+use crate::{
+    decode_event, merged_event_stream, performance, performance_timeline, runtime, spawn_cleanup,
+    CdpError, OwnedSession,
+};
+use futures::StreamExt;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::warn;
+
+const TIMELINE_EVENT_TYPES: [&str; 2] = ["largest-contentful-paint", "layout-shift"];
+
+/// Shifts less than this far apart belong to the same CLS session window.
+const CLS_WINDOW_GAP_MS: f64 = 1000.0;
+/// A CLS session window spans at most this long.
+const CLS_WINDOW_MAX_MS: f64 = 5000.0;
+
+/// `Performance.getMetrics` as a typed struct.
+///
+/// Counters are current values; durations and `*_count` fields are cumulative since the
+/// page was created, so compare two snapshots with [`diff`](Self::diff).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PageMetrics {
+    /// Monotonic time the snapshot was taken.
+    pub timestamp: Duration,
+    pub documents: u64,
+    pub frames: u64,
+    pub js_event_listeners: u64,
+    pub nodes: u64,
+    pub layout_objects: u64,
+    pub layout_count: u64,
+    pub recalc_style_count: u64,
+    pub layout_duration: Duration,
+    pub recalc_style_duration: Duration,
+    pub script_duration: Duration,
+    pub task_duration: Duration,
+    pub js_heap_used_size: u64,
+    pub js_heap_total_size: u64,
+    /// Metrics without a typed field, by their CDP name.
+    pub other: BTreeMap<String, f64>,
+}
+
+impl PageMetrics {
+    pub fn from_metrics(metrics: &[Metric]) -> Self {
+        let mut snapshot = Self::default();
+        for metric in metrics {
+            let count = metric.value.max(0.0) as u64;
+            let seconds = Duration::try_from_secs_f64(metric.value).unwrap_or_default();
+            match metric.name.as_str() {
+                "Timestamp" => snapshot.timestamp = seconds,
+                "Documents" => snapshot.documents = count,
+                "Frames" => snapshot.frames = count,
+                "JSEventListeners" => snapshot.js_event_listeners = count,
+                "Nodes" => snapshot.nodes = count,
+                "LayoutObjects" => snapshot.layout_objects = count,
+                "LayoutCount" => snapshot.layout_count = count,
+                "RecalcStyleCount" => snapshot.recalc_style_count = count,
+                "LayoutDuration" => snapshot.layout_duration = seconds,
+                "RecalcStyleDuration" => snapshot.recalc_style_duration = seconds,
+                "ScriptDuration" => snapshot.script_duration = seconds,
+                "TaskDuration" => snapshot.task_duration = seconds,
+                "JSHeapUsedSize" => snapshot.js_heap_used_size = count,
+                "JSHeapTotalSize" => snapshot.js_heap_total_size = count,
+                name => {
+                    snapshot.other.insert(name.to_string(), metric.value);
+                }
+            }
+        }
+        snapshot
+    }
+
+    /// Change from an `earlier` snapshot of the same page to this one.
+    pub fn diff(&self, earlier: &PageMetrics) -> MetricsDiff {
+        let delta = |now: u64, then: u64| now as i64 - then as i64;
+        MetricsDiff {
+            elapsed: self.timestamp.saturating_sub(earlier.timestamp),
+            documents: delta(self.documents, earlier.documents),
+            frames: delta(self.frames, earlier.frames),
+            js_event_listeners: delta(self.js_event_listeners, earlier.js_event_listeners),
+            nodes: delta(self.nodes, earlier.nodes),
+            layout_objects: delta(self.layout_objects, earlier.layout_objects),
+            layout_count: delta(self.layout_count, earlier.layout_count),
+            recalc_style_count: delta(self.recalc_style_count, earlier.recalc_style_count),
+            layout_duration: self.layout_duration.saturating_sub(earlier.layout_duration),
+            recalc_style_duration: self
+                .recalc_style_duration
+                .saturating_sub(earlier.recalc_style_duration),
+            script_duration: self.script_duration.saturating_sub(earlier.script_duration),
+            task_duration: self.task_duration.saturating_sub(earlier.task_duration),
+            js_heap_used_size: delta(self.js_heap_used_size, earlier.js_heap_used_size),
+            js_heap_total_size: delta(self.js_heap_total_size, earlier.js_heap_total_size),
+            other: self
+                .other
+                .iter()
+                .map(|(name, value)| {
+                    let before = earlier.other.get(name).copied().unwrap_or(0.0);
+                    (name.clone(), value - before)
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Difference between two [`PageMetrics`] snapshots, see [`PageMetrics::diff`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MetricsDiff {
+    pub elapsed: Duration,
+    pub documents: i64,
+    pub frames: i64,
+    pub js_event_listeners: i64,
+    pub nodes: i64,
+    pub layout_objects: i64,
+    /// Layouts and style recalculations performed in between.
+    pub layout_count: i64,
+    pub recalc_style_count: i64,
+    /// Time spent in between; cumulative counters never go down.
+    pub layout_duration: Duration,
+    pub recalc_style_duration: Duration,
+    pub script_duration: Duration,
+    pub task_duration: Duration,
+    pub js_heap_used_size: i64,
+    pub js_heap_total_size: i64,
+    pub other: BTreeMap<String, f64>,
+}
+
+/// Core Web Vitals for the current navigation, see [`Metrics::web_vitals`].
+///
+/// Times are relative to the start of the navigation (`performance.timeOrigin`).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WebVitals {
+    /// Largest Contentful Paint; `None` until the page painted a candidate.
+    pub lcp: Option<Duration>,
+    /// Backend node of the LCP element, if it is still in the document.
+    pub lcp_node_id: Option<BackendNodeId>,
+    /// URL of the LCP image, if it was one.
+    pub lcp_url: Option<String>,
+    /// Cumulative Layout Shift: the largest session window of unexpected shifts.
+    pub cls: f64,
+    /// Number of layout shifts not caused by recent input.
+    pub layout_shifts: usize,
+    /// First Contentful Paint.
+    pub fcp: Option<Duration>,
+    /// Time to First Byte: when the response to the navigation started arriving.
+    pub ttfb: Option<Duration>,
+}
+
+#[derive(Debug, Clone)]
+struct LcpCandidate {
+    /// Milliseconds since the epoch.
+    time: f64,
+    node_id: Option<BackendNodeId>,
+    url: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct LayoutShift {
+    /// Milliseconds since the epoch.
+    time: f64,
+    value: f64,
+}
+
+#[derive(Debug, Default)]
+struct Timeline {
+    lcp: Vec<LcpCandidate>,
+    /// Shifts without recent input, in report order.
+    shifts: Vec<LayoutShift>,
+}
+
+impl Timeline {
+    fn apply(&mut self, event: TimelineEventAdded) {
+        let event = event.event;
+        if let Some(lcp) = event.lcp_details {
+            // Cross-origin images without Timing-Allow-Origin report a zero render time.
+            let time = if lcp.render_time > 0.0 {
+                lcp.render_time
+            } else {
+                lcp.load_time
+            };
+            self.lcp.push(LcpCandidate {
+                time: time * 1000.0,
+                node_id: lcp.node_id,
+                url: lcp.url.filter(|url| !url.is_empty()),
+            });
+        }
+        if let Some(shift) = event.layout_shift_details {
+            if !shift.had_recent_input {
+                self.shifts.push(LayoutShift {
+                    time: event.time * 1000.0,
+                    value: shift.value,
+                });
+            }
+        }
+    }
+
+    /// Web Vitals for the navigation that started at `time_origin` (ms since the epoch);
+    /// entries from earlier navigations are ignored.
+    fn web_vitals(&self, time_origin: f64) -> WebVitals {
+        let since_origin = |time: f64| Duration::try_from_secs_f64((time - time_origin) / 1000.0);
+        let lcp = self
+            .lcp
+            .iter()
+            .rev()
+            .find(|candidate| candidate.time >= time_origin);
+        let mut shifts: Vec<LayoutShift> = self
+            .shifts
+            .iter()
+            .filter(|shift| shift.time >= time_origin)
+            .copied()
+            .collect();
+        shifts.sort_by(|a, b| a.time.total_cmp(&b.time));
+        WebVitals {
+            lcp: lcp.and_then(|candidate| since_origin(candidate.time).ok()),
+            lcp_node_id: lcp.and_then(|candidate| candidate.node_id),
+            lcp_url: lcp.and_then(|candidate| candidate.url.clone()),
+            cls: cumulative_layout_shift(&shifts),
+            layout_shifts: shifts.len(),
+            fcp: None,
+            ttfb: None,
+        }
+    }
+}
+
+/// The largest sum of shifts in a session window: shifts less than 1 s apart, spanning
+/// at most 5 s.
+fn cumulative_layout_shift(shifts: &[LayoutShift]) -> f64 {
+    let (mut largest, mut current) = (0.0f64, 0.0);
+    let mut window: Option<(f64, f64)> = None;
+    for shift in shifts {
+        match window {
+            Some((start, previous))
+                if shift.time - previous < CLS_WINDOW_GAP_MS
+                    && shift.time - start < CLS_WINDOW_MAX_MS =>
+            {
+                current += shift.value;
+                window = Some((start, shift.time));
+            }
+            _ => {
+                current = shift.value;
+                window = Some((shift.time, shift.time));
+            }
+        }
+        largest = largest.max(current);
+    }
+    largest
+}
+
+/// Navigation timing read from the page, in milliseconds.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NavigationTiming {
+    time_origin: f64,
+    ttfb: Option<f64>,
+    fcp: Option<f64>,
+}
+
+const NAVIGATION_TIMING: &str = "(() => {
+    const navigation = performance.getEntriesByType('navigation')[0];
+    const paint = performance.getEntriesByName('first-contentful-paint')[0];
+    return {
+        timeOrigin: performance.timeOrigin,
+        ttfb: navigation ? navigation.responseStart : null,
+        fcp: paint ? paint.startTime : null,
+    };
+})()";
+
+/// Performance metrics and Web Vitals for a page session.
+///
+/// [`snapshot`](Self::snapshot) reads `Performance.getMetrics`. Largest Contentful Paint
+/// and layout shifts are collected from `PerformanceTimeline` events, including the ones
+/// Chrome buffered before the collector started. FCP and TTFB come from the page's
+/// navigation timing.
+///
+/// # Example
+/// ```no_run
+/// # use cdpkit::{Metrics, OwnedSession};
+/// # async fn example(session: OwnedSession) -> Result<(), cdpkit::CdpError> {
+/// let metrics = Metrics::start(&session).await?;
+/// let before = metrics.snapshot().await?;
+/// // ... interact with the page ...
+/// let after = metrics.snapshot().await?;
+/// let diff = after.diff(&before);
+/// println!("{} layouts, {:?} of script", diff.layout_count, diff.script_duration);
+///
+/// let vitals = metrics.web_vitals().await?;
+/// println!("LCP {:?}, CLS {:.3}", vitals.lcp, vitals.cls);
+/// # Ok(())
+/// # }
+/// ```
+pub struct Metrics {
+    session: OwnedSession,
+    timeline: Arc<Mutex<Timeline>>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl Metrics {
+    /// Enable the `Performance` domain and subscribe to LCP and layout shift entries.
+    pub async fn start(session: &OwnedSession) -> Result<Self, CdpError> {
+        let mut events = merged_event_stream(session, &["PerformanceTimeline.timelineEventAdded"]);
+        let timeline = Arc::new(Mutex::new(Timeline::default()));
+        let task = tokio::spawn({
+            let timeline = Arc::clone(&timeline);
+            async move {
+                while let Some((name, params)) = events.next().await {
+                    if let Some(event) = decode_event::<TimelineEventAdded>(&name, &params) {
+                        timeline
+                            .lock()
+                            .unwrap_or_else(|e| e.into_inner())
+                            .apply(event);
+                    }
+                }
+            }
+        });
+        let metrics = Self {
+            session: session.clone(),
+            timeline,
+            task,
+        };
+        performance::methods::Enable::new().send(session).await?;
+        performance_timeline::methods::Enable::new(
+            TIMELINE_EVENT_TYPES.iter().map(|t| t.to_string()).collect(),
+        )
+        .send(session)
+        .await?;
+        Ok(metrics)
+    }
+
+    /// Current `Performance.getMetrics` values.
+    pub async fn snapshot(&self) -> Result<PageMetrics, CdpError> {
+        let response = performance::methods::GetMetrics::new()
+            .send(&self.session)
+            .await?;
+        Ok(PageMetrics::from_metrics(&response.metrics))
+    }
+
+    /// LCP, CLS, FCP and TTFB of the current navigation so far. LCP and CLS keep changing
+    /// until the user interacts with the page, so read them when the scenario is done.
+    pub async fn web_vitals(&self) -> Result<WebVitals, CdpError> {
+        let response = runtime::methods::Evaluate::new(NAVIGATION_TIMING)
+            .with_return_by_value(true)
+            .send(&self.session)
+            .await?;
+        if let Some(details) = response.exception_details {
+            return Err(exception_error(&details));
+        }
+        let timing: NavigationTiming =
+            serde_json::from_value(response.result.value.unwrap_or_default())?;
+        let millis =
+            |ms: Option<f64>| ms.and_then(|ms| Duration::try_from_secs_f64(ms / 1000.0).ok());
+
+        let mut vitals = self
+            .timeline
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .web_vitals(timing.time_origin);
+        vitals.fcp = millis(timing.fcp);
+        vitals.ttfb = millis(timing.ttfb);
+        Ok(vitals)
+    }
+}
+
+impl Drop for Metrics {
+    fn drop(&mut self) {
+        self.task.abort();
+        let session = self.session.clone();
+        spawn_cleanup(async move {
+            // An empty type list stops the timeline reporting.
+            let timeline = performance_timeline::methods::Enable::new(Vec::new())
+                .send(&session)
+                .await;
+            let performance = performance::methods::Disable::new().send(&session).await;
+            if let Err(e) = timeline.and(performance) {
+                warn!(error = %e, "Failed to stop performance metrics");
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn metric(name: &str, value: f64) -> Metric {
+        Metric {
+            name: name.into(),
+            value,
+        }
+    }
+
+    #[test]
+    fn snapshots_are_typed_and_diffable() {
+        let before = PageMetrics::from_metrics(&[
+            metric("Timestamp", 100.0),
+            metric("Nodes", 120.0),
+            metric("LayoutCount", 4.0),
+            metric("ScriptDuration", 0.25),
+            metric("AdSubframes", 0.0),
+        ]);
+        let after = PageMetrics::from_metrics(&[
+            metric("Timestamp", 102.5),
+            metric("Nodes", 80.0),
+            metric("LayoutCount", 9.0),
+            metric("ScriptDuration", 0.75),
+            metric("AdSubframes", 2.0),
+        ]);
+        assert_eq!(after.nodes, 80);
+        assert_eq!(after.other["AdSubframes"], 2.0);
+
+        let diff = after.diff(&before);
+        assert_eq!(diff.elapsed, Duration::from_millis(2500));
+        assert_eq!(diff.nodes, -40);
+        assert_eq!(diff.layout_count, 5);
+        assert_eq!(diff.script_duration, Duration::from_millis(500));
+        assert_eq!(diff.other["AdSubframes"], 2.0);
+    }
+
+    #[test]
+    fn cls_takes_the_largest_session_window() {
+        let shift = |time: f64, value: f64| LayoutShift { time, value };
+        // Window 1: 0.1 + 0.25 (500 ms apart). Window 2 starts after a 1.5 s gap and
+        // stays under 1 s gaps, but is cut at 5 s: 0.3, then 0.25 on its own.
+        let shifts = [
+            shift(0.0, 0.1),
+            shift(500.0, 0.25),
+            shift(2000.0, 0.05),
+            shift(2900.0, 0.05),
+            shift(3800.0, 0.05),
+            shift(4700.0, 0.05),
+            shift(5600.0, 0.05),
+            shift(6500.0, 0.05),
+            shift(7400.0, 0.25),
+        ];
+        let cls = cumulative_layout_shift(&shifts);
+        assert!((cls - 0.35).abs() < 1e-9, "{cls}");
+    }
+
+    #[test]
+    fn vitals_ignore_entries_from_earlier_navigations() {
+        let mut timeline = Timeline::default();
+        let event = |value: serde_json::Value| serde_json::from_value(value).unwrap();
+        timeline.apply(event(json!({"event": {
+            "frameId": "f", "type": "largest-contentful-paint", "name": "", "time": 1.0,
+            "lcpDetails": {"renderTime": 1.0, "loadTime": 0.9, "size": 10.0}
+        }})));
+        timeline.apply(event(json!({"event": {
+            "frameId": "f", "type": "largest-contentful-paint", "name": "", "time": 11.2,
+            "lcpDetails": {"renderTime": 0.0, "loadTime": 11.2, "size": 500.0,
+                           "url": "https://app.test/hero.jpg", "nodeId": 42}
+        }})));
+        timeline.apply(event(json!({"event": {
+            "frameId": "f", "type": "layout-shift", "name": "", "time": 10.5,
+            "layoutShiftDetails": {"value": 0.3, "hadRecentInput": true,
+                                   "lastInputTime": 10.4, "sources": []}
+        }})));
+
+        let vitals = timeline.web_vitals(10_000.0);
+        assert_eq!(vitals.lcp, Some(Duration::from_millis(1200)));
+        assert_eq!(vitals.lcp_node_id, Some(42));
+        assert_eq!(vitals.lcp_url.as_deref(), Some("https://app.test/hero.jpg"));
+        assert_eq!((vitals.cls, vitals.layout_shifts), (0.0, 0));
+    }
+}
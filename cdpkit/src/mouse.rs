@@ -0,0 +1,163 @@
+use crate::input::types::MouseButton;
+use crate::{input, CdpError, Sender};
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Bit of `button` in the `buttons` mask of `Input.dispatchMouseEvent`.
+fn button_bit(button: &MouseButton) -> i64 {
+    match button {
+        MouseButton::None => 0,
+        MouseButton::Left => 1,
+        MouseButton::Right => 2,
+        MouseButton::Middle => 4,
+        MouseButton::Back => 8,
+        MouseButton::Forward => 16,
+    }
+}
+
+/// Options for [`Mouse::click`].
+#[derive(Debug, Clone)]
+pub struct ClickOptions {
+    pub button: MouseButton,
+    /// Number of clicks; `2` produces a double click.
+    pub click_count: i64,
+    /// Pause between press and release.
+    pub delay: Option<std::time::Duration>,
+}
+
+impl Default for ClickOptions {
+    fn default() -> Self {
+        Self {
+            button: MouseButton::Left,
+            click_count: 1,
+            delay: None,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct MouseState {
+    x: f64,
+    y: f64,
+    buttons: i64,
+}
+
+/// Mouse input with tracked pointer position and pressed buttons.
+///
+/// Every event carries the current `buttons` mask, so a move between `down()` and `up()`
+/// is reported as a drag.
+pub struct Mouse<'a, S> {
+    target: &'a S,
+    state: Mutex<MouseState>,
+    modifiers: Arc<AtomicI64>,
+}
+
+impl<'a, S: Sender + Sync> Mouse<'a, S> {
+    /// Create a mouse at `(0, 0)` with no buttons pressed and no modifiers.
+    pub fn new(target: &'a S) -> Self {
+        Self::with_modifiers(target, Arc::new(AtomicI64::new(0)))
+    }
+
+    pub(crate) fn with_modifiers(target: &'a S, modifiers: Arc<AtomicI64>) -> Self {
+        Self {
+            target,
+            state: Mutex::new(MouseState::default()),
+            modifiers,
+        }
+    }
+
+    /// Current pointer position.
+    pub fn position(&self) -> (f64, f64) {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        (state.x, state.y)
+    }
+
+    /// Currently pressed buttons as a CDP bit mask (Left=1, Right=2, Middle=4, Back=8, Forward=16).
+    pub fn buttons(&self) -> i64 {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).buttons
+    }
+
+    /// Move the pointer to `(x, y)`, emitting `steps` evenly spaced `mouseMoved` events.
+    pub async fn move_to(&self, x: f64, y: f64, steps: u32) -> Result<(), CdpError> {
+        let (from_x, from_y) = self.position();
+        let steps = steps.max(1);
+        for step in 1..=steps {
+            let t = f64::from(step) / f64::from(steps);
+            let (step_x, step_y) = (from_x + (x - from_x) * t, from_y + (y - from_y) * t);
+            let buttons = {
+                let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+                state.x = step_x;
+                state.y = step_y;
+                state.buttons
+            };
+            input::methods::DispatchMouseEvent::new("mouseMoved", step_x, step_y)
+                .with_modifiers(self.modifiers.load(Ordering::Acquire))
+                .with_button(MouseButton::None)
+                .with_buttons(buttons)
+                .send(self.target)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Press a button at the current position.
+    pub async fn down(&self, button: MouseButton, click_count: i64) -> Result<(), CdpError> {
+        let (x, y, buttons) = {
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            state.buttons |= button_bit(&button);
+            (state.x, state.y, state.buttons)
+        };
+        input::methods::DispatchMouseEvent::new("mousePressed", x, y)
+            .with_modifiers(self.modifiers.load(Ordering::Acquire))
+            .with_button(button)
+            .with_buttons(buttons)
+            .with_click_count(click_count)
+            .send(self.target)
+            .await
+    }
+
+    /// Release a button at the current position.
+    pub async fn up(&self, button: MouseButton, click_count: i64) -> Result<(), CdpError> {
+        let (x, y, buttons) = {
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            state.buttons &= !button_bit(&button);
+            (state.x, state.y, state.buttons)
+        };
+        input::methods::DispatchMouseEvent::new("mouseReleased", x, y)
+            .with_modifiers(self.modifiers.load(Ordering::Acquire))
+            .with_button(button)
+            .with_buttons(buttons)
+            .with_click_count(click_count)
+            .send(self.target)
+            .await
+    }
+
+    /// Move to `(x, y)` and click. A `click_count` of `n` emits `n` press/release pairs
+    /// with increasing click counts, matching what Chrome sees from a real double click.
+    pub async fn click(&self, x: f64, y: f64, options: ClickOptions) -> Result<(), CdpError> {
+        self.move_to(x, y, 1).await?;
+        for count in 1..=options.click_count.max(1) {
+            self.down(options.button.clone(), count).await?;
+            if let Some(delay) = options.delay {
+                tokio::time::sleep(delay).await;
+            }
+            self.up(options.button.clone(), count).await?;
+        }
+        Ok(())
+    }
+
+    /// Scroll the wheel by `(delta_x, delta_y)` CSS pixels at the current position.
+    pub async fn wheel(&self, delta_x: f64, delta_y: f64) -> Result<(), CdpError> {
+        let (x, y, buttons) = {
+            let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            (state.x, state.y, state.buttons)
+        };
+        input::methods::DispatchMouseEvent::new("mouseWheel", x, y)
+            .with_modifiers(self.modifiers.load(Ordering::Acquire))
+            .with_buttons(buttons)
+            .with_delta_x(delta_x)
+            .with_delta_y(delta_y)
+            .send(self.target)
+            .await
+    }
+}
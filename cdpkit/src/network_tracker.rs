@@ -0,0 +1,322 @@
+use crate::{merged_event_stream, network, CdpError, EventStream, OwnedSession};
+use futures::StreamExt;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+const TRACKED_EVENTS: &[&str] = &[
+    "Network.requestWillBeSent",
+    "Network.loadingFinished",
+    "Network.loadingFailed",
+    "Network.webSocketCreated",
+    "Network.webSocketHandshakeResponseReceived",
+    "Network.webSocketClosed",
+];
+
+/// A request that has been sent but has not finished or failed yet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InflightRequest {
+    pub request_id: String,
+    /// Current URL; updated on each redirect hop.
+    pub url: String,
+    /// CDP resource type, or `"WebSocket"` for a handshake in progress.
+    pub resource_type: Option<String>,
+    pub started: Instant,
+}
+
+/// Network activity at one point in time, published by [`NetworkTracker`].
+#[derive(Debug, Clone)]
+pub struct NetworkSnapshot {
+    /// In-flight requests, ordered by start time.
+    pub inflight: Vec<InflightRequest>,
+    /// WebSockets past their handshake. These are long-lived, so they do not count as
+    /// in-flight.
+    pub open_websockets: usize,
+    /// Requests seen since the tracker started; redirect hops are not counted twice.
+    pub total_requests: u64,
+    pub finished_requests: u64,
+    pub failed_requests: u64,
+    /// Time of the last event that changed the in-flight set.
+    pub last_activity: Instant,
+}
+
+impl NetworkSnapshot {
+    fn new() -> Self {
+        Self {
+            inflight: Vec::new(),
+            open_websockets: 0,
+            total_requests: 0,
+            finished_requests: 0,
+            failed_requests: 0,
+            last_activity: Instant::now(),
+        }
+    }
+
+    /// Number of in-flight requests.
+    pub fn inflight_count(&self) -> usize {
+        self.inflight.len()
+    }
+}
+
+#[derive(Debug)]
+struct TrackerState {
+    snapshot: NetworkSnapshot,
+    websockets: HashSet<String>,
+}
+
+impl TrackerState {
+    fn new() -> Self {
+        Self {
+            snapshot: NetworkSnapshot::new(),
+            websockets: HashSet::new(),
+        }
+    }
+
+    /// Apply one event; returns whether the snapshot changed.
+    fn apply(&mut self, name: &str, params: &Value) -> bool {
+        let Some(request_id) = params["requestId"].as_str() else {
+            return false;
+        };
+        let snapshot = &mut self.snapshot;
+        match name {
+            "Network.requestWillBeSent" | "Network.webSocketCreated" => {
+                let url = params["request"]["url"]
+                    .as_str()
+                    .or(params["url"].as_str())
+                    .unwrap_or_default();
+                let resource_type = if name == "Network.webSocketCreated" {
+                    Some("WebSocket".to_string())
+                } else {
+                    params["type"].as_str().map(str::to_owned)
+                };
+                // A redirect reuses the request id: same request, new URL.
+                if let Some(existing) = snapshot
+                    .inflight
+                    .iter_mut()
+                    .find(|r| r.request_id == request_id)
+                {
+                    existing.url = url.to_string();
+                    return true;
+                }
+                snapshot.total_requests += 1;
+                snapshot.inflight.push(InflightRequest {
+                    request_id: request_id.to_string(),
+                    url: url.to_string(),
+                    resource_type,
+                    started: Instant::now(),
+                });
+            }
+            "Network.loadingFinished" | "Network.loadingFailed" => {
+                if !self.remove(request_id) {
+                    return false;
+                }
+                if name == "Network.loadingFinished" {
+                    self.snapshot.finished_requests += 1;
+                } else {
+                    self.snapshot.failed_requests += 1;
+                }
+            }
+            "Network.webSocketHandshakeResponseReceived" => {
+                if !self.remove(request_id) {
+                    return false;
+                }
+                self.websockets.insert(request_id.to_string());
+                self.snapshot.finished_requests += 1;
+                self.snapshot.open_websockets = self.websockets.len();
+            }
+            "Network.webSocketClosed" => {
+                // Closed during the handshake counts as a failed request.
+                if self.remove(request_id) {
+                    self.snapshot.failed_requests += 1;
+                } else if !self.websockets.remove(request_id) {
+                    return false;
+                }
+                self.snapshot.open_websockets = self.websockets.len();
+            }
+            _ => return false,
+        }
+        self.snapshot.last_activity = Instant::now();
+        true
+    }
+
+    fn remove(&mut self, request_id: &str) -> bool {
+        let before = self.snapshot.inflight.len();
+        self.snapshot
+            .inflight
+            .retain(|r| r.request_id != request_id);
+        self.snapshot.inflight.len() != before
+    }
+}
+
+/// Counts in-flight requests on a session from `Network` events.
+///
+/// A request is in flight from `requestWillBeSent` until `loadingFinished` or
+/// `loadingFailed`; redirects keep the same request in flight, and cached responses still
+/// end with `loadingFinished`. WebSockets are in flight only during their handshake.
+/// Requests already running when the tracker starts are not seen.
+///
+/// # Example
+/// ```no_run
+/// # use cdpkit::{NetworkTracker, OwnedSession};
+/// # use std::time::Duration;
+/// # async fn example(session: OwnedSession) -> Result<(), cdpkit::CdpError> {
+/// let tracker = NetworkTracker::start(&session).await?;
+/// // ... navigate ...
+/// tracker
+///     .wait_for_idle(Duration::from_millis(500), 0, Duration::from_secs(30))
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct NetworkTracker {
+    snapshots: watch::Receiver<NetworkSnapshot>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl NetworkTracker {
+    /// Subscribe to network events and call `Network.enable`.
+    pub async fn start(session: &OwnedSession) -> Result<Self, CdpError> {
+        let mut events = merged_event_stream(session, TRACKED_EVENTS);
+        network::methods::Enable::new().send(session).await?;
+
+        let (tx, rx) = watch::channel(NetworkSnapshot::new());
+        let task = tokio::spawn(async move {
+            let mut state = TrackerState::new();
+            while let Some((name, params)) = events.next().await {
+                if state.apply(&name, &params) {
+                    tx.send_replace(state.snapshot.clone());
+                }
+            }
+        });
+        Ok(Self {
+            snapshots: rx,
+            task,
+        })
+    }
+
+    /// Current network activity.
+    pub fn snapshot(&self) -> NetworkSnapshot {
+        self.snapshots.borrow().clone()
+    }
+
+    /// Number of requests currently in flight.
+    pub fn inflight_count(&self) -> usize {
+        self.snapshots.borrow().inflight.len()
+    }
+
+    /// Stream of snapshots, starting with the current one and yielding after each change.
+    ///
+    /// Intermediate snapshots are skipped when the consumer is slower than the network.
+    pub fn snapshots(&self) -> EventStream<NetworkSnapshot> {
+        let mut rx = self.snapshots.clone();
+        rx.mark_changed();
+        Box::pin(futures::stream::unfold(rx, |mut rx| async move {
+            rx.changed().await.ok()?;
+            let snapshot = rx.borrow_and_update().clone();
+            Some((snapshot, rx))
+        }))
+    }
+
+    /// Wait until at most `max_inflight` requests have been in flight for `idle_time`.
+    ///
+    /// The idle timer restarts whenever the count rises above `max_inflight`. Returns
+    /// [`CdpError::Timeout`] if the network does not settle within `timeout`, and
+    /// [`CdpError::ConnectionClosed`] if the event stream ends.
+    pub async fn wait_for_idle(
+        &self,
+        idle_time: Duration,
+        max_inflight: usize,
+        timeout: Duration,
+    ) -> Result<(), CdpError> {
+        let mut rx = self.snapshots.clone();
+        let wait = async move {
+            let mut quiet_since: Option<tokio::time::Instant> = None;
+            loop {
+                let inflight = rx.borrow_and_update().inflight.len();
+                if inflight > max_inflight {
+                    quiet_since = None;
+                } else if quiet_since.is_none() {
+                    quiet_since = Some(tokio::time::Instant::now());
+                }
+                match quiet_since {
+                    Some(since) => {
+                        tokio::select! {
+                            _ = tokio::time::sleep_until(since + idle_time) => return Ok(()),
+                            changed = rx.changed() => changed.map_err(|_| CdpError::ConnectionClosed)?,
+                        }
+                    }
+                    None => rx.changed().await.map_err(|_| CdpError::ConnectionClosed)?,
+                }
+            }
+        };
+        tokio::time::timeout(timeout, wait)
+            .await
+            .map_err(|_| CdpError::Timeout)?
+    }
+}
+
+impl Drop for NetworkTracker {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sent(id: &str, url: &str) -> Value {
+        json!({"requestId": id, "request": {"url": url}, "type": "Document"})
+    }
+
+    #[test]
+    fn redirects_keep_one_request_in_flight() {
+        let mut state = TrackerState::new();
+        assert!(state.apply("Network.requestWillBeSent", &sent("1", "https://a.test/")));
+        assert!(state.apply("Network.requestWillBeSent", &sent("1", "https://a.test/b")));
+        assert_eq!(state.snapshot.inflight_count(), 1);
+        assert_eq!(state.snapshot.inflight[0].url, "https://a.test/b");
+        assert_eq!(state.snapshot.total_requests, 1);
+
+        assert!(state.apply("Network.loadingFinished", &json!({"requestId": "1"})));
+        assert_eq!(state.snapshot.inflight_count(), 0);
+        assert_eq!(state.snapshot.finished_requests, 1);
+    }
+
+    #[test]
+    fn failures_and_unknown_requests() {
+        let mut state = TrackerState::new();
+        state.apply("Network.requestWillBeSent", &sent("1", "https://a.test/x"));
+        assert!(state.apply("Network.loadingFailed", &json!({"requestId": "1"})));
+        assert_eq!(state.snapshot.failed_requests, 1);
+
+        // Started before the tracker: ignored.
+        assert!(!state.apply("Network.loadingFinished", &json!({"requestId": "old"})));
+        assert_eq!(state.snapshot.finished_requests, 0);
+    }
+
+    #[test]
+    fn websockets_are_in_flight_only_during_handshake() {
+        let mut state = TrackerState::new();
+        let ws = json!({"requestId": "ws", "url": "wss://a.test/socket"});
+        state.apply("Network.webSocketCreated", &ws);
+        assert_eq!(state.snapshot.inflight_count(), 1);
+        assert_eq!(
+            state.snapshot.inflight[0].resource_type.as_deref(),
+            Some("WebSocket")
+        );
+
+        state.apply(
+            "Network.webSocketHandshakeResponseReceived",
+            &json!({"requestId": "ws"}),
+        );
+        assert_eq!(state.snapshot.inflight_count(), 0);
+        assert_eq!(state.snapshot.open_websockets, 1);
+
+        state.apply("Network.webSocketClosed", &json!({"requestId": "ws"}));
+        assert_eq!(state.snapshot.open_websockets, 0);
+        assert_eq!(state.snapshot.failed_requests, 0);
+    }
+}
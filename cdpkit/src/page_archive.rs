@@ -0,0 +1,567 @@
+use crate::encoding::decode_base64;
+use crate::har::format_iso8601;
+use crate::page::types::{FrameId, FrameResourceTree};
+use crate::{
+    dom, page, runtime, CdpError, Page, PageSnapshot, PageSnapshotOptions, ScreenshotOptions,
+    Sender,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+const MANIFEST_FILE: &str = "manifest.json";
+const RESOURCE_DIR: &str = "resources";
+
+/// How [`PageArchive::capture`] stores the page itself.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PageArchiveMode {
+    /// A single `page.mhtml` from `Page.captureSnapshot`, which browsers open offline.
+    #[default]
+    Mhtml,
+    /// Every frame document and resource Chrome has in memory, one file each, from
+    /// `Page.getResourceTree` and `Page.getResourceContent`.
+    Resources,
+}
+
+/// Configuration for [`PageArchive::capture`].
+///
+/// By default the archive holds the MHTML snapshot, a full-page PNG screenshot and the
+/// document's HTML.
+#[derive(Debug, Clone)]
+pub struct PageArchiveOptions {
+    mode: PageArchiveMode,
+    screenshots: Vec<(String, ScreenshotOptions)>,
+    dom_html: bool,
+    dom_snapshot: Option<PageSnapshotOptions>,
+}
+
+impl Default for PageArchiveOptions {
+    fn default() -> Self {
+        let full_page = ScreenshotOptions {
+            full_page: true,
+            ..Default::default()
+        };
+        Self {
+            mode: PageArchiveMode::default(),
+            screenshots: vec![("screenshot".to_string(), full_page)],
+            dom_html: true,
+            dom_snapshot: None,
+        }
+    }
+}
+
+impl PageArchiveOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_mode(mut self, mode: PageArchiveMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Screenshots to take, by file name without extension. Replaces the default
+    /// full-page screenshot; pass an empty list to take none. Names that end up as the
+    /// same file get a `-2`, `-3`, ... suffix.
+    pub fn with_screenshots<I, N>(mut self, screenshots: I) -> Self
+    where
+        I: IntoIterator<Item = (N, ScreenshotOptions)>,
+        N: Into<String>,
+    {
+        self.screenshots = screenshots
+            .into_iter()
+            .map(|(name, options)| (name.into(), options))
+            .collect();
+        self
+    }
+
+    /// Write the live DOM, serialized as HTML, to `dom.html`.
+    pub fn with_dom_html(mut self, enabled: bool) -> Self {
+        self.dom_html = enabled;
+        self
+    }
+
+    /// Also write a [`PageSnapshot`] with layout and computed styles to
+    /// `dom-snapshot.json`.
+    pub fn with_dom_snapshot(mut self, options: PageSnapshotOptions) -> Self {
+        self.dom_snapshot = Some(options);
+        self
+    }
+}
+
+/// What an [`ArchiveEntry`] holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ArchiveEntryKind {
+    Mhtml,
+    /// The document of a frame, as loaded from the network.
+    Document,
+    Resource,
+    Screenshot,
+    /// The live DOM serialized as HTML.
+    DomHtml,
+    DomSnapshot,
+}
+
+/// One file of a [`PageArchive`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveEntry {
+    /// Path relative to the archive directory, with `/` separators.
+    pub path: String,
+    pub kind: ArchiveEntryKind,
+    /// Where a document or resource was loaded from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frame_id: Option<FrameId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    pub size: u64,
+}
+
+/// A resource that [`PageArchiveMode::Resources`] could not read.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveFailure {
+    pub url: String,
+    pub frame_id: FrameId,
+    pub error: String,
+}
+
+/// `manifest.json` of a [`PageArchive`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveManifest {
+    pub url: String,
+    pub title: String,
+    /// ISO 8601 UTC time the capture started.
+    pub captured_at: String,
+    pub mode: PageArchiveMode,
+    pub entries: Vec<ArchiveEntry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failures: Vec<ArchiveFailure>,
+}
+
+/// A directory bundling everything needed to look at a page after the fact: an MHTML
+/// snapshot or the page's resources, screenshots, a DOM dump and a `manifest.json`
+/// describing each file.
+///
+/// # Example
+/// ```no_run
+/// # use cdpkit::{Page, PageArchive, PageArchiveMode, PageArchiveOptions, CDP};
+/// # async fn example(cdp: CDP, session_id: String) -> Result<(), cdpkit::CdpError> {
+/// let session = cdp.session(session_id);
+/// let options = PageArchiveOptions::new().with_mode(PageArchiveMode::Resources);
+/// let archive = PageArchive::capture(&Page::new(&session), "incident-1234", options).await?;
+/// for entry in &archive.manifest().entries {
+///     println!("{} {:?} {} bytes", entry.path, entry.kind, entry.size);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct PageArchive {
+    directory: PathBuf,
+    manifest: ArchiveManifest,
+}
+
+impl PageArchive {
+    /// Capture the page into `directory`, creating it if needed. Files already in the
+    /// directory are overwritten.
+    ///
+    /// Resources Chrome cannot return, e.g. ones evicted from its cache, are listed in
+    /// [`ArchiveManifest::failures`] instead of failing the capture.
+    pub async fn capture<S: Sender + Sync>(
+        page: &Page<'_, S>,
+        directory: impl AsRef<Path>,
+        options: PageArchiveOptions,
+    ) -> Result<Self, CdpError> {
+        let target = page.target();
+        let directory = directory.as_ref().to_path_buf();
+        let captured_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0.0, |elapsed| elapsed.as_secs_f64());
+        create_dir(&directory).await?;
+
+        let frame_tree = page::methods::GetFrameTree::new().send(target).await?;
+        let mut archive = Self {
+            manifest: ArchiveManifest {
+                url: frame_tree.frame_tree.frame.url,
+                title: document_title(target).await?,
+                captured_at: format_iso8601(captured_at),
+                mode: options.mode,
+                entries: Vec::new(),
+                failures: Vec::new(),
+            },
+            directory,
+        };
+
+        match options.mode {
+            PageArchiveMode::Mhtml => {
+                let snapshot = page::methods::CaptureSnapshot::new()
+                    .with_format("mhtml")
+                    .send(target)
+                    .await?;
+                archive
+                    .write(
+                        "page.mhtml",
+                        ArchiveEntryKind::Mhtml,
+                        snapshot.data.as_bytes(),
+                    )
+                    .await?
+                    .mime_type = Some("multipart/related".to_string());
+            }
+            PageArchiveMode::Resources => {
+                let tree = page::methods::GetResourceTree::new().send(target).await?;
+                create_dir(&archive.directory.join(RESOURCE_DIR)).await?;
+                archive.write_resources(target, &tree.frame_tree).await?;
+            }
+        }
+
+        let mut screenshot_files = HashSet::new();
+        for (name, screenshot) in options.screenshots {
+            let extension = screenshot.format.as_str();
+            let path = unique_file_name(
+                &sanitize(&name, "screenshot"),
+                extension,
+                &mut screenshot_files,
+            );
+            let image = page.screenshot(screenshot).await?;
+            archive
+                .write(&path, ArchiveEntryKind::Screenshot, &image)
+                .await?
+                .mime_type = Some(format!("image/{extension}"));
+        }
+
+        if options.dom_html {
+            let document = dom::methods::GetDocument::new().send(target).await?;
+            let html = dom::methods::GetOuterHtml::new()
+                .with_node_id(document.root.node_id)
+                .send(target)
+                .await?;
+            archive
+                .write(
+                    "dom.html",
+                    ArchiveEntryKind::DomHtml,
+                    html.outer_html.as_bytes(),
+                )
+                .await?
+                .mime_type = Some("text/html".to_string());
+        }
+
+        if let Some(snapshot_options) = options.dom_snapshot {
+            let snapshot = PageSnapshot::capture(target, snapshot_options).await?;
+            archive
+                .write(
+                    "dom-snapshot.json",
+                    ArchiveEntryKind::DomSnapshot,
+                    snapshot.to_json()?.as_bytes(),
+                )
+                .await?
+                .mime_type = Some("application/json".to_string());
+        }
+
+        let manifest = serde_json::to_string_pretty(&archive.manifest)?;
+        write_file(&archive.directory.join(MANIFEST_FILE), manifest.as_bytes()).await?;
+        Ok(archive)
+    }
+
+    /// Open an archive written by [`capture`](Self::capture).
+    pub async fn load(directory: impl AsRef<Path>) -> Result<Self, CdpError> {
+        let directory = directory.as_ref().to_path_buf();
+        let manifest = tokio::fs::read_to_string(directory.join(MANIFEST_FILE))
+            .await
+            .map_err(|e| CdpError::Io(e.to_string()))?;
+        Ok(Self {
+            manifest: serde_json::from_str(&manifest)?,
+            directory,
+        })
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    pub fn manifest(&self) -> &ArchiveManifest {
+        &self.manifest
+    }
+
+    /// Absolute path of an entry's file.
+    pub fn path(&self, entry: &ArchiveEntry) -> PathBuf {
+        self.directory.join(&entry.path)
+    }
+
+    /// The entry holding the document or resource loaded from `url`; the outermost frame's
+    /// if several frame documents share the URL.
+    pub fn entry_for_url(&self, url: &str) -> Option<&ArchiveEntry> {
+        self.manifest
+            .entries
+            .iter()
+            .find(|entry| entry.url.as_deref() == Some(url))
+    }
+
+    /// Write the document and resources of every frame in `root`, parents first.
+    async fn write_resources<S: Sender + Sync>(
+        &mut self,
+        target: &S,
+        root: &FrameResourceTree,
+    ) -> Result<(), CdpError> {
+        let mut frames = vec![root];
+        let mut seen = HashSet::new();
+        while let Some(tree) = frames.pop() {
+            if let Some(children) = &tree.child_frames {
+                frames.extend(children.iter().rev());
+            }
+            self.write_frame_resources(target, tree, &mut seen).await?;
+        }
+        Ok(())
+    }
+
+    async fn write_frame_resources<S: Sender + Sync>(
+        &mut self,
+        target: &S,
+        tree: &FrameResourceTree,
+        seen: &mut HashSet<String>,
+    ) -> Result<(), CdpError> {
+        let frame = &tree.frame;
+        for (url, kind, mime_type) in frame_resources(tree, seen) {
+            let content = page::methods::GetResourceContent::new(frame.id.clone(), url.clone())
+                .send(target)
+                .await
+                .and_then(|content| {
+                    if content.base64_encoded {
+                        decode_base64(&content.content)
+                    } else {
+                        Ok(content.content.into_bytes())
+                    }
+                });
+            let content = match content {
+                Ok(content) => content,
+                Err(e) => {
+                    warn!(%url, error = %e, "Failed to read resource for page archive");
+                    self.manifest.failures.push(ArchiveFailure {
+                        url: url.clone(),
+                        frame_id: frame.id.clone(),
+                        error: e.to_string(),
+                    });
+                    continue;
+                }
+            };
+            let path = resource_path(self.manifest.entries.len(), url, kind);
+            let entry = self.write(&path, kind, &content).await?;
+            entry.url = Some(url.clone());
+            entry.frame_id = Some(frame.id.clone());
+            entry.mime_type = Some(mime_type.clone());
+        }
+        Ok(())
+    }
+
+    /// Write `content` to `path` in the archive and add an entry for it.
+    async fn write(
+        &mut self,
+        path: &str,
+        kind: ArchiveEntryKind,
+        content: &[u8],
+    ) -> Result<&mut ArchiveEntry, CdpError> {
+        write_file(&self.directory.join(path), content).await?;
+        self.manifest.entries.push(ArchiveEntry {
+            path: path.to_string(),
+            kind,
+            url: None,
+            frame_id: None,
+            mime_type: None,
+            size: content.len() as u64,
+        });
+        Ok(self
+            .manifest
+            .entries
+            .last_mut()
+            .expect("entry was just added"))
+    }
+}
+
+/// The document and loaded resources of one frame as `(url, kind, mime type)`. Every
+/// frame keeps its own document, even one sharing its parent's URL (a same-URL iframe,
+/// `about:blank`); only subresources whose URL is already in `seen` are skipped.
+fn frame_resources<'a>(
+    tree: &'a FrameResourceTree,
+    seen: &mut HashSet<String>,
+) -> Vec<(&'a String, ArchiveEntryKind, &'a String)> {
+    let frame = &tree.frame;
+    let document = (&frame.url, ArchiveEntryKind::Document, &frame.mime_type);
+    let resources = tree
+        .resources
+        .iter()
+        .filter(|resource| !resource.failed.unwrap_or(false))
+        .filter(|resource| !resource.canceled.unwrap_or(false))
+        .map(|resource| {
+            (
+                &resource.url,
+                ArchiveEntryKind::Resource,
+                &resource.mime_type,
+            )
+        });
+    std::iter::once(document)
+        .chain(resources.filter(|(url, _, _)| seen.insert(url.to_string())))
+        .collect()
+}
+
+/// Archive path of the `index`-th entry, numbered so resources sharing a file name do
+/// not overwrite each other.
+fn resource_path(index: usize, url: &str, kind: ArchiveEntryKind) -> String {
+    let fallback = match kind {
+        ArchiveEntryKind::Document => "index.html",
+        _ => "resource",
+    };
+    format!(
+        "{RESOURCE_DIR}/{index:03}-{}",
+        sanitize(file_name(url), fallback)
+    )
+}
+
+/// `{stem}.{extension}`, or `{stem}-2.{extension}`, `-3`, ... if that is already in `used`.
+fn unique_file_name(stem: &str, extension: &str, used: &mut HashSet<String>) -> String {
+    let mut name = format!("{stem}.{extension}");
+    let mut n = 1;
+    while !used.insert(name.clone()) {
+        n += 1;
+        name = format!("{stem}-{n}.{extension}");
+    }
+    name
+}
+
+async fn document_title(target: &(impl Sender + Sync)) -> Result<String, CdpError> {
+    let response = runtime::methods::Evaluate::new("document.title")
+        .with_return_by_value(true)
+        .send(target)
+        .await?;
+    Ok(response
+        .result
+        .value
+        .as_ref()
+        .and_then(|title| title.as_str())
+        .unwrap_or_default()
+        .to_string())
+}
+
+async fn create_dir(path: &Path) -> Result<(), CdpError> {
+    tokio::fs::create_dir_all(path)
+        .await
+        .map_err(|e| CdpError::Io(e.to_string()))
+}
+
+async fn write_file(path: &Path, content: &[u8]) -> Result<(), CdpError> {
+    tokio::fs::write(path, content)
+        .await
+        .map_err(|e| CdpError::Io(e.to_string()))
+}
+
+/// Last path segment of a URL, without query or fragment.
+fn file_name(url: &str) -> &str {
+    let end = url.find(['?', '#']).unwrap_or(url.len());
+    let path = &url[..end];
+    path.rsplit('/').next().unwrap_or_default()
+}
+
+/// `name` reduced to characters that are safe in file names on every platform, or
+/// `fallback` when nothing is left.
+fn sanitize(name: &str, fallback: &str) -> String {
+    let sanitized: String = name
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '-' | '_' => c,
+            _ => '_',
+        })
+        .take(80)
+        .collect();
+    match sanitized.trim_matches(['.', '_']) {
+        "" => fallback.to_string(),
+        _ => sanitized,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn resource_file_names_are_safe() {
+        let name = |url: &str| sanitize(file_name(url), "resource");
+        assert_eq!(name("https://app.test/js/app.min.js?v=3#x"), "app.min.js");
+        assert_eq!(name("https://app.test/"), "resource");
+        assert_eq!(name("https://app.test/a b/ünï.css"), "_n_.css");
+        assert_eq!(name("https://app.test/.."), "resource");
+        assert_eq!(sanitize("../../etc/passwd", "x"), ".._.._etc_passwd");
+    }
+
+    #[test]
+    fn frame_documents_are_kept_and_subresources_deduplicated() {
+        let frame = |id: &str, url: &str| {
+            json!({"id": id, "loaderId": "L", "url": url, "domainAndRegistry": "app.test",
+                   "securityOrigin": "https://app.test", "mimeType": "text/html",
+                   "secureContextType": "Secure",
+                   "crossOriginIsolatedContextType": "NotIsolated", "gatedAPIFeatures": []})
+        };
+        let resource =
+            |url: &str| json!({"url": url, "type": "Script", "mimeType": "text/javascript"});
+        let main: FrameResourceTree = serde_json::from_value(json!({
+            "frame": frame("main", "https://app.test/"),
+            "resources": [
+                resource("https://app.test/a/app.js"),
+                resource("https://app.test/b/app.js"),
+                {"url": "https://app.test/gone.js", "type": "Script",
+                 "mimeType": "text/javascript", "failed": true},
+                {"url": "https://app.test/stop.js", "type": "Script",
+                 "mimeType": "text/javascript", "canceled": true},
+                resource("https://app.test/a/app.js")
+            ]
+        }))
+        .unwrap();
+        let child: FrameResourceTree = serde_json::from_value(json!({
+            "frame": frame("child", "https://app.test/"),
+            "resources": [resource("https://app.test/b/app.js"), resource("https://app.test/c.js")]
+        }))
+        .unwrap();
+
+        let mut seen = HashSet::new();
+        let mut resources = frame_resources(&main, &mut seen);
+        resources.extend(frame_resources(&child, &mut seen));
+        let paths: Vec<String> = resources
+            .iter()
+            .enumerate()
+            .map(|(index, (url, kind, _))| resource_path(index, url, *kind))
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                "resources/000-index.html",
+                "resources/001-app.js",
+                "resources/002-app.js",
+                "resources/003-index.html",
+                "resources/004-c.js",
+            ]
+        );
+        assert_eq!(resources[3].0, "https://app.test/");
+        assert_eq!(resources[3].1, ArchiveEntryKind::Document);
+        assert_eq!(resources[4].1, ArchiveEntryKind::Resource);
+    }
+
+    #[test]
+    fn duplicate_screenshot_names_get_a_suffix() {
+        let mut used = HashSet::new();
+        let names: Vec<String> = ["shot", "shot", "shot-2", "shot"]
+            .iter()
+            .map(|stem| unique_file_name(stem, "png", &mut used))
+            .collect();
+        assert_eq!(
+            names,
+            ["shot.png", "shot-2.png", "shot-2-2.png", "shot-3.png"]
+        );
+        assert_eq!(unique_file_name("shot", "jpeg", &mut used), "shot.jpeg");
+    }
+}
@@ -0,0 +1,59 @@
+use crate::element::{self, ElementHandle};
+use crate::{CdpError, Sender};
+
+/// Page-level helpers bound to a session.
+///
+/// `Page` is a thin borrowed wrapper: it holds no state of its own and every method
+/// forwards to CDP commands on the wrapped session.
+///
+/// # Example
+/// ```no_run
+/// # use cdpkit::{Page, CDP};
+/// # async fn example(cdp: CDP, session_id: String) -> Result<(), cdpkit::CdpError> {
+/// let session = cdp.session(session_id);
+/// let page = Page::new(&session);
+/// if let Some(heading) = page.query_selector("h1").await? {
+///     println!("{}", heading.text().await?);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct Page<'a, S> {
+    target: &'a S,
+}
+
+impl<S> Clone for Page<'_, S> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<S> Copy for Page<'_, S> {}
+
+impl<'a, S: Sender + Sync> Page<'a, S> {
+    /// Wrap a session (or any other [`Sender`]).
+    pub fn new(target: &'a S) -> Self {
+        Self { target }
+    }
+
+    /// The wrapped session.
+    pub fn target(&self) -> &'a S {
+        self.target
+    }
+
+    /// Find the first element in the document matching a CSS selector.
+    pub async fn query_selector(
+        &self,
+        selector: &str,
+    ) -> Result<Option<ElementHandle<'a, S>>, CdpError> {
+        element::query_document(self.target, selector).await
+    }
+
+    /// Find all elements in the document matching a CSS selector, in document order.
+    pub async fn query_selector_all(
+        &self,
+        selector: &str,
+    ) -> Result<Vec<ElementHandle<'a, S>>, CdpError> {
+        element::query_document_all(self.target, selector).await
+    }
+}
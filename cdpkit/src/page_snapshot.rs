@@ -0,0 +1,1241 @@
+use crate::dom::types::BackendNodeId;
+use crate::dom_snapshot::responses::CaptureSnapshotResponse;
+use crate::dom_snapshot::types::{
+    DocumentSnapshot, RareBooleanData, RareIntegerData, RareStringData, StringIndex,
+};
+use crate::{dom_snapshot, BoundingBox, CdpError, Sender};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+const ELEMENT_NODE: i64 = 1;
+const TEXT_NODE: i64 = 3;
+
+/// Configuration for [`PageSnapshot::capture`].
+#[derive(Debug, Clone, Default)]
+pub struct PageSnapshotOptions {
+    computed_styles: Vec<String>,
+    paint_order: bool,
+}
+
+impl PageSnapshotOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Computed style properties to record for every node with a layout box, e.g.
+    /// `["display", "visibility", "color"]`. None are recorded by default.
+    pub fn with_computed_styles<I, T>(mut self, properties: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.computed_styles = properties.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Record each layout box's paint order.
+    pub fn with_paint_order(mut self, enabled: bool) -> Self {
+        self.paint_order = enabled;
+        self
+    }
+}
+
+/// The layout box of a rendered [`SnapshotNode`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LayoutBox {
+    /// Position relative to the document, in CSS pixels.
+    pub bounds: BoundingBox,
+    /// Rendered text of text nodes, after whitespace collapsing and `text-transform`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// The computed styles requested with [`PageSnapshotOptions::with_computed_styles`].
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub styles: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paint_order: Option<i64>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stacking_context: bool,
+}
+
+/// One DOM node of a [`SnapshotDocument`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotNode {
+    /// Position in [`SnapshotDocument::nodes`].
+    pub index: usize,
+    /// DOM `nodeType`: 1 for elements, 3 for text, 9 for documents, ...
+    pub node_type: i64,
+    /// DOM `nodeName`: upper-case tag names for HTML elements, `#text`, `#document`, ...
+    pub name: String,
+    /// DOM `nodeValue`, e.g. the contents of text and comment nodes.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub value: String,
+    pub backend_node_id: BackendNodeId,
+    /// Attributes as name/value pairs, in source order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attributes: Vec<(String, String)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<usize>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<usize>,
+    /// Current value of `input`, `textarea` and `select` elements.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_value: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub checked: bool,
+    /// Whether an `option` element is selected.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub selected: bool,
+    /// Whether the node has a click handler or is natively clickable.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub clickable: bool,
+    /// `before`, `after`, `marker`, ... for pseudo elements.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pseudo_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shadow_root_type: Option<String>,
+    /// For `iframe`s: the index of their document in [`PageSnapshot::documents`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_document: Option<usize>,
+    /// `None` for nodes that are not rendered.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<LayoutBox>,
+}
+
+impl SnapshotNode {
+    pub fn is_element(&self) -> bool {
+        self.node_type == ELEMENT_NODE
+    }
+
+    /// Lower-case tag name of elements; `None` for other nodes.
+    pub fn tag_name(&self) -> Option<String> {
+        self.is_element().then(|| self.name.to_ascii_lowercase())
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(attribute, _)| attribute == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// A computed style recorded in the snapshot.
+    pub fn style(&self, property: &str) -> Option<&str> {
+        self.layout
+            .as_ref()?
+            .styles
+            .get(property)
+            .map(String::as_str)
+    }
+
+    fn has_class(&self, class: &str) -> bool {
+        self.attribute("class")
+            .is_some_and(|classes| classes.split_ascii_whitespace().any(|c| c == class))
+    }
+}
+
+/// One document of a [`PageSnapshot`]: the main frame or an iframe.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotDocument {
+    pub url: String,
+    pub title: String,
+    pub base_url: String,
+    pub frame_id: String,
+    pub scroll_x: f64,
+    pub scroll_y: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_width: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_height: Option<f64>,
+    /// Nodes in document order; the first is the document node.
+    pub nodes: Vec<SnapshotNode>,
+}
+
+impl SnapshotDocument {
+    pub fn root(&self) -> Option<&SnapshotNode> {
+        self.nodes.first()
+    }
+
+    pub fn node(&self, index: usize) -> Option<&SnapshotNode> {
+        self.nodes.get(index)
+    }
+
+    pub fn parent(&self, index: usize) -> Option<&SnapshotNode> {
+        self.nodes
+            .get(index)?
+            .parent
+            .map(|parent| &self.nodes[parent])
+    }
+
+    /// Check the invariants [`decode_document`] establishes, for deserialized documents.
+    fn validate(&self) -> Result<(), CdpError> {
+        let mut child_count = 0;
+        for (index, node) in self.nodes.iter().enumerate() {
+            if node.index != index {
+                return Err(invalid(format!("node {index} has index {}", node.index)));
+            }
+            if let Some(parent) = node.parent {
+                if parent >= index {
+                    return Err(invalid(format!("node {index} does not follow its parent")));
+                }
+                if !self.nodes[parent].children.contains(&index) {
+                    return Err(invalid(format!(
+                        "node {index} is not a child of its parent"
+                    )));
+                }
+                child_count += 1;
+            }
+            for &child in &node.children {
+                if self.nodes.get(child).and_then(|child| child.parent) != Some(index) {
+                    return Err(invalid(format!(
+                        "node {child} is not a child of node {index}"
+                    )));
+                }
+            }
+        }
+        // Every listed child has this node as parent; equal counts rule out duplicates.
+        if self
+            .nodes
+            .iter()
+            .map(|node| node.children.len())
+            .sum::<usize>()
+            != child_count
+        {
+            return Err(invalid("duplicate child index"));
+        }
+        Ok(())
+    }
+
+    pub fn children(&self, index: usize) -> impl Iterator<Item = &SnapshotNode> + '_ {
+        let children = self.nodes.get(index).map_or(&[][..], |node| &node.children);
+        children.iter().map(|&child| &self.nodes[child])
+    }
+
+    pub fn node_by_backend_node_id(&self, id: BackendNodeId) -> Option<&SnapshotNode> {
+        self.nodes.iter().find(|node| node.backend_node_id == id)
+    }
+
+    /// Concatenated text of the text nodes below node `index`, like DOM `textContent`.
+    pub fn text_content(&self, index: usize) -> String {
+        let mut text = String::new();
+        let mut stack = vec![index];
+        while let Some(index) = stack.pop() {
+            let Some(node) = self.nodes.get(index) else {
+                continue;
+            };
+            if node.node_type == TEXT_NODE {
+                text.push_str(&node.value);
+            }
+            stack.extend(node.children.iter().rev());
+        }
+        text
+    }
+
+    /// Elements matching a CSS selector, in document order.
+    ///
+    /// Supports type, `*`, `#id`, `.class` and attribute selectors (`[a]`, `[a=v]`,
+    /// `[a~=v]`, `[a^=v]`, `[a$=v]`, `[a*=v]`), `:first-child`, `:last-child`,
+    /// `:nth-child(n)`, descendant and `>` combinators, and selector lists.
+    pub fn query_selector_all(&self, selector: &str) -> Result<Vec<&SnapshotNode>, CdpError> {
+        let selector = SelectorList::parse(selector)?;
+        Ok(self
+            .nodes
+            .iter()
+            .filter(|node| selector.matches(self, node.index))
+            .collect())
+    }
+
+    /// The first element matching a CSS selector, see
+    /// [`query_selector_all`](Self::query_selector_all).
+    pub fn query_selector(&self, selector: &str) -> Result<Option<&SnapshotNode>, CdpError> {
+        let selector = SelectorList::parse(selector)?;
+        Ok(self
+            .nodes
+            .iter()
+            .find(|node| selector.matches(self, node.index)))
+    }
+
+    /// Stable path of each node: names from the root with a 1-based position among
+    /// same-named siblings when it is not the first, e.g. `#document/HTML/BODY/DIV[2]`.
+    fn paths(&self) -> Vec<String> {
+        let mut paths = vec![String::new(); self.nodes.len()];
+        for node in &self.nodes {
+            if node.parent.is_none() {
+                paths[node.index] = node.name.clone();
+            }
+            let mut seen: HashMap<&str, usize> = HashMap::new();
+            let parent = paths[node.index].clone();
+            for &child in &node.children {
+                let name = self.nodes[child].name.as_str();
+                let position = seen.entry(name).or_insert(0);
+                *position += 1;
+                paths[child] = match *position {
+                    1 => format!("{parent}/{name}"),
+                    n => format!("{parent}/{name}[{n}]"),
+                };
+            }
+        }
+        paths
+    }
+
+    fn diff(&self, newer: &SnapshotDocument, document: usize, changes: &mut Vec<SnapshotChange>) {
+        let (old_paths, new_paths) = (self.paths(), newer.paths());
+        let old_index: HashMap<&str, usize> = old_paths
+            .iter()
+            .enumerate()
+            .map(|(index, path)| (path.as_str(), index))
+            .collect();
+        let new_index: HashMap<&str, usize> = new_paths
+            .iter()
+            .enumerate()
+            .map(|(index, path)| (path.as_str(), index))
+            .collect();
+        let change = |path: &str, kind| SnapshotChange {
+            document,
+            path: path.to_string(),
+            kind,
+        };
+
+        for (old, path) in self.nodes.iter().zip(&old_paths) {
+            let Some(&index) = new_index.get(path.as_str()) else {
+                // Only report the top of a removed subtree.
+                let parent_kept = old.parent.map_or(true, |parent| {
+                    new_index.contains_key(old_paths[parent].as_str())
+                });
+                if parent_kept {
+                    changes.push(change(path, SnapshotChangeKind::Removed));
+                }
+                continue;
+            };
+            let new = &newer.nodes[index];
+            if old.value != new.value {
+                changes.push(change(
+                    path,
+                    SnapshotChangeKind::Text {
+                        old: old.value.clone(),
+                        new: new.value.clone(),
+                    },
+                ));
+            }
+            if old.input_value != new.input_value {
+                changes.push(change(
+                    path,
+                    SnapshotChangeKind::InputValue {
+                        old: old.input_value.clone(),
+                        new: new.input_value.clone(),
+                    },
+                ));
+            }
+            let attributes = |node: &'_ SnapshotNode| -> BTreeMap<String, String> {
+                node.attributes.iter().cloned().collect()
+            };
+            for (name, old, new) in changed_entries(&attributes(old), &attributes(new)) {
+                changes.push(change(
+                    path,
+                    SnapshotChangeKind::Attribute { name, old, new },
+                ));
+            }
+            let no_styles = BTreeMap::new();
+            let styles = |node: &'_ SnapshotNode| {
+                node.layout
+                    .as_ref()
+                    .map_or(&no_styles, |layout| &layout.styles)
+                    .clone()
+            };
+            for (name, old, new) in changed_entries(&styles(old), &styles(new)) {
+                changes.push(change(path, SnapshotChangeKind::Style { name, old, new }));
+            }
+            let bounds = |node: &SnapshotNode| node.layout.as_ref().map(|layout| layout.bounds);
+            if bounds(old) != bounds(new) {
+                changes.push(change(
+                    path,
+                    SnapshotChangeKind::Layout {
+                        old: bounds(old),
+                        new: bounds(new),
+                    },
+                ));
+            }
+        }
+        for (new, path) in newer.nodes.iter().zip(&new_paths) {
+            let parent_existed = new.parent.map_or(true, |parent| {
+                old_index.contains_key(new_paths[parent].as_str())
+            });
+            if !old_index.contains_key(path.as_str()) && parent_existed {
+                changes.push(change(path, SnapshotChangeKind::Added));
+            }
+        }
+    }
+}
+
+/// Keys whose values differ between two maps, with `None` for missing entries.
+fn changed_entries(
+    old: &BTreeMap<String, String>,
+    new: &BTreeMap<String, String>,
+) -> Vec<(String, Option<String>, Option<String>)> {
+    let names: std::collections::BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    names
+        .into_iter()
+        .filter(|name| old.get(*name) != new.get(*name))
+        .map(|name| (name.clone(), old.get(name).cloned(), new.get(name).cloned()))
+        .collect()
+}
+
+/// One difference found by [`PageSnapshot::diff`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotChange {
+    /// Index of the document in both snapshots.
+    pub document: usize,
+    /// Path of the node from the document root, e.g. `#document/HTML/BODY/DIV[2]`.
+    pub path: String,
+    pub kind: SnapshotChangeKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum SnapshotChangeKind {
+    /// The node and its subtree only exist in the newer snapshot.
+    Added,
+    /// The node and its subtree only exist in the older snapshot.
+    Removed,
+    Text {
+        old: String,
+        new: String,
+    },
+    InputValue {
+        old: Option<String>,
+        new: Option<String>,
+    },
+    Attribute {
+        name: String,
+        old: Option<String>,
+        new: Option<String>,
+    },
+    Style {
+        name: String,
+        old: Option<String>,
+        new: Option<String>,
+    },
+    /// The layout box moved, resized, appeared or disappeared.
+    Layout {
+        old: Option<BoundingBox>,
+        new: Option<BoundingBox>,
+    },
+}
+
+/// A decoded `DOMSnapshot.captureSnapshot`: every document of the page as a node tree
+/// with layout boxes and computed styles.
+///
+/// Chrome's response is columnar and indexes into a shared string table. `PageSnapshot`
+/// turns it into plain [`SnapshotNode`]s that can be queried with CSS selectors after
+/// the page is gone, saved as JSON, and compared with [`diff`](Self::diff).
+///
+/// # Example
+/// ```no_run
+/// # use cdpkit::{PageSnapshot, PageSnapshotOptions, CDP};
+/// # async fn example(cdp: CDP) -> Result<(), cdpkit::CdpError> {
+/// let options = PageSnapshotOptions::new().with_computed_styles(["display", "color"]);
+/// let snapshot = PageSnapshot::capture(&cdp, options).await?;
+/// let page = snapshot.main_document().expect("main document");
+/// for item in page.query_selector_all("ul.results > li")? {
+///     println!("{:?} {:?}", page.text_content(item.index), item.style("color"));
+/// }
+///
+/// let baseline = PageSnapshot::load("baseline.json").await?;
+/// for change in baseline.diff(&snapshot) {
+///     println!("{} {:?}", change.path, change.kind);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PageSnapshot {
+    pub documents: Vec<SnapshotDocument>,
+}
+
+impl PageSnapshot {
+    pub async fn capture(
+        target: &(impl Sender + Sync),
+        options: PageSnapshotOptions,
+    ) -> Result<Self, CdpError> {
+        let response = dom_snapshot::methods::CaptureSnapshot::new(options.computed_styles.clone())
+            .with_include_paint_order(options.paint_order)
+            .send(target)
+            .await?;
+        Self::from_response(&response, &options.computed_styles)
+    }
+
+    /// Decode a `DOMSnapshot.captureSnapshot` response. `computed_styles` must be the
+    /// property list the snapshot was captured with.
+    pub fn from_response(
+        response: &CaptureSnapshotResponse,
+        computed_styles: &[String],
+    ) -> Result<Self, CdpError> {
+        let strings = Strings(&response.strings);
+        let documents = response
+            .documents
+            .iter()
+            .map(|document| decode_document(document, &strings, computed_styles))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { documents })
+    }
+
+    /// The top-level document.
+    pub fn main_document(&self) -> Option<&SnapshotDocument> {
+        self.documents.first()
+    }
+
+    pub fn to_json(&self) -> Result<String, CdpError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Parse a snapshot saved with [`to_json`](Self::to_json).
+    ///
+    /// Returns [`CdpError::InvalidDomSnapshot`] if the node tree is inconsistent, e.g.
+    /// a child index out of range or a node listed before its parent.
+    pub fn from_json(json: &str) -> Result<Self, CdpError> {
+        let snapshot: Self = serde_json::from_str(json)?;
+        for document in &snapshot.documents {
+            document.validate()?;
+        }
+        Ok(snapshot)
+    }
+
+    pub async fn save(&self, path: impl AsRef<Path>) -> Result<(), CdpError> {
+        tokio::fs::write(path, self.to_json()?)
+            .await
+            .map_err(|e| CdpError::Io(e.to_string()))
+    }
+
+    pub async fn load(path: impl AsRef<Path>) -> Result<Self, CdpError> {
+        let json = tokio::fs::read_to_string(path)
+            .await
+            .map_err(|e| CdpError::Io(e.to_string()))?;
+        Self::from_json(&json)
+    }
+
+    /// Changes from this snapshot to `newer`, typically of the same page in another run.
+    ///
+    /// Nodes are matched by their path from the document root rather than by backend
+    /// node id, which differs between runs. Documents are matched by position.
+    pub fn diff(&self, newer: &PageSnapshot) -> Vec<SnapshotChange> {
+        let mut changes = Vec::new();
+        let documents = self.documents.len().max(newer.documents.len());
+        for document in 0..documents {
+            match (self.documents.get(document), newer.documents.get(document)) {
+                (Some(old), Some(new)) => old.diff(new, document, &mut changes),
+                (Some(old), None) => changes.extend(old.root().map(|root| SnapshotChange {
+                    document,
+                    path: root.name.clone(),
+                    kind: SnapshotChangeKind::Removed,
+                })),
+                (None, Some(new)) => changes.extend(new.root().map(|root| SnapshotChange {
+                    document,
+                    path: root.name.clone(),
+                    kind: SnapshotChangeKind::Added,
+                })),
+                (None, None) => {}
+            }
+        }
+        changes
+    }
+}
+
+struct Strings<'a>(&'a [String]);
+
+impl Strings<'_> {
+    /// `None` for the `-1` Chrome uses for absent strings.
+    fn get(&self, index: StringIndex) -> Result<Option<String>, CdpError> {
+        if index < 0 {
+            return Ok(None);
+        }
+        self.0
+            .get(index as usize)
+            .cloned()
+            .map(Some)
+            .ok_or_else(|| invalid(format!("string index {index} out of range")))
+    }
+
+    fn get_or_empty(&self, index: StringIndex) -> Result<String, CdpError> {
+        Ok(self.get(index)?.unwrap_or_default())
+    }
+
+    fn rare(&self, data: Option<&RareStringData>) -> Result<HashMap<usize, String>, CdpError> {
+        let Some(data) = data else {
+            return Ok(HashMap::new());
+        };
+        let mut values = HashMap::new();
+        for (&index, &value) in data.index.iter().zip(&data.value) {
+            if let Some(value) = self.get(value)? {
+                values.insert(index as usize, value);
+            }
+        }
+        Ok(values)
+    }
+}
+
+fn invalid(message: impl Into<String>) -> CdpError {
+    CdpError::InvalidDomSnapshot(message.into())
+}
+
+fn rare_flags(data: Option<&RareBooleanData>) -> HashSet<usize> {
+    data.map(|data| data.index.iter().map(|&index| index as usize).collect())
+        .unwrap_or_default()
+}
+
+fn rare_integers(data: Option<&RareIntegerData>) -> HashMap<usize, i64> {
+    data.map(|data| {
+        data.index
+            .iter()
+            .zip(&data.value)
+            .map(|(&index, &value)| (index as usize, value))
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
+fn decode_document(
+    document: &DocumentSnapshot,
+    strings: &Strings<'_>,
+    computed_styles: &[String],
+) -> Result<SnapshotDocument, CdpError> {
+    let tree = &document.nodes;
+    let count = tree.node_name.as_ref().map_or(0, Vec::len);
+    let column = |values: Option<&Vec<i64>>, name: &str| -> Result<Vec<i64>, CdpError> {
+        match values {
+            Some(values) if values.len() == count => Ok(values.clone()),
+            None if count == 0 => Ok(Vec::new()),
+            _ => Err(invalid(format!(
+                "nodes.{name} does not have {count} entries"
+            ))),
+        }
+    };
+    let parents = column(tree.parent_index.as_ref(), "parentIndex")?;
+    let node_types = column(tree.node_type.as_ref(), "nodeType")?;
+    let names = column(tree.node_name.as_ref(), "nodeName")?;
+    let values = column(tree.node_value.as_ref(), "nodeValue")?;
+    let backend_node_ids = column(tree.backend_node_id.as_ref(), "backendNodeId")?;
+    let attributes = tree.attributes.as_deref().unwrap_or_default();
+    let input_values = strings.rare(tree.input_value.as_ref())?;
+    let text_values = strings.rare(tree.text_value.as_ref())?;
+    let pseudo_types = strings.rare(tree.pseudo_type.as_ref())?;
+    let shadow_root_types = strings.rare(tree.shadow_root_type.as_ref())?;
+    let checked = rare_flags(tree.input_checked.as_ref());
+    let selected = rare_flags(tree.option_selected.as_ref());
+    let clickable = rare_flags(tree.is_clickable.as_ref());
+    let content_documents = rare_integers(tree.content_document_index.as_ref());
+
+    let mut nodes = Vec::with_capacity(count);
+    for index in 0..count {
+        let mut pairs = Vec::new();
+        if let Some(flat) = attributes.get(index) {
+            for pair in flat.chunks(2) {
+                let name = strings.get_or_empty(pair[0])?;
+                let value = match pair.get(1) {
+                    Some(&value) => strings.get_or_empty(value)?,
+                    None => String::new(),
+                };
+                pairs.push((name, value));
+            }
+        }
+        let parent = usize::try_from(parents[index]).ok();
+        if parent.is_some_and(|parent| parent >= index) {
+            return Err(invalid(format!("node {index} does not follow its parent")));
+        }
+        nodes.push(SnapshotNode {
+            index,
+            node_type: node_types[index],
+            name: strings.get_or_empty(names[index])?,
+            value: strings.get_or_empty(values[index])?,
+            backend_node_id: backend_node_ids[index],
+            attributes: pairs,
+            parent,
+            children: Vec::new(),
+            // `textarea` contents are reported as their text value.
+            input_value: input_values
+                .get(&index)
+                .or_else(|| text_values.get(&index))
+                .cloned(),
+            checked: checked.contains(&index),
+            selected: selected.contains(&index),
+            clickable: clickable.contains(&index),
+            pseudo_type: pseudo_types.get(&index).cloned(),
+            shadow_root_type: shadow_root_types.get(&index).cloned(),
+            content_document: content_documents
+                .get(&index)
+                .and_then(|&document| usize::try_from(document).ok()),
+            layout: None,
+        });
+        if let Some(parent) = parent {
+            nodes[parent].children.push(index);
+        }
+    }
+
+    let layout = &document.layout;
+    let stacking_contexts = rare_flags(Some(&layout.stacking_contexts));
+    for (position, &node_index) in layout.node_index.iter().enumerate() {
+        let Some(node) = usize::try_from(node_index)
+            .ok()
+            .and_then(|index| nodes.get_mut(index))
+        else {
+            return Err(invalid(format!("layout node {node_index} out of range")));
+        };
+        // Pseudo elements and continuations can add further boxes for the same node.
+        if node.layout.is_some() {
+            continue;
+        }
+        let rect = layout.bounds.get(position).map(Vec::as_slice);
+        let bounds = match rect {
+            Some(&[x, y, width, height, ..]) => BoundingBox {
+                x,
+                y,
+                width,
+                height,
+            },
+            _ => return Err(invalid(format!("layout {position} has no bounds"))),
+        };
+        let mut styles = BTreeMap::new();
+        if let Some(style) = layout.styles.get(position) {
+            for (name, &value) in computed_styles.iter().zip(style) {
+                if let Some(value) = strings.get(value)? {
+                    styles.insert(name.clone(), value);
+                }
+            }
+        }
+        let text = match layout.text.get(position) {
+            Some(&text) => strings.get(text)?.filter(|text| !text.is_empty()),
+            None => None,
+        };
+        node.layout = Some(LayoutBox {
+            bounds,
+            text,
+            styles,
+            paint_order: layout
+                .paint_orders
+                .as_ref()
+                .and_then(|orders| orders.get(position).copied()),
+            stacking_context: stacking_contexts.contains(&position),
+        });
+    }
+
+    let scroll_x = document.scroll_offset_x.unwrap_or(0.0);
+    let scroll_y = document.scroll_offset_y.unwrap_or(0.0);
+    Ok(SnapshotDocument {
+        url: strings.get_or_empty(document.document_url)?,
+        title: strings.get_or_empty(document.title)?,
+        base_url: strings.get_or_empty(document.base_url)?,
+        frame_id: strings.get_or_empty(document.frame_id)?,
+        scroll_x,
+        scroll_y,
+        content_width: document.content_width,
+        content_height: document.content_height,
+        nodes,
+    })
+}
+
+/// A comma-separated list of complex selectors.
+#[derive(Debug)]
+struct SelectorList(Vec<ComplexSelector>);
+
+/// Compound selectors joined by combinators, stored right to left.
+#[derive(Debug)]
+struct ComplexSelector {
+    /// The subject compound, then each compound to its left with the combinator that
+    /// joins it to the one before.
+    subject: CompoundSelector,
+    ancestors: Vec<(Combinator, CompoundSelector)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Combinator {
+    Descendant,
+    Child,
+}
+
+#[derive(Debug, Default)]
+struct CompoundSelector {
+    tag: Option<String>,
+    id: Option<String>,
+    classes: Vec<String>,
+    attributes: Vec<AttributeSelector>,
+    child_positions: Vec<ChildPosition>,
+}
+
+#[derive(Debug)]
+struct AttributeSelector {
+    name: String,
+    matcher: Option<(AttributeOperator, String)>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum AttributeOperator {
+    Equals,
+    Includes,
+    Prefix,
+    Suffix,
+    Substring,
+}
+
+/// `:first-child`, `:last-child` and `:nth-child(n)`.
+#[derive(Debug)]
+enum ChildPosition {
+    First,
+    Last,
+    Nth(usize),
+}
+
+impl SelectorList {
+    fn parse(selector: &str) -> Result<Self, CdpError> {
+        let mut parser = SelectorParser {
+            chars: selector.chars().collect(),
+            position: 0,
+            source: selector,
+        };
+        let mut list = Vec::new();
+        loop {
+            list.push(parser.complex()?);
+            parser.skip_whitespace();
+            match parser.next() {
+                Some(',') => continue,
+                None => break,
+                Some(c) => return Err(parser.error(&format!("unexpected '{c}'"))),
+            }
+        }
+        Ok(Self(list))
+    }
+
+    fn matches(&self, document: &SnapshotDocument, index: usize) -> bool {
+        document.nodes[index].is_element()
+            && self
+                .0
+                .iter()
+                .any(|selector| selector.matches(document, index))
+    }
+}
+
+impl ComplexSelector {
+    fn matches(&self, document: &SnapshotDocument, index: usize) -> bool {
+        self.subject.matches(document, index) && self.ancestors_match(document, index, 0)
+    }
+
+    /// Whether `ancestors[step..]` match to the left of the element at `index`.
+    fn ancestors_match(&self, document: &SnapshotDocument, index: usize, step: usize) -> bool {
+        let Some((combinator, compound)) = self.ancestors.get(step) else {
+            return true;
+        };
+        let mut parent = document.nodes[index].parent;
+        while let Some(candidate) = parent {
+            if document.nodes[candidate].is_element()
+                && compound.matches(document, candidate)
+                && self.ancestors_match(document, candidate, step + 1)
+            {
+                return true;
+            }
+            if *combinator == Combinator::Child {
+                return false;
+            }
+            parent = document.nodes[candidate].parent;
+        }
+        false
+    }
+}
+
+impl CompoundSelector {
+    fn matches(&self, document: &SnapshotDocument, index: usize) -> bool {
+        let node = &document.nodes[index];
+        self.tag
+            .as_ref()
+            .map_or(true, |tag| node.name.eq_ignore_ascii_case(tag))
+            && self
+                .id
+                .as_ref()
+                .map_or(true, |id| node.attribute("id") == Some(id.as_str()))
+            && self.classes.iter().all(|class| node.has_class(class))
+            && self
+                .attributes
+                .iter()
+                .all(|attribute| attribute.matches(node))
+            && self
+                .child_positions
+                .iter()
+                .all(|pseudo| pseudo.matches(document, node))
+    }
+}
+
+impl AttributeSelector {
+    fn matches(&self, node: &SnapshotNode) -> bool {
+        let Some(actual) = node.attribute(&self.name) else {
+            return false;
+        };
+        let Some((operator, expected)) = &self.matcher else {
+            return true;
+        };
+        match operator {
+            AttributeOperator::Equals => actual == expected,
+            AttributeOperator::Includes => actual.split_ascii_whitespace().any(|w| w == expected),
+            AttributeOperator::Prefix => !expected.is_empty() && actual.starts_with(expected),
+            AttributeOperator::Suffix => !expected.is_empty() && actual.ends_with(expected),
+            AttributeOperator::Substring => !expected.is_empty() && actual.contains(expected),
+        }
+    }
+}
+
+impl ChildPosition {
+    fn matches(&self, document: &SnapshotDocument, node: &SnapshotNode) -> bool {
+        let Some(parent) = node.parent else {
+            return false;
+        };
+        let siblings: Vec<usize> = document
+            .children(parent)
+            .filter(|sibling| sibling.is_element())
+            .map(|sibling| sibling.index)
+            .collect();
+        let position = siblings.iter().position(|&index| index == node.index);
+        match self {
+            ChildPosition::First => position == Some(0),
+            ChildPosition::Last => position.is_some_and(|p| p + 1 == siblings.len()),
+            ChildPosition::Nth(n) => position.is_some_and(|p| p + 1 == *n),
+        }
+    }
+}
+
+struct SelectorParser<'a> {
+    chars: Vec<char>,
+    position: usize,
+    source: &'a str,
+}
+
+impl SelectorParser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += 1;
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) -> bool {
+        let start = self.position;
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.position += 1;
+        }
+        self.position > start
+    }
+
+    fn error(&self, message: &str) -> CdpError {
+        CdpError::InvalidSelector(format!("{message} in {:?}", self.source))
+    }
+
+    fn complex(&mut self) -> Result<ComplexSelector, CdpError> {
+        self.skip_whitespace();
+        let mut compounds = vec![self.compound()?];
+        let mut combinators = Vec::new();
+        loop {
+            let spaced = self.skip_whitespace();
+            let combinator = match self.peek() {
+                Some('>') => {
+                    self.position += 1;
+                    self.skip_whitespace();
+                    Combinator::Child
+                }
+                Some(',') | None => break,
+                Some(_) if spaced => Combinator::Descendant,
+                Some(c) => return Err(self.error(&format!("unexpected '{c}'"))),
+            };
+            combinators.push(combinator);
+            compounds.push(self.compound()?);
+        }
+        let subject = compounds.pop().expect("at least one compound");
+        let ancestors = combinators
+            .into_iter()
+            .rev()
+            .zip(compounds.into_iter().rev());
+        Ok(ComplexSelector {
+            subject,
+            ancestors: ancestors.collect(),
+        })
+    }
+
+    fn compound(&mut self) -> Result<CompoundSelector, CdpError> {
+        let mut compound = CompoundSelector::default();
+        let start = self.position;
+        if self.peek() == Some('*') {
+            self.position += 1;
+        } else if self.peek().is_some_and(is_ident_char) {
+            compound.tag = Some(self.ident()?);
+        }
+        loop {
+            match self.peek() {
+                Some('#') => {
+                    self.position += 1;
+                    compound.id = Some(self.ident()?);
+                }
+                Some('.') => {
+                    self.position += 1;
+                    compound.classes.push(self.ident()?);
+                }
+                Some('[') => {
+                    self.position += 1;
+                    compound.attributes.push(self.attribute()?);
+                }
+                Some(':') => {
+                    self.position += 1;
+                    compound.child_positions.push(self.child_position()?);
+                }
+                _ => break,
+            }
+        }
+        if self.position == start {
+            return Err(self.error("expected a selector"));
+        }
+        Ok(compound)
+    }
+
+    fn ident(&mut self) -> Result<String, CdpError> {
+        let start = self.position;
+        while self.peek().is_some_and(is_ident_char) {
+            self.position += 1;
+        }
+        if self.position == start {
+            return Err(self.error("expected a name"));
+        }
+        Ok(self.chars[start..self.position].iter().collect())
+    }
+
+    fn attribute(&mut self) -> Result<AttributeSelector, CdpError> {
+        self.skip_whitespace();
+        let name = self.ident()?;
+        self.skip_whitespace();
+        let operator = match self.next() {
+            Some(']') => {
+                return Ok(AttributeSelector {
+                    name,
+                    matcher: None,
+                })
+            }
+            Some('=') => AttributeOperator::Equals,
+            Some(c @ ('~' | '^' | '$' | '*')) if self.next() == Some('=') => match c {
+                '~' => AttributeOperator::Includes,
+                '^' => AttributeOperator::Prefix,
+                '$' => AttributeOperator::Suffix,
+                _ => AttributeOperator::Substring,
+            },
+            _ => return Err(self.error("invalid attribute selector")),
+        };
+        self.skip_whitespace();
+        let value = match self.peek() {
+            Some(quote @ ('"' | '\'')) => {
+                self.position += 1;
+                let start = self.position;
+                while self.peek().is_some_and(|c| c != quote) {
+                    self.position += 1;
+                }
+                let value = self.chars[start..self.position].iter().collect();
+                if self.next() != Some(quote) {
+                    return Err(self.error("unterminated string"));
+                }
+                value
+            }
+            _ => self.ident()?,
+        };
+        self.skip_whitespace();
+        if self.next() != Some(']') {
+            return Err(self.error("expected ']'"));
+        }
+        Ok(AttributeSelector {
+            name,
+            matcher: Some((operator, value)),
+        })
+    }
+
+    fn child_position(&mut self) -> Result<ChildPosition, CdpError> {
+        let name = self.ident()?;
+        match name.as_str() {
+            "first-child" => Ok(ChildPosition::First),
+            "last-child" => Ok(ChildPosition::Last),
+            "nth-child" => {
+                if self.next() != Some('(') {
+                    return Err(self.error("expected '(' after :nth-child"));
+                }
+                self.skip_whitespace();
+                let n = self
+                    .ident()?
+                    .parse()
+                    .map_err(|_| self.error(":nth-child only supports a number"))?;
+                self.skip_whitespace();
+                if self.next() != Some(')') {
+                    return Err(self.error("expected ')'"));
+                }
+                Ok(ChildPosition::Nth(n))
+            }
+            other => Err(self.error(&format!("unsupported pseudo-class :{other}"))),
+        }
+    }
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '-' || c == '_' || !c.is_ascii()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// `<html><body><ul class="list"><li id="a" class="item">One</li>
+    /// <li class="item active" data-kind="x-1">Two</li></ul></body></html>`
+    fn snapshot() -> PageSnapshot {
+        let response: CaptureSnapshotResponse = serde_json::from_value(json!({
+            "strings": ["https://app.test/", "Shop", "#document", "HTML", "BODY", "UL",
+                        "class", "list", "LI", "id", "a", "item", "#text", "One",
+                        "item active", "data-kind", "x-1", "Two", "display", "block",
+                        "list-item", "", "main"],
+            "documents": [{
+                "documentURL": 0, "title": 1, "baseURL": 0, "contentLanguage": -1,
+                "encodingName": -1, "publicId": -1, "systemId": -1, "frameId": 22,
+                "nodes": {
+                    "parentIndex": [-1, 0, 1, 2, 3, 4, 3, 6],
+                    "nodeType": [9, 1, 1, 1, 1, 3, 1, 3],
+                    "nodeName": [2, 3, 4, 5, 8, 12, 8, 12],
+                    "nodeValue": [-1, -1, -1, -1, -1, 13, -1, 17],
+                    "backendNodeId": [1, 2, 3, 4, 5, 6, 7, 8],
+                    "attributes": [[], [], [], [6, 7], [9, 10, 6, 11], [], [6, 14, 15, 16], []],
+                    "isClickable": {"index": [6]}
+                },
+                "layout": {
+                    "nodeIndex": [1, 3, 4, 6],
+                    "styles": [[19], [19], [20], [20]],
+                    "bounds": [[0, 0, 800, 600], [8, 8, 784, 40], [48, 8, 744, 20],
+                               [48, 28, 744, 20]],
+                    "text": [-1, -1, -1, -1],
+                    "stackingContexts": {"index": [0]}
+                },
+                "textBoxes": {"layoutIndex": [], "bounds": [], "start": [], "length": []}
+            }]
+        }))
+        .unwrap();
+        PageSnapshot::from_response(&response, &["display".to_string()]).unwrap()
+    }
+
+    #[test]
+    fn decodes_tree_layout_and_styles() {
+        let snapshot = snapshot();
+        let page = snapshot.main_document().unwrap();
+        assert_eq!(
+            (page.url.as_str(), page.title.as_str()),
+            ("https://app.test/", "Shop")
+        );
+        assert_eq!(page.children(3).count(), 2);
+        let second = page.node_by_backend_node_id(7).unwrap();
+        assert_eq!(second.attribute("data-kind"), Some("x-1"));
+        assert!(second.clickable);
+        assert_eq!(second.style("display"), Some("list-item"));
+        assert_eq!(second.layout.as_ref().unwrap().bounds.y, 28.0);
+        assert_eq!(page.text_content(3), "OneTwo");
+        assert!(page.nodes[1].layout.as_ref().unwrap().stacking_context);
+    }
+
+    #[test]
+    fn queries_with_css_selectors() {
+        let snapshot = snapshot();
+        let page = snapshot.main_document().unwrap();
+        let ids = |selector: &str| -> Vec<BackendNodeId> {
+            page.query_selector_all(selector)
+                .unwrap()
+                .iter()
+                .map(|node| node.backend_node_id)
+                .collect()
+        };
+        assert_eq!(ids("li"), [5, 7]);
+        assert_eq!(ids("ul.list > li.item.active"), [7]);
+        assert_eq!(ids("body li#a"), [5]);
+        assert_eq!(ids("html > li"), Vec::<BackendNodeId>::new());
+        assert_eq!(ids("[data-kind^=x], #a"), [5, 7]);
+        assert_eq!(ids("[class~='active']"), [7]);
+        assert_eq!(ids("li:last-child"), [7]);
+        assert_eq!(ids("ul :nth-child(1)"), [5]);
+        assert_eq!(ids("*").len(), 5);
+        assert!(matches!(
+            page.query_selector("li:hover"),
+            Err(CdpError::InvalidSelector(_))
+        ));
+        assert!(page.query_selector("ul >").is_err());
+    }
+
+    #[test]
+    fn round_trips_and_diffs_by_path() {
+        let before = snapshot();
+        let after = PageSnapshot::from_json(&before.to_json().unwrap()).unwrap();
+        assert_eq!(after, before);
+        assert!(before.diff(&after).is_empty());
+
+        let mut after = after;
+        let page = &mut after.documents[0];
+        page.nodes[5].value = "Uno".into();
+        page.nodes[4].layout = None;
+        page.nodes[3].children.pop();
+        page.nodes.truncate(6);
+
+        let changes = before.diff(&after);
+        let summary: Vec<(&str, &SnapshotChangeKind)> = changes
+            .iter()
+            .map(|change| (change.path.as_str(), &change.kind))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (
+                    "#document/HTML/BODY/UL/LI",
+                    &SnapshotChangeKind::Style {
+                        name: "display".into(),
+                        old: Some("list-item".into()),
+                        new: None
+                    }
+                ),
+                (
+                    "#document/HTML/BODY/UL/LI",
+                    &SnapshotChangeKind::Layout {
+                        old: Some(BoundingBox {
+                            x: 48.0,
+                            y: 8.0,
+                            width: 744.0,
+                            height: 20.0
+                        }),
+                        new: None
+                    }
+                ),
+                (
+                    "#document/HTML/BODY/UL/LI/#text",
+                    &SnapshotChangeKind::Text {
+                        old: "One".into(),
+                        new: "Uno".into()
+                    }
+                ),
+                ("#document/HTML/BODY/UL/LI[2]", &SnapshotChangeKind::Removed),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_inconsistent_trees() {
+        let corrupt = |edit: fn(&mut SnapshotDocument)| {
+            let mut snapshot = snapshot();
+            edit(&mut snapshot.documents[0]);
+            PageSnapshot::from_json(&snapshot.to_json().unwrap()).unwrap_err()
+        };
+        let errors = [
+            corrupt(|document| document.nodes[3].children.push(42)),
+            corrupt(|document| document.nodes[2].parent = Some(5)),
+            corrupt(|document| document.nodes[1].children.clear()),
+            corrupt(|document| document.nodes[3].children.push(4)),
+            corrupt(|document| document.nodes[6].index = 1),
+        ];
+        for error in errors {
+            assert!(
+                matches!(error, CdpError::InvalidDomSnapshot(_)),
+                "{error:?}"
+            );
+        }
+    }
+}
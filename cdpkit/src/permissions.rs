@@ -0,0 +1,256 @@
+use crate::browser::types::PermissionType;
+use crate::{browser, spawn_cleanup, CdpError, CDP};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::warn;
+
+type Grant = (Option<String>, Vec<PermissionType>);
+
+#[derive(Debug, Default)]
+struct Grants {
+    next_id: u64,
+    /// Grants without a guard that last until [`Permissions::reset`], e.g. the ones made
+    /// with [`BrowserContext::grant_permissions`](crate::BrowserContext::grant_permissions).
+    persistent: Vec<Grant>,
+    /// Guarded grants in the order they were made: `(id, origin, permissions)`.
+    active: Vec<(u64, Option<String>, Vec<PermissionType>)>,
+}
+
+impl Grants {
+    /// Everything granted, merged into one permission list per origin. Chrome replaces
+    /// an origin's whole set on each `Browser.grantPermissions`, so an origin must always
+    /// be granted its merged list.
+    fn merged(&self) -> Vec<Grant> {
+        let persistent = self
+            .persistent
+            .iter()
+            .map(|(origin, permissions)| (origin, permissions));
+        let active = self
+            .active
+            .iter()
+            .map(|(_, origin, permissions)| (origin, permissions));
+        let all = persistent.chain(active);
+        let mut merged: Vec<Grant> = Vec::new();
+        for (origin, permissions) in all {
+            let index = match merged.iter().position(|(o, _)| o == origin) {
+                Some(index) => index,
+                None => {
+                    merged.push((origin.clone(), Vec::new()));
+                    merged.len() - 1
+                }
+            };
+            for permission in permissions {
+                if !merged[index].1.contains(permission) {
+                    merged[index].1.push(permission.clone());
+                }
+            }
+        }
+        merged
+    }
+
+    fn merged_for(&self, origin: Option<&str>) -> Vec<PermissionType> {
+        self.merged()
+            .into_iter()
+            .find(|(o, _)| o.as_deref() == origin)
+            .map(|(_, permissions)| permissions)
+            .unwrap_or_default()
+    }
+}
+
+/// Permission grants for one browser context (or the default context).
+///
+/// Each [`grant`](Self::grant) returns a [`PermissionGuard`]. Chrome can only reset all
+/// permissions of a context at once, so releasing a guard calls
+/// `Browser.resetPermissions` and then re-grants, one call per origin, whatever the
+/// remaining guards and the context-level grants still hold. Grants and releases of one
+/// registry reach Chrome one at a time, including releases of guards dropped together.
+///
+/// Clones share their grants. Separate `Permissions` for the same context do not know
+/// about each other's grants, so create one and clone it; [`BrowserContext::permissions`]
+/// always returns the context's shared registry.
+///
+/// [`BrowserContext::permissions`]: crate::BrowserContext::permissions
+///
+/// # Example
+/// ```no_run
+/// # use cdpkit::{Permissions, CDP};
+/// # use cdpkit::browser::types::PermissionType;
+/// # async fn example(cdp: CDP) -> Result<(), cdpkit::CdpError> {
+/// let permissions = Permissions::new(&cdp);
+/// let guard = permissions
+///     .grant(Some("https://maps.test"), [PermissionType::Geolocation])
+///     .await?;
+/// // ... the page may now call navigator.geolocation ...
+/// guard.restore().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Permissions {
+    cdp: CDP,
+    browser_context_id: Option<String>,
+    grants: Arc<Mutex<Grants>>,
+}
+
+impl Permissions {
+    /// Permissions of the default browser context.
+    pub fn new(cdp: &CDP) -> Self {
+        Self {
+            cdp: cdp.clone(),
+            browser_context_id: None,
+            grants: Arc::new(Mutex::new(Grants::default())),
+        }
+    }
+
+    /// Scope to a browser context, e.g. [`BrowserContext::id`](crate::BrowserContext::id).
+    pub fn with_browser_context_id(mut self, browser_context_id: impl Into<String>) -> Self {
+        self.browser_context_id = Some(browser_context_id.into());
+        self
+    }
+
+    /// Grant permissions to one origin, or to all origins with `None`, until the guard is
+    /// released.
+    pub async fn grant(
+        &self,
+        origin: Option<&str>,
+        permissions: impl IntoIterator<Item = PermissionType>,
+    ) -> Result<PermissionGuard, CdpError> {
+        let mut grants = self.grants.lock().await;
+        let id = grants.next_id;
+        grants.next_id += 1;
+        grants.active.push((
+            id,
+            origin.map(str::to_owned),
+            permissions.into_iter().collect(),
+        ));
+        let merged = grants.merged_for(origin);
+        if let Err(e) = grant_permissions(
+            &self.cdp,
+            self.browser_context_id.as_deref(),
+            origin,
+            merged,
+        )
+        .await
+        {
+            grants.active.retain(|(i, _, _)| *i != id);
+            return Err(e);
+        }
+        Ok(PermissionGuard {
+            permissions: self.clone(),
+            id,
+            restored: false,
+        })
+    }
+
+    /// Grant permissions without a guard; they are kept when guards are released.
+    pub(crate) async fn grant_persistent(
+        &self,
+        origin: Option<&str>,
+        permissions: Vec<PermissionType>,
+    ) -> Result<(), CdpError> {
+        let mut grants = self.grants.lock().await;
+        grants
+            .persistent
+            .push((origin.map(str::to_owned), permissions));
+        let merged = grants.merged_for(origin);
+        grant_permissions(
+            &self.cdp,
+            self.browser_context_id.as_deref(),
+            origin,
+            merged,
+        )
+        .await
+    }
+
+    /// Revoke every permission in the context, including grants still held by guards.
+    pub async fn reset(&self) -> Result<(), CdpError> {
+        let mut grants = self.grants.lock().await;
+        grants.persistent.clear();
+        grants.active.clear();
+        reset_permissions(&self.cdp, self.browser_context_id.as_deref()).await
+    }
+
+    /// Remove grant `id`, reset the context's permissions and re-apply the remaining
+    /// grants, one per origin. The lock is held throughout, so concurrent grants and
+    /// releases reach Chrome one at a time and in order.
+    async fn release(&self, id: u64) -> Result<(), CdpError> {
+        let mut grants = self.grants.lock().await;
+        let Some(index) = grants.active.iter().position(|(i, _, _)| *i == id) else {
+            return Ok(());
+        };
+        grants.active.remove(index);
+        let browser_context_id = self.browser_context_id.as_deref();
+        reset_permissions(&self.cdp, browser_context_id).await?;
+        for (origin, permissions) in grants.merged() {
+            grant_permissions(
+                &self.cdp,
+                browser_context_id,
+                origin.as_deref(),
+                permissions,
+            )
+            .await?;
+        }
+        Ok(())
+    }
+}
+
+/// Holds a permission grant; releasing it restores the grants that were active before.
+#[must_use = "dropping the guard revokes the permissions right away"]
+pub struct PermissionGuard {
+    permissions: Permissions,
+    id: u64,
+    restored: bool,
+}
+
+impl PermissionGuard {
+    /// Revoke the grant now and wait for Chrome to confirm.
+    pub async fn restore(mut self) -> Result<(), CdpError> {
+        self.restored = true;
+        self.permissions.release(self.id).await
+    }
+}
+
+impl Drop for PermissionGuard {
+    fn drop(&mut self) {
+        if self.restored {
+            return;
+        }
+        let permissions = self.permissions.clone();
+        let id = self.id;
+        spawn_cleanup(async move {
+            if let Err(e) = permissions.release(id).await {
+                warn!(error = %e, "Failed to restore permissions");
+            }
+        });
+    }
+}
+
+// `Browser.setPermission` sets one permission per call; `grantPermissions` is still
+// served by Chrome and maps directly onto a permission list.
+#[allow(deprecated)]
+pub(crate) async fn grant_permissions(
+    cdp: &CDP,
+    browser_context_id: Option<&str>,
+    origin: Option<&str>,
+    permissions: Vec<PermissionType>,
+) -> Result<(), CdpError> {
+    let mut cmd = browser::methods::GrantPermissions::new(permissions);
+    if let Some(origin) = origin {
+        cmd = cmd.with_origin(origin);
+    }
+    if let Some(id) = browser_context_id {
+        cmd = cmd.with_browser_context_id(id.to_string());
+    }
+    cmd.send(cdp).await
+}
+
+pub(crate) async fn reset_permissions(
+    cdp: &CDP,
+    browser_context_id: Option<&str>,
+) -> Result<(), CdpError> {
+    let mut cmd = browser::methods::ResetPermissions::new();
+    if let Some(id) = browser_context_id {
+        cmd = cmd.with_browser_context_id(id.to_string());
+    }
+    cmd.send(cdp).await
+}
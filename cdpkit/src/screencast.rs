@@ -0,0 +1,217 @@
+use crate::encoding::decode_base64;
+use crate::page::events::ScreencastFrame;
+use crate::page::types::ScreencastFrameMetadata;
+use crate::{page, spawn_cleanup, CdpError, EventStream, OwnedSession};
+use futures::StreamExt;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tracing::warn;
+
+/// Image encoding of screencast frames.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ScreencastFormat {
+    #[default]
+    Jpeg,
+    Png,
+}
+
+impl ScreencastFormat {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Jpeg => "jpeg",
+            Self::Png => "png",
+        }
+    }
+
+    /// File extension used by [`Screencast::save_frames`].
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Jpeg => "jpg",
+            Self::Png => "png",
+        }
+    }
+}
+
+/// Options for [`Screencast::start`].
+#[derive(Debug, Clone, Default)]
+pub struct ScreencastOptions {
+    pub format: ScreencastFormat,
+    /// Compression quality 0–100; ignored for PNG.
+    pub quality: Option<i64>,
+    /// Maximum frame width in device pixels; frames are scaled down to fit.
+    pub max_width: Option<i64>,
+    /// Maximum frame height in device pixels; frames are scaled down to fit.
+    pub max_height: Option<i64>,
+    /// Send only every n-th rendered frame.
+    pub every_nth_frame: Option<i64>,
+}
+
+/// One decoded screencast frame.
+#[derive(Debug, Clone)]
+pub struct ScreencastImage {
+    /// Position in this screencast, starting at 0.
+    pub index: u64,
+    pub format: ScreencastFormat,
+    /// Encoded image bytes.
+    pub data: Vec<u8>,
+    pub metadata: ScreencastFrameMetadata,
+}
+
+impl ScreencastImage {
+    /// Zero-padded file name such as `frame-00042.jpg`, suitable for `ffmpeg -i frame-%05d.jpg`.
+    pub fn file_name(&self) -> String {
+        format!("frame-{:05}.{}", self.index, self.format.extension())
+    }
+}
+
+/// A running `Page.startScreencast`, yielding decoded frames.
+///
+/// Each `Page.screencastFrame` is acknowledged with `Page.screencastFrameAck` when it is
+/// yielded, so Chrome keeps sending frames at the pace the consumer reads them. Dropping
+/// the screencast sends `Page.stopScreencast`.
+///
+/// # Example
+/// ```no_run
+/// # use cdpkit::{OwnedSession, Screencast, ScreencastOptions};
+/// # async fn example(session: OwnedSession) -> Result<(), cdpkit::CdpError> {
+/// let options = ScreencastOptions {
+///     max_width: Some(1280),
+///     every_nth_frame: Some(2),
+///     ..Default::default()
+/// };
+/// let mut screencast = Screencast::start(&session, options).await?;
+/// let written = screencast.save_frames("frames", 100).await?;
+/// println!("{} frames", written.len());
+/// screencast.stop().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Screencast {
+    session: OwnedSession,
+    frames: EventStream<ScreencastFrame>,
+    format: ScreencastFormat,
+    next_index: u64,
+    stopped: bool,
+}
+
+impl Screencast {
+    /// Subscribe to frames and call `Page.startScreencast`.
+    pub async fn start(
+        session: &OwnedSession,
+        options: ScreencastOptions,
+    ) -> Result<Self, CdpError> {
+        let frames = ScreencastFrame::subscribe(session);
+
+        let mut cmd = page::methods::StartScreencast::new().with_format(options.format.as_str());
+        if let (Some(quality), ScreencastFormat::Jpeg) = (options.quality, options.format) {
+            cmd = cmd.with_quality(quality);
+        }
+        if let Some(max_width) = options.max_width {
+            cmd = cmd.with_max_width(max_width);
+        }
+        if let Some(max_height) = options.max_height {
+            cmd = cmd.with_max_height(max_height);
+        }
+        if let Some(every_nth_frame) = options.every_nth_frame {
+            cmd = cmd.with_every_nth_frame(every_nth_frame);
+        }
+        cmd.send(session).await?;
+
+        Ok(Self {
+            session: session.clone(),
+            frames,
+            format: options.format,
+            next_index: 0,
+            stopped: false,
+        })
+    }
+
+    /// Call `Page.stopScreencast`.
+    pub async fn stop(mut self) -> Result<(), CdpError> {
+        self.stopped = true;
+        page::methods::StopScreencast::new()
+            .send(&self.session)
+            .await
+    }
+
+    /// Write up to `max_frames` frames into `dir` (created if missing) as numbered files.
+    ///
+    /// Returns the written paths in order. Stops early if the screencast ends.
+    pub async fn save_frames(
+        &mut self,
+        dir: impl AsRef<Path>,
+        max_frames: usize,
+    ) -> Result<Vec<PathBuf>, CdpError> {
+        let dir = dir.as_ref();
+        tokio::fs::create_dir_all(dir)
+            .await
+            .map_err(|e| CdpError::Io(e.to_string()))?;
+        let mut written = Vec::with_capacity(max_frames);
+        while written.len() < max_frames {
+            let Some(frame) = self.next().await else {
+                break;
+            };
+            let path = dir.join(frame.file_name());
+            tokio::fs::write(&path, &frame.data)
+                .await
+                .map_err(|e| CdpError::Io(e.to_string()))?;
+            written.push(path);
+        }
+        Ok(written)
+    }
+}
+
+impl futures::Stream for Screencast {
+    type Item = ScreencastImage;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        loop {
+            let frame = match this.frames.as_mut().poll_next(cx) {
+                Poll::Ready(Some(frame)) => frame,
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            };
+
+            let session = this.session.clone();
+            let ack_id = frame.session_id;
+            tokio::spawn(async move {
+                if let Err(e) = page::methods::ScreencastFrameAck::new(ack_id)
+                    .send(&session)
+                    .await
+                {
+                    warn!(frame = ack_id, error = %e, "Failed to ack screencast frame");
+                }
+            });
+
+            match decode_base64(&frame.data) {
+                Ok(data) => {
+                    let index = this.next_index;
+                    this.next_index += 1;
+                    return Poll::Ready(Some(ScreencastImage {
+                        index,
+                        format: this.format,
+                        data,
+                        metadata: frame.metadata,
+                    }));
+                }
+                Err(e) => {
+                    warn!(frame = ack_id, error = %e, "Skipping undecodable screencast frame")
+                }
+            }
+        }
+    }
+}
+
+impl Drop for Screencast {
+    fn drop(&mut self) {
+        if self.stopped {
+            return;
+        }
+        let session = self.session.clone();
+        spawn_cleanup(async move {
+            let _ = page::methods::StopScreencast::new().send(&session).await;
+        });
+    }
+}
@@ -0,0 +1,770 @@
+use crate::debugger::events::ScriptParsed;
+use crate::encoding::decode_base64;
+use crate::io_stream::read_chunk;
+use crate::runtime::types::{CallFrame, StackTrace};
+use crate::{
+    debugger, decode_event, io, merged_event_stream, network, page, CdpError, ScriptInfo, Sender,
+};
+use futures::StreamExt;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use tracing::{debug, warn};
+
+/// A position in an original source, resolved through a [`SourceMap`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OriginalPosition {
+    /// Source URL, resolved against the map's URL and `sourceRoot` when loaded through a
+    /// [`SourceMapResolver`].
+    pub source: String,
+    /// 0-based, like CDP positions.
+    pub line: i64,
+    pub column: i64,
+    /// Original identifier at this position, e.g. a function name before minification.
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Mapping {
+    generated_line: u32,
+    generated_column: u32,
+    /// `(source, line, column, name)`; `None` for segments that map to no source.
+    original: Option<(u32, u32, u32, Option<u32>)>,
+}
+
+/// A parsed [source map v3](https://tc39.es/ecma426/), including index maps with
+/// inline `sections`.
+#[derive(Debug, Clone)]
+pub struct SourceMap {
+    file: Option<String>,
+    sources: Vec<String>,
+    sources_content: Vec<Option<String>>,
+    names: Vec<String>,
+    /// Sorted by generated position.
+    mappings: Vec<Mapping>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawSourceMap {
+    file: Option<String>,
+    source_root: Option<String>,
+    #[serde(default)]
+    sources: Vec<Option<String>>,
+    #[serde(default)]
+    sources_content: Vec<Option<String>>,
+    #[serde(default)]
+    names: Vec<String>,
+    #[serde(default)]
+    mappings: String,
+    sections: Option<Vec<RawSection>>,
+}
+
+#[derive(Deserialize)]
+struct RawSection {
+    offset: RawOffset,
+    map: Option<Box<RawSourceMap>>,
+    url: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RawOffset {
+    line: u32,
+    column: u32,
+}
+
+impl SourceMap {
+    pub fn from_json(json: &str) -> Result<Self, CdpError> {
+        let raw: RawSourceMap = serde_json::from_str(json)?;
+        let mut map = Self {
+            file: raw.file.clone(),
+            sources: Vec::new(),
+            sources_content: Vec::new(),
+            names: Vec::new(),
+            mappings: Vec::new(),
+        };
+        map.append(raw, 0, 0)?;
+        map.mappings
+            .sort_by_key(|m| (m.generated_line, m.generated_column));
+        Ok(map)
+    }
+
+    /// Add `raw` with its generated positions shifted by a section offset.
+    fn append(&mut self, raw: RawSourceMap, line: u32, column: u32) -> Result<(), CdpError> {
+        if let Some(sections) = raw.sections {
+            for section in sections {
+                let Some(map) = section.map else {
+                    return Err(CdpError::InvalidSourceMap(format!(
+                        "index map section {} references an external map",
+                        section.url.unwrap_or_default()
+                    )));
+                };
+                self.append(*map, section.offset.line, section.offset.column)?;
+            }
+            return Ok(());
+        }
+
+        let source_base = self.sources.len() as u32;
+        let name_base = self.names.len() as u32;
+        let root = raw.source_root.unwrap_or_default();
+        for (i, source) in raw.sources.into_iter().enumerate() {
+            self.sources
+                .push(join_source_root(&root, &source.unwrap_or_default()));
+            self.sources_content
+                .push(raw.sources_content.get(i).cloned().flatten());
+        }
+        self.names.extend(raw.names);
+
+        for mut mapping in decode_mappings(&raw.mappings)? {
+            if mapping.generated_line == 0 {
+                mapping.generated_column += column;
+            }
+            mapping.generated_line += line;
+            if let Some((source, _, _, name)) = &mut mapping.original {
+                *source += source_base;
+                if (*source as usize) >= self.sources.len() {
+                    return Err(CdpError::InvalidSourceMap(format!(
+                        "source index {source} out of range"
+                    )));
+                }
+                if let Some(name) = name {
+                    *name += name_base;
+                }
+            }
+            self.mappings.push(mapping);
+        }
+        Ok(())
+    }
+
+    /// The generated file this map describes, if the map names it.
+    pub fn file(&self) -> Option<&str> {
+        self.file.as_deref()
+    }
+
+    pub fn sources(&self) -> &[String] {
+        &self.sources
+    }
+
+    /// Embedded original source text from `sourcesContent`.
+    pub fn source_content(&self, source: &str) -> Option<&str> {
+        let index = self.sources.iter().position(|s| s == source)?;
+        self.sources_content.get(index)?.as_deref()
+    }
+
+    /// Original position for a 0-based generated `line` and `column`: the closest mapping
+    /// at or before `column` on the same line.
+    pub fn lookup(&self, line: i64, column: i64) -> Option<OriginalPosition> {
+        let (line, column) = (u32::try_from(line).ok()?, u32::try_from(column).ok()?);
+        let end = self
+            .mappings
+            .partition_point(|m| (m.generated_line, m.generated_column) <= (line, column));
+        let mapping = self.mappings[..end].last()?;
+        if mapping.generated_line != line {
+            return None;
+        }
+        let (source, original_line, original_column, name) = mapping.original?;
+        Some(OriginalPosition {
+            source: self.sources[source as usize].clone(),
+            line: i64::from(original_line),
+            column: i64::from(original_column),
+            name: name.and_then(|n| self.names.get(n as usize).cloned()),
+        })
+    }
+
+    /// Resolve relative source paths against the URL the map was loaded from.
+    fn resolve_sources(&mut self, base_url: &str) {
+        for source in &mut self.sources {
+            *source = resolve_url(base_url, source);
+        }
+    }
+}
+
+fn join_source_root(root: &str, source: &str) -> String {
+    if root.is_empty() || has_scheme(source) || source.starts_with('/') {
+        source.to_string()
+    } else if root.ends_with('/') {
+        format!("{root}{source}")
+    } else {
+        format!("{root}/{source}")
+    }
+}
+
+fn decode_mappings(mappings: &str) -> Result<Vec<Mapping>, CdpError> {
+    let mut decoded = Vec::new();
+    let (mut source, mut original_line, mut original_column, mut name) = (0i64, 0i64, 0i64, 0i64);
+    for (line, segments) in mappings.split(';').enumerate() {
+        let mut column = 0i64;
+        for segment in segments.split(',').filter(|s| !s.is_empty()) {
+            let fields = decode_vlq(segment)?;
+            column += fields[0];
+            let original = match fields.len() {
+                1 => None,
+                4 | 5 => {
+                    source += fields[1];
+                    original_line += fields[2];
+                    original_column += fields[3];
+                    let name = fields.get(4).map(|delta| {
+                        name += delta;
+                        name
+                    });
+                    Some((source, original_line, original_column, name))
+                }
+                n => {
+                    return Err(CdpError::InvalidSourceMap(format!(
+                        "segment `{segment}` has {n} fields"
+                    )))
+                }
+            };
+            let non_negative = |value: i64| {
+                u32::try_from(value).map_err(|_| {
+                    CdpError::InvalidSourceMap(format!("negative position in `{segment}`"))
+                })
+            };
+            decoded.push(Mapping {
+                generated_line: non_negative(line as i64)?,
+                generated_column: non_negative(column)?,
+                original: match original {
+                    Some((source, line, column, name)) => Some((
+                        non_negative(source)?,
+                        non_negative(line)?,
+                        non_negative(column)?,
+                        name.map(non_negative).transpose()?,
+                    )),
+                    None => None,
+                },
+            });
+        }
+    }
+    Ok(decoded)
+}
+
+/// Decode the base64 VLQ values of one mappings segment.
+fn decode_vlq(segment: &str) -> Result<Vec<i64>, CdpError> {
+    let mut values = Vec::with_capacity(5);
+    let (mut value, mut shift) = (0i64, 0u32);
+    for byte in segment.bytes() {
+        let digit = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => {
+                return Err(CdpError::InvalidSourceMap(format!(
+                    "invalid VLQ character in `{segment}`"
+                )))
+            }
+        } as i64;
+        if shift > 60 {
+            return Err(CdpError::InvalidSourceMap(format!(
+                "VLQ value too large in `{segment}`"
+            )));
+        }
+        value += (digit & 31) << shift;
+        if digit & 32 != 0 {
+            shift += 5;
+            continue;
+        }
+        values.push(if value & 1 == 1 {
+            -(value >> 1)
+        } else {
+            value >> 1
+        });
+        (value, shift) = (0, 0);
+    }
+    if shift != 0 {
+        return Err(CdpError::InvalidSourceMap(format!(
+            "truncated VLQ value in `{segment}`"
+        )));
+    }
+    Ok(values)
+}
+
+fn has_scheme(url: &str) -> bool {
+    let Some(end) = url.find(':') else {
+        return false;
+    };
+    let scheme = &url.as_bytes()[..end];
+    scheme.first().is_some_and(u8::is_ascii_alphabetic)
+        && scheme
+            .iter()
+            .all(|b| b.is_ascii_alphanumeric() || b"+-.".contains(b))
+}
+
+/// Resolve `reference` against `base` like a browser resolves a relative link.
+pub(crate) fn resolve_url(base: &str, reference: &str) -> String {
+    if has_scheme(reference) || !has_scheme(base) {
+        return reference.to_string();
+    }
+    let scheme_end = base.find(':').unwrap_or(0);
+    if reference.starts_with("//") {
+        return format!("{}:{reference}", &base[..scheme_end]);
+    }
+    let rest = &base[scheme_end + 1..];
+    let Some(after_slashes) = rest.strip_prefix("//") else {
+        // Opaque URLs such as `data:` have no path to resolve against.
+        return reference.to_string();
+    };
+    let authority_end = after_slashes.find('/').unwrap_or(after_slashes.len());
+    let origin = &base[..scheme_end + 3 + authority_end];
+    let base_path = &after_slashes[authority_end..];
+    let base_path = &base_path[..base_path.find(['?', '#']).unwrap_or(base_path.len())];
+
+    let (path, suffix) = match reference.find(['?', '#']) {
+        Some(i) => reference.split_at(i),
+        None => (reference, ""),
+    };
+    if path.is_empty() {
+        return format!("{origin}{base_path}{suffix}");
+    }
+    let joined = if path.starts_with('/') {
+        path.to_string()
+    } else {
+        let directory = &base_path[..base_path.rfind('/').map_or(0, |i| i + 1)];
+        format!("{directory}{path}")
+    };
+
+    let mut segments: Vec<&str> = Vec::new();
+    let parts: Vec<&str> = joined.split('/').collect();
+    for (i, part) in parts.iter().enumerate() {
+        let last = i == parts.len() - 1;
+        match *part {
+            "." => {
+                if last {
+                    segments.push("");
+                }
+            }
+            ".." => {
+                if segments.len() > 1 {
+                    segments.pop();
+                }
+                if last {
+                    segments.push("");
+                }
+            }
+            _ => segments.push(part),
+        }
+    }
+    let path = segments.join("/");
+    let path = if path.starts_with('/') {
+        path
+    } else {
+        format!("/{path}")
+    };
+    format!("{origin}{path}{suffix}")
+}
+
+/// Decode the payload of a `data:` URL.
+fn decode_data_url(url: &str) -> Result<Vec<u8>, CdpError> {
+    let invalid = || CdpError::InvalidSourceMap("malformed data URL".to_string());
+    let rest = url.strip_prefix("data:").ok_or_else(invalid)?;
+    let (meta, payload) = rest.split_once(',').ok_or_else(invalid)?;
+    if meta.ends_with(";base64") {
+        return decode_base64(payload);
+    }
+    let bytes = payload.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    Ok(decoded)
+}
+
+/// A stack frame mapped back to its original source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MappedFrame {
+    pub function_name: String,
+    /// Generated (bundled) position, 0-based.
+    pub url: String,
+    pub line_number: i64,
+    pub column_number: i64,
+    /// Original position, if the script has a source map that covers it.
+    pub original: Option<OriginalPosition>,
+}
+
+impl fmt::Display for MappedFrame {
+    /// Formats like a V8 stack line with 1-based positions, preferring the original one.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (name, url, line, column) = match &self.original {
+            Some(original) => (
+                original.name.as_deref().unwrap_or(&self.function_name),
+                original.source.as_str(),
+                original.line,
+                original.column,
+            ),
+            None => (
+                self.function_name.as_str(),
+                self.url.as_str(),
+                self.line_number,
+                self.column_number,
+            ),
+        };
+        let name = if name.is_empty() { "<anonymous>" } else { name };
+        write!(f, "at {name} ({url}:{}:{})", line + 1, column + 1)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ScriptSource {
+    url: String,
+    source_map_url: Option<String>,
+}
+
+#[derive(Default)]
+struct ResolverState {
+    scripts: HashMap<String, ScriptSource>,
+    /// Maps by URL; `None` caches a failed load so it is not retried for every frame.
+    maps: HashMap<String, Option<Arc<SourceMap>>>,
+    frame_id: Option<Option<String>>,
+}
+
+/// Loads and caches the source maps of a target's scripts and maps stack traces back to
+/// original sources.
+///
+/// Scripts are learned from `Debugger.scriptParsed`, so [`enable`](Self::enable) turns on
+/// the `Debugger` domain; scripts can also be added by hand with
+/// [`add_script`](Self::add_script), e.g. from a [`DebuggerClient`](crate::DebuggerClient).
+/// Maps referenced by `data:` URLs are decoded in place; others are fetched by the page
+/// with `Network.loadNetworkResource`, so they load with the page's cookies and network
+/// conditions. Each map is loaded once.
+///
+/// # Example
+/// ```no_run
+/// # use cdpkit::{runtime, OwnedSession, SourceMapResolver};
+/// # use futures::StreamExt;
+/// # async fn example(session: OwnedSession) -> Result<(), cdpkit::CdpError> {
+/// let resolver = SourceMapResolver::enable(&session).await?;
+/// let mut exceptions = runtime::events::ExceptionThrown::subscribe(&session);
+/// runtime::methods::Enable::new().send(&session).await?;
+/// while let Some(event) = exceptions.next().await {
+///     if let Some(stack) = &event.exception_details.stack_trace {
+///         for frame in resolver.map_stack_trace(stack).await {
+///             println!("    {frame}");
+///         }
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct SourceMapResolver<S: Sender + Clone + Send + Sync + 'static> {
+    target: S,
+    state: Arc<Mutex<ResolverState>>,
+    task: Option<tokio::task::JoinHandle<()>>,
+}
+
+impl<S: Sender + Clone + Send + Sync + 'static> SourceMapResolver<S> {
+    /// A resolver that only knows the scripts passed to [`add_script`](Self::add_script).
+    pub fn new(target: &S) -> Self {
+        Self {
+            target: target.clone(),
+            state: Arc::default(),
+            task: None,
+        }
+    }
+
+    /// Track `Debugger.scriptParsed` and call `Debugger.enable`, which reports the scripts
+    /// that are already loaded.
+    pub async fn enable(target: &S) -> Result<Self, CdpError> {
+        let mut resolver = Self::new(target);
+        let mut events = merged_event_stream(target, &["Debugger.scriptParsed"]);
+        let state = Arc::clone(&resolver.state);
+        resolver.task = Some(tokio::spawn(async move {
+            while let Some((name, params)) = events.next().await {
+                if let Some(event) = decode_event::<ScriptParsed>(&name, &params) {
+                    lock(&state).scripts.insert(
+                        event.script_id,
+                        ScriptSource {
+                            url: event.url,
+                            source_map_url: event.source_map_url.filter(|url| !url.is_empty()),
+                        },
+                    );
+                }
+            }
+        }));
+        debugger::methods::Enable::new().send(target).await?;
+        Ok(resolver)
+    }
+
+    /// Frame used for `Network.loadNetworkResource`. By default the page's main frame
+    /// is looked up once; workers have none.
+    pub fn with_frame_id(self, frame_id: impl Into<String>) -> Self {
+        lock(&self.state).frame_id = Some(Some(frame_id.into()));
+        self
+    }
+
+    pub fn add_script(&self, script: &ScriptInfo) {
+        lock(&self.state).scripts.insert(
+            script.script_id.clone(),
+            ScriptSource {
+                url: script.url.clone(),
+                source_map_url: script.source_map_url.clone(),
+            },
+        );
+    }
+
+    /// The source map of a script, loading it on first use. `None` if the script is
+    /// unknown, has no map, or its map could not be loaded (logged once).
+    pub async fn source_map(&self, script_id: &str) -> Option<Arc<SourceMap>> {
+        let script = lock(&self.state).scripts.get(script_id).cloned()?;
+        let source_map_url = script.source_map_url?;
+        let map_url = if source_map_url.starts_with("data:") {
+            source_map_url
+        } else {
+            resolve_url(&script.url, &source_map_url)
+        };
+        if let Some(cached) = lock(&self.state).maps.get(&map_url) {
+            return cached.clone();
+        }
+
+        let loaded = match self.load(&map_url).await {
+            Ok(json) => match std::str::from_utf8(&json)
+                .map_err(|e| CdpError::InvalidSourceMap(e.to_string()))
+                .and_then(SourceMap::from_json)
+            {
+                Ok(mut map) => {
+                    let base = if map_url.starts_with("data:") {
+                        &script.url
+                    } else {
+                        &map_url
+                    };
+                    map.resolve_sources(base);
+                    debug!(url = %script.url, sources = map.sources().len(), "Loaded source map");
+                    Some(Arc::new(map))
+                }
+                Err(e) => {
+                    warn!(url = %script.url, error = %e, "Invalid source map");
+                    None
+                }
+            },
+            Err(e) => {
+                warn!(url = %script.url, error = %e, "Failed to load source map");
+                None
+            }
+        };
+        lock(&self.state).maps.insert(map_url, loaded.clone());
+        loaded
+    }
+
+    /// Map a 0-based position in a script to its original source.
+    pub async fn original_position(
+        &self,
+        script_id: &str,
+        line: i64,
+        column: i64,
+    ) -> Option<OriginalPosition> {
+        self.source_map(script_id).await?.lookup(line, column)
+    }
+
+    pub async fn map_call_frame(&self, frame: &CallFrame) -> MappedFrame {
+        MappedFrame {
+            function_name: frame.function_name.clone(),
+            url: frame.url.clone(),
+            line_number: frame.line_number,
+            column_number: frame.column_number,
+            original: self
+                .original_position(&frame.script_id, frame.line_number, frame.column_number)
+                .await,
+        }
+    }
+
+    /// Map every frame of `stack`, followed by the frames of its async parents.
+    pub async fn map_stack_trace(&self, stack: &StackTrace) -> Vec<MappedFrame> {
+        let mut frames = Vec::new();
+        let mut current = Some(stack);
+        while let Some(stack) = current {
+            for frame in &stack.call_frames {
+                frames.push(self.map_call_frame(frame).await);
+            }
+            current = stack.parent.as_deref();
+        }
+        frames
+    }
+
+    async fn load(&self, url: &str) -> Result<Vec<u8>, CdpError> {
+        if url.starts_with("data:") {
+            return decode_data_url(url);
+        }
+        let options = network::types::LoadNetworkResourceOptions {
+            disable_cache: false,
+            include_credentials: true,
+        };
+        let mut command = network::methods::LoadNetworkResource::new(url, options);
+        if let Some(frame_id) = self.frame_id().await {
+            command = command.with_frame_id(frame_id);
+        }
+        let resource = command.send(&self.target).await?.resource;
+        let stream = match (resource.success, resource.stream) {
+            (true, Some(stream)) => stream,
+            _ => {
+                let reason = resource
+                    .net_error_name
+                    .or_else(|| resource.http_status_code.map(|s| format!("HTTP {s}")))
+                    .unwrap_or_else(|| "no content".to_string());
+                return Err(CdpError::Io(format!("loading {url} failed: {reason}")));
+            }
+        };
+
+        let mut bytes = Vec::new();
+        let result = loop {
+            match read_chunk(&self.target, &stream, None).await {
+                Ok((chunk, eof)) => {
+                    bytes.extend_from_slice(&chunk);
+                    if eof {
+                        break Ok(bytes);
+                    }
+                }
+                Err(e) => break Err(e),
+            }
+        };
+        if let Err(e) = io::methods::Close::new(stream).send(&self.target).await {
+            warn!(error = %e, "Failed to close source map stream");
+        }
+        result
+    }
+
+    async fn frame_id(&self) -> Option<String> {
+        if let Some(frame_id) = lock(&self.state).frame_id.clone() {
+            return frame_id;
+        }
+        let frame_id = page::methods::GetFrameTree::new()
+            .send(&self.target)
+            .await
+            .ok()
+            .map(|tree| tree.frame_tree.frame.id);
+        lock(&self.state).frame_id = Some(frame_id.clone());
+        frame_id
+    }
+}
+
+impl<S: Sender + Clone + Send + Sync + 'static> Drop for SourceMapResolver<S> {
+    fn drop(&mut self) {
+        if let Some(task) = &self.task {
+            task.abort();
+        }
+    }
+}
+
+fn lock(state: &Mutex<ResolverState>) -> std::sync::MutexGuard<'_, ResolverState> {
+    state.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn lookup_finds_the_closest_segment_on_the_line() {
+        // Line 0: col 0 -> a.ts 0:0, col 9 -> a.ts 1:4 `total`.
+        // Line 1: col 2 -> b.ts 10:0, col 7 -> unmapped.
+        let map = SourceMap::from_json(
+            &json!({
+                "version": 3,
+                "file": "bundle.js",
+                "sourceRoot": "src",
+                "sources": ["a.ts", "b.ts"],
+                "sourcesContent": ["let a;\n    total();\n", null],
+                "names": ["total"],
+                "mappings": "AAAA,SACIA;ECSJ,K"
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        assert_eq!(map.file(), Some("bundle.js"));
+        assert_eq!(map.sources(), ["src/a.ts", "src/b.ts"]);
+        assert_eq!(
+            map.lookup(0, 12),
+            Some(OriginalPosition {
+                source: "src/a.ts".into(),
+                line: 1,
+                column: 4,
+                name: Some("total".into()),
+            })
+        );
+        assert_eq!(map.lookup(0, 3).unwrap().line, 0);
+        assert_eq!(map.lookup(1, 5).unwrap().line, 10);
+        assert_eq!(map.lookup(1, 8), None);
+        assert_eq!(map.lookup(1, 0), None);
+        assert_eq!(map.lookup(5, 0), None);
+        assert!(map.source_content("src/a.ts").unwrap().contains("total"));
+    }
+
+    #[test]
+    fn index_maps_offset_each_section() {
+        let map = SourceMap::from_json(
+            &json!({
+                "version": 3,
+                "sections": [
+                    {"offset": {"line": 0, "column": 0},
+                     "map": {"version": 3, "sources": ["one.js"], "names": [], "mappings": "AAAA"}},
+                    {"offset": {"line": 2, "column": 10},
+                     "map": {"version": 3, "sources": ["two.js"], "names": [], "mappings": "AACA"}}
+                ]
+            })
+            .to_string(),
+        )
+        .unwrap();
+        assert_eq!(map.lookup(2, 12).unwrap().source, "two.js");
+        assert_eq!(map.lookup(2, 12).unwrap().line, 1);
+        assert_eq!(map.lookup(2, 5), None);
+    }
+
+    #[test]
+    fn resolves_relative_urls() {
+        let base = "https://app.test/static/js/main.js?v=3";
+        assert_eq!(
+            resolve_url(base, "main.js.map"),
+            "https://app.test/static/js/main.js.map"
+        );
+        assert_eq!(
+            resolve_url(base, "../../src/./app.ts"),
+            "https://app.test/src/app.ts"
+        );
+        assert_eq!(
+            resolve_url(base, "/maps/x.map"),
+            "https://app.test/maps/x.map"
+        );
+        assert_eq!(
+            resolve_url(base, "//cdn.test/x.map"),
+            "https://cdn.test/x.map"
+        );
+        assert_eq!(
+            resolve_url(base, "webpack:///src/app.ts"),
+            "webpack:///src/app.ts"
+        );
+        assert_eq!(resolve_url("", "app.ts"), "app.ts");
+        assert_eq!(
+            resolve_url("file:///srv/app/dist/index.js", "../src/index.ts"),
+            "file:///srv/app/src/index.ts"
+        );
+    }
+
+    #[test]
+    fn decodes_data_urls() {
+        assert_eq!(
+            decode_data_url("data:application/json;base64,eyJhIjoxfQ==").unwrap(),
+            br#"{"a":1}"#
+        );
+        assert_eq!(
+            decode_data_url("data:application/json,%7B%22a%22%3A1%7D").unwrap(),
+            br#"{"a":1}"#
+        );
+        assert!(SourceMap::from_json(r#"{"version":3,"sources":[],"mappings":"A$"}"#).is_err());
+    }
+}
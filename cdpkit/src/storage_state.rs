@@ -0,0 +1,369 @@
+use crate::dom_storage::types::StorageId;
+use crate::fetch::events::RequestPaused;
+use crate::fetch::types::{HeaderEntry, RequestPattern, RequestStage};
+use crate::network::types::{Cookie, CookieParam, CookieSameSite, ResourceType};
+use crate::page::types::FrameTree;
+use crate::{dom_storage, fetch, page, storage, CdpError, EventStream, Sender};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Cookies and per-origin Web Storage, in Playwright's `storageState` JSON format.
+///
+/// A state saved here can be loaded by Playwright (`browser.newContext({ storageState })`)
+/// and vice versa. `sessionStorage` is an extension: it is written next to
+/// `localStorage` only when non-empty, and ignored by Playwright.
+///
+/// # Example
+/// ```no_run
+/// # use cdpkit::{OwnedSession, StorageState};
+/// # async fn example(session: OwnedSession, fresh: OwnedSession) -> Result<(), cdpkit::CdpError> {
+/// // ... log in ...
+/// StorageState::capture(&session).await?.save("auth.json").await?;
+///
+/// // Next run:
+/// StorageState::load("auth.json").await?.apply(&fresh).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StorageState {
+    pub cookies: Vec<StorageCookie>,
+    pub origins: Vec<OriginStorage>,
+}
+
+/// A cookie as stored in a `storageState` file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageCookie {
+    pub name: String,
+    pub value: String,
+    pub domain: String,
+    pub path: String,
+    /// Seconds since the Unix epoch, or -1 for a session cookie.
+    pub expires: f64,
+    pub http_only: bool,
+    pub secure: bool,
+    pub same_site: CookieSameSite,
+}
+
+impl From<Cookie> for StorageCookie {
+    fn from(cookie: Cookie) -> Self {
+        Self {
+            expires: if cookie.session { -1.0 } else { cookie.expires },
+            name: cookie.name,
+            value: cookie.value,
+            domain: cookie.domain,
+            path: cookie.path,
+            http_only: cookie.http_only,
+            secure: cookie.secure,
+            // Chrome omits SameSite when unspecified and then treats the cookie as Lax.
+            same_site: cookie.same_site.unwrap_or(CookieSameSite::Lax),
+        }
+    }
+}
+
+impl From<StorageCookie> for CookieParam {
+    fn from(cookie: StorageCookie) -> Self {
+        CookieParam {
+            name: cookie.name,
+            value: cookie.value,
+            url: None,
+            domain: Some(cookie.domain),
+            path: Some(cookie.path),
+            secure: Some(cookie.secure),
+            http_only: Some(cookie.http_only),
+            same_site: Some(cookie.same_site),
+            expires: (cookie.expires >= 0.0).then_some(cookie.expires),
+            priority: None,
+            source_scheme: None,
+            source_port: None,
+            partition_key: None,
+        }
+    }
+}
+
+/// Web Storage of one origin, such as `https://example.com`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OriginStorage {
+    pub origin: String,
+    pub local_storage: Vec<StorageItem>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub session_storage: Vec<StorageItem>,
+}
+
+/// One Web Storage key/value pair.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageItem {
+    pub name: String,
+    pub value: String,
+}
+
+fn storage_id(origin: &str, is_local_storage: bool) -> StorageId {
+    StorageId {
+        security_origin: Some(origin.to_string()),
+        storage_key: None,
+        is_local_storage,
+    }
+}
+
+/// Security origins of every frame, without duplicates or opaque origins.
+fn frame_origins(tree: &FrameTree, origins: &mut Vec<String>) {
+    let origin = &tree.frame.security_origin;
+    let opaque = origin.is_empty() || origin == "null" || origin.starts_with("://");
+    if !opaque && !origins.contains(origin) {
+        origins.push(origin.clone());
+    }
+    for child in tree.child_frames.iter().flatten() {
+        frame_origins(child, origins);
+    }
+}
+
+impl StorageState {
+    /// Capture all cookies and the Web Storage of every origin loaded in the page's frames.
+    ///
+    /// `target` must be a page session, since origins come from `Page.getFrameTree`.
+    pub async fn capture<S: Sender + Sync>(target: &S) -> Result<Self, CdpError> {
+        let tree = page::methods::GetFrameTree::new().send(target).await?;
+        let mut origins = Vec::new();
+        frame_origins(&tree.frame_tree, &mut origins);
+        Self::capture_origins(target, origins).await
+    }
+
+    /// Capture all cookies and the Web Storage of the given origins.
+    pub async fn capture_origins<S: Sender + Sync>(
+        target: &S,
+        origins: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Result<Self, CdpError> {
+        let cookies = storage::methods::GetCookies::new().send(target).await?;
+        let mut state = Self {
+            cookies: cookies.cookies.into_iter().map(Into::into).collect(),
+            origins: Vec::new(),
+        };
+        for origin in origins {
+            let origin = origin.into();
+            let local_storage = storage_items(target, &origin, true).await?;
+            let session_storage = storage_items(target, &origin, false).await?;
+            if local_storage.is_empty() && session_storage.is_empty() {
+                continue;
+            }
+            state.origins.push(OriginStorage {
+                origin,
+                local_storage,
+                session_storage,
+            });
+        }
+        Ok(state)
+    }
+
+    /// Set the cookies with `Storage.setCookies` and write every origin's storage items with
+    /// `DOMStorage.setDOMStorageItem`. Existing cookies and items are kept unless overwritten.
+    ///
+    /// `target` must be a page session. Chrome only accepts storage items for origins that
+    /// are loaded in a frame, so, like Playwright, the page visits every other origin in
+    /// turn: `Fetch` answers each visit with an empty document, so nothing reaches the
+    /// network. The page then returns to its original URL. Do not call this while a
+    /// [`RequestInterceptor`](crate::RequestInterceptor) is enabled on the session, since
+    /// `Fetch` is disabled afterwards.
+    pub async fn apply<S: Sender + Sync>(&self, target: &S) -> Result<(), CdpError> {
+        if !self.cookies.is_empty() {
+            let cookies = self.cookies.iter().cloned().map(Into::into).collect();
+            storage::methods::SetCookies::new(cookies)
+                .send(target)
+                .await?;
+        }
+        let origins: Vec<&OriginStorage> = self
+            .origins
+            .iter()
+            .filter(|origin| !origin.local_storage.is_empty() || !origin.session_storage.is_empty())
+            .collect();
+        if origins.is_empty() {
+            return Ok(());
+        }
+
+        let tree = page::methods::GetFrameTree::new().send(target).await?;
+        let mut loaded = Vec::new();
+        frame_origins(&tree.frame_tree, &mut loaded);
+        let (present, missing): (Vec<&OriginStorage>, Vec<&OriginStorage>) = origins
+            .into_iter()
+            .partition(|origin| loaded.contains(&origin.origin));
+        for origin in present {
+            set_items(target, origin).await?;
+        }
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        let mut paused = target.event_stream::<RequestPaused>("Fetch.requestPaused");
+        fetch::methods::Enable::new()
+            .with_patterns(vec![RequestPattern {
+                url_pattern: Some("*".to_string()),
+                resource_type: Some(ResourceType::Document),
+                request_stage: Some(RequestStage::Request),
+            }])
+            .send(target)
+            .await?;
+        let mut result = Ok(());
+        for origin in missing {
+            result = load_empty_document(target, &mut paused, &origin.origin).await;
+            if result.is_ok() {
+                result = set_items(target, origin).await;
+            }
+            if result.is_err() {
+                break;
+            }
+        }
+        let disabled = fetch::methods::Disable::new().send(target).await;
+        let returned = page::methods::Navigate::new(tree.frame_tree.frame.url)
+            .send(target)
+            .await;
+        result?;
+        disabled?;
+        returned.map(|_| ())
+    }
+
+    pub fn to_json(&self) -> Result<String, CdpError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, CdpError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Write the state to a JSON file.
+    pub async fn save(&self, path: impl AsRef<Path>) -> Result<(), CdpError> {
+        tokio::fs::write(path, self.to_json()?)
+            .await
+            .map_err(|e| CdpError::Io(e.to_string()))
+    }
+
+    /// Read a state written by [`save`](Self::save) or by Playwright.
+    pub async fn load(path: impl AsRef<Path>) -> Result<Self, CdpError> {
+        let json = tokio::fs::read_to_string(path)
+            .await
+            .map_err(|e| CdpError::Io(e.to_string()))?;
+        Self::from_json(&json)
+    }
+}
+
+async fn set_items<S: Sender + Sync>(target: &S, origin: &OriginStorage) -> Result<(), CdpError> {
+    for (items, is_local_storage) in [
+        (&origin.local_storage, true),
+        (&origin.session_storage, false),
+    ] {
+        for item in items {
+            dom_storage::methods::SetDomStorageItem::new(
+                storage_id(&origin.origin, is_local_storage),
+                &item.name,
+                &item.value,
+            )
+            .send(target)
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Navigate to `origin`, answering the document request with an empty page.
+async fn load_empty_document<S: Sender + Sync>(
+    target: &S,
+    paused: &mut EventStream<RequestPaused>,
+    origin: &str,
+) -> Result<(), CdpError> {
+    let url = format!("{origin}/");
+    let navigate = page::methods::Navigate::new(url.clone()).send(target);
+    tokio::pin!(navigate);
+    let response = loop {
+        tokio::select! {
+            biased;
+            Some(event) = paused.next() => {
+                fetch::methods::FulfillRequest::new(event.request_id, 200)
+                    .with_response_headers(vec![HeaderEntry {
+                        name: "Content-Type".to_string(),
+                        value: "text/html".to_string(),
+                    }])
+                    .with_body(String::new())
+                    .send(target)
+                    .await?;
+            }
+            response = &mut navigate => break response?,
+        }
+    };
+    match response.error_text {
+        Some(error) => Err(CdpError::NavigationFailed { url, error }),
+        None => Ok(()),
+    }
+}
+
+async fn storage_items<S: Sender + Sync>(
+    target: &S,
+    origin: &str,
+    is_local_storage: bool,
+) -> Result<Vec<StorageItem>, CdpError> {
+    let response =
+        dom_storage::methods::GetDomStorageItems::new(storage_id(origin, is_local_storage))
+            .send(target)
+            .await?;
+    Ok(response
+        .entries
+        .into_iter()
+        .filter_map(|entry| match <[String; 2]>::try_from(entry) {
+            Ok([name, value]) => Some(StorageItem { name, value }),
+            Err(_) => None,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reads_and_writes_playwright_format() {
+        // Playwright writes `-1` for session cookies; serde writes `-1.0`, which is equal in JS.
+        let playwright = json!({
+            "cookies": [{
+                "name": "sid", "value": "abc", "domain": ".a.test", "path": "/",
+                "expires": -1.0, "httpOnly": true, "secure": false, "sameSite": "Lax"
+            }],
+            "origins": [{
+                "origin": "https://a.test",
+                "localStorage": [{"name": "theme", "value": "dark"}]
+            }]
+        });
+        let state = StorageState::from_json(&playwright.to_string().replace("-1.0", "-1")).unwrap();
+        assert_eq!(state.origins[0].local_storage[0].value, "dark");
+        assert!(state.origins[0].session_storage.is_empty());
+        let round_trip: serde_json::Value =
+            serde_json::from_str(&state.to_json().unwrap()).unwrap();
+        assert_eq!(round_trip, playwright);
+
+        let param = CookieParam::from(state.cookies[0].clone());
+        assert_eq!(param.expires, None);
+        assert_eq!(param.domain.as_deref(), Some(".a.test"));
+    }
+
+    #[test]
+    fn collects_distinct_frame_origins() {
+        let frame = |id: &str, origin: &str| {
+            json!({"id": id, "loaderId": "L", "url": "", "domainAndRegistry": "",
+                   "securityOrigin": origin, "mimeType": "text/html",
+                   "secureContextType": "Secure", "crossOriginIsolatedContextType": "NotIsolated",
+                   "gatedAPIFeatures": []})
+        };
+        let tree: FrameTree = serde_json::from_value(json!({
+            "frame": frame("main", "https://a.test"),
+            "childFrames": [
+                {"frame": frame("ad", "https://ads.test")},
+                {"frame": frame("same", "https://a.test")},
+                {"frame": frame("blank", "null")}
+            ]
+        }))
+        .unwrap();
+        let mut origins = Vec::new();
+        frame_origins(&tree, &mut origins);
+        assert_eq!(origins, ["https://a.test", "https://ads.test"]);
+    }
+}
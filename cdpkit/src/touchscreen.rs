@@ -0,0 +1,109 @@
+use crate::input::types::TouchPoint;
+use crate::{input, CdpError, Sender};
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Mutex};
+
+fn touch_point(id: f64, x: f64, y: f64) -> TouchPoint {
+    TouchPoint {
+        x,
+        y,
+        radius_x: None,
+        radius_y: None,
+        rotation_angle: None,
+        force: None,
+        tangential_pressure: None,
+        tilt_x: None,
+        tilt_y: None,
+        twist: None,
+        id: Some(id),
+    }
+}
+
+#[derive(Debug, Default)]
+struct TouchState {
+    active: Vec<TouchPoint>,
+    next_id: f64,
+}
+
+/// Touch input with tracked active touch points.
+///
+/// Each `touchStart`/`touchMove` event lists every active point, as the protocol requires,
+/// so several fingers can be held at once.
+pub struct Touchscreen<'a, S> {
+    target: &'a S,
+    state: Mutex<TouchState>,
+    modifiers: Arc<AtomicI64>,
+}
+
+impl<'a, S: Sender + Sync> Touchscreen<'a, S> {
+    /// Create a touchscreen with no active touches and no modifiers.
+    pub fn new(target: &'a S) -> Self {
+        Self::with_modifiers(target, Arc::new(AtomicI64::new(0)))
+    }
+
+    pub(crate) fn with_modifiers(target: &'a S, modifiers: Arc<AtomicI64>) -> Self {
+        Self {
+            target,
+            state: Mutex::new(TouchState::default()),
+            modifiers,
+        }
+    }
+
+    /// Number of touch points currently held down.
+    pub fn active_touches(&self) -> usize {
+        self.state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .active
+            .len()
+    }
+
+    /// Put a finger down at `(x, y)` and return its touch id.
+    pub async fn touch_start(&self, x: f64, y: f64) -> Result<f64, CdpError> {
+        let (id, points) = {
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            let id = state.next_id;
+            state.next_id += 1.0;
+            state.active.push(touch_point(id, x, y));
+            (id, state.active.clone())
+        };
+        self.dispatch("touchStart", points).await?;
+        Ok(id)
+    }
+
+    /// Move an active touch point to `(x, y)`.
+    pub async fn touch_move(&self, id: f64, x: f64, y: f64) -> Result<(), CdpError> {
+        let points = {
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            for point in state.active.iter_mut().filter(|p| p.id == Some(id)) {
+                point.x = x;
+                point.y = y;
+            }
+            state.active.clone()
+        };
+        self.dispatch("touchMove", points).await
+    }
+
+    /// Lift an active touch point.
+    pub async fn touch_end(&self, id: f64) -> Result<(), CdpError> {
+        let points = {
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            state.active.retain(|p| p.id != Some(id));
+            state.active.clone()
+        };
+        self.dispatch("touchEnd", points).await
+    }
+
+    /// Tap at `(x, y)` with a single finger.
+    pub async fn tap(&self, x: f64, y: f64) -> Result<(), CdpError> {
+        let id = self.touch_start(x, y).await?;
+        self.touch_end(id).await
+    }
+
+    async fn dispatch(&self, type_: &str, points: Vec<TouchPoint>) -> Result<(), CdpError> {
+        input::methods::DispatchTouchEvent::new(type_, points)
+            .with_modifiers(self.modifiers.load(Ordering::Acquire))
+            .send(self.target)
+            .await
+    }
+}
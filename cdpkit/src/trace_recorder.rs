@@ -0,0 +1,447 @@
+use crate::tracing::events::{DataCollected, TracingComplete};
+use crate::tracing::types::{StreamCompression, StreamFormat, TraceConfig};
+use crate::{
+    decode_event, merged_event_stream, spawn_cleanup, tracing as trace_domain, CdpError,
+    CdpReadStream, MergedEventStream, Sender,
+};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::Write;
+use std::path::Path;
+use tokio::io::AsyncReadExt;
+use tracing::warn;
+
+const TRACE_EVENTS: &[&str] = &["Tracing.dataCollected", "Tracing.tracingComplete"];
+
+/// Category sets for common kinds of traces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TracePreset {
+    /// A light overview: top-level tasks, Blink, V8 and loading.
+    Default,
+    /// What the DevTools Performance panel records: main-thread tasks, JS sampling,
+    /// layout, paint and screenshots.
+    Performance,
+    /// Frame production: compositing, raster, GPU and input latency.
+    Rendering,
+    /// V8 execution and garbage collection, with JS sampling.
+    JavaScript,
+}
+
+impl TracePreset {
+    /// The included categories of this preset.
+    pub fn categories(self) -> &'static [&'static str] {
+        match self {
+            Self::Default => &["toplevel", "blink", "v8", "loading", "devtools.timeline"],
+            Self::Performance => &[
+                "-*",
+                "toplevel",
+                "v8.execute",
+                "blink.console",
+                "blink.user_timing",
+                "benchmark",
+                "loading",
+                "latencyInfo",
+                "devtools.timeline",
+                "disabled-by-default-devtools.timeline",
+                "disabled-by-default-devtools.timeline.frame",
+                "disabled-by-default-devtools.timeline.stack",
+                "disabled-by-default-devtools.screenshot",
+                "disabled-by-default-v8.cpu_profiler",
+            ],
+            Self::Rendering => &[
+                "-*",
+                "toplevel",
+                "benchmark",
+                "cc",
+                "gpu",
+                "viz",
+                "latencyInfo",
+                "input",
+                "devtools.timeline",
+                "disabled-by-default-devtools.timeline.frame",
+            ],
+            Self::JavaScript => &[
+                "-*",
+                "toplevel",
+                "v8",
+                "v8.execute",
+                "disabled-by-default-v8.gc",
+                "disabled-by-default-v8.cpu_profiler",
+                "devtools.timeline",
+            ],
+        }
+    }
+}
+
+/// How Chrome hands the recorded trace back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TraceTransferMode {
+    /// Events arrive in `Tracing.dataCollected` batches while the trace is flushed.
+    #[default]
+    ReportEvents,
+    /// The trace is returned as an `IO.StreamHandle` and read with `IO.read`; better for
+    /// large traces since nothing is buffered in event messages.
+    ReturnAsStream,
+}
+
+impl TraceTransferMode {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::ReportEvents => "ReportEvents",
+            Self::ReturnAsStream => "ReturnAsStream",
+        }
+    }
+}
+
+/// Configuration for [`TraceRecorder::start`].
+#[derive(Debug, Clone)]
+pub struct TraceOptions {
+    categories: Vec<String>,
+    transfer_mode: TraceTransferMode,
+    record_mode: Option<String>,
+    buffer_size_kb: Option<f64>,
+}
+
+impl Default for TraceOptions {
+    fn default() -> Self {
+        Self::preset(TracePreset::Default)
+    }
+}
+
+impl TraceOptions {
+    /// The [`TracePreset::Default`] categories, reported as events.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start from a preset's categories.
+    pub fn preset(preset: TracePreset) -> Self {
+        Self {
+            categories: preset.categories().iter().map(|c| c.to_string()).collect(),
+            transfer_mode: TraceTransferMode::default(),
+            record_mode: None,
+            buffer_size_kb: None,
+        }
+    }
+
+    /// Replace the categories. A `-` prefix excludes a category, and `-*` excludes
+    /// everything not listed explicitly.
+    pub fn with_categories<I, C>(mut self, categories: I) -> Self
+    where
+        I: IntoIterator<Item = C>,
+        C: Into<String>,
+    {
+        self.categories = categories.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Add one category, e.g. `disabled-by-default-devtools.screenshot`.
+    pub fn with_category(mut self, category: impl Into<String>) -> Self {
+        self.categories.push(category.into());
+        self
+    }
+
+    pub fn with_transfer_mode(mut self, transfer_mode: TraceTransferMode) -> Self {
+        self.transfer_mode = transfer_mode;
+        self
+    }
+
+    /// `recordUntilFull` (default), `recordContinuously` or `recordAsMuchAsPossible`.
+    pub fn with_record_mode(mut self, record_mode: impl Into<String>) -> Self {
+        self.record_mode = Some(record_mode.into());
+        self
+    }
+
+    /// Size of Chrome's trace buffer.
+    pub fn with_buffer_size_kb(mut self, buffer_size_kb: f64) -> Self {
+        self.buffer_size_kb = Some(buffer_size_kb);
+        self
+    }
+
+    fn trace_config(&self) -> TraceConfig {
+        let (excluded, included): (Vec<String>, Vec<String>) = self
+            .categories
+            .iter()
+            .cloned()
+            .partition(|c| c.starts_with('-'));
+        let excluded: Vec<String> = excluded.into_iter().map(|c| c[1..].to_string()).collect();
+        TraceConfig {
+            record_mode: self.record_mode.clone(),
+            trace_buffer_size_in_kb: self.buffer_size_kb,
+            enable_sampling: None,
+            enable_systrace: None,
+            enable_argument_filter: None,
+            included_categories: Some(included),
+            excluded_categories: (!excluded.is_empty()).then_some(excluded),
+            synthetic_delays: None,
+            memory_dump_config: None,
+        }
+    }
+}
+
+/// A recorded trace in the Chrome JSON trace event format.
+///
+/// Loads in Perfetto (`ui.perfetto.dev`), `chrome://tracing` and the DevTools
+/// Performance panel.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Trace {
+    pub trace_events: Vec<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Value>,
+    /// Set when Chrome's trace buffer overflowed and events were dropped.
+    #[serde(skip)]
+    pub data_loss_occurred: bool,
+}
+
+impl Trace {
+    pub fn to_json(&self) -> Result<String, CdpError> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// Write the trace to `path`, gzip-compressed if it ends in `.gz`.
+    pub async fn save(&self, path: impl AsRef<Path>) -> Result<(), CdpError> {
+        let path = path.as_ref();
+        let json = self.to_json()?.into_bytes();
+        let bytes = if is_gzip_path(path) {
+            gzip(&json)?
+        } else {
+            json
+        };
+        tokio::fs::write(path, bytes)
+            .await
+            .map_err(|e| CdpError::Io(e.to_string()))
+    }
+}
+
+fn is_gzip_path(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "gz")
+}
+
+fn gzip(bytes: &[u8]) -> Result<Vec<u8>, CdpError> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(bytes)
+        .map_err(|e| CdpError::Io(e.to_string()))?;
+    encoder.finish().map_err(|e| CdpError::Io(e.to_string()))
+}
+
+/// What the background task collected once `Tracing.tracingComplete` arrived.
+struct Collected {
+    events: Vec<Value>,
+    complete: TracingComplete,
+}
+
+/// Records a Chrome trace with `Tracing.start` / `Tracing.end`.
+///
+/// Works on the browser ([`CDP`](crate::CDP)) or a page session. Events are gathered in
+/// the background until [`stop`](Self::stop) ends tracing and waits for
+/// `Tracing.tracingComplete`; with [`TraceTransferMode::ReturnAsStream`] the trace is
+/// then read through `IO.read`. Dropping the recorder ends tracing without waiting.
+///
+/// # Example
+/// ```no_run
+/// # use cdpkit::{TraceOptions, TracePreset, TraceRecorder, CDP};
+/// # async fn example(cdp: CDP) -> Result<(), cdpkit::CdpError> {
+/// let recorder = TraceRecorder::start(&cdp, TraceOptions::preset(TracePreset::Performance)).await?;
+/// // ... navigate and interact ...
+/// recorder.stop_and_save("trace.json.gz").await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct TraceRecorder<S: Sender + Clone + Send + Sync + Unpin + 'static> {
+    target: S,
+    task: Option<tokio::task::JoinHandle<Result<Collected, CdpError>>>,
+    ended: bool,
+}
+
+impl<S: Sender + Clone + Send + Sync + Unpin + 'static> TraceRecorder<S> {
+    /// Subscribe to tracing events and call `Tracing.start`.
+    pub async fn start(target: &S, options: TraceOptions) -> Result<Self, CdpError> {
+        let events = merged_event_stream(target, TRACE_EVENTS);
+        let mut cmd = trace_domain::methods::Start::new()
+            .with_transfer_mode(options.transfer_mode.as_str())
+            .with_trace_config(options.trace_config());
+        if options.transfer_mode == TraceTransferMode::ReturnAsStream {
+            cmd = cmd
+                .with_stream_format(StreamFormat::Json)
+                .with_stream_compression(StreamCompression::None);
+        }
+        cmd.send(target).await?;
+
+        Ok(Self {
+            target: target.clone(),
+            task: Some(tokio::spawn(collect(events))),
+            ended: false,
+        })
+    }
+
+    /// Category names the browser knows about, from `Tracing.getCategories`.
+    pub async fn categories(target: &S) -> Result<Vec<String>, CdpError> {
+        Ok(trace_domain::methods::GetCategories::new()
+            .send(target)
+            .await?
+            .categories)
+    }
+
+    /// End tracing and return the whole trace.
+    pub async fn stop(mut self) -> Result<Trace, CdpError> {
+        let collected = self.end().await?;
+        let data_loss_occurred = collected.complete.data_loss_occurred;
+        let mut trace = match collected.complete.stream {
+            Some(handle) => {
+                let bytes = CdpReadStream::new(self.target.clone(), handle)
+                    .into_bytes()
+                    .await?;
+                serde_json::from_slice(&bytes)?
+            }
+            None => Trace {
+                trace_events: collected.events,
+                ..Trace::default()
+            },
+        };
+        trace.data_loss_occurred = data_loss_occurred;
+        Ok(trace)
+    }
+
+    /// End tracing and write the trace to `path`, gzip-compressed if it ends in `.gz`.
+    ///
+    /// With [`TraceTransferMode::ReturnAsStream`] the stream is copied to the file chunk
+    /// by chunk instead of being parsed. Returns whether Chrome reported data loss.
+    pub async fn stop_and_save(mut self, path: impl AsRef<Path>) -> Result<bool, CdpError> {
+        let path = path.as_ref();
+        let collected = self.end().await?;
+        let data_loss_occurred = collected.complete.data_loss_occurred;
+        let Some(handle) = collected.complete.stream else {
+            // Reported as events, or Chrome ignored `transferMode`.
+            let trace = Trace {
+                trace_events: collected.events,
+                ..Trace::default()
+            };
+            trace.save(path).await?;
+            return Ok(data_loss_occurred);
+        };
+        let stream = CdpReadStream::new(self.target.clone(), handle);
+        if is_gzip_path(path) {
+            let bytes = gzip_stream(stream).await?;
+            tokio::fs::write(path, bytes)
+                .await
+                .map_err(|e| CdpError::Io(e.to_string()))?;
+        } else {
+            stream.save(path).await?;
+        }
+        Ok(data_loss_occurred)
+    }
+
+    /// Send `Tracing.end` and wait for the background task to see `tracingComplete`.
+    async fn end(&mut self) -> Result<Collected, CdpError> {
+        self.ended = true;
+        trace_domain::methods::End::new().send(&self.target).await?;
+        let task = self.task.take().ok_or(CdpError::ChannelClosed)?;
+        task.await.map_err(|_| CdpError::ChannelClosed)?
+    }
+}
+
+/// Compress a trace stream while reading it, keeping only the compressed bytes in memory.
+async fn gzip_stream<S: Sender + Clone + Send + Sync + Unpin + 'static>(
+    mut stream: CdpReadStream<S>,
+) -> Result<Vec<u8>, CdpError> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    let mut chunk = vec![0; 64 * 1024];
+    loop {
+        let n = stream
+            .read(&mut chunk)
+            .await
+            .map_err(|e| CdpError::Io(e.to_string()))?;
+        if n == 0 {
+            break;
+        }
+        encoder
+            .write_all(&chunk[..n])
+            .map_err(|e| CdpError::Io(e.to_string()))?;
+    }
+    stream.close().await?;
+    encoder.finish().map_err(|e| CdpError::Io(e.to_string()))
+}
+
+async fn collect(mut events: MergedEventStream) -> Result<Collected, CdpError> {
+    let mut collected = Vec::new();
+    while let Some((name, params)) = events.next().await {
+        match &*name {
+            "Tracing.dataCollected" => {
+                if let Some(batch) = decode_event::<DataCollected>(&name, &params) {
+                    collected.extend(batch.value);
+                }
+            }
+            "Tracing.tracingComplete" => {
+                if let Some(complete) = decode_event::<TracingComplete>(&name, &params) {
+                    return Ok(Collected {
+                        events: collected,
+                        complete,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    Err(CdpError::ConnectionClosed)
+}
+
+impl<S: Sender + Clone + Send + Sync + Unpin + 'static> Drop for TraceRecorder<S> {
+    fn drop(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+        if self.ended {
+            return;
+        }
+        let target = self.target.clone();
+        spawn_cleanup(async move {
+            if let Err(e) = trace_domain::methods::End::new().send(&target).await {
+                warn!(error = %e, "Failed to end tracing");
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn categories_split_into_included_and_excluded() {
+        let config = TraceOptions::new()
+            .with_categories(["-*", "v8", "devtools.timeline"])
+            .with_category("-v8.gc")
+            .with_record_mode("recordContinuously")
+            .trace_config();
+        assert_eq!(
+            serde_json::to_value(config).unwrap(),
+            serde_json::json!({
+                "recordMode": "recordContinuously",
+                "includedCategories": ["v8", "devtools.timeline"],
+                "excludedCategories": ["*", "v8.gc"]
+            })
+        );
+    }
+
+    #[test]
+    fn gzip_output_round_trips() {
+        use std::io::Read;
+        let trace = Trace {
+            trace_events: vec![serde_json::json!({"name": "RunTask", "ph": "X"})],
+            ..Trace::default()
+        };
+        let compressed = gzip(trace.to_json().unwrap().as_bytes()).unwrap();
+        let mut json = String::new();
+        flate2::read::GzDecoder::new(&compressed[..])
+            .read_to_string(&mut json)
+            .unwrap();
+        assert_eq!(json, r#"{"traceEvents":[{"name":"RunTask","ph":"X"}]}"#);
+        assert!(is_gzip_path(Path::new("out/trace.json.gz")));
+        assert!(!is_gzip_path(Path::new("trace.json")));
+    }
+}
@@ -1,14 +1,26 @@
 use cdpkit::{
-    target, CdpError, CloseReason, EventOverflowStrategy, EventStreamPolicy, Method, Sender, CDP,
+    browser, fetch, input, runtime, target, ArchiveEntryKind, AxQuery, AxTree, BreakpointSpec,
+    BrowserContext, BrowserContextOptions, CdpError, CdpReadStream, CloseReason, ConsoleCollector,
+    ConsoleLevel, ConsoleOptions, ConsoleSource, ContinueOverrides, Coverage, CoverageKind,
+    CoverageOptions, CpuProfile, CpuProfiler, CpuProfilerOptions, Credentials, DebuggerClient,
+    Device, DeviceMetrics, DialogAction, DialogHandler, DialogPolicy, DownloadOptions,
+    DownloadState, Downloads, EmulationOverrides, EventOverflowStrategy, EventStreamPolicy,
+    Geolocation, HarOptions, HarRecorder, HeapEdgeName, HeapSnapshot, HeapSnapshotOptions,
+    InterceptorOptions, Keyboard, Method, Metrics, Mouse, NetworkTracker, Page, PageArchive,
+    PageArchiveMode, PageArchiveOptions, PageSnapshot, PageSnapshotOptions, PauseReason,
+    PdfOptions, Permissions, RequestInterceptor, Screencast, ScreencastOptions, ScreenshotFormat,
+    ScreenshotOptions, Sender, SourceMapResolver, StorageState, TraceOptions, TracePreset,
+    TraceRecorder, TraceTransferMode, CDP,
 };
 use futures::StreamExt;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::net::SocketAddr;
 use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tokio::net::TcpListener;
-use tokio::sync::oneshot;
+use tokio::sync::{mpsc, oneshot};
 use tokio_tungstenite::tungstenite::Message;
 
 async fn start_mock_server() -> (SocketAddr, tokio::task::JoinHandle<()>) {
@@ -166,6 +178,117 @@ async fn start_recording_server() -> (
     (addr, message_rx, handle)
 }
 
+type ScriptedHandler = Arc<dyn Fn(&str, &Value) -> Result<Value, String> + Send + Sync>;
+
+/// Mock server that answers each command through `handler`, records every request,
+/// and pushes CDP events sent through [`ScriptedServer::emit`].
+struct ScriptedServer {
+    url: String,
+    requests: Arc<Mutex<Vec<Value>>>,
+    events: mpsc::UnboundedSender<Value>,
+}
+
+impl ScriptedServer {
+    fn methods(&self) -> Vec<String> {
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|request| request["method"].as_str().unwrap_or_default().to_string())
+            .collect()
+    }
+
+    fn requests_for(&self, method: &str) -> Vec<Value> {
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|request| request["method"] == method)
+            .cloned()
+            .collect()
+    }
+
+    fn emit(&self, session_id: &str, method: &str, params: Value) {
+        self.events
+            .send(json!({"method": method, "params": params, "sessionId": session_id}))
+            .unwrap();
+    }
+
+    /// Emit an event on the browser connection, without a session id.
+    fn emit_browser(&self, method: &str, params: Value) {
+        self.events
+            .send(json!({"method": method, "params": params}))
+            .unwrap();
+    }
+
+    /// Wait until `count` requests for `method` have been received.
+    async fn wait_for(&self, method: &str, count: usize) -> Vec<Value> {
+        tokio::time::timeout(Duration::from_secs(2), async {
+            loop {
+                let requests = self.requests_for(method);
+                if requests.len() >= count {
+                    return requests;
+                }
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .unwrap_or_else(|_| panic!("timed out waiting for {count} x {method}"))
+    }
+}
+
+async fn start_scripted_server(
+    handler: impl Fn(&str, &Value) -> Result<Value, String> + Send + Sync + 'static,
+) -> ScriptedServer {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let handler: ScriptedHandler = Arc::new(handler);
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let (events_tx, mut events_rx) = mpsc::unbounded_channel::<Value>();
+
+    let recorded = Arc::clone(&requests);
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+        let (mut write, mut read) = ws.split();
+
+        use futures::SinkExt;
+        loop {
+            tokio::select! {
+                msg = read.next() => {
+                    let Some(Ok(Message::Text(text))) = msg else { break };
+                    let request: Value = serde_json::from_str(&text).unwrap();
+                    recorded.lock().unwrap().push(request.clone());
+                    let id = request["id"].as_u64().unwrap();
+                    let method = request["method"].as_str().unwrap_or_default();
+                    let resp = match handler(method, &request["params"]) {
+                        Ok(result) => json!({"id": id, "result": result}),
+                        Err(message) => {
+                            json!({"id": id, "error": {"code": -32000, "message": message}})
+                        }
+                    };
+                    // Events the handler queued go out before its response, like Chrome
+                    // streaming data before answering a command.
+                    while let Ok(event) = events_rx.try_recv() {
+                        let _ = write.send(Message::Text(event.to_string().into())).await;
+                    }
+                    let _ = write.send(Message::Text(resp.to_string().into())).await;
+                }
+                event = events_rx.recv() => {
+                    let Some(event) = event else { break };
+                    let _ = write.send(Message::Text(event.to_string().into())).await;
+                }
+            }
+        }
+    });
+
+    ScriptedServer {
+        url: format!("ws://127.0.0.1:{}", addr.port()),
+        requests,
+        events: events_tx,
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 struct TestEcho {
     value: String,
@@ -1162,3 +1285,2529 @@ async fn close_reason_remote_after_server_close() {
         reason
     );
 }
+
+fn element_server_handler(method: &str, params: &Value) -> Result<Value, String> {
+    match method {
+        "Runtime.evaluate" => Ok(json!({
+            "result": {"type": "object", "subtype": "node", "className": "HTMLHeadingElement", "objectId": "obj-1"}
+        })),
+        "DOM.describeNode" => Ok(json!({
+            "node": {
+                "nodeId": 0, "backendNodeId": 42, "nodeType": 1,
+                "nodeName": "H1", "localName": "h1", "nodeValue": ""
+            }
+        })),
+        "DOM.resolveNode" => Ok(json!({
+            "object": {"type": "object", "subtype": "node", "objectId": "obj-2"}
+        })),
+        "Runtime.callFunctionOn" if params["objectId"] == "obj-1" => {
+            Err("Could not find object with given id".to_string())
+        }
+        "Runtime.callFunctionOn" => {
+            Ok(json!({"result": {"type": "string", "value": "Example Domain"}}))
+        }
+        "DOM.getBoxModel" => Ok(json!({
+            "model": {
+                "content": [10.0, 20.0, 110.0, 20.0, 110.0, 60.0, 10.0, 60.0],
+                "padding": [10.0, 20.0, 110.0, 20.0, 110.0, 60.0, 10.0, 60.0],
+                "border": [8.0, 18.0, 112.0, 18.0, 112.0, 62.0, 8.0, 62.0],
+                "margin": [8.0, 18.0, 112.0, 18.0, 112.0, 62.0, 8.0, 62.0],
+                "width": 104, "height": 44
+            }
+        })),
+        _ => Ok(json!({})),
+    }
+}
+
+#[tokio::test]
+async fn element_handle_re_resolves_stale_object_from_backend_node() {
+    let server = start_scripted_server(element_server_handler).await;
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+    let session = cdp.session("page-session");
+    let page = Page::new(&session);
+
+    let heading = page.query_selector("h1").await.unwrap().expect("h1");
+    assert_eq!(heading.backend_node_id(), 42);
+    assert_eq!(heading.object_id(), "obj-1");
+
+    assert_eq!(heading.text().await.unwrap(), "Example Domain");
+    assert_eq!(heading.object_id(), "obj-2");
+
+    let resolve = server.requests_for("DOM.resolveNode");
+    assert_eq!(resolve.len(), 1);
+    assert_eq!(resolve[0]["params"]["backendNodeId"], 42);
+}
+
+#[tokio::test]
+async fn element_handles_release_their_object_group_with_the_last_clone() {
+    let server = start_scripted_server(element_server_handler).await;
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+    let session = cdp.session("page-session");
+    let page = Page::new(&session);
+
+    let heading = page.query_selector("h1").await.unwrap().expect("h1");
+    let group = server.requests_for("Runtime.evaluate")[0]["params"]["objectGroup"].clone();
+    assert!(group.as_str().unwrap().starts_with("cdpkit-element-"));
+    // Re-resolving the stale object puts the new one into the same group.
+    heading.text().await.unwrap();
+    assert_eq!(
+        server.requests_for("DOM.resolveNode")[0]["params"]["objectGroup"],
+        group
+    );
+
+    let clone = heading.clone();
+    drop(heading);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(server.requests_for("Runtime.releaseObjectGroup").is_empty());
+
+    drop(clone);
+    let released = server.wait_for("Runtime.releaseObjectGroup", 1).await;
+    assert_eq!(released[0]["params"]["objectGroup"], group);
+}
+
+#[tokio::test]
+async fn element_handle_click_dispatches_at_box_center() {
+    let server = start_scripted_server(element_server_handler).await;
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+    let session = cdp.session("page-session");
+    let page = Page::new(&session);
+
+    let heading = page.query_selector("h1").await.unwrap().expect("h1");
+    let bbox = heading.bounding_box().await.unwrap().expect("box");
+    assert_eq!(
+        (bbox.x, bbox.y, bbox.width, bbox.height),
+        (8.0, 18.0, 104.0, 44.0)
+    );
+
+    heading.click().await.unwrap();
+
+    let mouse = server.requests_for("Input.dispatchMouseEvent");
+    let types: Vec<_> = mouse.iter().map(|m| m["params"]["type"].clone()).collect();
+    assert_eq!(types, vec!["mouseMoved", "mousePressed", "mouseReleased"]);
+    assert_eq!(mouse[1]["params"]["x"], 60.0);
+    assert_eq!(mouse[1]["params"]["y"], 40.0);
+    assert_eq!(mouse[1]["params"]["button"], "left");
+    assert!(server
+        .methods()
+        .contains(&"DOM.scrollIntoViewIfNeeded".to_string()));
+}
+
+#[tokio::test]
+async fn query_selector_returns_none_for_null_result() {
+    let server = start_scripted_server(|method, _| match method {
+        "Runtime.evaluate" => {
+            Ok(json!({"result": {"type": "object", "subtype": "null", "value": null}}))
+        }
+        _ => Ok(json!({})),
+    })
+    .await;
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+    let session = cdp.session("page-session");
+
+    assert!(Page::new(&session)
+        .query_selector("#missing")
+        .await
+        .unwrap()
+        .is_none());
+    assert_eq!(server.methods(), vec!["Runtime.evaluate"]);
+}
+
+#[tokio::test]
+async fn keyboard_types_text_and_tracks_modifiers() {
+    let server = start_scripted_server(|_, _| Ok(json!({}))).await;
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+    let session = cdp.session("page-session");
+    let keyboard = Keyboard::new(&session);
+
+    keyboard.type_text("Hi\n").await.unwrap();
+    let keys = server.requests_for("Input.dispatchKeyEvent");
+    let summary: Vec<_> = keys
+        .iter()
+        .map(|k| {
+            (
+                k["params"]["type"].as_str().unwrap().to_string(),
+                k["params"]["code"].as_str().unwrap().to_string(),
+                k["params"]["text"].as_str().map(str::to_string),
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("keyDown".into(), "KeyH".into(), Some("H".into())),
+            ("keyUp".into(), "KeyH".into(), None),
+            ("keyDown".into(), "KeyI".into(), Some("i".into())),
+            ("keyUp".into(), "KeyI".into(), None),
+            ("keyDown".into(), "Enter".into(), Some("\r".into())),
+            ("keyUp".into(), "Enter".into(), None),
+        ]
+    );
+    assert_eq!(keys[4]["params"]["windowsVirtualKeyCode"], 13);
+
+    keyboard.down("Control").await.unwrap();
+    assert_eq!(keyboard.modifiers(), 2);
+    keyboard.press("a").await.unwrap();
+    let mouse = keyboard.mouse();
+    mouse.click(5.0, 5.0, Default::default()).await.unwrap();
+    keyboard.up("Control").await.unwrap();
+    assert_eq!(keyboard.modifiers(), 0);
+
+    let keys = server.requests_for("Input.dispatchKeyEvent");
+    let ctrl_a = &keys[7]["params"];
+    assert_eq!(ctrl_a["type"], "rawKeyDown");
+    assert_eq!(ctrl_a["modifiers"], 2);
+    assert!(ctrl_a.get("text").is_none());
+    let clicks = server.requests_for("Input.dispatchMouseEvent");
+    assert!(clicks.iter().all(|m| m["params"]["modifiers"] == 2));
+
+    assert!(matches!(
+        keyboard.press("NoSuchKey").await,
+        Err(CdpError::UnknownKey(_))
+    ));
+}
+
+#[tokio::test]
+async fn mouse_interpolates_moves_and_reports_held_buttons() {
+    let server = start_scripted_server(|_, _| Ok(json!({}))).await;
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+    let session = cdp.session("page-session");
+    let mouse = Mouse::new(&session);
+
+    mouse.move_to(10.0, 10.0, 1).await.unwrap();
+    mouse
+        .down(input::types::MouseButton::Left, 1)
+        .await
+        .unwrap();
+    mouse.move_to(30.0, 50.0, 2).await.unwrap();
+    mouse.up(input::types::MouseButton::Left, 1).await.unwrap();
+    mouse.wheel(0.0, 120.0).await.unwrap();
+    assert_eq!(mouse.position(), (30.0, 50.0));
+    assert_eq!(mouse.buttons(), 0);
+
+    let events: Vec<_> = server
+        .requests_for("Input.dispatchMouseEvent")
+        .into_iter()
+        .map(|m| {
+            (
+                m["params"]["type"].as_str().unwrap().to_string(),
+                m["params"]["x"].as_f64().unwrap(),
+                m["params"]["y"].as_f64().unwrap(),
+                m["params"]["buttons"].as_i64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        events,
+        vec![
+            ("mouseMoved".into(), 10.0, 10.0, 0),
+            ("mousePressed".into(), 10.0, 10.0, 1),
+            ("mouseMoved".into(), 20.0, 30.0, 1),
+            ("mouseMoved".into(), 30.0, 50.0, 1),
+            ("mouseReleased".into(), 30.0, 50.0, 0),
+            ("mouseWheel".into(), 30.0, 50.0, 0),
+        ]
+    );
+}
+
+fn request_paused(request_id: &str, url: &str) -> Value {
+    json!({
+        "requestId": request_id,
+        "request": {
+            "url": url,
+            "method": "GET",
+            "headers": {},
+            "initialPriority": "High",
+            "referrerPolicy": "no-referrer"
+        },
+        "frameId": "frame-1",
+        "resourceType": "Image"
+    })
+}
+
+#[tokio::test]
+async fn interceptor_fulfills_and_continues_dropped_requests() {
+    let server = start_scripted_server(|_, _| Ok(json!({}))).await;
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+    let session = cdp.owned_session("page-session");
+
+    let options =
+        InterceptorOptions::new().with_url_pattern("*.png", fetch::types::RequestStage::Request);
+    let mut interceptor = RequestInterceptor::enable(&session, options).await.unwrap();
+    let enable = server.requests_for("Fetch.enable");
+    assert_eq!(enable[0]["params"]["patterns"][0]["urlPattern"], "*.png");
+    assert_eq!(
+        enable[0]["params"]["patterns"][0]["requestStage"],
+        "Request"
+    );
+
+    server.emit(
+        "page-session",
+        "Fetch.requestPaused",
+        request_paused("r-1", "https://a.test/x.png"),
+    );
+    let request = interceptor.next().await.unwrap();
+    assert_eq!(request.request().url, "https://a.test/x.png");
+    assert!(!request.is_response_stage());
+    request
+        .fulfill(200, &[("Content-Type", "image/png")], b"png")
+        .await
+        .unwrap();
+    let fulfill = server.requests_for("Fetch.fulfillRequest");
+    assert_eq!(fulfill[0]["params"]["requestId"], "r-1");
+    assert_eq!(fulfill[0]["params"]["body"], "cG5n");
+    assert_eq!(
+        fulfill[0]["params"]["responseHeaders"][0]["name"],
+        "Content-Type"
+    );
+
+    server.emit(
+        "page-session",
+        "Fetch.requestPaused",
+        request_paused("r-2", "https://a.test/y.png"),
+    );
+    drop(interceptor.next().await.unwrap());
+    let continued = server.wait_for("Fetch.continueRequest", 1).await;
+    assert_eq!(continued[0]["params"]["requestId"], "r-2");
+
+    server.emit(
+        "page-session",
+        "Fetch.requestPaused",
+        request_paused("r-3", "https://a.test/z.png"),
+    );
+    let request = interceptor.next().await.unwrap();
+    let handler = tokio::spawn(async move {
+        let _owned = request;
+        panic!("handler failed");
+    });
+    assert!(handler.await.unwrap_err().is_panic());
+    let continued = server.wait_for("Fetch.continueRequest", 2).await;
+    assert_eq!(continued[1]["params"]["requestId"], "r-3");
+
+    server.emit(
+        "page-session",
+        "Fetch.requestPaused",
+        request_paused("r-4", "https://a.test/w.png"),
+    );
+    interceptor
+        .next()
+        .await
+        .unwrap()
+        .continue_with(ContinueOverrides {
+            headers: Some(vec![("X-Test".into(), "1".into())]),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    let continued = server.wait_for("Fetch.continueRequest", 3).await;
+    assert_eq!(continued[2]["params"]["headers"][0]["name"], "X-Test");
+
+    drop(interceptor);
+    server.wait_for("Fetch.disable", 1).await;
+    assert_eq!(server.requests_for("Fetch.continueRequest").len(), 3);
+}
+
+#[tokio::test]
+async fn interceptor_answers_auth_challenges() {
+    let server = start_scripted_server(|_, _| Ok(json!({}))).await;
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+    let session = cdp.owned_session("page-session");
+
+    let options = InterceptorOptions::new().with_credentials(|event| {
+        (event.auth_challenge.realm == "staging").then(|| Credentials::new("user", "secret"))
+    });
+    let interceptor = RequestInterceptor::enable(&session, options).await.unwrap();
+    assert_eq!(
+        server.requests_for("Fetch.enable")[0]["params"]["handleAuthRequests"],
+        true
+    );
+
+    let mut challenge = request_paused("r-1", "https://a.test/");
+    challenge["authChallenge"] =
+        json!({"origin": "https://a.test", "scheme": "basic", "realm": "staging"});
+    server.emit("page-session", "Fetch.authRequired", challenge.clone());
+    challenge["requestId"] = json!("r-2");
+    challenge["authChallenge"]["realm"] = json!("prod");
+    server.emit("page-session", "Fetch.authRequired", challenge);
+
+    let answers = server.wait_for("Fetch.continueWithAuth", 2).await;
+    let provided = &answers[0]["params"];
+    assert_eq!(provided["requestId"], "r-1");
+    assert_eq!(
+        provided["authChallengeResponse"]["response"],
+        "ProvideCredentials"
+    );
+    assert_eq!(provided["authChallengeResponse"]["username"], "user");
+    assert_eq!(
+        answers[1]["params"]["authChallengeResponse"]["response"],
+        "CancelAuth"
+    );
+
+    interceptor.disable().await.unwrap();
+}
+
+#[tokio::test]
+async fn interceptor_cancels_auth_when_the_credentials_callback_panics() {
+    let server = start_scripted_server(|_, _| Ok(json!({}))).await;
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+    let session = cdp.owned_session("page-session");
+
+    let options = InterceptorOptions::new().with_credentials(|event| {
+        assert_ne!(event.auth_challenge.realm, "broken", "callback bug");
+        Some(Credentials::new("user", "secret"))
+    });
+    let interceptor = RequestInterceptor::enable(&session, options).await.unwrap();
+
+    let mut challenge = request_paused("r-1", "https://a.test/");
+    challenge["authChallenge"] =
+        json!({"origin": "https://a.test", "scheme": "basic", "realm": "broken"});
+    server.emit("page-session", "Fetch.authRequired", challenge.clone());
+    challenge["requestId"] = json!("r-2");
+    challenge["authChallenge"]["realm"] = json!("staging");
+    server.emit("page-session", "Fetch.authRequired", challenge);
+
+    let answers = server.wait_for("Fetch.continueWithAuth", 2).await;
+    assert_eq!(answers[0]["params"]["requestId"], "r-1");
+    assert_eq!(
+        answers[0]["params"]["authChallengeResponse"]["response"],
+        "CancelAuth"
+    );
+    assert_eq!(answers[1]["params"]["requestId"], "r-2");
+    assert_eq!(
+        answers[1]["params"]["authChallengeResponse"]["response"],
+        "ProvideCredentials"
+    );
+
+    interceptor.disable().await.unwrap();
+}
+
+fn network_request(url: &str) -> Value {
+    json!({
+        "url": url,
+        "method": "GET",
+        "headers": {"Accept": "*/*"},
+        "initialPriority": "High",
+        "referrerPolicy": "no-referrer"
+    })
+}
+
+fn network_response(url: &str, status: i64, headers: Value) -> Value {
+    json!({
+        "url": url,
+        "status": status,
+        "statusText": if status == 200 { "OK" } else { "Found" },
+        "headers": headers,
+        "mimeType": "text/html",
+        "charset": "utf-8",
+        "connectionReused": false,
+        "connectionId": 7.0,
+        "remoteIPAddress": "127.0.0.1",
+        "encodedDataLength": 120.0,
+        "protocol": "http/1.1",
+        "securityState": "neutral"
+    })
+}
+
+#[tokio::test]
+async fn har_recorder_builds_entries_for_redirects_and_failures() {
+    let server = start_scripted_server(|method, _| match method {
+        "Network.getResponseBody" => Ok(json!({"body": "hello", "base64Encoded": false})),
+        _ => Ok(json!({})),
+    })
+    .await;
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+    let session = cdp.owned_session("page-session");
+
+    let recorder = HarRecorder::start(&session, HarOptions::new().with_content(true))
+        .await
+        .unwrap();
+    assert_eq!(server.requests_for("Network.enable").len(), 1);
+
+    let initiator = json!({"type": "other"});
+    server.emit(
+        "page-session",
+        "Network.requestWillBeSent",
+        json!({
+            "requestId": "r-1", "loaderId": "l-1", "documentURL": "https://a.test/old?q=1",
+            "request": network_request("https://a.test/old?q=1"),
+            "timestamp": 10.0, "wallTime": 1_792_329_845.0, "initiator": initiator,
+            "redirectHasExtraInfo": false, "type": "Document"
+        }),
+    );
+    server.emit(
+        "page-session",
+        "Network.requestWillBeSent",
+        json!({
+            "requestId": "r-1", "loaderId": "l-1", "documentURL": "https://a.test/new",
+            "request": network_request("https://a.test/new"),
+            "timestamp": 10.1, "wallTime": 1_792_329_845.1, "initiator": initiator,
+            "redirectHasExtraInfo": false, "type": "Document",
+            "redirectResponse": network_response(
+                "https://a.test/old?q=1", 302, json!({"Location": "/new"})
+            )
+        }),
+    );
+    server.emit(
+        "page-session",
+        "Network.requestWillBeSent",
+        json!({
+            "requestId": "r-2", "loaderId": "l-1", "documentURL": "https://a.test/new",
+            "request": network_request("https://b.test/blocked.js"),
+            "timestamp": 10.15, "wallTime": 1_792_329_845.15, "initiator": initiator,
+            "redirectHasExtraInfo": false, "type": "Script"
+        }),
+    );
+    server.emit(
+        "page-session",
+        "Network.loadingFailed",
+        json!({
+            "requestId": "r-2", "timestamp": 10.2, "type": "Script",
+            "errorText": "net::ERR_BLOCKED_BY_CLIENT"
+        }),
+    );
+    server.emit(
+        "page-session",
+        "Network.responseReceived",
+        json!({
+            "requestId": "r-1", "loaderId": "l-1", "timestamp": 10.3, "type": "Document",
+            "response": network_response(
+                "https://a.test/new", 200, json!({"Set-Cookie": "sid=abc; Path=/\nlang=en"})
+            ),
+            "hasExtraInfo": false
+        }),
+    );
+    server.emit(
+        "page-session",
+        "Network.dataReceived",
+        json!({"requestId": "r-1", "timestamp": 10.35, "dataLength": 5, "encodedDataLength": 5}),
+    );
+    server.emit(
+        "page-session",
+        "Network.loadingFinished",
+        json!({"requestId": "r-1", "timestamp": 10.4, "encodedDataLength": 125.0}),
+    );
+    let body_requests = server.wait_for("Network.getResponseBody", 1).await;
+    assert_eq!(body_requests[0]["params"]["requestId"], "r-1");
+
+    let har = recorder.stop().await.unwrap();
+    assert_eq!(har.log.version, "1.2");
+    let entries = &har.log.entries;
+    assert_eq!(entries.len(), 3);
+
+    let redirect = &entries[0];
+    assert_eq!(redirect.started_date_time, "2026-10-18T13:24:05.000Z");
+    assert_eq!(redirect.response.status, 302);
+    assert_eq!(redirect.response.redirect_url, "https://a.test/new");
+    assert_eq!(redirect.request.query_string[0].value, "1");
+    assert_eq!(redirect.request.http_version, "HTTP/1.1");
+
+    let document = &entries[1];
+    assert_eq!(document.request.url, "https://a.test/new");
+    assert_eq!(document.response.status, 200);
+    assert_eq!(document.response.content.text.as_deref(), Some("hello"));
+    assert_eq!(document.response.content.size, 5);
+    assert_eq!(document.response.transfer_size, Some(125));
+    assert_eq!(document.response.cookies.len(), 2);
+    assert_eq!(document.server_ip_address.as_deref(), Some("127.0.0.1"));
+    assert!((document.time - 300.0).abs() < 1e-6);
+
+    let failed = &entries[2];
+    assert_eq!(failed.response.status, 0);
+    assert_eq!(failed.error.as_deref(), Some("net::ERR_BLOCKED_BY_CLIENT"));
+    assert_eq!(failed.resource_type.as_deref(), Some("Script"));
+
+    let json: Value = serde_json::from_str(&har.to_json().unwrap()).unwrap();
+    assert_eq!(
+        json["log"]["entries"][0]["response"]["redirectURL"],
+        "https://a.test/new"
+    );
+    assert_eq!(json["log"]["creator"]["name"], "cdpkit");
+}
+
+#[tokio::test]
+async fn network_tracker_waits_for_idle() {
+    let server = start_scripted_server(|_, _| Ok(json!({}))).await;
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+    let session = cdp.owned_session("page-session");
+
+    let tracker = NetworkTracker::start(&session).await.unwrap();
+    let mut snapshots = tracker.snapshots();
+    assert_eq!(snapshots.next().await.unwrap().inflight_count(), 0);
+
+    server.emit(
+        "page-session",
+        "Network.requestWillBeSent",
+        json!({"requestId": "r-1", "request": {"url": "https://a.test/slow"}, "type": "XHR"}),
+    );
+    let busy = snapshots.next().await.unwrap();
+    assert_eq!(busy.inflight[0].url, "https://a.test/slow");
+
+    let err = tracker
+        .wait_for_idle(Duration::from_millis(20), 0, Duration::from_millis(100))
+        .await
+        .unwrap_err();
+    assert!(err.is_timeout());
+    tracker
+        .wait_for_idle(Duration::from_millis(20), 1, Duration::from_secs(1))
+        .await
+        .unwrap();
+
+    let (idle, _) = tokio::join!(
+        tracker.wait_for_idle(Duration::from_millis(50), 0, Duration::from_secs(2)),
+        async {
+            tokio::time::sleep(Duration::from_millis(30)).await;
+            server.emit(
+                "page-session",
+                "Network.loadingFinished",
+                json!({"requestId": "r-1", "timestamp": 1.0, "encodedDataLength": 0.0}),
+            );
+        }
+    );
+    idle.unwrap();
+    let snapshot = tracker.snapshot();
+    assert_eq!(snapshot.inflight_count(), 0);
+    assert_eq!(snapshot.finished_requests, 1);
+}
+
+fn io_read_handler() -> impl Fn(&str, &Value) -> Result<Value, String> + Send + Sync + 'static {
+    let reads = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    move |method, _| match method {
+        "IO.read" => match reads.fetch_add(1, std::sync::atomic::Ordering::SeqCst) {
+            0 => Ok(json!({"data": "aGVsbG8g", "base64Encoded": true, "eof": false})),
+            1 => Ok(json!({"data": "", "eof": false})),
+            _ => Ok(json!({"data": "world", "eof": true})),
+        },
+        _ => Ok(json!({})),
+    }
+}
+
+#[tokio::test]
+async fn read_stream_decodes_chunks_until_eof() {
+    let server = start_scripted_server(io_read_handler()).await;
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+    let session = cdp.owned_session("page-session");
+
+    let stream = CdpReadStream::new(session, "stream-1").with_chunk_size(6);
+    let bytes = stream.into_bytes().await.unwrap();
+    assert_eq!(bytes, b"hello world");
+
+    let reads = server.requests_for("IO.read");
+    assert_eq!(reads.len(), 3);
+    assert_eq!(reads[0]["params"]["handle"], "stream-1");
+    assert_eq!(reads[0]["params"]["size"], 6);
+    assert_eq!(server.requests_for("IO.close").len(), 1);
+}
+
+#[tokio::test]
+async fn read_stream_closes_handle_on_drop() {
+    let server = start_scripted_server(io_read_handler()).await;
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+
+    let mut stream = CdpReadStream::new(cdp.clone(), "stream-2");
+    let mut first = [0u8; 3];
+    tokio::io::AsyncReadExt::read_exact(&mut stream, &mut first)
+        .await
+        .unwrap();
+    assert_eq!(&first, b"hel");
+    assert!(!stream.is_eof());
+    drop(stream);
+
+    let close = server.wait_for("IO.close", 1).await;
+    assert_eq!(close[0]["params"]["handle"], "stream-2");
+    assert!(close[0].get("sessionId").is_none());
+}
+
+fn layout_metrics() -> Value {
+    let layout = json!({"pageX": 0, "pageY": 0, "clientWidth": 800, "clientHeight": 600});
+    let visual = json!({
+        "offsetX": 0.0, "offsetY": 0.0, "pageX": 0.0, "pageY": 250.0,
+        "clientWidth": 800.0, "clientHeight": 600.0, "scale": 1.0
+    });
+    let content = json!({"x": 0.0, "y": 0.0, "width": 800.0, "height": 2400.5});
+    json!({
+        "layoutViewport": layout, "visualViewport": visual, "contentSize": content,
+        "cssLayoutViewport": layout, "cssVisualViewport": visual, "cssContentSize": content
+    })
+}
+
+#[tokio::test]
+async fn full_page_screenshot_overrides_and_restores_metrics() {
+    let server = start_scripted_server(|method, _| match method {
+        "Page.getLayoutMetrics" => Ok(layout_metrics()),
+        "Page.captureScreenshot" => Ok(json!({"data": "aW1n"})),
+        _ => Ok(json!({})),
+    })
+    .await;
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+    let session = cdp.session("page-session");
+
+    let image = Page::new(&session)
+        .screenshot(ScreenshotOptions {
+            format: ScreenshotFormat::Jpeg,
+            quality: Some(80),
+            full_page: true,
+            omit_background: true,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(image, b"img");
+    assert_eq!(
+        server.methods(),
+        vec![
+            "Emulation.setDefaultBackgroundColorOverride",
+            "Page.getLayoutMetrics",
+            "Emulation.setDeviceMetricsOverride",
+            "Page.captureScreenshot",
+            "Emulation.clearDeviceMetricsOverride",
+            "Emulation.setDefaultBackgroundColorOverride",
+        ]
+    );
+    let metrics = &server.requests_for("Emulation.setDeviceMetricsOverride")[0]["params"];
+    assert_eq!(
+        (metrics["width"].clone(), metrics["height"].clone()),
+        (json!(800), json!(2401))
+    );
+    let capture = &server.requests_for("Page.captureScreenshot")[0]["params"];
+    assert_eq!(capture["format"], "jpeg");
+    assert_eq!(capture["quality"], 80);
+    assert_eq!(
+        capture["clip"],
+        json!({"x": 0.0, "y": 0.0, "width": 800.0, "height": 2401.0, "scale": 1.0})
+    );
+    assert_eq!(capture["captureBeyondViewport"], true);
+    let restore = &server.requests_for("Emulation.setDefaultBackgroundColorOverride")[1];
+    assert!(restore["params"].get("color").is_none());
+}
+
+#[tokio::test]
+async fn full_page_screenshot_restores_stacked_device_metrics() {
+    let server = start_scripted_server(|method, _| match method {
+        "Page.getLayoutMetrics" => Ok(layout_metrics()),
+        "Page.captureScreenshot" => Ok(json!({"data": "aW1n"})),
+        _ => Ok(json!({})),
+    })
+    .await;
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+    let session = cdp.owned_session("page-session");
+    let overrides = EmulationOverrides::new(&session);
+    let phone = overrides
+        .device_metrics(DeviceMetrics {
+            width: 390,
+            height: 844,
+            device_scale_factor: 3.0,
+            mobile: true,
+        })
+        .await
+        .unwrap();
+
+    Page::new(&session)
+        .screenshot(ScreenshotOptions {
+            full_page: true,
+            emulation: Some(overrides.clone()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        server.methods(),
+        vec![
+            "Emulation.setDeviceMetricsOverride",
+            "Page.getLayoutMetrics",
+            "Emulation.setDeviceMetricsOverride",
+            "Page.captureScreenshot",
+            "Emulation.setDeviceMetricsOverride",
+        ]
+    );
+    let params: Vec<Value> = server
+        .requests_for("Emulation.setDeviceMetricsOverride")
+        .iter()
+        .map(|r| {
+            let p = &r["params"];
+            json!([p["width"], p["height"], p["deviceScaleFactor"], p["mobile"]])
+        })
+        .collect();
+    assert_eq!(
+        params,
+        vec![
+            json!([390, 844, 3.0, true]),
+            json!([800, 2401, 3.0, true]),
+            json!([390, 844, 3.0, true]),
+        ]
+    );
+
+    phone.restore().await.unwrap();
+    assert_eq!(
+        server.methods().last().unwrap(),
+        "Emulation.clearDeviceMetricsOverride"
+    );
+}
+
+#[tokio::test]
+async fn element_screenshot_clips_to_document_coordinates() {
+    let server = start_scripted_server(|method, params| match method {
+        "Page.getLayoutMetrics" => Ok(layout_metrics()),
+        "Page.captureScreenshot" => Ok(json!({"data": "aW1n"})),
+        _ => element_server_handler(method, params),
+    })
+    .await;
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+    let session = cdp.session("page-session");
+
+    let heading = Page::new(&session)
+        .query_selector("h1")
+        .await
+        .unwrap()
+        .unwrap();
+    heading
+        .screenshot(ScreenshotOptions::default())
+        .await
+        .unwrap();
+    let clip = &server.requests_for("Page.captureScreenshot")[0]["params"]["clip"];
+    assert_eq!(
+        clip,
+        &json!({"x": 8.0, "y": 268.0, "width": 104.0, "height": 44.0, "scale": 1.0})
+    );
+    assert!(server
+        .requests_for("Emulation.setDeviceMetricsOverride")
+        .is_empty());
+}
+
+#[tokio::test]
+async fn pdf_streams_chunks_and_closes_handle() {
+    let reads = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let server = start_scripted_server(move |method, _| match method {
+        "Page.printToPDF" => Ok(json!({"data": "", "stream": "pdf-1"})),
+        "IO.read" => match reads.fetch_add(1, std::sync::atomic::Ordering::SeqCst) {
+            0 => Ok(json!({"data": "JVBERi0=", "base64Encoded": true, "eof": false})),
+            _ => Ok(json!({"data": "JUVPRg==", "base64Encoded": true, "eof": true})),
+        },
+        _ => Ok(json!({})),
+    })
+    .await;
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+    let session = cdp.session("page-session");
+
+    let pdf = Page::new(&session)
+        .pdf(PdfOptions {
+            landscape: Some(true),
+            page_ranges: Some("1-2".into()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(pdf, b"%PDF-%EOF");
+    let print = &server.requests_for("Page.printToPDF")[0]["params"];
+    assert_eq!(print["transferMode"], "ReturnAsStream");
+    assert_eq!(print["landscape"], true);
+    assert_eq!(print["pageRanges"], "1-2");
+    assert!(print.get("scale").is_none());
+    assert_eq!(
+        server.requests_for("IO.close")[0]["params"]["handle"],
+        "pdf-1"
+    );
+}
+
+fn screencast_frame(ack_id: i64, data: &str) -> Value {
+    json!({
+        "data": data,
+        "sessionId": ack_id,
+        "metadata": {
+            "offsetTop": 0.0, "pageScaleFactor": 1.0, "deviceWidth": 640.0,
+            "deviceHeight": 480.0, "scrollOffsetX": 0.0, "scrollOffsetY": 12.0,
+            "timestamp": 1_792_329_845.0
+        }
+    })
+}
+
+#[tokio::test]
+async fn screencast_acks_frames_and_saves_numbered_files() {
+    let server = start_scripted_server(|_, _| Ok(json!({}))).await;
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+    let session = cdp.owned_session("page-session");
+
+    let mut screencast = Screencast::start(
+        &session,
+        ScreencastOptions {
+            quality: Some(60),
+            max_width: Some(640),
+            max_height: Some(480),
+            every_nth_frame: Some(3),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    let start = &server.requests_for("Page.startScreencast")[0]["params"];
+    assert_eq!(
+        start,
+        &json!({"format": "jpeg", "quality": 60, "maxWidth": 640, "maxHeight": 480, "everyNthFrame": 3})
+    );
+
+    server.emit(
+        "page-session",
+        "Page.screencastFrame",
+        screencast_frame(11, "ZnJhbWUw"),
+    );
+    let first = screencast.next().await.unwrap();
+    assert_eq!(
+        (first.index, first.data.as_slice()),
+        (0, b"frame0".as_slice())
+    );
+    assert_eq!(first.metadata.scroll_offset_y, 12.0);
+    assert_eq!(
+        server.wait_for("Page.screencastFrameAck", 1).await[0]["params"]["sessionId"],
+        11
+    );
+
+    let dir = std::env::temp_dir().join(format!("cdpkit-screencast-{}", std::process::id()));
+    server.emit(
+        "page-session",
+        "Page.screencastFrame",
+        screencast_frame(12, "ZnJhbWUx"),
+    );
+    server.emit(
+        "page-session",
+        "Page.screencastFrame",
+        screencast_frame(13, "ZnJhbWUy"),
+    );
+    let written = screencast.save_frames(&dir, 2).await.unwrap();
+    assert_eq!(
+        written,
+        vec![dir.join("frame-00001.jpg"), dir.join("frame-00002.jpg")]
+    );
+    assert_eq!(std::fs::read(&written[1]).unwrap(), b"frame2");
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(server.wait_for("Page.screencastFrameAck", 3).await.len(), 3);
+
+    drop(screencast);
+    server.wait_for("Page.stopScreencast", 1).await;
+}
+
+#[tokio::test]
+async fn console_collector_merges_console_exceptions_and_log() {
+    let server = start_scripted_server(|_, _| Ok(json!({}))).await;
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+    let session = cdp.owned_session("page-session");
+
+    let options = ConsoleOptions::new().with_fail_on_uncaught_exception(true);
+    let mut console = ConsoleCollector::start(&session, options).await.unwrap();
+    let methods = server.methods();
+    assert!(methods.contains(&"Runtime.enable".to_string()));
+    assert!(methods.contains(&"Log.enable".to_string()));
+
+    server.emit(
+        "page-session",
+        "Runtime.consoleAPICalled",
+        json!({
+            "type": "warning",
+            "args": [
+                {"type": "string", "value": "count=%d"},
+                {"type": "number", "value": 2, "description": "2"},
+                {"type": "object", "subtype": "array", "description": "Array(1)",
+                 "preview": {"type": "object", "subtype": "array", "description": "Array(1)",
+                             "overflow": false,
+                             "properties": [{"name": "0", "type": "string", "value": "x"}]}}
+            ],
+            "executionContextId": 1,
+            "timestamp": 1000.0,
+            "stackTrace": {"callFrames": [{"functionName": "f", "scriptId": "1",
+                "url": "https://a.test/app.js", "lineNumber": 9, "columnNumber": 4}]}
+        }),
+    );
+    server.emit(
+        "page-session",
+        "Runtime.exceptionThrown",
+        json!({
+            "timestamp": 1001.0,
+            "exceptionDetails": {
+                "exceptionId": 1, "text": "Uncaught", "lineNumber": 2, "columnNumber": 7,
+                "url": "https://a.test/app.js",
+                "exception": {"type": "object", "subtype": "error",
+                              "description": "TypeError: nope\n    at app.js:3:8"}
+            }
+        }),
+    );
+    server.emit(
+        "page-session",
+        "Log.entryAdded",
+        json!({"entry": {"source": "network", "level": "error", "timestamp": 1002.0,
+                         "text": "Failed to load resource", "url": "https://a.test/missing.png"}}),
+    );
+
+    let warning = console.next().await.unwrap().unwrap();
+    assert_eq!(warning.source, ConsoleSource::ConsoleApi);
+    assert_eq!(warning.level, ConsoleLevel::Warning);
+    assert_eq!(warning.text, "count=2 (1) ['x']");
+    assert_eq!(
+        warning.to_string(),
+        "[warning] count=2 (1) ['x'] (https://a.test/app.js:10:5)"
+    );
+
+    let err = console.next().await.unwrap().unwrap_err();
+    assert!(
+        matches!(err, CdpError::JavaScriptException(ref text) if text == "Uncaught TypeError: nope")
+    );
+
+    let log = console.next().await.unwrap().unwrap();
+    assert_eq!(log.source, ConsoleSource::Log);
+    assert_eq!(log.kind, "network");
+    assert_eq!(log.level, ConsoleLevel::Error);
+
+    assert_eq!(console.messages().len(), 3);
+    assert_eq!(console.errors().len(), 2);
+    assert!(console.assert_no_uncaught_exceptions().is_err());
+    console.clear();
+    console.assert_no_uncaught_exceptions().unwrap();
+}
+
+#[tokio::test]
+async fn dialog_handler_answers_dialogs_by_policy() {
+    let server = start_scripted_server(|_, _| Ok(json!({}))).await;
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+    let session = cdp.owned_session("page-session");
+
+    let dialogs = DialogHandler::start(&session, DialogPolicy::PromptText("Ada".to_string()))
+        .await
+        .unwrap();
+    assert!(server.methods().contains(&"Page.enable".to_string()));
+
+    let opening = |type_: &str, message: &str| {
+        json!({"url": "https://a.test/", "frameId": "F", "message": message, "type": type_,
+               "hasBrowserHandler": false, "defaultPrompt": ""})
+    };
+    let (handled, _) = tokio::join!(dialogs.wait_for_dialog(Duration::from_secs(2)), async {
+        server.emit(
+            "page-session",
+            "Page.javascriptDialogOpening",
+            opening("prompt", "Name?"),
+        )
+    });
+    let handled = handled.unwrap();
+    assert_eq!(handled.message(), "Name?");
+    assert_eq!(handled.action, DialogAction::AcceptWith("Ada".to_string()));
+    assert!(handled.error.is_none());
+
+    dialogs.set_policy(DialogPolicy::Dismiss);
+    server.emit(
+        "page-session",
+        "Page.javascriptDialogOpening",
+        opening("confirm", "Sure?"),
+    );
+    let requests = server.wait_for("Page.handleJavaScriptDialog", 2).await;
+    assert_eq!(
+        requests[0]["params"],
+        json!({"accept": true, "promptText": "Ada"})
+    );
+    assert_eq!(requests[1]["params"], json!({"accept": false}));
+
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    let handled = dialogs.handled();
+    assert_eq!(handled.len(), 2);
+    assert_eq!(handled[1].action, DialogAction::Dismiss);
+
+    let err = dialogs
+        .wait_for_dialog(Duration::from_millis(50))
+        .await
+        .unwrap_err();
+    assert!(err.is_timeout());
+}
+
+#[tokio::test]
+async fn storage_state_captures_and_applies_cookies_and_web_storage() {
+    let frame = |id: &str, origin: &str| {
+        json!({"id": id, "loaderId": "L", "url": format!("{origin}/"), "domainAndRegistry": "",
+               "securityOrigin": origin, "mimeType": "text/html",
+               "secureContextType": "Secure", "crossOriginIsolatedContextType": "NotIsolated",
+               "gatedAPIFeatures": []})
+    };
+    let tree = json!({"frameTree": {
+        "frame": frame("main", "https://a.test"),
+        "childFrames": [{"frame": frame("child", "https://b.test")}]
+    }});
+    let server = start_scripted_server(move |method, params| match method {
+        "Page.getFrameTree" => Ok(tree.clone()),
+        "Storage.getCookies" => Ok(json!({"cookies": [{
+            "name": "sid", "value": "abc", "domain": "a.test", "path": "/", "expires": 1900000000.5,
+            "size": 6, "httpOnly": true, "secure": true, "session": false,
+            "priority": "Medium", "sourceScheme": "Secure", "sourcePort": 443
+        }]})),
+        "DOMStorage.getDOMStorageItems" => {
+            let id = &params["storageId"];
+            match (
+                id["securityOrigin"].as_str(),
+                id["isLocalStorage"].as_bool(),
+            ) {
+                (Some("https://a.test"), Some(true)) => Ok(json!({"entries": [["theme", "dark"]]})),
+                (Some("https://a.test"), Some(false)) => Ok(json!({"entries": [["tab", "2"]]})),
+                _ => Ok(json!({"entries": []})),
+            }
+        }
+        _ => Ok(json!({})),
+    })
+    .await;
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+    let session = cdp.owned_session("page-session");
+
+    let state = StorageState::capture(&session).await.unwrap();
+    assert_eq!(state.origins.len(), 1, "empty origins are skipped");
+    let json: Value = serde_json::from_str(&state.to_json().unwrap()).unwrap();
+    assert_eq!(
+        json,
+        json!({
+            "cookies": [{"name": "sid", "value": "abc", "domain": "a.test", "path": "/",
+                         "expires": 1900000000.5, "httpOnly": true, "secure": true, "sameSite": "Lax"}],
+            "origins": [{"origin": "https://a.test",
+                         "localStorage": [{"name": "theme", "value": "dark"}],
+                         "sessionStorage": [{"name": "tab", "value": "2"}]}]
+        })
+    );
+
+    let path = std::env::temp_dir().join(format!("cdpkit-state-{}.json", std::process::id()));
+    state.save(&path).await.unwrap();
+    let loaded = StorageState::load(&path).await.unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded, state);
+
+    loaded.apply(&session).await.unwrap();
+    let set_cookies = server.requests_for("Storage.setCookies");
+    assert_eq!(
+        set_cookies[0]["params"]["cookies"][0],
+        json!({"name": "sid", "value": "abc", "domain": "a.test", "path": "/", "secure": true,
+               "httpOnly": true, "sameSite": "Lax", "expires": 1900000000.5})
+    );
+    let items = server.requests_for("DOMStorage.setDOMStorageItem");
+    assert_eq!(items.len(), 2);
+    assert_eq!(
+        items[1]["params"],
+        json!({"storageId": {"securityOrigin": "https://a.test", "isLocalStorage": false},
+               "key": "tab", "value": "2"})
+    );
+}
+
+#[tokio::test]
+async fn storage_state_loads_origins_the_page_has_not_visited() {
+    let emitter: Arc<Mutex<Option<mpsc::UnboundedSender<Value>>>> = Arc::default();
+    let server = start_scripted_server({
+        let emitter = Arc::clone(&emitter);
+        move |method, params| match method {
+            "Page.getFrameTree" => Ok(json!({"frameTree": {"frame": {
+                "id": "main", "loaderId": "L", "url": "about:blank", "domainAndRegistry": "",
+                "securityOrigin": "://", "mimeType": "text/html", "secureContextType": "Secure",
+                "crossOriginIsolatedContextType": "NotIsolated", "gatedAPIFeatures": []
+            }}})),
+            "Page.navigate" => {
+                let url = params["url"].as_str().unwrap();
+                if url != "about:blank" {
+                    let event = json!({"method": "Fetch.requestPaused", "sessionId": "page-session",
+                                       "params": request_paused("r-1", url)});
+                    emitter
+                        .lock()
+                        .unwrap()
+                        .as_ref()
+                        .unwrap()
+                        .send(event)
+                        .unwrap();
+                }
+                Ok(json!({"frameId": "main"}))
+            }
+            _ => Ok(json!({})),
+        }
+    })
+    .await;
+    *emitter.lock().unwrap() = Some(server.events.clone());
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+    let session = cdp.owned_session("page-session");
+
+    let state = StorageState::from_json(
+        r#"{"cookies": [], "origins": [{"origin": "https://b.test",
+            "localStorage": [{"name": "token", "value": "t"}]}]}"#,
+    )
+    .unwrap();
+    state.apply(&session).await.unwrap();
+    assert_eq!(
+        server.methods(),
+        [
+            "Page.getFrameTree",
+            "Fetch.enable",
+            "Page.navigate",
+            "Fetch.fulfillRequest",
+            "DOMStorage.setDOMStorageItem",
+            "Fetch.disable",
+            "Page.navigate",
+        ]
+    );
+    let navigations: Vec<Value> = server
+        .requests_for("Page.navigate")
+        .iter()
+        .map(|request| request["params"]["url"].clone())
+        .collect();
+    assert_eq!(
+        navigations,
+        [json!("https://b.test/"), json!("about:blank")]
+    );
+    assert_eq!(
+        server.requests_for("Fetch.fulfillRequest")[0]["params"]["requestId"],
+        "r-1"
+    );
+}
+
+#[tokio::test]
+async fn device_apply_and_reset_issue_emulation_overrides_in_order() {
+    let server = start_scripted_server(|_, _| Ok(json!({}))).await;
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+    let session = cdp.owned_session("page-session");
+
+    let mut device = Device::named("iPhone 13").unwrap().landscape();
+    device.color_scheme = Some("dark".to_string());
+    device.apply(&session).await.unwrap();
+    Device::reset(&session).await.unwrap();
+
+    assert_eq!(
+        server.methods(),
+        [
+            "Emulation.setDeviceMetricsOverride",
+            "Emulation.setTouchEmulationEnabled",
+            "Emulation.setUserAgentOverride",
+            "Emulation.setEmulatedMedia",
+            "Emulation.clearDeviceMetricsOverride",
+            "Emulation.setTouchEmulationEnabled",
+            "Emulation.setUserAgentOverride",
+            "Emulation.setEmulatedMedia",
+        ]
+    );
+    let metrics = &server.requests_for("Emulation.setDeviceMetricsOverride")[0]["params"];
+    assert_eq!(
+        *metrics,
+        json!({"width": 664, "height": 390, "deviceScaleFactor": 3.0, "mobile": true,
+               "screenWidth": 844, "screenHeight": 390,
+               "screenOrientation": {"type": "landscapePrimary", "angle": 90}})
+    );
+    let touch = server.requests_for("Emulation.setTouchEmulationEnabled");
+    assert_eq!(
+        touch[0]["params"],
+        json!({"enabled": true, "maxTouchPoints": 5})
+    );
+    assert_eq!(touch[1]["params"], json!({"enabled": false}));
+    let media = server.requests_for("Emulation.setEmulatedMedia");
+    assert_eq!(
+        media[0]["params"]["features"],
+        json!([{"name": "prefers-color-scheme", "value": "dark"}])
+    );
+}
+
+#[tokio::test]
+async fn downloads_track_progress_completion_and_cancel() {
+    let server = start_scripted_server(|_, _| Ok(json!({}))).await;
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+    let dir = std::env::temp_dir().join(format!("cdpkit-downloads-{}", std::process::id()));
+
+    let options = DownloadOptions::new(&dir).with_browser_context_id("ctx-1");
+    let mut downloads = Downloads::start(&cdp, options).await.unwrap();
+    let behavior = &server.requests_for("Browser.setDownloadBehavior")[0]["params"];
+    assert_eq!(behavior["behavior"], "allowAndName");
+    assert_eq!(behavior["eventsEnabled"], true);
+    assert_eq!(behavior["browserContextId"], "ctx-1");
+    assert_eq!(behavior["downloadPath"], dir.to_string_lossy().as_ref());
+
+    let will_begin = |guid: &str, name: &str| {
+        json!({"frameId": "F", "guid": guid, "url": format!("https://a.test/{name}"),
+               "suggestedFilename": name})
+    };
+    let progress = |guid: &str, received: f64, state: &str| json!({"guid": guid, "totalBytes": 4.0, "receivedBytes": received, "state": state});
+    server.emit_browser("Browser.downloadWillBegin", will_begin("g-1", "report.csv"));
+    let report = downloads.next().await.unwrap();
+    assert_eq!(report.suggested_filename(), "report.csv");
+    assert_eq!(report.path(), dir.join("g-1"));
+
+    let mut updates = report.progress();
+    assert_eq!(updates.next().await.unwrap().received_bytes, 0.0);
+    server.emit_browser(
+        "Browser.downloadProgress",
+        progress("g-1", 2.0, "inProgress"),
+    );
+    assert_eq!(updates.next().await.unwrap().fraction(), Some(0.5));
+
+    std::fs::write(dir.join("g-1"), "a,b\n").unwrap();
+    server.emit_browser(
+        "Browser.downloadProgress",
+        progress("g-1", 4.0, "completed"),
+    );
+    let copy = dir.join("report.csv");
+    report.save_as(&copy).await.unwrap();
+    assert_eq!(std::fs::read_to_string(&copy).unwrap(), "a,b\n");
+    assert_eq!(
+        updates.next().await.unwrap().state,
+        DownloadState::Completed
+    );
+    assert!(updates.next().await.is_none());
+
+    server.emit_browser("Browser.downloadWillBegin", will_begin("g-2", "big.iso"));
+    let big = downloads.next().await.unwrap();
+    big.cancel().await.unwrap();
+    assert_eq!(
+        server.requests_for("Browser.cancelDownload")[0]["params"],
+        json!({"guid": "g-2", "browserContextId": "ctx-1"})
+    );
+    server.emit_browser("Browser.downloadProgress", progress("g-2", 1.0, "canceled"));
+    let err = big.wait_for_completion().await.unwrap_err();
+    assert!(matches!(err, CdpError::DownloadCanceled(ref name) if name == "big.iso"));
+
+    drop(downloads);
+    let restored = server.wait_for("Browser.setDownloadBehavior", 2).await;
+    assert_eq!(
+        restored[1]["params"],
+        json!({"behavior": "default", "browserContextId": "ctx-1"})
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+fn browser_context_handler(
+) -> impl Fn(&str, &Value) -> Result<Value, String> + Send + Sync + 'static {
+    let targets = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    move |method, params| match method {
+        "Target.createBrowserContext" => Ok(json!({"browserContextId": "ctx-1"})),
+        "Target.createTarget" => {
+            let n = targets.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(json!({"targetId": format!("t-{n}")}))
+        }
+        "Target.attachToTarget" => {
+            Ok(json!({"sessionId": format!("s-{}", params["targetId"].as_str().unwrap())}))
+        }
+        "Target.closeTarget" => Ok(json!({"success": true})),
+        _ => Ok(json!({})),
+    }
+}
+
+#[tokio::test]
+async fn downloads_only_yield_downloads_of_their_browser_context() {
+    let server = start_scripted_server(|method, params| match method {
+        "Target.getTargetInfo" => {
+            let target_id = params["targetId"].as_str().unwrap();
+            let context = match target_id {
+                "page-a" => "ctx-a",
+                "page-b" => "ctx-b",
+                _ => "ctx-default",
+            };
+            Ok(
+                json!({"targetInfo": {"targetId": target_id, "type": "page", "title": "",
+                                     "url": "", "attached": true, "canAccessOpener": false,
+                                     "browserContextId": context}}),
+            )
+        }
+        "Target.getBrowserContexts" => Ok(json!({"browserContextIds": ["ctx-a", "ctx-b"]})),
+        _ => Ok(json!({})),
+    })
+    .await;
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+    let dir = std::env::temp_dir().join(format!("cdpkit-context-downloads-{}", std::process::id()));
+
+    let options = |name: &str| DownloadOptions::new(dir.join(name));
+    let mut a = Downloads::start(&cdp, options("a").with_browser_context_id("ctx-a"))
+        .await
+        .unwrap();
+    let mut b = Downloads::start(&cdp, options("b").with_browser_context_id("ctx-b"))
+        .await
+        .unwrap();
+    let mut default = Downloads::start(&cdp, options("default")).await.unwrap();
+
+    for (frame, guid) in [
+        ("page-b", "g-b"),
+        ("page-default", "g-default"),
+        ("page-a", "g-a"),
+    ] {
+        server.emit_browser(
+            "Browser.downloadWillBegin",
+            json!({"frameId": frame, "guid": guid, "url": "https://a.test/f",
+                   "suggestedFilename": "f"}),
+        );
+    }
+    let download = a.next().await.unwrap();
+    assert_eq!(download.guid(), "g-a");
+    assert_eq!(download.path(), dir.join("a").join("g-a"));
+    assert_eq!(b.next().await.unwrap().guid(), "g-b");
+    assert_eq!(default.next().await.unwrap().guid(), "g-default");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn browser_context_owns_pages_and_settings_and_disposes_on_close() {
+    let server = start_scripted_server(browser_context_handler()).await;
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+    let dir = std::env::temp_dir().join(format!("cdpkit-context-{}", std::process::id()));
+
+    let options = BrowserContextOptions::new()
+        .with_proxy_server("http://127.0.0.1:8080")
+        .with_proxy_bypass_list("localhost")
+        .with_download_path(&dir)
+        .with_permissions(
+            Some("https://maps.test"),
+            [browser::types::PermissionType::Geolocation],
+        );
+    let mut context = BrowserContext::create(&cdp, options).await.unwrap();
+    assert_eq!(context.id(), "ctx-1");
+    assert!(context.downloads().is_some());
+
+    let first = context.new_page("about:blank").await.unwrap();
+    let second = context.new_page("https://maps.test").await.unwrap();
+    assert_eq!(first.id(), "s-t-0");
+    assert_eq!(second.id(), "s-t-1");
+    context.close_page("t-0").await.unwrap();
+    let pages = context.pages();
+    assert_eq!(pages.len(), 1);
+    assert_eq!(pages[0].target_id, "t-1");
+
+    context.close().await.unwrap();
+    assert_eq!(
+        server.methods(),
+        [
+            "Target.createBrowserContext",
+            "Browser.grantPermissions",
+            "Browser.setDownloadBehavior",
+            "Target.createTarget",
+            "Target.attachToTarget",
+            "Target.createTarget",
+            "Target.attachToTarget",
+            "Target.closeTarget",
+            "Target.disposeBrowserContext",
+        ]
+    );
+    assert_eq!(
+        server.requests_for("Target.createBrowserContext")[0]["params"],
+        json!({"proxyServer": "http://127.0.0.1:8080", "proxyBypassList": "localhost"})
+    );
+    assert_eq!(
+        server.requests_for("Browser.grantPermissions")[0]["params"],
+        json!({"permissions": ["geolocation"], "origin": "https://maps.test",
+               "browserContextId": "ctx-1"})
+    );
+    assert_eq!(
+        server.requests_for("Target.createTarget")[1]["params"],
+        json!({"url": "https://maps.test", "browserContextId": "ctx-1"})
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn browser_context_disposes_on_drop() {
+    let server = start_scripted_server(browser_context_handler()).await;
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+
+    let context = BrowserContext::create(&cdp, BrowserContextOptions::new())
+        .await
+        .unwrap();
+    drop(context);
+    let disposed = server.wait_for("Target.disposeBrowserContext", 1).await;
+    assert_eq!(disposed[0]["params"], json!({"browserContextId": "ctx-1"}));
+}
+
+#[tokio::test]
+async fn permission_guards_regrant_remaining_grants_on_release() {
+    let server = start_scripted_server(|_, _| Ok(json!({}))).await;
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+
+    let permissions = Permissions::new(&cdp).with_browser_context_id("ctx-1");
+    let camera = permissions
+        .grant(None, [browser::types::PermissionType::VideoCapture])
+        .await
+        .unwrap();
+    let geolocation = permissions
+        .grant(
+            Some("https://maps.test"),
+            [browser::types::PermissionType::Geolocation],
+        )
+        .await
+        .unwrap();
+
+    geolocation.restore().await.unwrap();
+    assert_eq!(
+        server.methods(),
+        [
+            "Browser.grantPermissions",
+            "Browser.grantPermissions",
+            "Browser.resetPermissions",
+            "Browser.grantPermissions",
+        ]
+    );
+    let grants = server.requests_for("Browser.grantPermissions");
+    assert_eq!(
+        grants[1]["params"],
+        json!({
+            "permissions": ["geolocation"],
+            "origin": "https://maps.test",
+            "browserContextId": "ctx-1"
+        })
+    );
+    assert_eq!(grants[2]["params"], grants[0]["params"]);
+
+    drop(camera);
+    let resets = server.wait_for("Browser.resetPermissions", 2).await;
+    assert_eq!(resets[1]["params"], json!({"browserContextId": "ctx-1"}));
+    assert_eq!(server.requests_for("Browser.grantPermissions").len(), 3);
+}
+
+#[tokio::test]
+async fn permission_guards_dropped_together_release_in_order() {
+    let server = start_scripted_server(|_, _| Ok(json!({}))).await;
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+
+    let permissions = Permissions::new(&cdp);
+    let mut guards = Vec::new();
+    for origin in ["https://a.test", "https://b.test", "https://c.test"] {
+        let guard = permissions
+            .grant(Some(origin), [browser::types::PermissionType::Geolocation])
+            .await
+            .unwrap();
+        guards.push(guard);
+    }
+    let c = guards.pop().unwrap();
+    drop(guards);
+
+    server.wait_for("Browser.resetPermissions", 2).await;
+    server.wait_for("Browser.grantPermissions", 6).await;
+    let requests = server.requests.lock().unwrap().clone();
+    let last_reset = requests
+        .iter()
+        .rposition(|request| request["method"] == "Browser.resetPermissions")
+        .unwrap();
+    let regranted: Vec<&Value> = requests[last_reset + 1..]
+        .iter()
+        .map(|request| &request["params"]["origin"])
+        .collect();
+    assert_eq!(regranted, [&json!("https://c.test")]);
+    c.restore().await.unwrap();
+}
+
+#[tokio::test]
+async fn context_permission_guards_keep_context_grants_on_release() {
+    let server = start_scripted_server(browser_context_handler()).await;
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+
+    let options = BrowserContextOptions::new().with_permissions(
+        Some("https://maps.test"),
+        [browser::types::PermissionType::Geolocation],
+    );
+    let context = BrowserContext::create(&cdp, options).await.unwrap();
+    let camera = context
+        .permissions()
+        .grant(None, [browser::types::PermissionType::VideoCapture])
+        .await
+        .unwrap();
+    let notifications = context
+        .permissions()
+        .grant(
+            Some("https://maps.test"),
+            [browser::types::PermissionType::Notifications],
+        )
+        .await
+        .unwrap();
+    let grants = server.requests_for("Browser.grantPermissions");
+    assert_eq!(
+        grants[2]["params"],
+        json!({"permissions": ["geolocation", "notifications"], "origin": "https://maps.test",
+               "browserContextId": "ctx-1"})
+    );
+
+    drop(notifications);
+    server.wait_for("Browser.resetPermissions", 1).await;
+    let grants = server.wait_for("Browser.grantPermissions", 5).await;
+    assert_eq!(
+        grants[3]["params"],
+        json!({"permissions": ["geolocation"], "origin": "https://maps.test",
+               "browserContextId": "ctx-1"})
+    );
+    assert_eq!(grants[4]["params"], grants[1]["params"]);
+
+    camera.restore().await.unwrap();
+    let grants = server.requests_for("Browser.grantPermissions");
+    assert_eq!(grants.len(), 6);
+    assert_eq!(grants[5]["params"], grants[0]["params"]);
+}
+
+#[tokio::test]
+async fn emulation_override_guards_restore_previous_values() {
+    let server = start_scripted_server(|_, _| Ok(json!({}))).await;
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+    let session = cdp.owned_session("page-session");
+
+    let overrides = EmulationOverrides::new(&session);
+    let berlin = overrides.timezone("Europe/Berlin").await.unwrap();
+    let tokyo = overrides.timezone("Asia/Tokyo").await.unwrap();
+    let locale = overrides.locale("de-DE").await.unwrap();
+    let position = overrides
+        .geolocation(Geolocation::new(52.52, 13.40))
+        .await
+        .unwrap();
+
+    tokyo.restore().await.unwrap();
+    berlin.restore().await.unwrap();
+    locale.restore().await.unwrap();
+    drop(position);
+    server
+        .wait_for("Emulation.clearGeolocationOverride", 1)
+        .await;
+
+    let timezones: Vec<Value> = server
+        .requests_for("Emulation.setTimezoneOverride")
+        .iter()
+        .map(|r| r["params"]["timezoneId"].clone())
+        .collect();
+    assert_eq!(
+        timezones,
+        [
+            json!("Europe/Berlin"),
+            json!("Asia/Tokyo"),
+            json!("Europe/Berlin"),
+            json!("")
+        ]
+    );
+    let locales = server.requests_for("Emulation.setLocaleOverride");
+    assert_eq!(locales[0]["params"], json!({"locale": "de-DE"}));
+    assert_eq!(locales[1]["params"], json!({}));
+    let geolocation = server.requests_for("Emulation.setGeolocationOverride");
+    assert_eq!(
+        geolocation[0]["params"],
+        json!({"latitude": 52.52, "longitude": 13.40, "accuracy": 1.0})
+    );
+}
+
+#[tokio::test]
+async fn nested_override_guards_dropped_together_clear_the_override() {
+    let server = start_scripted_server(|_, _| Ok(json!({}))).await;
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+    let session = cdp.owned_session("page-session");
+
+    let overrides = EmulationOverrides::new(&session);
+    let mut guards = Vec::new();
+    for timezone in ["Europe/Berlin", "Asia/Tokyo", "America/New_York"] {
+        guards.push(overrides.timezone(timezone).await.unwrap());
+    }
+    // Newest first, so every drop changes the effective timezone.
+    while let Some(guard) = guards.pop() {
+        drop(guard);
+    }
+
+    let timezones: Vec<Value> = server
+        .wait_for("Emulation.setTimezoneOverride", 6)
+        .await
+        .iter()
+        .map(|r| r["params"]["timezoneId"].clone())
+        .collect();
+    assert_eq!(
+        timezones[3..],
+        [json!("Asia/Tokyo"), json!("Europe/Berlin"), json!("")]
+    );
+}
+
+#[tokio::test]
+async fn trace_recorder_collects_reported_events() {
+    let server = start_scripted_server(|_, _| Ok(json!({}))).await;
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+
+    let options = TraceOptions::preset(TracePreset::JavaScript).with_category("-v8.gc");
+    let recorder = TraceRecorder::start(&cdp, options).await.unwrap();
+    let start = server.requests_for("Tracing.start");
+    assert_eq!(start[0]["params"]["transferMode"], "ReportEvents");
+    let config = &start[0]["params"]["traceConfig"];
+    assert_eq!(config["excludedCategories"], json!(["*", "v8.gc"]));
+    assert_eq!(config["includedCategories"][0], "toplevel");
+
+    let stop = tokio::spawn(recorder.stop());
+    server.wait_for("Tracing.end", 1).await;
+    server.emit_browser(
+        "Tracing.dataCollected",
+        json!({"value": [{"name": "RunTask", "ph": "X", "ts": 1}]}),
+    );
+    server.emit_browser(
+        "Tracing.dataCollected",
+        json!({"value": [{"name": "GCEvent", "ph": "X", "ts": 2}]}),
+    );
+    server.emit_browser("Tracing.tracingComplete", json!({"dataLossOccurred": true}));
+
+    let trace = stop.await.unwrap().unwrap();
+    assert!(trace.data_loss_occurred);
+    let names: Vec<&str> = trace
+        .trace_events
+        .iter()
+        .map(|e| e["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["RunTask", "GCEvent"]);
+}
+
+#[tokio::test]
+async fn trace_recorder_streams_trace_into_gzip_file() {
+    let reads = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let server = start_scripted_server(move |method, _| match method {
+        "IO.read" => match reads.fetch_add(1, std::sync::atomic::Ordering::SeqCst) {
+            0 => Ok(json!({"data": "{\"traceEvents\":[{\"name\":", "eof": false})),
+            _ => Ok(json!({"data": "\"RunTask\"}]}", "eof": true})),
+        },
+        _ => Ok(json!({})),
+    })
+    .await;
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+    let path = std::env::temp_dir().join(format!("cdpkit-trace-{}.json.gz", std::process::id()));
+
+    let options = TraceOptions::new().with_transfer_mode(TraceTransferMode::ReturnAsStream);
+    let recorder = TraceRecorder::start(&cdp, options).await.unwrap();
+    let start = server.requests_for("Tracing.start");
+    assert_eq!(start[0]["params"]["transferMode"], "ReturnAsStream");
+    assert_eq!(start[0]["params"]["streamFormat"], "json");
+    assert_eq!(start[0]["params"]["streamCompression"], "none");
+
+    let save = tokio::spawn({
+        let path = path.clone();
+        async move { recorder.stop_and_save(path).await }
+    });
+    server.wait_for("Tracing.end", 1).await;
+    server.emit_browser(
+        "Tracing.tracingComplete",
+        json!({"dataLossOccurred": false, "stream": "trace-1", "traceFormat": "json"}),
+    );
+    assert!(!save.await.unwrap().unwrap());
+
+    let mut json = String::new();
+    std::io::Read::read_to_string(
+        &mut flate2::read::GzDecoder::new(std::fs::File::open(&path).unwrap()),
+        &mut json,
+    )
+    .unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(json, r#"{"traceEvents":[{"name":"RunTask"}]}"#);
+    assert_eq!(
+        server.requests_for("IO.read")[0]["params"]["handle"],
+        "trace-1"
+    );
+    assert_eq!(server.requests_for("IO.close").len(), 1);
+}
+
+#[tokio::test]
+async fn coverage_reports_js_and_css_with_sources() {
+    let server = start_scripted_server(|method, params| match method {
+        "Debugger.enable" => Ok(json!({"debuggerId": "debugger-1"})),
+        "Profiler.startPreciseCoverage" => Ok(json!({"timestamp": 1.0})),
+        "Profiler.takePreciseCoverage" => Ok(json!({
+            "timestamp": 1.0,
+            "result": [
+                {"scriptId": "7", "url": "https://app.test/app.js", "functions": [
+                    {"functionName": "", "isBlockCoverage": false,
+                     "ranges": [{"startOffset": 0, "endOffset": 41, "count": 1}]},
+                    {"functionName": "unused", "isBlockCoverage": true,
+                     "ranges": [{"startOffset": 20, "endOffset": 40, "count": 0}]}
+                ]},
+                {"scriptId": "8", "url": "", "functions": []}
+            ]
+        })),
+        "Debugger.getScriptSource" => {
+            assert_eq!(params["scriptId"], "7");
+            Ok(json!({"scriptSource": "const a = 1;\nlet b;\nfunction unused() {}\n"}))
+        }
+        "CSS.stopRuleUsageTracking" => Ok(json!({"ruleUsage": [
+            {"styleSheetId": "css-1", "startOffset": 0, "endOffset": 9, "used": true},
+            {"styleSheetId": "css-1", "startOffset": 10, "endOffset": 19, "used": false}
+        ]})),
+        "CSS.getStyleSheetText" => Ok(json!({"text": "a { x: 1 }\nb { y: 2 }\n"})),
+        _ => Ok(json!({})),
+    })
+    .await;
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+    let session = cdp.owned_session("page-session");
+
+    let coverage = Coverage::start(&session, CoverageOptions::new())
+        .await
+        .unwrap();
+    assert_eq!(
+        server.methods(),
+        [
+            "Debugger.enable",
+            "Profiler.enable",
+            "Profiler.startPreciseCoverage",
+            "DOM.enable",
+            "CSS.enable",
+            "CSS.startRuleUsageTracking",
+        ]
+    );
+    assert_eq!(
+        server.requests_for("Profiler.startPreciseCoverage")[0]["params"],
+        json!({"callCount": true, "detailed": true})
+    );
+    server.emit(
+        "page-session",
+        "CSS.styleSheetAdded",
+        json!({"header": {
+            "styleSheetId": "css-1", "frameId": "f", "sourceURL": "https://app.test/app.css",
+            "origin": "regular", "title": "", "disabled": false, "isInline": false,
+            "isMutable": false, "isConstructed": false, "startLine": 0, "startColumn": 0,
+            "length": 22, "endLine": 2, "endColumn": 0
+        }}),
+    );
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let report = coverage.stop().await.unwrap();
+    assert_eq!(report.files.len(), 2);
+    let js = &report.files[0];
+    assert_eq!(js.kind, CoverageKind::JavaScript);
+    assert_eq!(js.url, "https://app.test/app.js");
+    let lines: Vec<(u32, u64)> = js.lines.iter().map(|l| (l.line, l.count)).collect();
+    assert_eq!(lines, [(1, 1), (2, 1), (3, 0)]);
+    assert_eq!(js.functions.len(), 1);
+    assert_eq!(js.functions[0].name, "unused");
+    assert_eq!(js.functions[0].count, 0);
+
+    let css = &report.files[1];
+    assert_eq!(css.kind, CoverageKind::Css);
+    assert_eq!(css.url, "https://app.test/app.css");
+    assert_eq!(css.line_rate(), Some(0.5));
+    assert!(report
+        .to_lcov()
+        .contains("SF:https://app.test/app.css\nFNF:0\nFNH:0\nDA:1,1\nDA:2,0\n"));
+    assert_eq!(server.requests_for("Debugger.getScriptSource").len(), 1);
+}
+
+#[tokio::test]
+async fn coverage_merges_inline_scripts_of_one_document() {
+    let server = start_scripted_server(|method, params| match method {
+        "Debugger.enable" => Ok(json!({"debuggerId": "debugger-1"})),
+        "Profiler.startPreciseCoverage" => Ok(json!({"timestamp": 1.0})),
+        "Profiler.takePreciseCoverage" => Ok(json!({
+            "timestamp": 1.0,
+            "result": [
+                {"scriptId": "7", "url": "https://app.test/", "functions": [
+                    {"functionName": "", "isBlockCoverage": true,
+                     "ranges": [{"startOffset": 0, "endOffset": 27, "count": 1}]},
+                    {"functionName": "f", "isBlockCoverage": true,
+                     "ranges": [{"startOffset": 11, "endOffset": 26, "count": 0}]}
+                ]},
+                {"scriptId": "9", "url": "https://app.test/", "functions": [
+                    {"functionName": "", "isBlockCoverage": true,
+                     "ranges": [{"startOffset": 0, "endOffset": 4, "count": 1}]}
+                ]}
+            ]
+        })),
+        "Debugger.getScriptSource" => match params["scriptId"].as_str() {
+            Some("7") => Ok(json!({"scriptSource": "let a = 1;\nfunction f() {}\n"})),
+            _ => Ok(json!({"scriptSource": "f();"})),
+        },
+        _ => Ok(json!({})),
+    })
+    .await;
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+    let session = cdp.owned_session("page-session");
+
+    let coverage = Coverage::start(&session, CoverageOptions::new().with_css(false))
+        .await
+        .unwrap();
+    for (id, line) in [("7", 2), ("9", 6)] {
+        server.emit(
+            "page-session",
+            "Debugger.scriptParsed",
+            json!({"scriptId": id, "url": "https://app.test/", "startLine": line,
+                   "startColumn": 8, "endLine": line + 2, "endColumn": 0,
+                   "executionContextId": 1, "hash": "h", "buildId": ""}),
+        );
+    }
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let report = coverage.stop().await.unwrap();
+    let lines: Vec<Vec<(u32, u64)>> = report
+        .files
+        .iter()
+        .map(|f| f.lines.iter().map(|l| (l.line, l.count)).collect())
+        .collect();
+    assert_eq!(lines, [vec![(3, 1), (4, 0)], vec![(7, 1)]]);
+
+    let lcov = report.to_lcov();
+    assert_eq!(lcov.matches("SF:").count(), 1);
+    assert!(lcov.contains(
+        "SF:https://app.test/\nFN:4,f\nFNDA:0,f\nFNF:1\nFNH:0\n\
+         DA:3,1\nDA:4,0\nDA:7,1\nLF:3\nLH:2\nend_of_record\n"
+    ));
+
+    let istanbul = report.to_istanbul();
+    let page = &istanbul["https://app.test/"];
+    assert_eq!(istanbul.as_object().unwrap().len(), 1);
+    assert_eq!(page["s"], json!({"0": 1, "1": 0, "2": 1}));
+    assert_eq!(
+        page["statementMap"]["0"],
+        json!({"start": {"line": 3, "column": 8}, "end": {"line": 3, "column": 18}})
+    );
+    assert_eq!(
+        page["statementMap"]["2"],
+        json!({"start": {"line": 7, "column": 8}, "end": {"line": 7, "column": 12}})
+    );
+    assert_eq!(
+        page["fnMap"]["0"]["loc"],
+        json!({"start": {"line": 4, "column": 0}, "end": {"line": 4, "column": 15}})
+    );
+}
+
+#[tokio::test]
+async fn cpu_profiler_records_profile_on_node_style_target() {
+    let server = start_scripted_server(|method, _| match method {
+        "Profiler.stop" => Ok(json!({"profile": {
+            "nodes": [
+                {"id": 1, "callFrame": {"functionName": "(root)", "scriptId": "0", "url": "",
+                 "lineNumber": -1, "columnNumber": -1}, "children": [2]},
+                {"id": 2, "callFrame": {"functionName": "handler", "scriptId": "3",
+                 "url": "file:///srv/app.js", "lineNumber": 9, "columnNumber": 4}}
+            ],
+            "startTime": 0,
+            "endTime": 2000,
+            "samples": [2, 2],
+            "timeDeltas": [0, 1000]
+        }})),
+        _ => Ok(json!({})),
+    })
+    .await;
+    // A Node inspector has no sessions: commands go straight to the connection.
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+
+    let options = CpuProfilerOptions::new().with_sampling_interval(Duration::from_micros(250));
+    let profiler = CpuProfiler::start(&cdp, options).await.unwrap();
+    assert_eq!(
+        server.methods(),
+        [
+            "Profiler.enable",
+            "Profiler.setSamplingInterval",
+            "Profiler.start"
+        ]
+    );
+    assert_eq!(
+        server.requests_for("Profiler.setSamplingInterval")[0]["params"],
+        json!({"interval": 250})
+    );
+
+    let profile = profiler.stop().await.unwrap();
+    let top = profile.top(5);
+    assert_eq!(top.len(), 1);
+    assert_eq!(top[0].function_name, "handler");
+    assert_eq!(top[0].self_time, Duration::from_millis(2));
+    assert_eq!(
+        profile.folded_stacks(),
+        "handler (file:///srv/app.js:10) 2\n"
+    );
+
+    let path = std::env::temp_dir().join(format!("cdpkit-{}.cpuprofile", std::process::id()));
+    profile.save(&path).await.unwrap();
+    let loaded = CpuProfile::load(&path).await.unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded.profile.nodes.len(), 2);
+    assert_eq!(loaded.functions(), profile.functions());
+
+    let profiler = CpuProfiler::start(&cdp, CpuProfilerOptions::new())
+        .await
+        .unwrap();
+    drop(profiler);
+    server.wait_for("Profiler.stop", 2).await;
+}
+
+#[tokio::test]
+async fn heap_snapshot_streams_every_chunk_before_the_command_returns() {
+    let snapshot = json!({
+        "snapshot": {"meta": {
+            "node_fields": ["type", "name", "id", "self_size", "edge_count", "trace_node_id"],
+            "node_types": [["hidden", "object", "synthetic"], "string", "number", "number",
+                           "number", "number"],
+            "edge_fields": ["type", "name_or_index", "to_node"],
+            "edge_types": [["element", "property", "weak"], "string_or_number", "node"],
+        }},
+        "nodes": [2, 0, 1, 0, 1, 0,  1, 1, 3, 64, 1, 0,  1, 2, 5, 16, 0, 0],
+        "edges": [0, 0, 6,  1, 3, 12],
+        "strings": ["", "Store", "Item", "items"],
+    })
+    .to_string();
+    let emitter: Arc<Mutex<Option<mpsc::UnboundedSender<Value>>>> = Arc::default();
+    let server = start_scripted_server({
+        let emitter = Arc::clone(&emitter);
+        let snapshot = snapshot.clone();
+        move |method, _| {
+            if method == "HeapProfiler.takeHeapSnapshot" {
+                let emitter = emitter.lock().unwrap();
+                // Far more chunks than the connection queues before applying backpressure.
+                for chunk in snapshot.as_bytes().chunks(4) {
+                    let chunk = std::str::from_utf8(chunk).unwrap();
+                    let event = json!({
+                        "method": "HeapProfiler.addHeapSnapshotChunk",
+                        "params": {"chunk": chunk},
+                    });
+                    emitter.as_ref().unwrap().send(event).unwrap();
+                }
+            }
+            Ok(json!({}))
+        }
+    })
+    .await;
+    *emitter.lock().unwrap() = Some(server.events.clone());
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+
+    let options = HeapSnapshotOptions::new().with_collect_garbage(true);
+    let heap = HeapSnapshot::capture(&cdp, options.clone()).await.unwrap();
+    assert_eq!(
+        server.methods(),
+        [
+            "HeapProfiler.enable",
+            "HeapProfiler.collectGarbage",
+            "HeapProfiler.takeHeapSnapshot"
+        ]
+    );
+    assert_eq!(heap.node_count(), 3);
+    assert_eq!(heap.total_size(), 80);
+    let summary = heap.summary();
+    assert_eq!(summary[0].name, "Store");
+    assert_eq!(summary[0].retained_size, 80);
+    let edge = heap.edges(1).next().unwrap();
+    assert_eq!((edge.name, edge.to), (HeapEdgeName::Name("items"), 2));
+
+    let path = std::env::temp_dir().join(format!("cdpkit-{}.heapsnapshot", std::process::id()));
+    let written = HeapSnapshot::capture_to_file(&cdp, &path, options)
+        .await
+        .unwrap();
+    assert_eq!(written, snapshot.len() as u64);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), snapshot);
+    let loaded = HeapSnapshot::load(&path).await.unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(heap.diff(&loaded).is_empty());
+}
+
+#[tokio::test]
+async fn heap_snapshot_file_is_removed_when_the_capture_fails() {
+    let server = start_scripted_server(|method, _| match method {
+        "HeapProfiler.takeHeapSnapshot" => Err("Heap snapshot failed".into()),
+        _ => Ok(json!({})),
+    })
+    .await;
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+
+    let path =
+        std::env::temp_dir().join(format!("cdpkit-{}-failed.heapsnapshot", std::process::id()));
+    let err = HeapSnapshot::capture_to_file(&cdp, &path, HeapSnapshotOptions::new())
+        .await
+        .unwrap_err();
+    assert!(matches!(err, CdpError::Protocol { .. }), "{err:?}");
+    assert!(!path.exists());
+}
+
+#[tokio::test]
+async fn debugger_client_breaks_inspects_and_steps() {
+    let server = start_scripted_server(|method, params| match method {
+        "Debugger.enable" => Ok(json!({"debuggerId": "debugger-1"})),
+        "Debugger.setBreakpointByUrl" => Ok(json!({
+            "breakpointId": "1:4:0:https://app.test/cart.js",
+            "locations": [{"scriptId": "12", "lineNumber": 4, "columnNumber": 2}]
+        })),
+        "Debugger.evaluateOnCallFrame" => match params["expression"].as_str() {
+            Some("items.length") => Ok(json!({"result": {
+                "type": "number", "value": 3, "description": "3"
+            }})),
+            _ => Ok(json!({
+                "result": {"type": "object", "subtype": "error"},
+                "exceptionDetails": {
+                    "exceptionId": 1, "text": "Uncaught", "lineNumber": 0, "columnNumber": 0,
+                    "exception": {"type": "object", "description": "ReferenceError: nope is not defined"}
+                }
+            })),
+        },
+        "Runtime.getProperties" => Ok(json!({"result": [
+            {"name": "items", "configurable": true, "enumerable": true,
+             "value": {"type": "object", "subtype": "array", "className": "Array",
+                       "description": "Array(3)", "objectId": "items-1"}},
+            {"name": "sum", "configurable": true, "enumerable": true,
+             "value": {"type": "number", "value": 0, "description": "0"}}
+        ]})),
+        _ => Ok(json!({})),
+    })
+    .await;
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+    let session = cdp.owned_session("page-session");
+
+    let debugger = DebuggerClient::enable(&session).await.unwrap();
+    server.emit(
+        "page-session",
+        "Debugger.scriptParsed",
+        json!({"scriptId": "12", "url": "https://app.test/cart.js", "startLine": 0,
+               "startColumn": 0, "endLine": 30, "endColumn": 0, "executionContextId": 1,
+               "hash": "h", "buildId": "", "sourceMapURL": "cart.js.map"}),
+    );
+
+    let breakpoint = debugger
+        .set_breakpoint(
+            BreakpointSpec::url_regex(r"cart\.js$", 4).with_condition("items.length > 2"),
+        )
+        .await
+        .unwrap();
+    assert_eq!(breakpoint.locations[0].line_number, 4);
+    assert_eq!(
+        server.requests_for("Debugger.setBreakpointByUrl")[0]["params"],
+        json!({"lineNumber": 4, "urlRegex": r"cart\.js$", "condition": "items.length > 2"})
+    );
+
+    let paused = |line: i64| {
+        json!({
+            "reason": "other",
+            "hitBreakpoints": if line == 4 { json!([breakpoint.id]) } else { json!([]) },
+            "callFrames": [{
+                "callFrameId": format!("frame-{line}"),
+                "functionName": "total",
+                "location": {"scriptId": "12", "lineNumber": line, "columnNumber": 2},
+                "url": "",
+                "scopeChain": [{"type": "local", "name": "total",
+                                "object": {"type": "object", "objectId": "scope-local"}}],
+                "this": {"type": "undefined"}
+            }]
+        })
+    };
+    server.emit("page-session", "Debugger.paused", paused(4));
+    let pause = debugger
+        .wait_for_pause(Duration::from_secs(2))
+        .await
+        .unwrap();
+    assert_eq!(pause.reason, PauseReason::Breakpoint);
+    assert_eq!(
+        debugger.scripts_for_url("https://app.test/cart.js").len(),
+        1
+    );
+    let frame = pause.top_frame().unwrap();
+    assert_eq!(frame.url, "https://app.test/cart.js");
+
+    let variables = debugger.scope_variables(&frame.scopes[0]).await.unwrap();
+    let rendered: Vec<(String, String)> = variables
+        .iter()
+        .map(|v| (v.name.clone(), v.display()))
+        .collect();
+    assert_eq!(
+        rendered,
+        [
+            ("items".to_string(), "Array(3)".to_string()),
+            ("sum".to_string(), "0".to_string())
+        ]
+    );
+    assert_eq!(
+        server.requests_for("Runtime.getProperties")[0]["params"],
+        json!({"objectId": "scope-local", "ownProperties": true})
+    );
+
+    let length = debugger
+        .evaluate_on_frame(frame, "items.length")
+        .await
+        .unwrap();
+    assert_eq!(length.value, Some(json!(3)));
+    let err = debugger.evaluate_on_frame(frame, "nope").await.unwrap_err();
+    assert!(
+        matches!(err, CdpError::JavaScriptException(ref m) if m.contains("ReferenceError")),
+        "{err:?}"
+    );
+    assert_eq!(
+        server.requests_for("Debugger.evaluateOnCallFrame")[0]["params"]["callFrameId"],
+        "frame-4"
+    );
+
+    debugger.step_over().await.unwrap();
+    assert!(!debugger.is_paused());
+    server.emit("page-session", "Debugger.resumed", json!({}));
+    server.emit("page-session", "Debugger.paused", paused(5));
+    let pause = debugger
+        .wait_for_pause(Duration::from_secs(2))
+        .await
+        .unwrap();
+    assert_eq!(pause.reason, PauseReason::Other("other".into()));
+    assert_eq!(pause.top_frame().unwrap().line_number, 5);
+
+    debugger.resume().await.unwrap();
+    drop(debugger);
+    server.wait_for("Debugger.disable", 1).await;
+}
+
+#[tokio::test]
+async fn debugger_wait_for_pause_fails_when_the_connection_closes() {
+    let server = start_scripted_server(|method, _| match method {
+        "Debugger.enable" => Ok(json!({"debuggerId": "debugger-1"})),
+        _ => Ok(json!({})),
+    })
+    .await;
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+    let session = cdp.owned_session("page-session");
+
+    let debugger = DebuggerClient::enable(&session).await.unwrap();
+    cdp.close().await;
+    let err = debugger
+        .wait_for_pause(Duration::from_secs(2))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, CdpError::ConnectionClosed));
+}
+
+#[tokio::test]
+async fn source_map_resolver_maps_stack_traces_to_original_sources() {
+    let app_map = json!({
+        "version": 3,
+        "sources": ["../src/app.ts"],
+        "names": ["handle"],
+        "mappings": "AAAAA"
+    });
+    let server = start_scripted_server(move |method, _| match method {
+        "Debugger.enable" => Ok(json!({"debuggerId": "debugger-1"})),
+        "Network.loadNetworkResource" => {
+            Ok(json!({"resource": {"success": true, "stream": "map-stream"}}))
+        }
+        "IO.read" => Ok(json!({"data": app_map.to_string(), "eof": true})),
+        _ => Ok(json!({})),
+    })
+    .await;
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+    let session = cdp.owned_session("page-session");
+
+    let resolver = SourceMapResolver::enable(&session)
+        .await
+        .unwrap()
+        .with_frame_id("main-frame");
+    let script = |id: &str, url: &str, map: &str| {
+        json!({"scriptId": id, "url": url, "startLine": 0, "startColumn": 0, "endLine": 1,
+               "endColumn": 0, "executionContextId": 1, "hash": "h", "buildId": "",
+               "sourceMapURL": map})
+    };
+    let inline_map = r#"{"version":3,"sources":["inline.ts"],"names":[],"mappings":"AAAA"}"#;
+    server.emit(
+        "page-session",
+        "Debugger.scriptParsed",
+        script(
+            "1",
+            "https://app.test/index.html",
+            &format!("data:application/json;charset=utf-8,{inline_map}"),
+        ),
+    );
+    server.emit(
+        "page-session",
+        "Debugger.scriptParsed",
+        script("2", "https://app.test/js/app.js", "app.js.map"),
+    );
+    tokio::time::timeout(Duration::from_secs(2), async {
+        while resolver.source_map("2").await.is_none() {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    })
+    .await
+    .unwrap();
+
+    let frame = |id: &str, url: &str, name: &str, column: i64| {
+        json!({"functionName": name, "scriptId": id, "url": url, "lineNumber": 0,
+               "columnNumber": column})
+    };
+    let stack: runtime::types::StackTrace = serde_json::from_value(json!({
+        "callFrames": [frame("2", "https://app.test/js/app.js", "a", 10)],
+        "parent": {
+            "description": "setTimeout",
+            "callFrames": [
+                frame("1", "https://app.test/index.html", "", 5),
+                frame("9", "https://app.test/vendor.js", "lib", 0)
+            ]
+        }
+    }))
+    .unwrap();
+    let frames = resolver.map_stack_trace(&stack).await;
+    let rendered: Vec<String> = frames.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        rendered,
+        [
+            "at handle (https://app.test/src/app.ts:1:1)",
+            "at <anonymous> (https://app.test/inline.ts:1:1)",
+            "at lib (https://app.test/vendor.js:1:1)"
+        ]
+    );
+    assert!(frames[2].original.is_none());
+
+    // Each map is loaded once, with the page's frame and credentials.
+    let loads = server.requests_for("Network.loadNetworkResource");
+    assert_eq!(loads.len(), 1);
+    assert_eq!(
+        loads[0]["params"],
+        json!({"frameId": "main-frame", "url": "https://app.test/js/app.js.map",
+               "options": {"disableCache": false, "includeCredentials": true}})
+    );
+    assert_eq!(server.requests_for("IO.close").len(), 1);
+}
+
+#[tokio::test]
+async fn metrics_snapshots_diff_and_web_vitals() {
+    let emitter: Arc<Mutex<Option<mpsc::UnboundedSender<Value>>>> = Arc::new(Mutex::new(None));
+    let snapshots = Arc::new(Mutex::new(0u32));
+    let server = start_scripted_server({
+        let emitter = Arc::clone(&emitter);
+        move |method, _| match method {
+            "Performance.getMetrics" => {
+                let mut taken = snapshots.lock().unwrap();
+                *taken += 1;
+                let n = f64::from(*taken);
+                Ok(json!({"metrics": [
+                    {"name": "Timestamp", "value": 10.0 * n},
+                    {"name": "Nodes", "value": 100.0 * n},
+                    {"name": "LayoutCount", "value": 3.0 * n},
+                    {"name": "TaskDuration", "value": 0.5 * n}
+                ]}))
+            }
+            "PerformanceTimeline.enable" => {
+                // Chrome replays buffered entries when the timeline is enabled.
+                let emitter = emitter.lock().unwrap();
+                let entry = |entry: Value| {
+                    let event = json!({
+                        "method": "PerformanceTimeline.timelineEventAdded",
+                        "params": {"event": entry},
+                        "sessionId": "page-session",
+                    });
+                    emitter.as_ref().unwrap().send(event).unwrap();
+                };
+                // From the previous navigation.
+                entry(
+                    json!({"frameId": "f", "type": "layout-shift", "name": "", "time": 999.0,
+                             "layoutShiftDetails": {"value": 0.5, "hadRecentInput": false,
+                                                    "lastInputTime": 0.0, "sources": []}}),
+                );
+                entry(
+                    json!({"frameId": "f", "type": "layout-shift", "name": "", "time": 1000.4,
+                             "layoutShiftDetails": {"value": 0.1, "hadRecentInput": false,
+                                                    "lastInputTime": 0.0, "sources": []}}),
+                );
+                entry(
+                    json!({"frameId": "f", "type": "largest-contentful-paint", "name": "",
+                             "time": 1000.8, "lcpDetails": {"renderTime": 1000.8,
+                             "loadTime": 1000.7, "size": 9000.0, "nodeId": 7}}),
+                );
+                Ok(json!({}))
+            }
+            "Runtime.evaluate" => Ok(json!({"result": {"type": "object", "value": {
+                "timeOrigin": 1_000_000.0, "ttfb": 120.0, "fcp": 450.0
+            }}})),
+            _ => Ok(json!({})),
+        }
+    })
+    .await;
+    *emitter.lock().unwrap() = Some(server.events.clone());
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+    let session = cdp.owned_session("page-session");
+
+    let metrics = Metrics::start(&session).await.unwrap();
+    assert_eq!(
+        server.requests_for("PerformanceTimeline.enable")[0]["params"],
+        json!({"eventTypes": ["largest-contentful-paint", "layout-shift"]})
+    );
+
+    let before = metrics.snapshot().await.unwrap();
+    let after = metrics.snapshot().await.unwrap();
+    assert_eq!(after.nodes, 200);
+    let diff = after.diff(&before);
+    assert_eq!(diff.elapsed, Duration::from_secs(10));
+    assert_eq!((diff.nodes, diff.layout_count), (100, 3));
+    assert_eq!(diff.task_duration, Duration::from_millis(500));
+
+    let vitals = tokio::time::timeout(Duration::from_secs(2), async {
+        loop {
+            let vitals = metrics.web_vitals().await.unwrap();
+            if vitals.lcp.is_some() {
+                break vitals;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    })
+    .await
+    .unwrap();
+    assert_eq!(vitals.lcp, Some(Duration::from_millis(800)));
+    assert_eq!(vitals.lcp_node_id, Some(7));
+    assert!((vitals.cls - 0.1).abs() < 1e-9);
+    assert_eq!(vitals.layout_shifts, 1);
+    assert_eq!(vitals.fcp, Some(Duration::from_millis(450)));
+    assert_eq!(vitals.ttfb, Some(Duration::from_millis(120)));
+
+    drop(metrics);
+    server.wait_for("Performance.disable", 1).await;
+    assert_eq!(
+        server.requests_for("PerformanceTimeline.enable")[1]["params"],
+        json!({"eventTypes": []})
+    );
+}
+
+#[tokio::test]
+async fn ax_tree_capture_links_nodes_and_snapshots() {
+    let server = start_scripted_server(|method, _| match method {
+        "Accessibility.getFullAXTree" => Ok(json!({"nodes": [
+            {"nodeId": "1", "ignored": false,
+             "role": {"type": "internalRole", "value": "RootWebArea"},
+             "name": {"type": "computedString", "value": "Checkout"},
+             "childIds": ["2", "3"], "backendDOMNodeId": 1},
+            {"nodeId": "2", "ignored": false, "role": {"type": "role", "value": "link"},
+             "name": {"type": "computedString", "value": "Back"}, "parentId": "1",
+             "backendDOMNodeId": 20},
+            {"nodeId": "3", "ignored": false, "role": {"type": "role", "value": "button"},
+             "name": {"type": "computedString", "value": "Pay"}, "parentId": "1",
+             "backendDOMNodeId": 21, "properties": [
+                {"name": "focusable", "value": {"type": "booleanOrUndefined", "value": true}}]}
+        ]})),
+        _ => Ok(json!({})),
+    })
+    .await;
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+    let session = cdp.owned_session("page-session");
+
+    let tree = AxTree::capture_frame(&session, "frame-1".into())
+        .await
+        .unwrap();
+    assert_eq!(
+        server.requests_for("Accessibility.getFullAXTree")[0]["params"],
+        json!({"frameId": "frame-1"})
+    );
+    let pay = tree
+        .find(&AxQuery::new().with_role("button").with_name("Pay"))
+        .unwrap();
+    assert!(pay.states.focusable);
+    assert_eq!(tree.parent(pay.index).unwrap().role, "RootWebArea");
+    assert_eq!(tree.node_by_backend_node_id(20).unwrap().name, "Back");
+    assert_eq!(
+        tree.to_yaml(),
+        "- RootWebArea \"Checkout\":\n  - link \"Back\"\n  - button \"Pay\"\n"
+    );
+}
+
+#[tokio::test]
+async fn page_snapshot_capture_decodes_and_queries_offline() {
+    let server = start_scripted_server(|method, _| match method {
+        "DOMSnapshot.captureSnapshot" => Ok(json!({
+            "strings": ["https://app.test/", "", "#document", "HTML", "BUTTON", "id", "buy",
+                        "inline-block", "f1"],
+            "documents": [{
+                "documentURL": 0, "title": 1, "baseURL": 0, "contentLanguage": -1,
+                "encodingName": -1, "publicId": -1, "systemId": -1, "frameId": 8,
+                "nodes": {
+                    "parentIndex": [-1, 0, 1],
+                    "nodeType": [9, 1, 1],
+                    "nodeName": [2, 3, 4],
+                    "nodeValue": [-1, -1, -1],
+                    "backendNodeId": [1, 2, 3],
+                    "attributes": [[], [], [5, 6]]
+                },
+                "layout": {
+                    "nodeIndex": [2],
+                    "styles": [[7]],
+                    "bounds": [[10, 20, 100, 30]],
+                    "text": [-1],
+                    "stackingContexts": {"index": []},
+                    "paintOrders": [4]
+                },
+                "textBoxes": {"layoutIndex": [], "bounds": [], "start": [], "length": []}
+            }]
+        })),
+        _ => Ok(json!({})),
+    })
+    .await;
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+
+    let options = PageSnapshotOptions::new()
+        .with_computed_styles(["display"])
+        .with_paint_order(true);
+    let snapshot = PageSnapshot::capture(&cdp, options).await.unwrap();
+    assert_eq!(
+        server.requests_for("DOMSnapshot.captureSnapshot")[0]["params"],
+        json!({"computedStyles": ["display"], "includePaintOrder": true})
+    );
+    let page = snapshot.main_document().unwrap();
+    assert_eq!(page.frame_id, "f1");
+    let buy = page.query_selector("html > button#buy").unwrap().unwrap();
+    assert_eq!(buy.backend_node_id, 3);
+    let layout = buy.layout.as_ref().unwrap();
+    assert_eq!((layout.bounds.x, layout.paint_order), (10.0, Some(4)));
+    assert_eq!(buy.style("display"), Some("inline-block"));
+
+    let path = std::env::temp_dir().join(format!("cdpkit-{}.domsnapshot", std::process::id()));
+    snapshot.save(&path).await.unwrap();
+    let archived = PageSnapshot::load(&path).await.unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(archived.diff(&snapshot).is_empty());
+}
+
+#[tokio::test]
+async fn page_archive_bundles_resources_screenshots_and_dom() {
+    let frame = |id: &str, url: &str| {
+        json!({"id": id, "loaderId": "L", "url": url, "domainAndRegistry": "app.test",
+               "securityOrigin": "https://app.test", "mimeType": "text/html",
+               "secureContextType": "Secure", "crossOriginIsolatedContextType": "NotIsolated",
+               "gatedAPIFeatures": []})
+    };
+    let main = frame("main", "https://app.test/");
+    let resource =
+        |url: &str, kind: &str, mime: &str| json!({"url": url, "type": kind, "mimeType": mime});
+    let tree = json!({"frameTree": {
+        "frame": main.clone(),
+        "resources": [
+            resource("https://app.test/app.js?v=2", "Script", "text/javascript"),
+            resource("https://app.test/logo.png", "Image", "image/png"),
+            resource("https://app.test/gone.css", "Stylesheet", "text/css")
+        ],
+        "childFrames": [{
+            "frame": frame("child", "https://app.test/embed"),
+            "resources": [resource("https://app.test/app.js?v=2", "Script", "text/javascript")]
+        }]
+    }});
+    let server = start_scripted_server(move |method, params| match method {
+        "Page.getFrameTree" => Ok(json!({"frameTree": {"frame": main.clone()}})),
+        "Runtime.evaluate" => Ok(json!({"result": {"type": "string", "value": "Checkout"}})),
+        "Page.getResourceTree" => Ok(tree.clone()),
+        "Page.getResourceContent" => match params["url"].as_str().unwrap() {
+            "https://app.test/" => Ok(json!({"content": "<html></html>", "base64Encoded": false})),
+            "https://app.test/embed" => Ok(json!({"content": "<p>", "base64Encoded": false})),
+            "https://app.test/app.js?v=2" => Ok(json!({"content": "go()", "base64Encoded": false})),
+            "https://app.test/logo.png" => Ok(json!({"content": "iVBO", "base64Encoded": true})),
+            _ => Err("No resource with given URL found".to_string()),
+        },
+        "Page.captureScreenshot" => Ok(json!({"data": "aW1n"})),
+        "DOM.getDocument" => Ok(json!({"root": {
+            "nodeId": 1, "backendNodeId": 1, "nodeType": 9, "nodeName": "#document",
+            "localName": "", "nodeValue": ""
+        }})),
+        "DOM.getOuterHTML" => Ok(json!({"outerHTML": "<html><body>live</body></html>"})),
+        _ => Ok(json!({})),
+    })
+    .await;
+    let cdp = CDP::connect_ws(&server.url).await.unwrap();
+    let session = cdp.owned_session("page-session");
+    let directory = std::env::temp_dir().join(format!("cdpkit-archive-{}", std::process::id()));
+
+    let options = PageArchiveOptions::new()
+        .with_mode(PageArchiveMode::Resources)
+        .with_screenshots([("viewport", ScreenshotOptions::default())]);
+    let archive = PageArchive::capture(&Page::new(&session), &directory, options)
+        .await
+        .unwrap();
+    let manifest = archive.manifest();
+    assert_eq!(
+        (manifest.url.as_str(), manifest.title.as_str()),
+        ("https://app.test/", "Checkout")
+    );
+    let paths: Vec<(&str, ArchiveEntryKind)> = manifest
+        .entries
+        .iter()
+        .map(|entry| (entry.path.as_str(), entry.kind))
+        .collect();
+    assert_eq!(
+        paths,
+        [
+            ("resources/000-index.html", ArchiveEntryKind::Document),
+            ("resources/001-app.js", ArchiveEntryKind::Resource),
+            ("resources/002-logo.png", ArchiveEntryKind::Resource),
+            ("resources/003-embed", ArchiveEntryKind::Document),
+            ("viewport.png", ArchiveEntryKind::Screenshot),
+            ("dom.html", ArchiveEntryKind::DomHtml),
+        ]
+    );
+    assert_eq!(manifest.failures[0].url, "https://app.test/gone.css");
+    // Shared resources are fetched once.
+    assert_eq!(server.requests_for("Page.getResourceContent").len(), 5);
+
+    let logo = archive.entry_for_url("https://app.test/logo.png").unwrap();
+    assert_eq!(
+        std::fs::read(archive.path(logo)).unwrap(),
+        [0x89, 0x50, 0x4e]
+    );
+    assert_eq!(
+        std::fs::read_to_string(directory.join("dom.html")).unwrap(),
+        "<html><body>live</body></html>"
+    );
+    let reloaded = PageArchive::load(&directory).await.unwrap();
+    assert_eq!(reloaded.manifest(), manifest);
+    std::fs::remove_dir_all(&directory).unwrap();
+}